@@ -0,0 +1,138 @@
+//! AlertEngine rule evaluation in isolation: pre-built rows, no database.
+
+use std::time::Instant;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use laminardb_fraud_detect::alerts::AlertEngine;
+use laminardb_fraud_detect::generator::SYMBOLS;
+use laminardb_fraud_detect::types::{SuspiciousMatch, VolumeBaseline, WashScore};
+
+const ROWS_PER_ITER: usize = 1_000;
+
+fn volume_rows(count: usize) -> Vec<VolumeBaseline> {
+    (0..count)
+        .map(|i| {
+            let (sym, price) = SYMBOLS[i % SYMBOLS.len()];
+            // Mostly steady with a spike every 50 rows so some alerts fire
+            let total_volume = if i % 50 == 0 { 50_000 } else { 2_000 + (i % 7) as i64 * 100 };
+            VolumeBaseline {
+                symbol: sym.to_string(),
+                window_start: (i as i64) * 2_000,
+                total_volume,
+                trade_count: 10,
+                avg_price: price,
+                total_notional: total_volume as f64 * price,
+            }
+        })
+        .collect()
+}
+
+fn wash_rows(count: usize, accounts: usize) -> Vec<WashScore> {
+    (0..count)
+        .map(|i| {
+            let (sym, price) = SYMBOLS[i % SYMBOLS.len()];
+            let buy = 100 + (i % 13) as i64 * 10;
+            let sell = if i % 3 == 0 { buy } else { buy / 4 };
+            WashScore {
+                account_id: format!("ACCT-{:05}", i % accounts),
+                symbol: sym.to_string(),
+                window_start: (i as i64) * 5_000,
+                buy_volume: buy,
+                sell_volume: sell,
+                buy_count: 3,
+                sell_count: if i % 3 == 0 { 3 } else { 1 },
+                buy_notional: buy as f64 * price,
+                sell_notional: sell as f64 * price,
+            }
+        })
+        .collect()
+}
+
+fn match_rows(count: usize, accounts: usize) -> Vec<SuspiciousMatch> {
+    (0..count)
+        .map(|i| {
+            let (sym, price) = SYMBOLS[i % SYMBOLS.len()];
+            let diff = (i % 40) as f64 * 0.05;
+            SuspiciousMatch {
+                symbol: sym.to_string(),
+                trade_price: price + diff,
+                volume: 100,
+                order_id: format!("ORD-{:06}", i),
+                account_id: format!("ACCT-{:05}", i % accounts),
+                side: if i % 2 == 0 { "buy" } else { "sell" }.to_string(),
+                order_price: price,
+                price_diff: diff,
+                notional: (price + diff) * 100.0,
+            }
+        })
+        .collect()
+}
+
+fn volume_history(c: &mut Criterion) {
+    let rows = volume_rows(ROWS_PER_ITER);
+
+    let mut group = c.benchmark_group("alert_engine/volume");
+    group.throughput(Throughput::Elements(rows.len() as u64));
+    for history_len in [5, 20, 100, 500] {
+        let mut engine = AlertEngine::new();
+        engine.volume_history_len = history_len;
+        // Fill every symbol's history before measuring
+        for row in volume_rows(history_len * SYMBOLS.len()) {
+            engine.evaluate_volume(&row, Instant::now());
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(history_len), &rows, |b, rows| {
+            b.iter(|| {
+                let gen_instant = Instant::now();
+                for row in rows {
+                    engine.evaluate_volume(row, gen_instant);
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn wash_accounts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alert_engine/wash");
+    group.throughput(Throughput::Elements(ROWS_PER_ITER as u64));
+    for accounts in [10, 100, 1_000, 10_000] {
+        let rows = wash_rows(ROWS_PER_ITER, accounts);
+        let mut engine = AlertEngine::new();
+
+        group.bench_with_input(BenchmarkId::from_parameter(accounts), &rows, |b, rows| {
+            b.iter(|| {
+                let gen_instant = Instant::now();
+                for row in rows {
+                    engine.evaluate_wash(row, gen_instant);
+                }
+                engine.evaluate_ml(gen_instant);
+            });
+        });
+    }
+    group.finish();
+}
+
+fn match_accounts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alert_engine/match");
+    group.throughput(Throughput::Elements(ROWS_PER_ITER as u64));
+    for accounts in [10, 100, 1_000, 10_000] {
+        let rows = match_rows(ROWS_PER_ITER, accounts);
+        let mut engine = AlertEngine::new();
+
+        group.bench_with_input(BenchmarkId::from_parameter(accounts), &rows, |b, rows| {
+            b.iter(|| {
+                let gen_instant = Instant::now();
+                for row in rows {
+                    engine.evaluate_match(row, gen_instant);
+                }
+                engine.evaluate_ml(gen_instant);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, volume_history, wash_accounts, match_accounts);
+criterion_main!(benches);
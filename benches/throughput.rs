@@ -1,10 +1,22 @@
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use laminar_db::{FromBatch, TypedSubscription};
 use tokio::runtime::Runtime;
 
 use laminardb_fraud_detect::alerts::AlertEngine;
-use laminardb_fraud_detect::detection;
+use laminardb_fraud_detect::detection::{self, DetectionPipeline, PipelineOptions};
 use laminardb_fraud_detect::generator::FraudGenerator;
 use laminardb_fraud_detect::latency::LatencyTracker;
+use laminardb_fraud_detect::testkit;
+
+/// Warm-up before sampling starts, covering window warm-up in the pipeline.
+const BENCH_WARMUP: Duration = Duration::from_secs(5);
+/// Trades per push in the first-row latency bench.
+const LATENCY_BATCH: usize = 500;
+/// Event-time step between pushes, wide enough that every window type closes.
+const LATENCY_STEP_MS: i64 = 10_000;
 
 fn push_throughput(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
@@ -98,6 +110,7 @@ fn end_to_end(c: &mut Criterion) {
                         }
                     }
                 }
+                alert_engine.evaluate_ml(gen_instant);
 
                 latency.reset();
             });
@@ -108,6 +121,78 @@ fn end_to_end(c: &mut Criterion) {
     rt.block_on(pipeline.db.shutdown()).ok();
 }
 
+/// Time from `push_batch` until `sub` yields its first row, summed over
+/// `iters` pushes. Leftover rows are drained untimed between pushes so a
+/// late row from one push is never counted as the first row of the next.
+async fn first_row_latency<T: Clone + FromBatch>(
+    pipeline: &DetectionPipeline,
+    sub: &TypedSubscription<T>,
+    gen: &RefCell<FraudGenerator>,
+    next_ts: &Cell<i64>,
+    iters: u64,
+) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..iters {
+        testkit::collect(sub, Duration::ZERO).await;
+
+        let ts = next_ts.get();
+        next_ts.set(ts + LATENCY_STEP_MS);
+        let (trades, orders) = gen.borrow_mut().generate_stress_cycle(ts, LATENCY_BATCH);
+
+        let start = Instant::now();
+        pipeline.trade_source.push_batch(trades);
+        if !orders.is_empty() {
+            pipeline.order_source.push_batch(orders);
+        }
+        pipeline.trade_source.watermark(ts + LATENCY_STEP_MS);
+        pipeline.order_source.watermark(ts + LATENCY_STEP_MS);
+        loop {
+            if sub.poll().is_some() {
+                break;
+            }
+            if start.elapsed() > Duration::from_secs(10) {
+                panic!("no row within 10s of push");
+            }
+            tokio::task::yield_now().await;
+        }
+        total += start.elapsed();
+
+        testkit::collect(sub, Duration::from_secs(1)).await;
+    }
+    total
+}
+
+fn stream_latency(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let pipeline = rt.block_on(detection::setup()).unwrap();
+    let gen = RefCell::new(FraudGenerator::new(0.0));
+    let next_ts = Cell::new(FraudGenerator::now_ms());
+
+    let mut group = c.benchmark_group("first_row_latency");
+    group.sample_size(10);
+
+    macro_rules! bench_stream {
+        ($name:expr, $sub:expr) => {
+            if let Some(ref sub) = $sub {
+                group.bench_function($name, |b| {
+                    b.to_async(&rt)
+                        .iter_custom(|iters| first_row_latency(&pipeline, sub, &gen, &next_ts, iters));
+                });
+            }
+        };
+    }
+
+    bench_stream!("vol_baseline", pipeline.vol_baseline_sub);
+    bench_stream!("ohlc_vol", pipeline.ohlc_vol_sub);
+    bench_stream!("rapid_fire", pipeline.rapid_fire_sub);
+    bench_stream!("wash_score", pipeline.wash_score_sub);
+    bench_stream!("suspicious_match", pipeline.suspicious_match_sub);
+    bench_stream!("asof_match", pipeline.asof_match_sub);
+    group.finish();
+
+    rt.block_on(pipeline.db.shutdown()).ok();
+}
+
 fn pipeline_setup(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
 
@@ -117,7 +202,26 @@ fn pipeline_setup(c: &mut Criterion) {
             rt.block_on(pipeline.db.shutdown()).ok();
         });
     });
+
+    // The same, one statement at a time: the gap is what concurrent setup saves
+    let sequential = PipelineOptions { sequential_setup: true, ..Default::default() };
+    c.bench_function("pipeline_setup_sequential", |b| {
+        b.iter(|| {
+            let pipeline = rt.block_on(detection::setup_with(&sequential)).unwrap();
+            rt.block_on(pipeline.db.shutdown()).ok();
+        });
+    });
 }
 
-criterion_group!(benches, push_throughput, end_to_end, pipeline_setup);
+/// Criterion discards samples taken during warm-up. The pipeline needs longer
+/// than the 3s default to fill its windows; override with `--warm-up-time`.
+fn config() -> Criterion {
+    Criterion::default().warm_up_time(BENCH_WARMUP)
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = push_throughput, end_to_end, stream_latency, pipeline_setup
+}
 criterion_main!(benches);
@@ -0,0 +1,41 @@
+//! Config TOML: parse arbitrary text, then run it through every section's
+//! validation as `validate` does. Validation may reject it; it must not panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use laminardb_fraud_detect::alerts::AlertEngine;
+use laminardb_fraud_detect::auth::Authorizer;
+use laminardb_fraud_detect::breakpoints::Breakpoints;
+use laminardb_fraud_detect::calendar::TradingCalendar;
+use laminardb_fraud_detect::config::Config;
+use laminardb_fraud_detect::detection;
+use laminardb_fraud_detect::engine::WatermarkStrategy;
+use laminardb_fraud_detect::pacing::Pacer;
+use laminardb_fraud_detect::risk::RiskMerger;
+use laminardb_fraud_detect::slo::SloTracker;
+use laminardb_fraud_detect::ticks::TickDetector;
+use laminardb_fraud_detect::tui::Keymap;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(mut config) = Config::parse(text) else {
+        return;
+    };
+    // Loading a model reads the file system, which is not what's under test.
+    config.scoring.model_path = None;
+    let _ = AlertEngine::from_config(&config);
+    let _ = Keymap::from_config(&config.keys);
+    let _ = Pacer::from_config(&config);
+    let _ = TickDetector::from_config(&config.ticks);
+    let _ = WatermarkStrategy::from_config(&config.watermark);
+    let _ = Breakpoints::from_config(&config.breakpoints);
+    let _ = Authorizer::from_config(&config.auth);
+    let _ = RiskMerger::from_config(&config.coordinator);
+    let _ = SloTracker::from_config(&config.slo);
+    let _ = TradingCalendar::from_config(&config.calendar);
+    let _ = detection::check_sessions(&config.sessions);
+});
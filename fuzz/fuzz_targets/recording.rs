@@ -0,0 +1,22 @@
+//! Row recordings (`replay <file>`): parse arbitrary text, then play
+//! whatever parsed through the default rules.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use laminardb_fraud_detect::config::Config;
+use laminardb_fraud_detect::recording::{self, Player};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(cycles) = recording::parse(text) else {
+        return;
+    };
+    let mut player = Player::from_config(&Config::default()).expect("default config is valid");
+    for cycle in cycles {
+        player.play(cycle);
+    }
+});
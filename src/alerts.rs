@@ -1,18 +1,40 @@
-use std::collections::{HashMap, VecDeque};
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
 
-use serde::Serialize;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize, Serializer};
 
+use crate::calendar::TradingCalendar;
+use crate::config::{
+    Config, CooldownConfig, NotionalConfig, RetentionConfig, Rollover, RolloverConfig, SeverityBounds, SeverityConfig, SeverityLevel, ShadowConfig, ThresholdsConfig, TickConfig, VolumeConfig, VolumeMode,
+};
+use crate::detection::NEWS_LOOKBACK_MS;
+use crate::ml::{AnomalyScorer, MlScore};
+use crate::risk::{AccountRisk, RiskTally};
+use crate::scoring::{self, AlertScorer};
+use crate::sequence::SequenceBreak;
+use crate::ticks::{TickMove, TICK_MAX_WINDOW_MS};
 use crate::types::*;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AlertSeverity {
     Medium,
     High,
     Critical,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl From<SeverityLevel> for AlertSeverity {
+    fn from(level: SeverityLevel) -> Self {
+        match level {
+            SeverityLevel::Medium => AlertSeverity::Medium,
+            SeverityLevel::High => AlertSeverity::High,
+            SeverityLevel::Critical => AlertSeverity::Critical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AlertType {
     VolumeAnomaly,
     PriceSpike,
@@ -20,9 +42,43 @@ pub enum AlertType {
     WashTrading,
     SuspiciousMatch,
     FrontRunning,
+    MlAnomaly,
+    Diversification,
+    BookImbalance,
+    MomentumIgnition,
+    InsiderTrading,
+    PriceImprovement,
+    CounterpartyConcentration,
+    /// From the engine's tick-level detector rather than a SQL stream.
+    PriceVelocity,
+    /// Raised by the engine itself, not a rule: a cycle ran over its budget
+    /// or cycles stopped for longer than it.
+    EngineStall,
+    /// Raised by the engine itself: a trade or order feed skipped or
+    /// repeated sequence numbers.
+    FeedIntegrity,
 }
 
 impl AlertType {
+    /// Every type a detection rule raises, i.e. all but `EngineStall` and
+    /// `FeedIntegrity`.
+    pub const RULES: [AlertType; 14] = [
+        AlertType::VolumeAnomaly,
+        AlertType::PriceSpike,
+        AlertType::RapidFire,
+        AlertType::WashTrading,
+        AlertType::SuspiciousMatch,
+        AlertType::FrontRunning,
+        AlertType::MlAnomaly,
+        AlertType::Diversification,
+        AlertType::BookImbalance,
+        AlertType::MomentumIgnition,
+        AlertType::InsiderTrading,
+        AlertType::PriceImprovement,
+        AlertType::CounterpartyConcentration,
+        AlertType::PriceVelocity,
+    ];
+
     pub fn label(&self) -> &'static str {
         match self {
             AlertType::VolumeAnomaly => "VolumeAnomaly",
@@ -31,103 +87,1129 @@ impl AlertType {
             AlertType::WashTrading => "WashTrading",
             AlertType::SuspiciousMatch => "SuspiciousMatch",
             AlertType::FrontRunning => "FrontRunning",
+            AlertType::MlAnomaly => "MlAnomaly",
+            AlertType::Diversification => "Diversification",
+            AlertType::BookImbalance => "BookImbalance",
+            AlertType::MomentumIgnition => "MomentumIgnition",
+            AlertType::InsiderTrading => "InsiderTrading",
+            AlertType::PriceImprovement => "PriceImprovement",
+            AlertType::CounterpartyConcentration => "CounterpartyConcentration",
+            AlertType::PriceVelocity => "PriceVelocity",
+            AlertType::EngineStall => "EngineStall",
+            AlertType::FeedIntegrity => "FeedIntegrity",
+        }
+    }
+
+    /// The detection stream whose rows raise this type, from `STREAM_NAMES`
+    /// (`rapid_fire` also covers its per-profile streams). None for the ML,
+    /// tick-level and engine alerts.
+    pub fn stream(&self) -> Option<&'static str> {
+        match self {
+            AlertType::VolumeAnomaly => Some("vol_baseline"),
+            AlertType::PriceSpike => Some("ohlc_vol"),
+            AlertType::RapidFire => Some("rapid_fire"),
+            AlertType::WashTrading => Some("wash_score"),
+            AlertType::SuspiciousMatch => Some("suspicious_match"),
+            AlertType::FrontRunning => Some("asof_match"),
+            AlertType::Diversification => Some("account_activity"),
+            AlertType::BookImbalance => Some("book_imbalance"),
+            AlertType::MomentumIgnition => Some("momentum_burst"),
+            AlertType::InsiderTrading => Some("pre_news"),
+            AlertType::PriceImprovement => Some("price_improvement"),
+            AlertType::CounterpartyConcentration => Some("counterparty_volume"),
+            AlertType::MlAnomaly | AlertType::PriceVelocity | AlertType::EngineStall | AlertType::FeedIntegrity => None,
+        }
+    }
+
+    /// Event time before the alert that holds the rows behind it: the rule's
+    /// window, plus the match stream's ±2s join for the join rules.
+    pub fn evidence_window_ms(&self) -> i64 {
+        match self {
+            AlertType::VolumeAnomaly | AlertType::RapidFire | AlertType::MlAnomaly => 10_000,
+            AlertType::PriceSpike | AlertType::WashTrading | AlertType::Diversification => 5_000,
+            AlertType::SuspiciousMatch | AlertType::FrontRunning => 4_000,
+            AlertType::BookImbalance => BOOK_CONFIRM_MS + 1_000,
+            AlertType::MomentumIgnition => MOMENTUM_BURST_MAX_MS + MOMENTUM_UNWIND_MS + 1_000,
+            AlertType::InsiderTrading => NEWS_LOOKBACK_MS + 1_000,
+            AlertType::PriceImprovement => PRICE_IMPROVEMENT_WINDOW_MS,
+            AlertType::CounterpartyConcentration => 2 * CONCENTRATION_WINDOW_MS,
+            AlertType::PriceVelocity => TICK_MAX_WINDOW_MS,
+            AlertType::EngineStall | AlertType::FeedIntegrity => 0,
+        }
+    }
+}
+
+/// `[retention]` limits on an in-memory alert history, shared by the engine,
+/// the TUI feed and (sent with each dashboard frame) the web page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertRetention {
+    pub max_alerts: Option<usize>,
+    pub max_age_ms: Option<i64>,
+}
+
+impl Default for AlertRetention {
+    fn default() -> Self {
+        Self { max_alerts: Some(200), max_age_ms: None }
+    }
+}
+
+impl AlertRetention {
+    pub fn from_config(config: &RetentionConfig) -> Result<Self, String> {
+        if config.max_alerts == 0 && config.max_age_secs == 0 {
+            return Err("[retention] needs max_alerts or max_age_secs; with neither, alert history grows without bound".into());
+        }
+        Ok(Self {
+            max_alerts: (config.max_alerts > 0).then_some(config.max_alerts),
+            max_age_ms: (config.max_age_secs > 0).then(|| config.max_age_secs as i64 * 1000),
+        })
+    }
+
+    /// Append `alert` to `history`, oldest first, and trim it to the limits.
+    pub fn push(&self, history: &mut VecDeque<Alert>, alert: Alert) {
+        history.push_back(alert);
+        self.trim(history, chrono::Utc::now().timestamp_millis());
+    }
+
+    /// Drop the oldest alerts of `history` beyond `max_alerts`, and those
+    /// raised more than `max_age_ms` before `now_ms`.
+    pub fn trim(&self, history: &mut VecDeque<Alert>, now_ms: i64) {
+        if let Some(max) = self.max_alerts {
+            while history.len() > max {
+                history.pop_front();
+            }
+        }
+        if let Some(age) = self.max_age_ms {
+            while history.front().is_some_and(|a| a.timestamp_ms < now_ms - age) {
+                history.pop_front();
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Serialized with `description` text in place of `detail`; read back, the
+/// text becomes `AlertDetail::Text`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "AlertRecord")]
 pub struct Alert {
     pub id: u64,
     pub alert_type: AlertType,
     pub severity: AlertSeverity,
-    pub description: String,
+    pub symbol: Option<String>,
+    pub account_id: Option<String>,
+    /// What the rule measured; `description()` turns it into text.
+    pub detail: AlertDetail,
     pub latency_us: u64,
     pub timestamp_ms: i64,
+    /// Anomaly score for MlAnomaly alerts, or the scoring model's output when one
+    /// is configured; `None` for unscored rule-based alerts.
+    pub score: Option<f64>,
+    /// FrontRunning only: event time from the order to the trade that
+    /// followed it. Carried into the alert's evidence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_ms: Option<i64>,
+    /// The measurement the rule compared against its threshold (ratio,
+    /// imbalance, spread, ...); `None` for engine-raised alerts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric: Option<f64>,
+    /// Globally unique ID (ULID or UUIDv7) given at delivery under `[ids]
+    /// format`; `None` with the default sequence IDs. `id` stays the
+    /// in-process sequence either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+}
+
+/// The values behind an alert's description. Rules store these rather than
+/// the formatted text: under load most alerts are suppressed, capped or only
+/// counted, so the text is built when a sink or screen asks for it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlertDetail {
+    Volume { volume: i64, avg: i64, ratio: f64, pct_of_adv: f64, notional: f64 },
+    PriceRange { range_pct: f64, open: f64, high: f64, low: f64, notional: f64 },
+    RapidFire { trades: i64, volume: i64, notional: f64 },
+    Wash { imbalance: f64, buy_volume: i64, sell_volume: i64, notional: f64 },
+    Match { order_id: String, price_diff: f64, notional: f64 },
+    FrontRun { order_account: String, gap_ms: i64, order_quantity: i64, pct_of_adv: f64, spread: f64, notional: f64 },
+    Diversification { symbols: i64, avg: f64, ratio: f64, notional: f64 },
+    Book { bid_heavy: bool, imbalance: f64, price_move: f64 },
+    Momentum { buy: bool, trades: i64, start_price: f64, vwap: f64, notional: f64 },
+    Insider { bought: bool, volume: i64, trades: i64, lead_ms: i64, headline: String, impact: f64, notional: f64 },
+    Improvement { fills: usize, mean: f64, counterparty: String, span_ms: i64, buy: bool, trade_price: f64, quote_price: f64, notional: f64 },
+    Concentration { share: f64, volume: i64, counterparty: String, counterparty_trades: i64, trades: i64, counterparties: usize, window_start: i64, notional: f64 },
+    Velocity { pct: f64, span_ms: i64, from_price: f64, to_price: f64 },
+    Ml { score: f64, burst_trades: i64, burst_volume: i64, wash_balance: f64, match_count: u64 },
+    /// Engine- and feed-raised alerts, and alerts read back from JSON.
+    Text(String),
+}
+
+/// An alert's description, formatted on display.
+pub struct Description<'a>(&'a Alert);
+
+impl Alert {
+    pub fn description(&self) -> Description<'_> {
+        Description(self)
+    }
+}
+
+impl fmt::Display for Description<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alert = self.0;
+        let symbol = alert.symbol.as_deref().unwrap_or_default();
+        let account = alert.account_id.as_deref().unwrap_or_default();
+        match &alert.detail {
+            AlertDetail::Volume { volume, avg, ratio, pct_of_adv, notional } => {
+                write!(f, "{symbol} vol={volume} avg={avg} ({ratio:.1}x) {pct_of_adv:.2}% of ADV notional=${notional:.0}")
+            }
+            AlertDetail::PriceRange { range_pct, open, high, low, notional } => {
+                write!(f, "{symbol} range={:.2}% O={open:.2} H={high:.2} L={low:.2} notional=${notional:.0}", range_pct * 100.0)
+            }
+            AlertDetail::RapidFire { trades, volume, notional } => write!(f, "{account} {trades} trades vol={volume} notional=${notional:.0}"),
+            AlertDetail::Wash { imbalance, buy_volume, sell_volume, notional } => {
+                write!(f, "{account} {symbol} imb={imbalance:.3} buy={buy_volume} sell={sell_volume} notional=${notional:.0}")
+            }
+            AlertDetail::Match { order_id, price_diff, notional } => write!(f, "{account} {symbol} order={order_id} diff={price_diff:.4} notional=${notional:.0}"),
+            AlertDetail::FrontRun { order_account, gap_ms, order_quantity, pct_of_adv, spread, notional } => write!(
+                f,
+                "{account}->{order_account} {symbol} gap={gap_ms}ms order={order_quantity} ({pct_of_adv:.2}% of ADV) spread={spread:.4} notional=${notional:.0}"
+            ),
+            AlertDetail::Diversification { symbols, avg, ratio, notional } => write!(f, "{account} symbols={symbols} avg={avg:.1} ({ratio:.1}x) notional=${notional:.0}"),
+            AlertDetail::Book { bid_heavy, imbalance, price_move } => write!(
+                f,
+                "{symbol} {}-heavy book imbalance={imbalance:.2} then mid {:+.2}%",
+                if *bid_heavy { "bid" } else { "ask" },
+                price_move * 100.0
+            ),
+            AlertDetail::Momentum { buy, trades, start_price, vwap, notional } => write!(
+                f,
+                "{account} {symbol} {} burst={trades} trades from {start_price:.2}, unwound at {vwap:.2} ({:+.2}%) notional=${notional:.0}",
+                if *buy { "buy" } else { "sell" },
+                (vwap - start_price) / start_price * 100.0
+            ),
+            AlertDetail::Insider { bought, volume, trades, lead_ms, headline, impact, notional } => write!(
+                f,
+                "{account} {} {symbol} {volume} in {trades} trades {:.1}s before \"{headline}\" (impact {impact:+.2}) notional=${notional:.0}",
+                if *bought { "bought" } else { "sold" },
+                *lead_ms as f64 / 1000.0
+            ),
+            AlertDetail::Improvement { fills, mean, counterparty, span_ms, buy, trade_price, quote_price, notional } => write!(
+                f,
+                "{account} filled {fills} times {:.1}bps through the quote by {counterparty} in {:.1}s (last {} {symbol} @ {trade_price:.2} vs {quote_price:.2}) notional=${notional:.0}",
+                mean * 10_000.0,
+                *span_ms as f64 / 1000.0,
+                if *buy { "buy" } else { "sell" }
+            ),
+            AlertDetail::Concentration { share, volume, counterparty, counterparty_trades, trades, counterparties, window_start, notional } => write!(
+                f,
+                "{account} did {:.0}% of {volume} shares against {counterparty} ({counterparty_trades} of {trades} trades, {counterparties} counterparties) in the window at {window_start} notional=${notional:.0}",
+                share * 100.0
+            ),
+            AlertDetail::Velocity { pct, span_ms, from_price, to_price } => {
+                write!(f, "{symbol} moved {:+.2}% in {span_ms}ms ({from_price:.2} -> {to_price:.2})", pct * 100.0)
+            }
+            AlertDetail::Ml { score, burst_trades, burst_volume, wash_balance, match_count } => {
+                write!(f, "{account} score={score:.3} burst={burst_trades} vol={burst_volume} bal={wash_balance:.2} matches={match_count}")
+            }
+            AlertDetail::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+impl Serialize for Description<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// `Alert` as it is written out, with the description formatted.
+#[derive(Serialize)]
+struct AlertView<'a> {
+    id: u64,
+    alert_type: &'a AlertType,
+    severity: &'a AlertSeverity,
+    symbol: &'a Option<String>,
+    account_id: &'a Option<String>,
+    description: Description<'a>,
+    latency_us: u64,
+    timestamp_ms: i64,
+    score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gap_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metric: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uid: &'a Option<String>,
+}
+
+impl Serialize for Alert {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        AlertView {
+            id: self.id,
+            alert_type: &self.alert_type,
+            severity: &self.severity,
+            symbol: &self.symbol,
+            account_id: &self.account_id,
+            description: self.description(),
+            latency_us: self.latency_us,
+            timestamp_ms: self.timestamp_ms,
+            score: self.score,
+            gap_ms: self.gap_ms,
+            metric: self.metric,
+            uid: &self.uid,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// `Alert` as it is read back: the description is only text now.
+#[derive(Deserialize)]
+struct AlertRecord {
+    id: u64,
+    alert_type: AlertType,
+    severity: AlertSeverity,
+    symbol: Option<String>,
+    account_id: Option<String>,
+    description: String,
+    latency_us: u64,
+    timestamp_ms: i64,
+    score: Option<f64>,
+    #[serde(default)]
+    gap_ms: Option<i64>,
+    #[serde(default)]
+    metric: Option<f64>,
+    #[serde(default)]
+    uid: Option<String>,
+}
+
+impl From<AlertRecord> for Alert {
+    fn from(r: AlertRecord) -> Self {
+        Alert {
+            id: r.id,
+            alert_type: r.alert_type,
+            severity: r.severity,
+            symbol: r.symbol,
+            account_id: r.account_id,
+            detail: AlertDetail::Text(r.description),
+            latency_us: r.latency_us,
+            timestamp_ms: r.timestamp_ms,
+            score: r.score,
+            gap_ms: r.gap_ms,
+            metric: r.metric,
+            uid: r.uid,
+        }
+    }
+}
+
+/// Alert type of a [`Cooldown`] set by [`AlertEngine::silence`], which
+/// holds back alerts of every type.
+pub const SILENCE_TYPE: &str = "*";
+
+/// An alert type and subject (account, else symbol) whose repeats are
+/// suppressed until `until_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cooldown {
+    pub alert_type: String,
+    pub subject: String,
+    pub until_ms: i64,
+    /// Seconds left when this snapshot was taken.
+    pub remaining_secs: u64,
+    /// Repeats suppressed since the cooldown started.
+    pub suppressed: u64,
+}
+
+impl Cooldown {
+    /// Whole seconds left at `now_ms`, rounded up; 0 once expired.
+    pub fn remaining_at(&self, now_ms: i64) -> u64 {
+        ((self.until_ms - now_ms).max(0) as u64).div_ceil(1000)
+    }
+
+    /// `WashTrading for FRAUD-02 suppressed, 42s remaining`.
+    pub fn describe(&self) -> String {
+        format!("{} for {} suppressed, {}s remaining", self.alert_type, self.subject, self.remaining_secs)
+    }
+}
+
+/// Delivered alerts on one symbol, by type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolAlerts {
+    pub symbol: String,
+    pub total: u64,
+    pub by_type: BTreeMap<String, u64>,
+}
+
+impl SymbolAlerts {
+    /// The type with the most alerts on this symbol; ties go to the first by name.
+    pub fn top_type(&self) -> Option<(&str, u64)> {
+        self.by_type.iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0))).map(|(t, &n)| (t.as_str(), n))
+    }
+}
+
+/// Seconds of trading in each `vol_baseline` row (its HOP window size).
+const VOL_WINDOW_SECS: f64 = 10.0;
+
+/// Slowest weight a new window gets in a symbol's ADV estimate; until
+/// `1 / ADV_EWMA_ALPHA` windows are seen the estimate is a plain mean.
+const ADV_EWMA_ALPHA: f64 = 0.01;
+
+/// A symbol's long-run average window volume, the basis of its ADV estimate.
+#[derive(Default)]
+struct AdvEstimate {
+    window_avg: f64,
+    samples: u64,
+}
+
+impl AdvEstimate {
+    fn observe(&mut self, volume: i64) {
+        self.samples += 1;
+        let alpha = (1.0 / self.samples as f64).max(ADV_EWMA_ALPHA);
+        self.window_avg += alpha * (volume as f64 - self.window_avg);
+    }
+
+    fn adv(&self, trading_day_secs: f64) -> f64 {
+        self.window_avg * trading_day_secs / VOL_WINDOW_SECS
+    }
+}
+
+/// FrontRunning severity steps: a trade within `FRONT_RUN_NEAR_MS` of the
+/// order it follows earns one, within `FRONT_RUN_TIGHT_MS` two; an order of
+/// `FRONT_RUN_SIZEABLE_ADV_PCT` of the symbol's ADV earns one, of
+/// `FRONT_RUN_LARGE_ADV_PCT` two.
+const FRONT_RUN_TIGHT_MS: i64 = 100;
+const FRONT_RUN_NEAR_MS: i64 = 500;
+const FRONT_RUN_SIZEABLE_ADV_PCT: f64 = 0.25;
+const FRONT_RUN_LARGE_ADV_PCT: f64 = 1.0;
+
+/// Event time after an imbalanced book window in which a price move toward
+/// the heavy side confirms it.
+const BOOK_CONFIRM_MS: i64 = 5_000;
+
+/// Finished book windows a symbol needs before an imbalance can count as sudden.
+const BOOK_MIN_HISTORY: usize = 3;
+
+/// One symbol's depth imbalance: finished windows, the one in progress, an
+/// imbalance waiting for the price to confirm it, and whether the symbol is
+/// still cooling off from its last alert.
+#[derive(Default)]
+struct BookHistory {
+    windows: VecDeque<f64>,
+    current_window: Option<i64>,
+    current_imbalance: f64,
+    pending: Option<PendingImbalance>,
+    /// Set when the symbol's alert fires; cleared once `book_exit_windows`
+    /// finished windows in a row are under the threshold.
+    latched: bool,
+    clean_windows: usize,
+}
+
+struct PendingImbalance {
+    window_start: i64,
+    imbalance: f64,
+    mid_price: f64,
+}
+
+/// Longest `momentum_burst` session, first trade to last, that counts as a burst.
+const MOMENTUM_BURST_MAX_MS: i64 = 1_500;
+
+/// Event time after a burst in which the account's unwind confirms it.
+const MOMENTUM_UNWIND_MS: i64 = 10_000;
+
+/// An account's one-sided burst on a symbol, waiting for the unwind.
+struct PendingIgnition {
+    start_ts: i64,
+    end_ts: i64,
+    buy: bool,
+    trades: i64,
+    /// The price the burst pushed away from: its low for buys, high for sells.
+    start_price: f64,
+    notional: f64,
+    fired: bool,
+}
+
+/// One account's trading in a symbol ahead of one news event.
+#[derive(Default)]
+struct PrePosition {
+    aligned_volume: i64,
+    opposite_volume: i64,
+    trades: i64,
+    first_trade_ts: Option<i64>,
+    notional: f64,
+    fired: bool,
+}
+
+/// Event time over which one account pair's improved fills are counted.
+const PRICE_IMPROVEMENT_WINDOW_MS: i64 = 30_000;
+
+/// A fill that went through the quote in the receiving account's favour.
+struct ImprovedFill {
+    order_ref: String,
+    trade_ts: i64,
+    improvement: f64,
+    notional: f64,
+}
+
+/// Length of a `counterparty_volume` window. A window is judged once the
+/// next one starts, so an alert's evidence reaches back two of them.
+const CONCENTRATION_WINDOW_MS: i64 = 10_000;
+
+/// One account's window in progress, by counterparty.
+#[derive(Default)]
+struct CounterpartyWindow {
+    window_start: Option<i64>,
+    by_counterparty: HashMap<String, CounterpartyVolume>,
+}
+
+/// One account's distinct-symbol counts: finished windows plus the one in progress.
+#[derive(Default)]
+struct ActivityHistory {
+    windows: VecDeque<i64>,
+    current_window: Option<i64>,
+    current_symbols: i64,
+    alerted: bool,
 }
 
 pub struct AlertEngine {
     next_id: u64,
     alerts: VecDeque<Alert>,
+    retention: AlertRetention,
     vol_baselines: HashMap<String, VecDeque<i64>>,
+    adv: HashMap<String, AdvEstimate>,
+    activity: HashMap<String, ActivityHistory>,
+    books: HashMap<String, BookHistory>,
+    ignitions: HashMap<(String, String), PendingIgnition>,
+    /// Keyed by account, symbol and news `ts`.
+    pre_positions: HashMap<(String, String, i64), PrePosition>,
+    latest_news_ts: i64,
+    /// Improved fills within the window, keyed by receiving account and counterparty.
+    improved_fills: HashMap<(String, String), VecDeque<ImprovedFill>>,
+    counterparty_windows: HashMap<String, CounterpartyWindow>,
+    /// Trading sessions; VolumeAnomaly skips windows outside one.
+    pub calendar: Option<TradingCalendar>,
+    /// Event time of the cycle being evaluated, set by `advance_to`.
+    event_ts: i64,
+    /// What resets when the calendar's session date changes.
+    pub rollover: RolloverConfig,
+    /// Exchange-local date of the last session `advance_to` saw open.
+    session_date: Option<NaiveDate>,
+    /// Per-symbol `total_volume` samples averaged for the volume baseline.
+    pub volume_history_len: usize,
     pub volume_ratio_threshold: f64,
+    /// ADV estimation and whether VolumeAnomaly thresholds on it instead of the ratio.
+    pub volume: VolumeConfig,
     pub price_range_pct_threshold: f64,
     pub rapid_fire_threshold: i64,
     pub wash_imbalance_threshold: f64,
     pub match_price_diff_threshold: f64,
     pub front_run_spread_threshold: f64,
+    pub ml_score_threshold: f64,
+    /// Distinct symbols in a window over the account's average that triggers Diversification.
+    pub diversification_ratio_threshold: f64,
+    /// Fewest distinct symbols in a window that can trigger Diversification.
+    pub diversification_min_symbols: i64,
+    /// Finished windows an account needs before its baseline is trusted.
+    pub diversification_min_history: usize,
+    /// One-sided depth, `(bid - ask) / (bid + ask)`, that arms BookImbalance.
+    pub book_imbalance_threshold: f64,
+    /// Mid-price move toward the heavy side that confirms an armed imbalance.
+    pub book_move_pct_threshold: f64,
+    /// Consecutive windows imbalanced the same way that arm BookImbalance.
+    pub book_enter_windows: usize,
+    /// Finished windows under the threshold in a row after an alert before
+    /// the symbol can arm again.
+    pub book_exit_windows: usize,
+    /// Fewest trades in a one-sided session that can arm MomentumIgnition.
+    pub momentum_min_trades: i64,
+    /// Share of a session's trades on one side for it to count as one-sided.
+    pub momentum_side_share: f64,
+    /// Move from the burst's first price to the unwind's VWAP that confirms an ignition.
+    pub momentum_displacement_pct: f64,
+    /// Fewest |impact| a news event needs before trading ahead of it is checked.
+    pub insider_min_impact: f64,
+    /// Volume traded in the news' direction ahead of it that fires InsiderTrading.
+    pub insider_min_volume: i64,
+    /// Share of the account's pre-news volume that must be on the news' side.
+    pub insider_side_share: f64,
+    /// How far through the quote, as a fraction of it, a fill must be to count as improved.
+    pub price_improvement_pct: f64,
+    /// Improved fills between one account pair within the window that fire PriceImprovement.
+    pub price_improvement_fills: usize,
+    /// Share of an account's window volume against one counterparty that fires CounterpartyConcentration.
+    pub concentration_share: f64,
+    /// Fewest trades with a known counterparty in a window before its concentration is checked.
+    pub concentration_min_trades: i64,
+    /// Dollar floors: a row below its rule's minimum notional never fires.
+    pub min_notional: NotionalConfig,
+    /// Rules whose alerts are recorded as would-have-fired instead of delivered.
+    pub shadow: ShadowConfig,
+    /// Per-type severity floors and ceilings applied before delivery.
+    pub severity: SeverityConfig,
+    /// Per-type cooldowns between alerts for the same subject.
+    pub cooldown: CooldownConfig,
+    /// The tick detector's settings, for `active_thresholds`.
+    pub ticks: TickConfig,
+    cooldowns: HashMap<(&'static str, String), Cooldown>,
+    shadow_alerts: VecDeque<Alert>,
+    shadow_counts: HashMap<String, u64>,
+    ml: AnomalyScorer,
+    last_ml_scores: Vec<MlScore>,
+    risk: RiskTally,
+    scorer: Option<Box<dyn AlertScorer>>,
+    pub model_min_score: f64,
+    model_suppressed: u64,
+    cooldown_suppressed: u64,
     counts: HashMap<String, u64>,
+    severity_counts: HashMap<String, u64>,
+    symbol_counts: HashMap<(&'static str, String), u64>,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AlertEngine {
     pub fn new() -> Self {
+        Self::with_thresholds(&ThresholdsConfig::default())
+    }
+
+    pub fn with_thresholds(t: &ThresholdsConfig) -> Self {
         Self {
             next_id: 0,
-            alerts: VecDeque::with_capacity(200),
+            alerts: VecDeque::new(),
+            retention: AlertRetention::default(),
             vol_baselines: HashMap::new(),
-            volume_ratio_threshold: 2.0,
-            price_range_pct_threshold: 0.002,
-            rapid_fire_threshold: 5,
-            wash_imbalance_threshold: 0.3,
-            match_price_diff_threshold: 1.0,
-            front_run_spread_threshold: 0.5,
+            adv: HashMap::new(),
+            activity: HashMap::new(),
+            books: HashMap::new(),
+            ignitions: HashMap::new(),
+            pre_positions: HashMap::new(),
+            latest_news_ts: 0,
+            improved_fills: HashMap::new(),
+            counterparty_windows: HashMap::new(),
+            calendar: None,
+            event_ts: 0,
+            rollover: RolloverConfig::default(),
+            session_date: None,
+            volume_history_len: t.volume_history_len,
+            volume_ratio_threshold: t.volume_ratio,
+            volume: VolumeConfig::default(),
+            price_range_pct_threshold: t.price_range_pct,
+            rapid_fire_threshold: t.rapid_fire_trades,
+            wash_imbalance_threshold: t.wash_imbalance,
+            match_price_diff_threshold: t.match_price_diff,
+            front_run_spread_threshold: t.front_run_spread,
+            ml_score_threshold: t.ml_score,
+            diversification_ratio_threshold: t.diversification_ratio,
+            diversification_min_symbols: t.diversification_min_symbols,
+            diversification_min_history: t.diversification_min_history,
+            book_imbalance_threshold: t.book_imbalance,
+            book_move_pct_threshold: t.book_move_pct,
+            book_enter_windows: t.book_enter_windows,
+            book_exit_windows: t.book_exit_windows,
+            momentum_min_trades: t.momentum_min_trades,
+            momentum_side_share: t.momentum_side_share,
+            momentum_displacement_pct: t.momentum_displacement_pct,
+            insider_min_impact: t.insider_min_impact,
+            insider_min_volume: t.insider_min_volume,
+            insider_side_share: t.insider_side_share,
+            price_improvement_pct: t.price_improvement_pct,
+            price_improvement_fills: t.price_improvement_fills,
+            concentration_share: t.concentration_share,
+            concentration_min_trades: t.concentration_min_trades,
+            min_notional: NotionalConfig::default(),
+            shadow: ShadowConfig::default(),
+            severity: SeverityConfig::default(),
+            cooldown: CooldownConfig::default(),
+            ticks: TickConfig::default(),
+            cooldowns: HashMap::new(),
+            shadow_alerts: VecDeque::new(),
+            shadow_counts: HashMap::new(),
+            ml: AnomalyScorer::new(),
+            last_ml_scores: Vec::new(),
+            risk: RiskTally::default(),
+            scorer: None,
+            model_min_score: 0.0,
+            model_suppressed: 0,
+            cooldown_suppressed: 0,
             counts: HashMap::new(),
+            severity_counts: HashMap::new(),
+            symbol_counts: HashMap::new(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        if config.thresholds.book_enter_windows == 0 {
+            return Err("[thresholds] book_enter_windows must be at least 1".into());
+        }
+        let mut engine = Self::with_thresholds(&config.thresholds);
+        engine.scorer = scoring::load(&config.scoring)?;
+        engine.model_min_score = config.scoring.min_score;
+        engine.min_notional = config.notional.clone();
+        engine.volume = config.volume.clone();
+        engine.shadow = config.shadow.clone();
+        engine.severity = config.severity.clone();
+        engine.cooldown = config.cooldown.clone();
+        engine.ticks = config.ticks.clone();
+        engine.retention = AlertRetention::from_config(&config.retention)?;
+        engine.calendar = TradingCalendar::from_config(&config.calendar)?;
+        engine.rollover = config.rollover.clone();
+        if let Some(calendar) = &engine.calendar {
+            engine.volume.trading_day_secs = calendar.regular_session_secs();
+        }
+        for alert_type in AlertType::RULES {
+            let bounds = engine.severity_bounds(&alert_type);
+            if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+                if min > max {
+                    return Err(format!("[severity] {}: min {min:?} is above max {max:?}", alert_type.label()).into());
+                }
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Set the event time the next polled rows belong to. With a calendar,
+    /// the first open cycle of a new session date rolls the rules over.
+    pub fn advance_to(&mut self, ts: i64) {
+        self.event_ts = ts;
+        let Some(date) = self.calendar.as_ref().filter(|c| c.is_open(ts)).map(|c| c.local(ts).date()) else {
+            return;
+        };
+        if self.session_date.replace(date).is_some_and(|prev| prev != date) {
+            self.roll_over();
+        }
+    }
+
+    /// Start a new trading session: clear the state of every rule
+    /// `[rollover]` resets, and keep the rest.
+    pub fn roll_over(&mut self) {
+        let r = self.rollover.clone();
+        if r.volume_anomaly == Rollover::Reset {
+            self.vol_baselines.clear();
+            self.adv.clear();
         }
+        if r.diversification == Rollover::Reset {
+            self.activity.clear();
+        }
+        if r.book_imbalance == Rollover::Reset {
+            self.books.clear();
+        }
+        if r.momentum_ignition == Rollover::Reset {
+            self.ignitions.clear();
+        }
+        if r.insider_trading == Rollover::Reset {
+            self.pre_positions.clear();
+        }
+        if r.price_improvement == Rollover::Reset {
+            self.improved_fills.clear();
+        }
+        if r.counterparty_concentration == Rollover::Reset {
+            self.counterparty_windows.clear();
+        }
+        if r.ml_anomaly == Rollover::Reset {
+            self.ml = AnomalyScorer::new();
+        }
+        if r.cooldowns == Rollover::Reset {
+            self.cooldowns.clear();
+        }
+    }
+
+    /// Candidate alerts dropped, by the scoring model or a cooldown.
+    pub fn suppressed_alerts(&self) -> u64 {
+        self.model_suppressed + self.cooldown_suppressed
+    }
+
+    /// Candidate alerts the scoring model rated below `model_min_score`.
+    pub fn model_suppressed(&self) -> u64 {
+        self.model_suppressed
+    }
+
+    /// Candidate alerts a `[cooldown]` or a silence held back as repeats.
+    pub fn cooldown_suppressed(&self) -> u64 {
+        self.cooldown_suppressed
     }
 
+    /// Every account score from the last `evaluate_ml`, alerting or not.
+    pub fn last_ml_scores(&self) -> &[MlScore] {
+        &self.last_ml_scores
+    }
+
+    /// Per-account bursts, wash volume, score and alert count from the last
+    /// `evaluate_ml`'s cycle, for a `coordinate` fan-in to add up.
+    pub fn account_risk(&self) -> &[AccountRisk] {
+        self.risk.last()
+    }
+
+    /// Delivered alerts, oldest first, within `[retention]`.
     pub fn recent_alerts(&self) -> &VecDeque<Alert> {
         &self.alerts
     }
 
+    pub fn retention(&self) -> AlertRetention {
+        self.retention
+    }
+
     pub fn alert_counts(&self) -> &HashMap<String, u64> {
         &self.counts
     }
 
+    /// The last alert ID issued, shadow alerts included; 0 before the first.
+    pub fn last_id(&self) -> u64 {
+        self.next_id
+    }
+
+    /// Issue IDs after `id` from now on, e.g. to continue a previous run's
+    /// sequence. Never moves the counter back.
+    pub fn resume_ids_after(&mut self, id: u64) {
+        self.next_id = self.next_id.max(id);
+    }
+
+    /// Cooldowns still running at `now_ms`, soonest to expire first.
+    pub fn active_cooldowns(&self, now_ms: i64) -> Vec<Cooldown> {
+        let mut active: Vec<Cooldown> = self
+            .cooldowns
+            .values()
+            .filter(|c| c.until_ms > now_ms)
+            .map(|c| Cooldown { remaining_secs: c.remaining_at(now_ms), ..c.clone() })
+            .collect();
+        active.sort_by_key(|c| c.until_ms);
+        active
+    }
+
+    /// Delivered alerts per severity (`Medium`, `High`, `Critical`).
+    pub fn severity_counts(&self) -> &HashMap<String, u64> {
+        &self.severity_counts
+    }
+
+    /// Delivered alerts per type and symbol. Alerts that name only an
+    /// account (RapidFire, MlAnomaly, ...) are not in it.
+    pub fn symbol_counts(&self) -> &HashMap<(&'static str, String), u64> {
+        &self.symbol_counts
+    }
+
+    /// [`AlertEngine::symbol_counts`] grouped by symbol, most-alerted first.
+    pub fn symbol_breakdown(&self) -> Vec<SymbolAlerts> {
+        let mut by_symbol: BTreeMap<&str, SymbolAlerts> = BTreeMap::new();
+        for ((alert_type, symbol), &n) in &self.symbol_counts {
+            let entry = by_symbol.entry(symbol.as_str()).or_insert_with(|| SymbolAlerts { symbol: symbol.clone(), ..Default::default() });
+            entry.total += n;
+            *entry.by_type.entry(alert_type.to_string()).or_insert(0) += n;
+        }
+        let mut breakdown: Vec<SymbolAlerts> = by_symbol.into_values().collect();
+        breakdown.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.symbol.cmp(&b.symbol)));
+        breakdown
+    }
+
     pub fn total_alerts(&self) -> u64 {
         self.counts.values().sum()
     }
 
+    /// Would-have-fired alerts per type from rules in shadow mode.
+    pub fn shadow_alert_counts(&self) -> &HashMap<String, u64> {
+        &self.shadow_counts
+    }
+
+    /// Shadow alerts raised since the last call, within `[retention]`, oldest first.
+    pub fn take_shadow_alerts(&mut self) -> Vec<Alert> {
+        self.shadow_alerts.drain(..).collect()
+    }
+
+    /// The settings `alert_type`'s rule currently fires against, by config key.
+    pub fn active_thresholds(&self, alert_type: &AlertType) -> Vec<(&'static str, f64)> {
+        let n = &self.min_notional;
+        let mut active = match alert_type {
+            AlertType::VolumeAnomaly => match self.volume.mode {
+                VolumeMode::Ratio => vec![
+                    ("volume_ratio", self.volume_ratio_threshold),
+                    ("volume_history_len", self.volume_history_len as f64),
+                    ("min_notional", n.volume_anomaly),
+                ],
+                VolumeMode::Adv => vec![("adv_pct_threshold", self.volume.adv_pct_threshold), ("min_notional", n.volume_anomaly)],
+            },
+            AlertType::PriceSpike => vec![("price_range_pct", self.price_range_pct_threshold), ("min_notional", n.price_spike)],
+            AlertType::RapidFire => vec![("rapid_fire_trades", self.rapid_fire_threshold as f64), ("min_notional", n.rapid_fire)],
+            AlertType::WashTrading => vec![("wash_imbalance", self.wash_imbalance_threshold), ("min_notional", n.wash_trading)],
+            AlertType::SuspiciousMatch => vec![("match_price_diff", self.match_price_diff_threshold), ("min_notional", n.suspicious_match)],
+            AlertType::FrontRunning => vec![("front_run_spread", self.front_run_spread_threshold), ("min_notional", n.front_running)],
+            AlertType::MlAnomaly => return vec![("ml_score", self.ml_score_threshold)],
+            AlertType::Diversification => vec![
+                ("diversification_ratio", self.diversification_ratio_threshold),
+                ("diversification_min_symbols", self.diversification_min_symbols as f64),
+                ("diversification_min_history", self.diversification_min_history as f64),
+                ("min_notional", n.diversification),
+            ],
+            AlertType::BookImbalance => vec![
+                ("book_imbalance", self.book_imbalance_threshold),
+                ("book_move_pct", self.book_move_pct_threshold),
+                ("book_enter_windows", self.book_enter_windows as f64),
+                ("book_exit_windows", self.book_exit_windows as f64),
+            ],
+            AlertType::MomentumIgnition => vec![
+                ("momentum_min_trades", self.momentum_min_trades as f64),
+                ("momentum_side_share", self.momentum_side_share),
+                ("momentum_displacement_pct", self.momentum_displacement_pct),
+                ("min_notional", n.momentum_ignition),
+            ],
+            AlertType::InsiderTrading => vec![
+                ("insider_min_impact", self.insider_min_impact),
+                ("insider_min_volume", self.insider_min_volume as f64),
+                ("insider_side_share", self.insider_side_share),
+                ("min_notional", n.insider_trading),
+            ],
+            AlertType::PriceImprovement => vec![
+                ("price_improvement_pct", self.price_improvement_pct),
+                ("price_improvement_fills", self.price_improvement_fills as f64),
+                ("min_notional", n.price_improvement),
+            ],
+            AlertType::CounterpartyConcentration => vec![
+                ("concentration_share", self.concentration_share),
+                ("concentration_min_trades", self.concentration_min_trades as f64),
+                ("min_notional", n.counterparty_concentration),
+            ],
+            AlertType::PriceVelocity => vec![("tick_move_pct", self.ticks.move_pct), ("tick_window_ms", self.ticks.window_ms as f64)],
+            AlertType::EngineStall | AlertType::FeedIntegrity => return Vec::new(),
+        };
+        if self.scorer.is_some() {
+            active.push(("model_min_score", self.model_min_score));
+        }
+        active
+    }
+
+    fn is_shadow(&self, alert_type: &AlertType) -> bool {
+        let s = &self.shadow;
+        match alert_type {
+            AlertType::VolumeAnomaly => s.volume_anomaly,
+            AlertType::PriceSpike => s.price_spike,
+            AlertType::RapidFire => s.rapid_fire,
+            AlertType::WashTrading => s.wash_trading,
+            AlertType::SuspiciousMatch => s.suspicious_match,
+            AlertType::FrontRunning => s.front_running,
+            AlertType::MlAnomaly => s.ml_anomaly,
+            AlertType::Diversification => s.diversification,
+            AlertType::BookImbalance => s.book_imbalance,
+            AlertType::MomentumIgnition => s.momentum_ignition,
+            AlertType::InsiderTrading => s.insider_trading,
+            AlertType::PriceImprovement => s.price_improvement,
+            AlertType::CounterpartyConcentration => s.counterparty_concentration,
+            AlertType::PriceVelocity => s.price_velocity,
+            AlertType::EngineStall | AlertType::FeedIntegrity => false,
+        }
+    }
+
+    fn cooldown_secs(&self, alert_type: &AlertType) -> u64 {
+        let c = &self.cooldown;
+        match alert_type {
+            AlertType::VolumeAnomaly => c.volume_anomaly,
+            AlertType::PriceSpike => c.price_spike,
+            AlertType::RapidFire => c.rapid_fire,
+            AlertType::WashTrading => c.wash_trading,
+            AlertType::SuspiciousMatch => c.suspicious_match,
+            AlertType::FrontRunning => c.front_running,
+            AlertType::MlAnomaly => c.ml_anomaly,
+            AlertType::Diversification => c.diversification,
+            AlertType::BookImbalance => c.book_imbalance,
+            AlertType::MomentumIgnition => c.momentum_ignition,
+            AlertType::InsiderTrading => c.insider_trading,
+            AlertType::PriceImprovement => c.price_improvement,
+            AlertType::CounterpartyConcentration => c.counterparty_concentration,
+            AlertType::PriceVelocity => c.price_velocity,
+            AlertType::EngineStall | AlertType::FeedIntegrity => 0,
+        }
+    }
+
+    /// Suppress every alert naming `subject` as its account or symbol until
+    /// `duration` from `now_ms`, as a cooldown over all types (listed with
+    /// type `*`). Replaces any silence of it already running.
+    pub fn silence(&mut self, subject: &str, duration: Duration, now_ms: i64) {
+        let cooldown = Cooldown {
+            alert_type: SILENCE_TYPE.to_string(),
+            subject: subject.to_string(),
+            until_ms: now_ms + duration.as_millis() as i64,
+            remaining_secs: duration.as_secs(),
+            suppressed: 0,
+        };
+        self.cooldowns.insert((SILENCE_TYPE, subject.to_string()), cooldown);
+    }
+
+    /// Whether `alert` is silenced or repeats one whose cooldown is still
+    /// running at the current event time; if not, and its type has a
+    /// cooldown, start one for its subject. Event time rather than the wall
+    /// clock, so replays and simulations cool down as the live feed would.
+    fn cooling_down(&mut self, alert: &Alert) -> bool {
+        let now_ms = self.event_ts;
+        for subject in [&alert.account_id, &alert.symbol].into_iter().flatten() {
+            if let Some(c) = self.cooldowns.get_mut(&(SILENCE_TYPE, subject.clone())).filter(|c| c.until_ms > now_ms) {
+                c.suppressed += 1;
+                return true;
+            }
+        }
+        let secs = self.cooldown_secs(&alert.alert_type);
+        if secs == 0 {
+            return false;
+        }
+        let subject = alert.account_id.clone().or_else(|| alert.symbol.clone()).unwrap_or_default();
+        let key = (alert.alert_type.label(), subject);
+        if let Some(c) = self.cooldowns.get_mut(&key).filter(|c| c.until_ms > now_ms) {
+            c.suppressed += 1;
+            return true;
+        }
+        self.cooldowns.retain(|_, c| c.until_ms > now_ms);
+        let cooldown = Cooldown {
+            alert_type: key.0.to_string(),
+            subject: key.1.clone(),
+            until_ms: now_ms + secs as i64 * 1000,
+            remaining_secs: secs,
+            suppressed: 0,
+        };
+        self.cooldowns.insert(key, cooldown);
+        false
+    }
+
+    fn severity_bounds(&self, alert_type: &AlertType) -> SeverityBounds {
+        let s = &self.severity;
+        match alert_type {
+            AlertType::VolumeAnomaly => s.volume_anomaly,
+            AlertType::PriceSpike => s.price_spike,
+            AlertType::RapidFire => s.rapid_fire,
+            AlertType::WashTrading => s.wash_trading,
+            AlertType::SuspiciousMatch => s.suspicious_match,
+            AlertType::FrontRunning => s.front_running,
+            AlertType::MlAnomaly => s.ml_anomaly,
+            AlertType::Diversification => s.diversification,
+            AlertType::BookImbalance => s.book_imbalance,
+            AlertType::MomentumIgnition => s.momentum_ignition,
+            AlertType::InsiderTrading => s.insider_trading,
+            AlertType::PriceImprovement => s.price_improvement,
+            AlertType::CounterpartyConcentration => s.counterparty_concentration,
+            AlertType::PriceVelocity => s.price_velocity,
+            AlertType::EngineStall | AlertType::FeedIntegrity => SeverityBounds::default(),
+        }
+    }
+
+    /// Record `alert` and hand it back for delivery, or set it aside as a
+    /// shadow alert if its rule is in shadow mode. `[severity]` bounds are
+    /// applied first, so every consumer sees the deployment's severity.
+    /// Rules build their alerts with ID 0; the ID is issued here, so a
+    /// candidate the model or a cooldown suppresses doesn't use one up.
+    fn deliver(&mut self, mut alert: Alert) -> Option<Alert> {
+        let bounds = self.severity_bounds(&alert.alert_type);
+        if let Some(min) = bounds.min.map(AlertSeverity::from).filter(|min| alert.severity < *min) {
+            alert.severity = min;
+        }
+        if let Some(max) = bounds.max.map(AlertSeverity::from).filter(|max| alert.severity > *max) {
+            alert.severity = max;
+        }
+        if self.is_shadow(&alert.alert_type) {
+            self.next_id += 1;
+            alert.id = self.next_id;
+            *self.shadow_counts.entry(alert.alert_type.label().to_string()).or_insert(0) += 1;
+            self.retention.push(&mut self.shadow_alerts, alert);
+            return None;
+        }
+        if self.cooling_down(&alert) {
+            self.cooldown_suppressed += 1;
+            return None;
+        }
+        self.next_id += 1;
+        alert.id = self.next_id;
+        self.push_alert(alert.clone());
+        Some(alert)
+    }
+
     fn push_alert(&mut self, alert: Alert) {
         *self.counts.entry(alert.alert_type.label().to_string()).or_insert(0) += 1;
-        if self.alerts.len() >= 200 {
-            self.alerts.pop_front();
+        *self.severity_counts.entry(format!("{:?}", alert.severity)).or_insert(0) += 1;
+        if let Some(account_id) = &alert.account_id {
+            self.risk.observe_alert(account_id);
+        }
+        if let Some(symbol) = &alert.symbol {
+            *self.symbol_counts.entry((alert.alert_type.label(), symbol.clone())).or_insert(0) += 1;
+        }
+        self.retention.push(&mut self.alerts, alert);
+    }
+
+    /// Run a rule-fired candidate through the optional scoring model, then record it.
+    /// `metric` is the rule's primary measurement (ratio, imbalance, ...).
+    fn emit(&mut self, mut alert: Alert, metric: f64, account_id: Option<&str>) -> Option<Alert> {
+        alert.metric = Some(metric);
+        if let Some(scorer) = self.scorer.as_mut() {
+            let history = account_id.map(|a| self.ml.features(a)).unwrap_or_default();
+            let features = scoring::candidate_features(&alert, metric, &history);
+            match scorer.score(&features) {
+                Ok(score) => {
+                    let score = score as f64;
+                    alert.score = Some(score);
+                    if score < self.model_min_score {
+                        self.model_suppressed += 1;
+                        return None;
+                    }
+                }
+                Err(e) => eprintln!("  [WARN] alert scoring failed: {e}"),
+            }
         }
-        self.alerts.push_back(alert);
+        self.deliver(alert)
     }
 
+    /// Volume anomaly against the symbol's recent average, reported as a
+    /// percentage of its estimated ADV. In `adv` mode the ADV percentage,
+    /// not the ratio, decides whether it fires, once `volume_history_len`
+    /// windows have been seen. With a calendar, a window that ended outside
+    /// a session is neither judged nor added to the baseline.
     pub fn evaluate_volume(&mut self, row: &VolumeBaseline, gen_instant: Instant) -> Option<Alert> {
-        let history = self.vol_baselines.entry(row.symbol.clone()).or_insert_with(VecDeque::new);
+        // The window closes up to one window length before it is polled
+        let window_ms = (VOL_WINDOW_SECS * 1000.0) as i64;
+        if self.calendar.as_ref().is_some_and(|c| !c.is_open(self.event_ts) && !c.is_open(self.event_ts - window_ms)) {
+            return None;
+        }
+        let history = self.vol_baselines.entry(row.symbol.clone()).or_default();
         let avg = if history.is_empty() {
             row.total_volume
         } else {
             history.iter().sum::<i64>() / history.len() as i64
         };
 
-        if history.len() >= 20 {
+        if history.len() >= self.volume_history_len {
             history.pop_front();
         }
         history.push_back(row.total_volume);
 
-        if avg > 0 {
+        let estimate = self.adv.entry(row.symbol.clone()).or_default();
+        let adv = estimate.adv(self.volume.trading_day_secs as f64);
+        let adv_warm = estimate.samples >= self.volume_history_len as u64;
+        estimate.observe(row.total_volume);
+        let pct_of_adv = if adv > 0.0 { row.total_volume as f64 / adv * 100.0 } else { 0.0 };
+
+        if avg > 0 && row.total_notional >= self.min_notional.volume_anomaly {
             let ratio = row.total_volume as f64 / avg as f64;
-            if ratio > self.volume_ratio_threshold {
-                let severity = if ratio > 10.0 {
+            // ADV mode bands at 2.5x and 5x the threshold, as the ratio bands are at the default
+            let (metric, threshold, high, critical) = match self.volume.mode {
+                VolumeMode::Ratio => (ratio, self.volume_ratio_threshold, 5.0, 10.0),
+                VolumeMode::Adv if adv_warm => {
+                    let t = self.volume.adv_pct_threshold;
+                    (pct_of_adv, t, t * 2.5, t * 5.0)
+                }
+                VolumeMode::Adv => return None,
+            };
+            if metric > threshold {
+                let severity = if metric > critical {
                     AlertSeverity::Critical
-                } else if ratio > 5.0 {
+                } else if metric > high {
                     AlertSeverity::High
                 } else {
                     AlertSeverity::Medium
                 };
-                self.next_id += 1;
                 let alert = Alert {
-                    id: self.next_id,
+                    id: 0,
                     alert_type: AlertType::VolumeAnomaly,
                     severity,
-                    description: format!("{} vol={} avg={} ({:.1}x)", row.symbol, row.total_volume, avg, ratio),
+                    symbol: Some(row.symbol.clone()),
+                    account_id: None,
+                    detail: AlertDetail::Volume { volume: row.total_volume, avg, ratio, pct_of_adv, notional: row.total_notional },
                     latency_us: gen_instant.elapsed().as_micros() as u64,
                     timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                    score: None,
+                    gap_ms: None,
+                    metric: None,
+                    uid: None,
                 };
-                self.push_alert(alert.clone());
-                return Some(alert);
+                return self.emit(alert, metric, None);
             }
         }
         None
@@ -136,7 +1218,7 @@ impl AlertEngine {
     pub fn evaluate_ohlc(&mut self, row: &OhlcVolatility, gen_instant: Instant) -> Option<Alert> {
         if row.open > 0.0 {
             let range_pct = row.price_range / row.open;
-            if range_pct > self.price_range_pct_threshold {
+            if range_pct > self.price_range_pct_threshold && row.notional >= self.min_notional.price_spike {
                 let severity = if range_pct > 0.05 {
                     AlertSeverity::Critical
                 } else if range_pct > 0.01 {
@@ -144,24 +1226,30 @@ impl AlertEngine {
                 } else {
                     AlertSeverity::Medium
                 };
-                self.next_id += 1;
                 let alert = Alert {
-                    id: self.next_id,
+                    id: 0,
                     alert_type: AlertType::PriceSpike,
                     severity,
-                    description: format!("{} range={:.2}% O={:.2} H={:.2} L={:.2}", row.symbol, range_pct * 100.0, row.open, row.high, row.low),
+                    symbol: Some(row.symbol.clone()),
+                    account_id: None,
+                    detail: AlertDetail::PriceRange { range_pct, open: row.open, high: row.high, low: row.low, notional: row.notional },
                     latency_us: gen_instant.elapsed().as_micros() as u64,
                     timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                    score: None,
+                    gap_ms: None,
+                    metric: None,
+                    uid: None,
                 };
-                self.push_alert(alert.clone());
-                return Some(alert);
+                return self.emit(alert, range_pct, None);
             }
         }
         None
     }
 
     pub fn evaluate_rapid_fire(&mut self, row: &RapidFireBurst, gen_instant: Instant) -> Option<Alert> {
-        if row.burst_trades >= self.rapid_fire_threshold {
+        self.ml.observe_burst(&row.account_id, row.burst_trades, row.burst_volume);
+        self.risk.observe_burst(row);
+        if row.burst_trades >= self.rapid_fire_threshold && row.burst_notional >= self.min_notional.rapid_fire {
             let severity = if row.burst_trades > 50 {
                 AlertSeverity::Critical
             } else if row.burst_trades > 20 {
@@ -169,24 +1257,37 @@ impl AlertEngine {
             } else {
                 AlertSeverity::Medium
             };
-            self.next_id += 1;
             let alert = Alert {
-                id: self.next_id,
+                id: 0,
                 alert_type: AlertType::RapidFire,
                 severity,
-                description: format!("{} {} trades vol={}", row.account_id, row.burst_trades, row.burst_volume),
+                symbol: None,
+                account_id: Some(row.account_id.clone()),
+                detail: AlertDetail::RapidFire { trades: row.burst_trades, volume: row.burst_volume, notional: row.burst_notional },
                 latency_us: gen_instant.elapsed().as_micros() as u64,
                 timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                score: None,
+                gap_ms: None,
+                metric: None,
+                uid: None,
             };
-            self.push_alert(alert.clone());
-            return Some(alert);
+            return self.emit(alert, row.burst_trades as f64, Some(&row.account_id));
         }
         None
     }
 
     pub fn evaluate_wash(&mut self, row: &WashScore, gen_instant: Instant) -> Option<Alert> {
         let total = row.buy_volume + row.sell_volume;
-        if total > 0 && row.buy_count >= 2 && row.sell_count >= 2 {
+        let balance = if total > 0 && row.buy_count > 0 && row.sell_count > 0 {
+            1.0 - (row.buy_volume - row.sell_volume).unsigned_abs() as f64 / total as f64
+        } else {
+            0.0
+        };
+        self.ml.observe_wash(&row.account_id, balance);
+        self.risk.observe_wash(row);
+
+        let notional = row.buy_notional + row.sell_notional;
+        if total > 0 && row.buy_count >= 2 && row.sell_count >= 2 && notional >= self.min_notional.wash_trading {
             let imbalance = (row.buy_volume - row.sell_volume).unsigned_abs() as f64 / total as f64;
             if imbalance < self.wash_imbalance_threshold {
                 let severity = if imbalance < 0.02 {
@@ -196,66 +1297,850 @@ impl AlertEngine {
                 } else {
                     AlertSeverity::Medium
                 };
-                self.next_id += 1;
                 let alert = Alert {
-                    id: self.next_id,
+                    id: 0,
                     alert_type: AlertType::WashTrading,
                     severity,
-                    description: format!("{} {} imb={:.3} buy={} sell={}", row.account_id, row.symbol, imbalance, row.buy_volume, row.sell_volume),
+                    symbol: Some(row.symbol.clone()),
+                    account_id: Some(row.account_id.clone()),
+                    detail: AlertDetail::Wash { imbalance, buy_volume: row.buy_volume, sell_volume: row.sell_volume, notional },
                     latency_us: gen_instant.elapsed().as_micros() as u64,
                     timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                    score: None,
+                    gap_ms: None,
+                    metric: None,
+                    uid: None,
                 };
-                self.push_alert(alert.clone());
-                return Some(alert);
+                return self.emit(alert, imbalance, Some(&row.account_id));
             }
         }
         None
     }
 
     pub fn evaluate_match(&mut self, row: &SuspiciousMatch, gen_instant: Instant) -> Option<Alert> {
-        if row.price_diff.abs() < self.match_price_diff_threshold {
+        self.ml.observe_match(&row.account_id);
+        if row.price_diff.abs() < self.match_price_diff_threshold && row.notional >= self.min_notional.suspicious_match {
             let severity = if row.price_diff.abs() < 0.001 {
                 AlertSeverity::High
             } else {
                 AlertSeverity::Medium
             };
-            self.next_id += 1;
             let alert = Alert {
-                id: self.next_id,
+                id: 0,
                 alert_type: AlertType::SuspiciousMatch,
                 severity,
-                description: format!("{} {} order={} diff={:.4}", row.account_id, row.symbol, row.order_id, row.price_diff),
+                symbol: Some(row.symbol.clone()),
+                account_id: Some(row.account_id.clone()),
+                detail: AlertDetail::Match { order_id: row.order_id.clone(), price_diff: row.price_diff, notional: row.notional },
                 latency_us: gen_instant.elapsed().as_micros() as u64,
                 timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                score: None,
+                gap_ms: None,
+                metric: None,
+                uid: None,
             };
-            self.push_alert(alert.clone());
-            return Some(alert);
+            return self.emit(alert, row.price_diff.abs(), Some(&row.account_id));
         }
         None
     }
 
+    /// Front-running: a different account trading right behind an order at
+    /// nearly its price. Severity rises the tighter the trade follows the
+    /// order and the larger the order is against the symbol's ADV estimate.
     pub fn evaluate_asof(&mut self, row: &AsofMatch, gen_instant: Instant) -> Option<Alert> {
-        // Front-running: different accounts, trade executed near order price
-        if row.trade_account != row.order_account && row.price_spread.abs() < self.front_run_spread_threshold {
-            let severity = if row.price_spread.abs() < 0.01 {
-                AlertSeverity::Critical
-            } else if row.price_spread.abs() < 0.1 {
-                AlertSeverity::High
+        if row.trade_account != row.order_account
+            && row.price_spread.abs() < self.front_run_spread_threshold
+            && row.notional >= self.min_notional.front_running
+        {
+            let gap_ms = row.trade_ts - row.order_ts;
+            let adv = self.adv.get(&row.symbol).map_or(0.0, |e| e.adv(self.volume.trading_day_secs as f64));
+            let pct_of_adv = if adv > 0.0 { row.order_quantity as f64 / adv * 100.0 } else { 0.0 };
+            let timing = match gap_ms.abs() {
+                g if g <= FRONT_RUN_TIGHT_MS => 2,
+                g if g <= FRONT_RUN_NEAR_MS => 1,
+                _ => 0,
+            };
+            let size = if pct_of_adv >= FRONT_RUN_LARGE_ADV_PCT {
+                2
+            } else if pct_of_adv >= FRONT_RUN_SIZEABLE_ADV_PCT {
+                1
             } else {
-                AlertSeverity::Medium
+                0
+            };
+            let severity = match timing + size {
+                3.. => AlertSeverity::Critical,
+                2 => AlertSeverity::High,
+                _ => AlertSeverity::Medium,
             };
-            self.next_id += 1;
             let alert = Alert {
-                id: self.next_id,
+                id: 0,
                 alert_type: AlertType::FrontRunning,
                 severity,
-                description: format!("{}->{} {} spread={:.4}", row.trade_account, row.order_account, row.symbol, row.price_spread),
+                symbol: Some(row.symbol.clone()),
+                account_id: Some(row.trade_account.clone()),
+                detail: AlertDetail::FrontRun {
+                    order_account: row.order_account.clone(),
+                    gap_ms,
+                    order_quantity: row.order_quantity,
+                    pct_of_adv,
+                    spread: row.price_spread,
+                    notional: row.notional,
+                },
                 latency_us: gen_instant.elapsed().as_micros() as u64,
                 timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                score: None,
+                gap_ms: Some(gap_ms),
+                metric: None,
+                uid: None,
             };
-            self.push_alert(alert.clone());
-            return Some(alert);
+            return self.emit(alert, row.price_spread.abs(), Some(&row.trade_account));
         }
         None
     }
+
+    /// Diversification: an account suddenly touching many more symbols in a
+    /// window than it usually does. Windows re-emit every tick while open, so
+    /// a window enters the baseline once the next one starts, and alerts at
+    /// most once.
+    pub fn evaluate_activity(&mut self, row: &AccountActivity, gen_instant: Instant) -> Option<Alert> {
+        let history = self.activity.entry(row.account_id.clone()).or_default();
+        match history.current_window {
+            Some(w) if w == row.window_start => {
+                history.current_symbols = history.current_symbols.max(row.distinct_symbols);
+            }
+            Some(w) if w > row.window_start => return None,
+            previous => {
+                if previous.is_some() {
+                    if history.windows.len() >= self.volume_history_len {
+                        history.windows.pop_front();
+                    }
+                    history.windows.push_back(history.current_symbols);
+                }
+                history.current_window = Some(row.window_start);
+                history.current_symbols = row.distinct_symbols;
+                history.alerted = false;
+            }
+        }
+
+        if history.alerted
+            || history.windows.len() < self.diversification_min_history
+            || row.distinct_symbols < self.diversification_min_symbols
+            || row.total_notional < self.min_notional.diversification
+        {
+            return None;
+        }
+        let avg = history.windows.iter().sum::<i64>() as f64 / history.windows.len() as f64;
+        let ratio = row.distinct_symbols as f64 / avg.max(1.0);
+        if ratio < self.diversification_ratio_threshold {
+            return None;
+        }
+        history.alerted = true;
+
+        let severity = if ratio > 8.0 {
+            AlertSeverity::Critical
+        } else if ratio > 5.0 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::Diversification,
+            severity,
+            symbol: None,
+            account_id: Some(row.account_id.clone()),
+            detail: AlertDetail::Diversification { symbols: row.distinct_symbols, avg, ratio, notional: row.total_notional },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, ratio, Some(&row.account_id))
+    }
+
+    /// BookImbalance: depth suddenly piling up on one side of a symbol's book,
+    /// then the price moving toward that side within `BOOK_CONFIRM_MS` — the
+    /// footprint of spoofing. An imbalanced window arms the rule only if the
+    /// symbol's recent windows were balanced; a later window's mid confirms
+    /// it. Windows re-emit every tick while open, so a window enters the
+    /// baseline once the next one starts.
+    ///
+    /// Hysteresis keeps a long spoof from flapping: the imbalance has to hold
+    /// for `book_enter_windows` windows in a row (the earlier ones leave the
+    /// baseline), and after an alert the symbol stays quiet until
+    /// `book_exit_windows` finished windows in a row are under the threshold.
+    pub fn evaluate_book(&mut self, row: &BookImbalance, gen_instant: Instant) -> Option<Alert> {
+        let total = row.bid_depth + row.ask_depth;
+        if total <= 0 {
+            return None;
+        }
+        let imbalance = (row.bid_depth - row.ask_depth) as f64 / total as f64;
+        let history = self.books.entry(row.symbol.clone()).or_default();
+        match history.current_window {
+            Some(w) if w == row.window_start => history.current_imbalance = imbalance,
+            Some(w) if w > row.window_start => return None,
+            previous => {
+                if previous.is_some() {
+                    if history.windows.len() >= self.volume_history_len {
+                        history.windows.pop_front();
+                    }
+                    history.windows.push_back(history.current_imbalance);
+                    if history.latched {
+                        let clean = history.current_imbalance.abs() < self.book_imbalance_threshold;
+                        history.clean_windows = if clean { history.clean_windows + 1 } else { 0 };
+                        history.latched = history.clean_windows < self.book_exit_windows;
+                    }
+                }
+                history.current_window = Some(row.window_start);
+                history.current_imbalance = imbalance;
+            }
+        }
+
+        if history.pending.as_ref().is_some_and(|p| row.window_start - p.window_start > BOOK_CONFIRM_MS) {
+            history.pending = None;
+        }
+        let Some(pending) = history.pending.as_ref() else {
+            // The windows just before this one must already lean the same
+            // way; the ones before them are the baseline it is sudden against
+            let lead = self.book_enter_windows.saturating_sub(1);
+            let split = history.windows.len().saturating_sub(lead);
+            let sustained = history.windows.len() >= lead
+                && history.windows.range(split..).all(|w| w.abs() >= self.book_imbalance_threshold && w.signum() == imbalance.signum());
+            let baseline = history.windows.range(..split).map(|w| w.abs()).sum::<f64>() / split.max(1) as f64;
+            if imbalance.abs() >= self.book_imbalance_threshold
+                && !history.latched
+                && sustained
+                && split >= BOOK_MIN_HISTORY
+                && baseline < self.book_imbalance_threshold / 2.0
+            {
+                history.pending = Some(PendingImbalance { window_start: row.window_start, imbalance, mid_price: row.mid_price });
+            }
+            return None;
+        };
+        let price_move = (row.mid_price - pending.mid_price) / pending.mid_price;
+        if row.window_start == pending.window_start || price_move * pending.imbalance.signum() < self.book_move_pct_threshold {
+            return None;
+        }
+        let pending = history.pending.take()?;
+        history.latched = true;
+        history.clean_windows = 0;
+
+        let strength = pending.imbalance.abs();
+        let severity = if strength > 0.85 {
+            AlertSeverity::Critical
+        } else if strength > 0.7 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::BookImbalance,
+            severity,
+            symbol: Some(row.symbol.clone()),
+            account_id: None,
+            detail: AlertDetail::Book { bid_heavy: pending.imbalance > 0.0, imbalance: pending.imbalance, price_move },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, strength, None)
+    }
+
+    /// MomentumIgnition: an account fires a fast, one-sided burst that walks
+    /// a symbol's price, then trades the other side once the price has moved.
+    /// A `momentum_burst` session of at least `momentum_min_trades` trades
+    /// within `MOMENTUM_BURST_MAX_MS` arms the rule; a later session of the
+    /// same account and symbol confirms it if it is one-sided the other way
+    /// and its VWAP sits `momentum_displacement_pct` past the burst's first
+    /// price. Sessions re-emit every tick while open, so the burst's own
+    /// session only ever re-arms.
+    pub fn evaluate_momentum(&mut self, row: &MomentumBurst, gen_instant: Instant) -> Option<Alert> {
+        let trades = row.buy_count + row.sell_count;
+        if trades == 0 || row.low <= 0.0 {
+            return None;
+        }
+        let buy_share = row.buy_count as f64 / trades as f64;
+        let key = (row.account_id.clone(), row.symbol.clone());
+        if self.ignitions.get(&key).is_some_and(|p| row.start_ts - p.end_ts > MOMENTUM_UNWIND_MS) {
+            self.ignitions.remove(&key);
+        }
+
+        match self.ignitions.get(&key) {
+            Some(p) if row.start_ts < p.start_ts || p.fired => return None,
+            Some(p) if row.start_ts > p.end_ts => {
+                let unwind_share = if p.buy { 1.0 - buy_share } else { buy_share };
+                let direction = if p.buy { 1.0 } else { -1.0 };
+                let displacement = (row.vwap - p.start_price) / p.start_price * direction;
+                if unwind_share < self.momentum_side_share || displacement < self.momentum_displacement_pct {
+                    return None;
+                }
+            }
+            _ => {
+                let walk = (row.high - row.low) / row.low;
+                if trades >= self.momentum_min_trades
+                    && buy_share.max(1.0 - buy_share) >= self.momentum_side_share
+                    && row.end_ts - row.start_ts <= MOMENTUM_BURST_MAX_MS
+                    && walk >= self.momentum_displacement_pct / 2.0
+                    && row.notional >= self.min_notional.momentum_ignition
+                {
+                    let buy = buy_share > 0.5;
+                    let start_price = if buy { row.low } else { row.high };
+                    self.ignitions.insert(
+                        key,
+                        PendingIgnition {
+                            start_ts: row.start_ts,
+                            end_ts: row.end_ts,
+                            buy,
+                            trades,
+                            start_price,
+                            notional: row.notional,
+                            fired: false,
+                        },
+                    );
+                }
+                return None;
+            }
+        }
+        let pending = self.ignitions.get_mut(&key)?;
+        pending.fired = true;
+        let (buy, burst_trades, start_price, notional) = (pending.buy, pending.trades, pending.start_price, pending.notional);
+
+        let displacement = (row.vwap - start_price).abs() / start_price;
+        let severity = if displacement > 0.02 {
+            AlertSeverity::Critical
+        } else if displacement > 0.01 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::MomentumIgnition,
+            severity,
+            symbol: Some(row.symbol.clone()),
+            account_id: Some(row.account_id.clone()),
+            detail: AlertDetail::Momentum { buy, trades: burst_trades, start_price, vwap: row.vwap, notional },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, displacement, Some(&row.account_id))
+    }
+
+    /// InsiderTrading: an account building a position in a symbol shortly
+    /// before high-impact news about it, on the side the news favours. Each
+    /// `pre_news` row adds one trade to its account's tally for that event;
+    /// the rule fires once per account and event when the volume on the
+    /// news' side reaches `insider_min_volume` and makes up at least
+    /// `insider_side_share` of the account's volume ahead of it.
+    pub fn evaluate_news(&mut self, row: &PreNewsTrade, gen_instant: Instant) -> Option<Alert> {
+        if row.impact.abs() < self.insider_min_impact {
+            return None;
+        }
+        if row.news_ts > self.latest_news_ts {
+            self.latest_news_ts = row.news_ts;
+            let horizon = row.news_ts - 3 * NEWS_LOOKBACK_MS;
+            self.pre_positions.retain(|(_, _, news_ts), _| *news_ts >= horizon);
+        }
+        let key = (row.account_id.clone(), row.symbol.clone(), row.news_ts);
+        let position = self.pre_positions.entry(key).or_default();
+        if position.fired {
+            return None;
+        }
+        let aligned = (row.side == "buy") == (row.impact > 0.0);
+        if aligned {
+            position.aligned_volume += row.volume;
+        } else {
+            position.opposite_volume += row.volume;
+        }
+        position.trades += 1;
+        position.notional += row.notional;
+        position.first_trade_ts = Some(position.first_trade_ts.map_or(row.trade_ts, |ts| ts.min(row.trade_ts)));
+
+        let total = position.aligned_volume + position.opposite_volume;
+        let share = position.aligned_volume as f64 / total.max(1) as f64;
+        if position.aligned_volume < self.insider_min_volume
+            || share < self.insider_side_share
+            || position.notional < self.min_notional.insider_trading
+        {
+            return None;
+        }
+        position.fired = true;
+        let (volume, trades, notional) = (position.aligned_volume, position.trades, position.notional);
+        let lead_ms = row.news_ts - position.first_trade_ts.unwrap_or(row.trade_ts);
+
+        let ratio = volume as f64 / self.insider_min_volume.max(1) as f64;
+        let severity = if ratio > 3.0 {
+            AlertSeverity::Critical
+        } else if ratio > 1.5 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::InsiderTrading,
+            severity,
+            symbol: Some(row.symbol.clone()),
+            account_id: Some(row.account_id.clone()),
+            detail: AlertDetail::Insider {
+                bought: row.impact > 0.0,
+                volume,
+                trades,
+                lead_ms,
+                headline: row.headline.clone(),
+                impact: row.impact,
+                notional,
+            },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, ratio, Some(&row.account_id))
+    }
+
+    /// PriceImprovement: one account repeatedly filled through the quote by
+    /// the same counterparty, a way to hand it money that never shows up as
+    /// a price outlier. A `price_improvement` row at least
+    /// `price_improvement_pct` through the quote counts as an improved fill
+    /// for its account pair; the rule fires when a pair has
+    /// `price_improvement_fills` of them within `PRICE_IMPROVEMENT_WINDOW_MS`,
+    /// then starts counting again. A trade met by more than one snapshot
+    /// counts once.
+    pub fn evaluate_improvement(&mut self, row: &PriceImprovement, gen_instant: Instant) -> Option<Alert> {
+        if row.improvement < self.price_improvement_pct || row.counterparty.is_empty() || row.counterparty == row.account_id {
+            return None;
+        }
+        let key = (row.account_id.clone(), row.counterparty.clone());
+        let fills = self.improved_fills.entry(key).or_default();
+        if fills.iter().any(|f| f.order_ref == row.order_ref) {
+            return None;
+        }
+        while fills.front().is_some_and(|f| f.trade_ts < row.trade_ts - PRICE_IMPROVEMENT_WINDOW_MS) {
+            fills.pop_front();
+        }
+        fills.push_back(ImprovedFill {
+            order_ref: row.order_ref.clone(),
+            trade_ts: row.trade_ts,
+            improvement: row.improvement,
+            notional: row.notional,
+        });
+        let notional: f64 = fills.iter().map(|f| f.notional).sum();
+        if fills.len() < self.price_improvement_fills.max(1) || notional < self.min_notional.price_improvement {
+            return None;
+        }
+        let count = fills.len();
+        let mean = fills.iter().map(|f| f.improvement).sum::<f64>() / count as f64;
+        let span_ms = row.trade_ts - fills.front().map_or(row.trade_ts, |f| f.trade_ts);
+        fills.clear();
+
+        let ratio = mean / self.price_improvement_pct;
+        let severity = if ratio > 3.0 {
+            AlertSeverity::Critical
+        } else if ratio > 1.5 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::PriceImprovement,
+            severity,
+            symbol: Some(row.symbol.clone()),
+            account_id: Some(row.account_id.clone()),
+            detail: AlertDetail::Improvement {
+                fills: count,
+                mean,
+                counterparty: row.counterparty.clone(),
+                span_ms,
+                buy: row.side == "buy",
+                trade_price: row.trade_price,
+                quote_price: row.quote_price,
+                notional,
+            },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, mean, Some(&row.account_id))
+    }
+
+    /// CounterpartyConcentration: an account doing most of a window's volume
+    /// against a single counterparty, a precursor of collusion. Windows
+    /// re-emit while open, so an account's window is judged once its next
+    /// one starts: it fires when the window had `concentration_min_trades`
+    /// trades with known counterparties and the largest counterparty took
+    /// `concentration_share` of their volume.
+    pub fn evaluate_concentration(&mut self, row: &CounterpartyVolume, gen_instant: Instant) -> Option<Alert> {
+        if row.counterparty == row.account_id {
+            return None;
+        }
+        let window = self.counterparty_windows.entry(row.account_id.clone()).or_default();
+        let finished = match window.window_start {
+            Some(w) if w == row.window_start => None,
+            Some(w) if w > row.window_start => return None,
+            previous => {
+                window.window_start = Some(row.window_start);
+                previous.map(|w| (w, std::mem::take(&mut window.by_counterparty)))
+            }
+        };
+        window.by_counterparty.insert(row.counterparty.clone(), row.clone());
+        let (window_start, by_counterparty) = finished?;
+
+        let trades: i64 = by_counterparty.values().map(|v| v.trade_count).sum();
+        let volume: i64 = by_counterparty.values().map(|v| v.volume).sum();
+        let notional: f64 = by_counterparty.values().map(|v| v.notional).sum();
+        let top = by_counterparty.values().max_by(|a, b| a.volume.cmp(&b.volume).then_with(|| b.counterparty.cmp(&a.counterparty)))?;
+        if trades < self.concentration_min_trades || volume <= 0 || notional < self.min_notional.counterparty_concentration {
+            return None;
+        }
+        let share = top.volume as f64 / volume as f64;
+        if share < self.concentration_share {
+            return None;
+        }
+
+        let severity = if share > 0.95 {
+            AlertSeverity::Critical
+        } else if share > 0.9 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::CounterpartyConcentration,
+            severity,
+            symbol: None,
+            account_id: Some(row.account_id.clone()),
+            detail: AlertDetail::Concentration {
+                share,
+                volume,
+                counterparty: top.counterparty.clone(),
+                counterparty_trades: top.trade_count,
+                trades,
+                counterparties: by_counterparty.len(),
+                window_start,
+                notional,
+            },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, share, Some(&row.account_id))
+    }
+
+    /// PriceVelocity for a move the tick detector saw, graded like PriceSpike
+    /// on the size of the move.
+    pub fn evaluate_tick(&mut self, m: &TickMove, gen_instant: Instant) -> Option<Alert> {
+        let severity = if m.pct.abs() > 0.05 {
+            AlertSeverity::Critical
+        } else if m.pct.abs() > 0.01 {
+            AlertSeverity::High
+        } else {
+            AlertSeverity::Medium
+        };
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::PriceVelocity,
+            severity,
+            symbol: Some(m.symbol.clone()),
+            account_id: None,
+            detail: AlertDetail::Velocity { pct: m.pct, span_ms: m.ts - m.from_ts, from_price: m.from_price, to_price: m.to_price },
+            latency_us: gen_instant.elapsed().as_micros() as u64,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.emit(alert, m.pct.abs(), None)
+    }
+
+    /// Record an `EngineStall` alert: `what` ran `took`, over `budget`. It
+    /// bypasses scoring and shadow mode, as no rule raised it.
+    pub fn engine_stall(&mut self, what: &str, took: Duration, budget: Duration) -> Alert {
+        self.next_id += 1;
+        let alert = Alert {
+            id: self.next_id,
+            alert_type: AlertType::EngineStall,
+            severity: AlertSeverity::Critical,
+            symbol: None,
+            account_id: None,
+            detail: AlertDetail::Text(format!("{what} took {}ms (budget {}ms)", took.as_millis(), budget.as_millis())),
+            latency_us: 0,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.push_alert(alert.clone());
+        alert
+    }
+
+    /// Record a `FeedIntegrity` alert for a batch that broke its feed's
+    /// sequence: High if records went missing, Medium for repeats alone.
+    /// Like `EngineStall`, it bypasses scoring and shadow mode.
+    pub fn feed_integrity(&mut self, brk: &SequenceBreak) -> Alert {
+        let mut parts = Vec::new();
+        if let Some(after) = brk.first_gap_after {
+            parts.push(format!("{} missing in {} gap(s), first after seq {after}", brk.missing, brk.gaps));
+        }
+        if brk.duplicates > 0 {
+            parts.push(format!("{} duplicate(s)", brk.duplicates));
+        }
+        self.next_id += 1;
+        let alert = Alert {
+            id: self.next_id,
+            alert_type: AlertType::FeedIntegrity,
+            severity: if brk.missing > 0 { AlertSeverity::High } else { AlertSeverity::Medium },
+            symbol: None,
+            account_id: None,
+            detail: AlertDetail::Text(format!("{} feed: {}", brk.feed, parts.join(", "))),
+            latency_us: 0,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        self.push_alert(alert.clone());
+        alert
+    }
+
+    /// Score every account whose features changed this cycle with the online
+    /// anomaly model. Call once per cycle after all streams have been polled.
+    pub fn evaluate_ml(&mut self, gen_instant: Instant) -> Vec<Alert> {
+        let mut fired = Vec::new();
+        let scores = self.ml.score_dirty();
+        for s in &scores {
+            if s.score > self.ml_score_threshold {
+                let severity = if s.score > 0.98 {
+                    AlertSeverity::Critical
+                } else if s.score > 0.95 {
+                    AlertSeverity::High
+                } else {
+                    AlertSeverity::Medium
+                };
+                let alert = Alert {
+                    id: 0,
+                    alert_type: AlertType::MlAnomaly,
+                    severity,
+                    symbol: None,
+                    account_id: Some(s.account_id.clone()),
+                    detail: AlertDetail::Ml {
+                        score: s.score,
+                        burst_trades: s.features.burst_trades,
+                        burst_volume: s.features.burst_volume,
+                        wash_balance: s.features.wash_balance,
+                        match_count: s.features.match_count,
+                    },
+                    latency_us: gen_instant.elapsed().as_micros() as u64,
+                    timestamp_ms: chrono::Utc::now().timestamp_millis(),
+                    score: Some(s.score),
+                    gap_ms: None,
+                    metric: Some(s.score),
+                    uid: None,
+                };
+                if let Some(alert) = self.deliver(alert) {
+                    fired.push(alert);
+                }
+            }
+        }
+        self.risk.finish(&scores);
+        self.last_ml_scores = scores;
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::CANDIDATE_FEATURES;
+
+    /// Scores candidates in turn from a fixed list.
+    struct ListScorer(VecDeque<f32>);
+
+    impl AlertScorer for ListScorer {
+        fn score(&mut self, _: &[f32; CANDIDATE_FEATURES]) -> Result<f32, Box<dyn std::error::Error>> {
+            Ok(self.0.pop_front().unwrap_or(1.0))
+        }
+    }
+
+    #[test]
+    fn suppressed_candidates_leave_no_id_gaps() {
+        let mut engine = AlertEngine::new();
+        engine.scorer = Some(Box::new(ListScorer(VecDeque::from([0.9, 0.1, 0.2, 0.9]))));
+        engine.model_min_score = 0.5;
+        let burst = |account: &str| RapidFireBurst { account_id: account.into(), burst_trades: 100, burst_volume: 1_000, low: 100.0, high: 100.0, burst_notional: 1e9 };
+
+        let ids: Vec<u64> = ["A1", "A2", "A3", "A4"].iter().filter_map(|a| engine.evaluate_rapid_fire(&burst(a), Instant::now())).map(|a| a.id).collect();
+        assert_eq!(ids, [1, 2], "A2 and A3 were scored under model_min_score");
+        assert_eq!((engine.last_id(), engine.model_suppressed(), engine.cooldown_suppressed()), (2, 2, 0));
+    }
+
+    #[test]
+    fn model_score_is_attached_and_gates_at_min_score() {
+        let mut engine = AlertEngine::new();
+        engine.scorer = Some(Box::new(ListScorer(VecDeque::from([0.5, 0.49]))));
+        engine.model_min_score = 0.5;
+        let burst = |account: &str| RapidFireBurst { account_id: account.into(), burst_trades: 100, burst_volume: 1_000, low: 100.0, high: 100.0, burst_notional: 1e9 };
+
+        let alert = engine.evaluate_rapid_fire(&burst("A1"), Instant::now()).expect("a score at min_score is kept");
+        assert_eq!((alert.score, alert.metric), (Some(0.5), Some(100.0)));
+        assert!(engine.evaluate_rapid_fire(&burst("A2"), Instant::now()).is_none());
+        assert_eq!(engine.total_alerts(), 1);
+    }
+
+    #[test]
+    fn severity_counts_cover_delivered_alerts_only() {
+        let mut engine = AlertEngine::new();
+        let burst = |account: &str, burst_trades: i64| RapidFireBurst { account_id: account.into(), burst_trades, burst_volume: 1_000, low: 100.0, high: 100.0, burst_notional: 1e9 };
+        for (account, trades) in [("A1", 60), ("A2", 30), ("A3", 51)] {
+            assert!(engine.evaluate_rapid_fire(&burst(account, trades), Instant::now()).is_some());
+        }
+        engine.shadow.rapid_fire = true;
+        assert!(engine.evaluate_rapid_fire(&burst("A4", 60), Instant::now()).is_none());
+
+        let expected = HashMap::from([("Critical".to_string(), 2), ("High".to_string(), 1)]);
+        assert_eq!(engine.severity_counts(), &expected);
+        assert_eq!(engine.shadow_alert_counts().get("RapidFire"), Some(&1));
+    }
+
+    fn book(window_start: i64, bid_depth: i64, ask_depth: i64, mid_price: f64) -> BookImbalance {
+        BookImbalance { symbol: "GOOGL".into(), window_start, bid_depth, ask_depth, mid_price }
+    }
+
+    /// Feed `rows` of (bid depth, ask depth, mid) one window apart from
+    /// `start`, returning the windows that fired.
+    fn feed_book(engine: &mut AlertEngine, start: i64, rows: &[(i64, i64, f64)]) -> Vec<i64> {
+        let mut fired = Vec::new();
+        for (i, &(bid, ask, mid)) in rows.iter().enumerate() {
+            let window_start = start + i as i64 * 1_000;
+            if engine.evaluate_book(&book(window_start, bid, ask, mid), Instant::now()).is_some() {
+                fired.push(window_start);
+            }
+        }
+        fired
+    }
+
+    const BALANCED: (i64, i64, f64) = (5_000, 5_200, 2_800.0);
+
+    #[test]
+    fn book_imbalance_arms_only_after_enter_windows_in_a_row() {
+        let mut engine = AlertEngine::new();
+        engine.book_enter_windows = 3;
+        engine.book_exit_windows = 0;
+        // A two-window blip never arms, so the move after it doesn't fire
+        let blip = [BALANCED, BALANCED, BALANCED, BALANCED, (40_000, 5_000, 2_800.0), (40_000, 5_000, 2_800.0), (5_000, 5_000, 2_830.0)];
+        assert!(feed_book(&mut engine, 0, &blip).is_empty());
+
+        let mut engine = AlertEngine::new();
+        engine.book_enter_windows = 3;
+        let sustained = [BALANCED, BALANCED, BALANCED, BALANCED, (40_000, 5_000, 2_800.0), (40_000, 5_000, 2_800.0), (40_000, 5_000, 2_800.0), (40_000, 5_000, 2_810.0)];
+        assert_eq!(feed_book(&mut engine, 0, &sustained), vec![7_000]);
+
+        // Windows leaning opposite ways are not a streak
+        let mut engine = AlertEngine::new();
+        engine.book_enter_windows = 2;
+        let flipped = [BALANCED, BALANCED, BALANCED, BALANCED, (40_000, 5_000, 2_800.0), (5_000, 40_000, 2_800.0), (5_000, 5_000, 2_780.0)];
+        assert!(feed_book(&mut engine, 0, &flipped).is_empty());
+    }
+
+    #[test]
+    fn book_imbalance_stays_quiet_until_exit_windows_are_clean() {
+        let mut engine = AlertEngine::new();
+        engine.book_exit_windows = 2;
+        let heavy = |mid: f64| (40_000, 5_000, mid);
+        let mut rows = vec![BALANCED; 10];
+        rows.extend([heavy(2_800.0), heavy(2_810.0)]);
+        // One clean window, then the imbalance is back and the price follows: still latched
+        rows.extend([(5_000, 5_200, 2_810.0), heavy(2_810.0), (5_000, 5_200, 2_830.0)]);
+        // Two clean windows release it
+        rows.extend([(5_000, 5_200, 2_830.0), heavy(2_830.0), heavy(2_850.0)]);
+        assert_eq!(feed_book(&mut engine, 0, &rows), vec![11_000, 17_000]);
+    }
+
+    fn volume(total_volume: i64) -> VolumeBaseline {
+        VolumeBaseline { symbol: "AAPL".into(), window_start: 100_000, total_volume, trade_count: 10, avg_price: 100.0, total_notional: total_volume as f64 * 100.0 }
+    }
+
+    /// An engine that has seen `volume_history_len` windows of 1,000 on AAPL:
+    /// an ADV of 1,000 × 23,400s / 10s = 2.34M.
+    fn warmed_up(mode: VolumeMode) -> AlertEngine {
+        let mut engine = AlertEngine::new();
+        engine.volume_history_len = 5;
+        engine.volume.mode = mode;
+        for _ in 0..5 {
+            assert!(engine.evaluate_volume(&volume(1_000), Instant::now()).is_none());
+        }
+        engine
+    }
+
+    #[test]
+    fn adv_estimate_is_a_mean_then_an_ewma() {
+        let mut estimate = AdvEstimate::default();
+        assert_eq!(estimate.adv(23_400.0), 0.0);
+        estimate.observe(100);
+        estimate.observe(300);
+        assert_eq!(estimate.window_avg, 200.0);
+        assert_eq!(estimate.adv(23_400.0), 468_000.0);
+
+        // Past 1 / ADV_EWMA_ALPHA windows, a new one moves it by alpha only
+        let mut estimate = AdvEstimate { window_avg: 1_000.0, samples: 1_000 };
+        estimate.observe(2_000);
+        assert!((estimate.window_avg - 1_010.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn volume_alerts_carry_the_pct_of_adv() {
+        let mut engine = warmed_up(VolumeMode::Ratio);
+        // 5x the average is the top of the Medium band
+        let alert = engine.evaluate_volume(&volume(5_000), Instant::now()).expect("5x the average");
+        assert!(matches!(alert.severity, AlertSeverity::Medium));
+        let AlertDetail::Volume { ratio, pct_of_adv, avg, .. } = alert.detail else { panic!("volume detail") };
+        assert_eq!((ratio, avg), (5.0, 1_000));
+        assert!((pct_of_adv - 5_000.0 / 2_340_000.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adv_mode_waits_for_history_then_thresholds_on_pct_of_adv() {
+        let mut cold = AlertEngine::new();
+        cold.volume_history_len = 5;
+        cold.volume.mode = VolumeMode::Adv;
+        cold.evaluate_volume(&volume(1_000), Instant::now());
+        assert!(cold.evaluate_volume(&volume(100_000), Instant::now()).is_none(), "no ADV estimate yet");
+
+        // The default threshold is 0.25% of 2.34M, 5,850 in a window; the ratio no longer matters
+        assert!(warmed_up(VolumeMode::Adv).evaluate_volume(&volume(5_800), Instant::now()).is_none());
+        let alert = warmed_up(VolumeMode::Adv).evaluate_volume(&volume(5_900), Instant::now()).expect("over 0.25% of ADV");
+        assert!(matches!(alert.severity, AlertSeverity::Medium));
+        let alert = warmed_up(VolumeMode::Adv).evaluate_volume(&volume(30_000), Instant::now()).expect("over 1.25% of ADV");
+        assert!(matches!(alert.severity, AlertSeverity::Critical));
+    }
+
+    #[test]
+    fn book_enter_windows_must_be_positive() {
+        let mut config = Config::default();
+        config.thresholds.book_enter_windows = 0;
+        assert!(AlertEngine::from_config(&config).err().unwrap().to_string().contains("book_enter_windows"));
+    }
 }
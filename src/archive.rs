@@ -0,0 +1,498 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::Alert;
+use crate::config::ArchiveConfig;
+use crate::locale;
+
+const HOUR_MS: i64 = 3_600_000;
+
+/// One line of the archive file: a raw alert, or an hour of alerts that
+/// compaction has folded into counts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ArchiveEntry {
+    Alert(Alert),
+    Hourly(HourlySummary),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlySummary {
+    pub hour_start_ms: i64,
+    pub by_type: BTreeMap<String, u64>,
+    pub by_severity: BTreeMap<String, u64>,
+}
+
+impl HourlySummary {
+    fn new(hour_start_ms: i64) -> Self {
+        Self { hour_start_ms, ..Default::default() }
+    }
+
+    fn add(&mut self, alert: &Alert) {
+        *self.by_type.entry(alert.alert_type.label().to_string()).or_default() += 1;
+        *self.by_severity.entry(format!("{:?}", alert.severity)).or_default() += 1;
+    }
+
+    fn merge(&mut self, other: &HourlySummary) {
+        for (k, v) in &other.by_type {
+            *self.by_type.entry(k.clone()).or_default() += v;
+        }
+        for (k, v) in &other.by_severity {
+            *self.by_severity.entry(k.clone()).or_default() += v;
+        }
+    }
+}
+
+/// Archive size after the last append or compaction.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveStats {
+    pub alert_rows: u64,
+    pub summary_rows: u64,
+    pub bytes: u64,
+    pub compactions: u64,
+    /// Raw alerts removed by the last compaction, whether dropped or summarized.
+    pub last_compacted: u64,
+}
+
+struct Inner {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    stats: ArchiveStats,
+}
+
+/// Append-only JSON-lines store of every alert a run raises, kept bounded by
+/// a background compaction task that applies the `[archive]` retention policy.
+#[derive(Clone)]
+pub struct AlertArchive {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl AlertArchive {
+    /// Open the archive `config` names, if any, and start its compaction task.
+    pub fn open(config: &ArchiveConfig) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Some(path) = &config.path else {
+            return Ok(None);
+        };
+        let mut stats = ArchiveStats::default();
+        for entry in read_entries(path)? {
+            match entry {
+                ArchiveEntry::Alert(_) => stats.alert_rows += 1,
+                ArchiveEntry::Hourly(_) => stats.summary_rows += 1,
+            }
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("failed to open alert archive {}: {e}", path.display()))?;
+        stats.bytes = file.metadata()?.len();
+
+        let archive = Self { inner: Arc::new(Mutex::new(Inner { path: path.clone(), writer: BufWriter::new(file), stats })) };
+
+        if config.compact_interval_secs > 0 {
+            let task = archive.clone();
+            let config = config.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(config.compact_interval_secs));
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    let archive = task.clone();
+                    let config = config.clone();
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    let result = tokio::task::spawn_blocking(move || archive.compact(&config, now_ms).map_err(|e| e.to_string())).await;
+                    if let Ok(Err(e)) = result {
+                        eprintln!("Alert archive compaction failed: {e}");
+                    }
+                }
+            });
+        }
+        Ok(Some(archive))
+    }
+
+    pub fn append(&self, alerts: &[Alert]) -> Result<(), Box<dyn std::error::Error>> {
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        let mut inner = self.inner.lock().unwrap();
+        for alert in alerts {
+            let line = serde_json::to_string(&ArchiveEntry::Alert(alert.clone()))?;
+            inner.writer.write_all(line.as_bytes())?;
+            inner.writer.write_all(b"\n")?;
+            inner.stats.bytes += line.len() as u64 + 1;
+        }
+        inner.writer.flush()?;
+        inner.stats.alert_rows += alerts.len() as u64;
+        Ok(())
+    }
+
+    /// The newest `limit` raw alerts naming `account_id`, oldest first.
+    /// Alerts already folded into hourly summaries are gone.
+    pub fn account_alerts(&self, account_id: &str, limit: usize) -> Result<Vec<Alert>, Box<dyn std::error::Error>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writer.flush()?;
+        let mut alerts: Vec<Alert> = read_entries(&inner.path)?
+            .into_iter()
+            .filter_map(|entry| match entry {
+                ArchiveEntry::Alert(a) if a.account_id.as_deref() == Some(account_id) => Some(a),
+                _ => None,
+            })
+            .collect();
+        alerts.drain(..alerts.len().saturating_sub(limit));
+        Ok(alerts)
+    }
+
+    /// The raw alert with `id`. Ids restart each run, so the newest match wins.
+    pub fn find_alert(&self, id: u64) -> Result<Option<Alert>, Box<dyn std::error::Error>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writer.flush()?;
+        Ok(read_entries(&inner.path)?.into_iter().rev().find_map(|entry| match entry {
+            ArchiveEntry::Alert(a) if a.id == id => Some(a),
+            _ => None,
+        }))
+    }
+
+    /// Every raw alert still in the archive, oldest first.
+    pub fn alerts(&self) -> Result<Vec<Alert>, Box<dyn std::error::Error>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writer.flush()?;
+        Ok(read_entries(&inner.path)?
+            .into_iter()
+            .filter_map(|entry| match entry {
+                ArchiveEntry::Alert(a) => Some(a),
+                ArchiveEntry::Hourly(_) => None,
+            })
+            .collect())
+    }
+
+    pub fn stats(&self) -> ArchiveStats {
+        self.inner.lock().unwrap().stats.clone()
+    }
+
+    /// Apply the retention policy as of `now_ms`: drop entries older than
+    /// `max_age_secs`, fold alerts older than `summarize_after_secs` into
+    /// hourly summaries, then drop the oldest raw alerts beyond `max_rows`.
+    /// Rewrites the file; appends wait on the lock meanwhile.
+    pub fn compact(&self, config: &ArchiveConfig, now_ms: i64) -> Result<ArchiveStats, Box<dyn std::error::Error>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.writer.flush()?;
+
+        let max_age_cutoff = (config.max_age_secs > 0).then(|| now_ms - config.max_age_secs as i64 * 1000);
+        let summarize_cutoff = (config.summarize_after_secs > 0).then(|| now_ms - config.summarize_after_secs as i64 * 1000);
+        let expired = |ts: i64| max_age_cutoff.is_some_and(|cutoff| ts < cutoff);
+
+        let mut hours: BTreeMap<i64, HourlySummary> = BTreeMap::new();
+        let mut alerts = Vec::new();
+        let mut before = 0u64;
+        for entry in read_entries(&inner.path)? {
+            match entry {
+                ArchiveEntry::Hourly(h) if !expired(h.hour_start_ms + HOUR_MS) => {
+                    hours.entry(h.hour_start_ms).or_insert_with(|| HourlySummary::new(h.hour_start_ms)).merge(&h);
+                }
+                ArchiveEntry::Hourly(_) => {}
+                ArchiveEntry::Alert(a) => {
+                    before += 1;
+                    if expired(a.timestamp_ms) {
+                        continue;
+                    }
+                    if summarize_cutoff.is_some_and(|cutoff| a.timestamp_ms < cutoff) {
+                        let hour = a.timestamp_ms - a.timestamp_ms.rem_euclid(HOUR_MS);
+                        hours.entry(hour).or_insert_with(|| HourlySummary::new(hour)).add(&a);
+                    } else {
+                        alerts.push(a);
+                    }
+                }
+            }
+        }
+        if config.max_rows > 0 && alerts.len() > config.max_rows {
+            alerts.drain(..alerts.len() - config.max_rows);
+        }
+
+        let summary_rows = hours.len() as u64;
+        let tmp = inner.path.with_extension("compacting");
+        {
+            let mut out = BufWriter::new(File::create(&tmp)?);
+            for h in hours.into_values() {
+                serde_json::to_writer(&mut out, &ArchiveEntry::Hourly(h))?;
+                out.write_all(b"\n")?;
+            }
+            for a in &alerts {
+                serde_json::to_writer(&mut out, &ArchiveEntry::Alert(a.clone()))?;
+                out.write_all(b"\n")?;
+            }
+            out.flush()?;
+        }
+        std::fs::rename(&tmp, &inner.path)?;
+
+        let file = OpenOptions::new().append(true).open(&inner.path)?;
+        inner.stats = ArchiveStats {
+            alert_rows: alerts.len() as u64,
+            summary_rows,
+            bytes: file.metadata()?.len(),
+            compactions: inner.stats.compactions + 1,
+            last_compacted: before - alerts.len() as u64,
+        };
+        inner.writer = BufWriter::new(file);
+        Ok(inner.stats.clone())
+    }
+}
+
+/// Every entry in the archive at `path`; a missing file is empty.
+pub fn read_entries(path: &Path) -> Result<Vec<ArchiveEntry>, Box<dyn std::error::Error>> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read alert archive {}: {e}", path.display()).into()),
+    };
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| format!("invalid alert archive line {} in {}: {e}", i + 1, path.display()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// What an archive holds, for `analyze`: alert counts by type and severity
+/// across raw alerts and hourly summaries, and the busiest symbols, accounts
+/// and hour. Summarized alerts carry no symbol or account.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveAnalysis {
+    pub alerts: u64,
+    /// Of `alerts`, those only counted in hourly summaries.
+    pub summarized: u64,
+    pub first_ms: Option<i64>,
+    pub last_ms: Option<i64>,
+    pub by_type: BTreeMap<String, u64>,
+    pub by_severity: BTreeMap<String, u64>,
+    /// Most alerted symbols first, at most `top`.
+    pub top_symbols: Vec<(String, u64)>,
+    pub top_accounts: Vec<(String, u64)>,
+    /// Start of the hour with the most alerts, and its count.
+    pub peak_hour: Option<(i64, u64)>,
+}
+
+/// Summarize archive `entries`, listing the `top` symbols and accounts.
+pub fn analyze(entries: &[ArchiveEntry], top: usize) -> ArchiveAnalysis {
+    let mut analysis = ArchiveAnalysis::default();
+    let mut symbols: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut accounts: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut hours: BTreeMap<i64, u64> = BTreeMap::new();
+    let mut span = |start: i64, end: i64| {
+        analysis.first_ms = Some(analysis.first_ms.map_or(start, |t| t.min(start)));
+        analysis.last_ms = Some(analysis.last_ms.map_or(end, |t| t.max(end)));
+    };
+    let mut by_type: BTreeMap<String, u64> = BTreeMap::new();
+    let mut by_severity: BTreeMap<String, u64> = BTreeMap::new();
+    let (mut alerts, mut summarized) = (0, 0);
+    for entry in entries {
+        match entry {
+            ArchiveEntry::Alert(alert) => {
+                alerts += 1;
+                span(alert.timestamp_ms, alert.timestamp_ms);
+                *by_type.entry(alert.alert_type.label().to_string()).or_default() += 1;
+                *by_severity.entry(format!("{:?}", alert.severity)).or_default() += 1;
+                *hours.entry(alert.timestamp_ms - alert.timestamp_ms.rem_euclid(HOUR_MS)).or_default() += 1;
+                if let Some(symbol) = alert.symbol.as_deref() {
+                    *symbols.entry(symbol).or_default() += 1;
+                }
+                if let Some(account) = alert.account_id.as_deref() {
+                    *accounts.entry(account).or_default() += 1;
+                }
+            }
+            ArchiveEntry::Hourly(h) => {
+                let n: u64 = h.by_type.values().sum();
+                alerts += n;
+                summarized += n;
+                span(h.hour_start_ms, h.hour_start_ms + HOUR_MS - 1);
+                for (k, v) in &h.by_type {
+                    *by_type.entry(k.clone()).or_default() += v;
+                }
+                for (k, v) in &h.by_severity {
+                    *by_severity.entry(k.clone()).or_default() += v;
+                }
+                *hours.entry(h.hour_start_ms).or_default() += n;
+            }
+        }
+    }
+    let busiest = |counts: BTreeMap<&str, u64>| {
+        let mut counts: Vec<(String, u64)> = counts.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(top);
+        counts
+    };
+    ArchiveAnalysis {
+        alerts,
+        summarized,
+        by_type,
+        by_severity,
+        top_symbols: busiest(symbols),
+        top_accounts: busiest(accounts),
+        // Earliest of equally busy hours
+        peak_hour: hours.into_iter().fold(None, |peak, (hour, n)| match peak {
+            Some((_, best)) if best >= n => peak,
+            _ => Some((hour, n)),
+        }),
+        ..analysis
+    }
+}
+
+impl ArchiveAnalysis {
+    pub fn print(&self) {
+        let (Some(first), Some(last)) = (self.first_ms, self.last_ms) else {
+            println!("  No alerts archived");
+            return;
+        };
+        println!(
+            "  {} alerts ({} in hourly summaries), {} to {}",
+            locale::count(self.alerts),
+            locale::count(self.summarized),
+            locale::timestamp(first),
+            locale::timestamp(last)
+        );
+        if let Some((hour, n)) = self.peak_hour {
+            println!("  Busiest hour:       {} ({} alerts)", locale::timestamp(hour), locale::count(n));
+        }
+        let table = |title: &str, rows: &mut dyn Iterator<Item = (&str, u64)>| {
+            println!("  {title}");
+            for (name, n) in rows {
+                println!("    {:<28} {:>10}", name, locale::count(n));
+            }
+        };
+        table("By type", &mut self.by_type.iter().map(|(k, v)| (k.as_str(), *v)));
+        table("By severity", &mut self.by_severity.iter().map(|(k, v)| (k.as_str(), *v)));
+        table("Top symbols", &mut self.top_symbols.iter().map(|(k, v)| (k.as_str(), *v)));
+        table("Top accounts", &mut self.top_accounts.iter().map(|(k, v)| (k.as_str(), *v)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity, AlertType};
+
+    const DAY_MS: i64 = 86_400_000;
+    const NOW_MS: i64 = 100 * DAY_MS;
+
+    fn alert(id: u64, account: &str, timestamp_ms: i64) -> Alert {
+        Alert {
+            id,
+            alert_type: AlertType::WashTrading,
+            severity: AlertSeverity::High,
+            symbol: Some("AAPL".into()),
+            account_id: Some(account.into()),
+            detail: AlertDetail::Text(String::new()),
+            latency_us: 0,
+            timestamp_ms,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    /// A policy without the background task: 10 days kept, raw for 1 day.
+    fn config(path: &Path, max_rows: usize) -> ArchiveConfig {
+        ArchiveConfig { path: Some(path.to_path_buf()), max_age_secs: 10 * 86_400, max_rows, summarize_after_secs: 86_400, compact_interval_secs: 0 }
+    }
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("archive-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn summaries(path: &Path) -> Vec<HourlySummary> {
+        read_entries(path)
+            .unwrap()
+            .into_iter()
+            .filter_map(|e| match e {
+                ArchiveEntry::Hourly(h) => Some(h),
+                ArchiveEntry::Alert(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_path_means_no_archive() {
+        assert!(AlertArchive::open(&ArchiveConfig::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn lookups_see_appended_alerts_and_reopening_counts_them() {
+        let path = temp_path();
+        let archive = AlertArchive::open(&config(&path, 0)).unwrap().unwrap();
+        archive.append(&[alert(1, "ACC-1", NOW_MS), alert(2, "ACC-2", NOW_MS), alert(3, "ACC-1", NOW_MS)]).unwrap();
+        // Ids restart each run; the newest alert with the id wins
+        archive.append(&[alert(1, "ACC-3", NOW_MS)]).unwrap();
+
+        assert_eq!(archive.find_alert(1).unwrap().unwrap().account_id.as_deref(), Some("ACC-3"));
+        assert!(archive.find_alert(9).unwrap().is_none());
+        let ids: Vec<u64> = archive.account_alerts("ACC-1", 1).unwrap().iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![3]);
+        drop(archive);
+
+        let reopened = AlertArchive::open(&config(&path, 0)).unwrap().unwrap();
+        assert_eq!(reopened.stats().alert_rows, 4);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn compaction_expires_summarizes_and_caps_raw_alerts() {
+        let path = temp_path();
+        let archive = AlertArchive::open(&config(&path, 2)).unwrap().unwrap();
+        let two_days_ago = NOW_MS - 2 * DAY_MS;
+        archive
+            .append(&[
+                alert(1, "ACC-1", NOW_MS - 11 * DAY_MS),
+                alert(2, "ACC-1", two_days_ago),
+                alert(3, "ACC-1", two_days_ago + 60_000),
+                alert(4, "ACC-1", NOW_MS - 3_000),
+                alert(5, "ACC-1", NOW_MS - 2_000),
+                alert(6, "ACC-1", NOW_MS - 1_000),
+            ])
+            .unwrap();
+
+        let stats = archive.compact(&config(&path, 2), NOW_MS).unwrap();
+        assert_eq!((stats.alert_rows, stats.summary_rows, stats.compactions, stats.last_compacted), (2, 1, 1, 4));
+        assert_eq!(archive.alerts().unwrap().iter().map(|a| a.id).collect::<Vec<_>>(), vec![5, 6]);
+        let hours = summaries(&path);
+        assert_eq!(hours[0].hour_start_ms, two_days_ago - two_days_ago.rem_euclid(HOUR_MS));
+        assert_eq!(hours[0].by_type.get("WashTrading"), Some(&2));
+
+        // Later, the summarized hour ages out too and the raw alerts fold into an hour
+        let stats = archive.compact(&config(&path, 2), NOW_MS + 9 * DAY_MS).unwrap();
+        assert_eq!((stats.alert_rows, stats.summary_rows, stats.last_compacted), (0, 1, 2));
+        assert_eq!(summaries(&path)[0].by_severity.get("High"), Some(&2));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn analysis_counts_raw_and_summarized_alerts() {
+        let hour = NOW_MS - NOW_MS.rem_euclid(HOUR_MS);
+        let mut summary = HourlySummary::new(hour - 2 * HOUR_MS);
+        summary.add(&alert(1, "ACC-1", hour - 2 * HOUR_MS));
+        let entries = vec![
+            ArchiveEntry::Hourly(summary),
+            ArchiveEntry::Alert(alert(2, "ACC-1", hour + 10)),
+            ArchiveEntry::Alert(alert(3, "ACC-2", hour + 20)),
+            ArchiveEntry::Alert(alert(4, "ACC-2", hour + 30)),
+        ];
+
+        let analysis = analyze(&entries, 1);
+        assert_eq!((analysis.alerts, analysis.summarized), (4, 1));
+        assert_eq!((analysis.first_ms, analysis.last_ms), (Some(hour - 2 * HOUR_MS), Some(hour + 30)));
+        assert_eq!(analysis.by_type.get("WashTrading"), Some(&4));
+        assert_eq!(analysis.top_accounts, vec![("ACC-2".to_string(), 2)], "summarized alerts have no account");
+        assert_eq!(analysis.top_symbols, vec![("AAPL".to_string(), 3)]);
+        assert_eq!(analysis.peak_hour, Some((hour, 3)));
+        assert!(analyze(&[], 10).first_ms.is_none());
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertEngine};
+use crate::config::{Config, Scope};
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEntry {
+    /// The rule configuration an engine started with, or changed to.
+    ConfigChange {
+        ts_ms: i64,
+        /// OS user that made the change.
+        actor: String,
+        /// Where the change came from, e.g. `startup`.
+        source: String,
+        config: Box<Config>,
+    },
+    /// An alert raised by a rule, with that rule's thresholds at the time.
+    Firing {
+        ts_ms: i64,
+        alert_id: u64,
+        /// The alert's globally unique ID, under `[ids] format`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        alert_uid: Option<String>,
+        alert_type: String,
+        severity: String,
+        symbol: Option<String>,
+        account_id: Option<String>,
+        /// Raised by a rule in shadow mode, so never delivered.
+        shadow: bool,
+        thresholds: BTreeMap<String, f64>,
+    },
+    /// A web API call that needs more than read access, whether or not it
+    /// was let through.
+    ApiCall {
+        ts_ms: i64,
+        /// Name of the token presented, `slack:@user` for a Slack command,
+        /// or none when `[auth]` is off or no valid token was given.
+        token: Option<String>,
+        /// Scope the call needs.
+        scope: Scope,
+        method: String,
+        path: String,
+        status: u16,
+    },
+}
+
+/// Append-only JSON-lines audit trail (`[audit]`): every rule configuration
+/// in effect and every firing. Nothing ever rewrites or compacts it.
+#[derive(Clone)]
+pub struct AuditLog {
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl AuditLog {
+    /// Open the log `config.audit` names, if any, and record `config` as the
+    /// starting configuration.
+    pub fn open(config: &Config) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Some(path) = &config.audit.path else {
+            return Ok(None);
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("failed to open audit log {}: {e}", path.display()))?;
+        let log = Self { writer: Arc::new(Mutex::new(BufWriter::new(file))) };
+        log.record_config(config, "startup")?;
+        Ok(Some(log))
+    }
+
+    pub fn record_config(&self, config: &Config, source: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let actor = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| "unknown".to_string());
+        self.append(&[AuditEntry::ConfigChange {
+            ts_ms: chrono::Utc::now().timestamp_millis(),
+            actor,
+            source: source.to_string(),
+            config: Box::new(config.redacted()),
+        }])
+    }
+
+    /// Record one cycle's delivered and shadow alerts against the thresholds
+    /// `engine` is running with.
+    pub fn record_firings(&self, engine: &AlertEngine, alerts: &[Alert], shadow: &[Alert]) -> Result<(), Box<dyn std::error::Error>> {
+        let entries: Vec<AuditEntry> = alerts
+            .iter()
+            .map(|a| (a, false))
+            .chain(shadow.iter().map(|a| (a, true)))
+            .map(|(a, shadow)| AuditEntry::Firing {
+                ts_ms: a.timestamp_ms,
+                alert_id: a.id,
+                alert_uid: a.uid.clone(),
+                alert_type: a.alert_type.label().to_string(),
+                severity: format!("{:?}", a.severity),
+                symbol: a.symbol.clone(),
+                account_id: a.account_id.clone(),
+                shadow,
+                thresholds: engine.active_thresholds(&a.alert_type).into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            })
+            .collect();
+        self.append(&entries)
+    }
+
+    pub fn record_api_call(&self, token: Option<&str>, scope: Scope, method: &str, path: &str, status: u16) -> Result<(), Box<dyn std::error::Error>> {
+        self.append(&[AuditEntry::ApiCall {
+            ts_ms: chrono::Utc::now().timestamp_millis(),
+            token: token.map(str::to_string),
+            scope,
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+        }])
+    }
+
+    fn append(&self, entries: &[AuditEntry]) -> Result<(), Box<dyn std::error::Error>> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.writer.lock().unwrap();
+        for entry in entries {
+            serde_json::to_writer(&mut *writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Every entry in the audit log at `path`.
+pub fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, Box<dyn std::error::Error>> {
+    let file = File::open(path).map_err(|e| format!("failed to read audit log {}: {e}", path.display()))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| format!("invalid audit log line {} in {}: {e}", i + 1, path.display()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+const CSV_HEADER: &str = "ts_ms,event,actor,source,alert_id,alert_type,severity,symbol,account_id,shadow,settings";
+
+/// Write `entries` as CSV for compliance review, one row per entry. A
+/// config change's settings column is its rule sections as JSON; a firing's
+/// is its thresholds as `key=value` pairs. An API call's actor is its token,
+/// its source the method and path, and its settings the scope and status.
+pub fn export_csv(entries: &[AuditEntry], out: &mut impl Write) -> Result<(), Box<dyn std::error::Error>> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    writeln!(out, "{CSV_HEADER}")?;
+    for entry in entries {
+        match entry {
+            AuditEntry::ConfigChange { ts_ms, actor, source, config } => {
+                let settings = serde_json::json!({
+                    "thresholds": config.thresholds,
+                    "notional": config.notional,
+                    "volume": config.volume,
+                    "shadow": config.shadow,
+                    "scoring": config.scoring,
+                });
+                writeln!(out, "{ts_ms},config_change,{},{},,,,,,,{}", quote(actor), quote(source), quote(&settings.to_string()))?;
+            }
+            AuditEntry::Firing { ts_ms, alert_id, alert_type, severity, symbol, account_id, shadow, thresholds, .. } => {
+                let settings = thresholds.iter().map(|(k, v)| format!("{k}={v}")).collect::<Vec<_>>().join(";");
+                writeln!(
+                    out,
+                    "{ts_ms},firing,,,{alert_id},{alert_type},{severity},{},{},{shadow},{}",
+                    symbol.as_deref().unwrap_or(""),
+                    account_id.as_deref().unwrap_or(""),
+                    quote(&settings),
+                )?;
+            }
+            AuditEntry::ApiCall { ts_ms, token, scope, method, path, status } => {
+                let settings = format!("scope={};status={status}", scope.label());
+                writeln!(out, "{ts_ms},api_call,{},{},,,,,,,{}", quote(token.as_deref().unwrap_or("")), quote(&format!("{method} {path}")), quote(&settings))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity, AlertType};
+
+    fn temp_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    fn alert(id: u64, account: &str) -> Alert {
+        Alert {
+            id,
+            alert_type: AlertType::RapidFire,
+            severity: AlertSeverity::High,
+            symbol: None,
+            account_id: Some(account.into()),
+            detail: AlertDetail::Text("burst".into()),
+            latency_us: 0,
+            timestamp_ms: 1_000 + id as i64,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    #[test]
+    fn open_without_a_path_is_off() {
+        assert!(AuditLog::open(&Config::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn entries_are_appended_across_reopens() {
+        let path = temp_path();
+        let mut config = Config::default();
+        config.audit.path = Some(path.clone());
+        let engine = AlertEngine::from_config(&config).unwrap();
+
+        let log = AuditLog::open(&config).unwrap().unwrap();
+        log.record_firings(&engine, &[alert(1, "A1")], &[alert(2, "A2")]).unwrap();
+        log.record_firings(&engine, &[], &[]).unwrap();
+        log.record_api_call(Some("ops"), Scope::Admin, "POST", "/api/reset", 403).unwrap();
+        drop(log);
+        AuditLog::open(&config).unwrap().unwrap();
+
+        let entries = read_entries(&path).unwrap();
+        let events: Vec<&str> = entries
+            .iter()
+            .map(|e| match e {
+                AuditEntry::ConfigChange { .. } => "config_change",
+                AuditEntry::Firing { shadow: false, .. } => "firing",
+                AuditEntry::Firing { shadow: true, .. } => "shadow_firing",
+                AuditEntry::ApiCall { .. } => "api_call",
+            })
+            .collect();
+        assert_eq!(events, ["config_change", "firing", "shadow_firing", "api_call", "config_change"]);
+        let AuditEntry::Firing { alert_id, account_id, thresholds, .. } = &entries[1] else { unreachable!() };
+        assert_eq!((*alert_id, account_id.as_deref()), (1, Some("A1")));
+        assert_eq!(thresholds.get("rapid_fire_trades"), Some(&5.0));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn read_entries_skips_blank_lines_and_names_a_bad_one() {
+        let path = temp_path();
+        let call = serde_json::to_string(&AuditEntry::ApiCall { ts_ms: 1, token: None, scope: Scope::Operate, method: "POST".into(), path: "/api/ack".into(), status: 200 }).unwrap();
+        std::fs::write(&path, format!("{call}\n\n{call}\n")).unwrap();
+        assert_eq!(read_entries(&path).unwrap().len(), 2);
+
+        std::fs::write(&path, format!("{call}\nnot json\n")).unwrap();
+        let err = read_entries(&path).unwrap_err().to_string();
+        assert!(err.contains("invalid audit log line 2"), "{err}");
+        std::fs::remove_file(&path).ok();
+        assert!(read_entries(&path).is_err());
+    }
+
+    #[test]
+    fn csv_export_quotes_settings_and_leaves_unused_columns_empty() {
+        let mut empty = Vec::new();
+        export_csv(&[], &mut empty).unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap(), format!("{CSV_HEADER}\n"));
+
+        let entries = [
+            AuditEntry::Firing {
+                ts_ms: 5,
+                alert_id: 7,
+                alert_uid: None,
+                alert_type: "RapidFire".into(),
+                severity: "High".into(),
+                symbol: None,
+                account_id: Some("A1".into()),
+                shadow: true,
+                thresholds: BTreeMap::from([("min_notional".to_string(), 0.0), ("rapid_fire_trades".to_string(), 5.0)]),
+            },
+            AuditEntry::ApiCall { ts_ms: 6, token: Some("say \"hi\"".into()), scope: Scope::Admin, method: "POST".into(), path: "/api/reset".into(), status: 401 },
+        ];
+        let mut out = Vec::new();
+        export_csv(&entries, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], "5,firing,,,7,RapidFire,High,,A1,true,\"min_notional=0;rapid_fire_trades=5\"");
+        assert_eq!(rows[2], "6,api_call,\"say \"\"hi\"\"\",\"POST /api/reset\",,,,,,,\"scope=admin;status=401\"");
+        assert!(rows.iter().all(|r| r.matches(',').count() >= CSV_HEADER.matches(',').count()));
+    }
+}
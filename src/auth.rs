@@ -0,0 +1,67 @@
+//! Scoped bearer tokens for the web API (`[auth]`). Each route needs a
+//! scope: reads need `read`, alert reviews `operate`, and anything that
+//! changes the run `admin`. The web server checks the token on every API
+//! call and audits the ones above `read`.
+
+use crate::config::{ApiToken, AuthConfig, Scope};
+
+/// Path the Slack integration posts to; it is signed with the Slack app's
+/// secret rather than a bearer token.
+const SLACK_PATH: &str = "/api/slack/command";
+
+pub struct Authorizer {
+    tokens: Vec<ApiToken>,
+}
+
+impl Authorizer {
+    /// Check every token has a name, a token of at least 16 characters, and
+    /// that neither is shared with another.
+    pub fn from_config(config: &AuthConfig) -> Result<Self, String> {
+        for (i, t) in config.tokens.iter().enumerate() {
+            if t.name.trim().is_empty() {
+                return Err(format!("[auth] token {} needs a name", i + 1));
+            }
+            if t.token.len() < 16 {
+                return Err(format!("[auth] token '{}' is shorter than 16 characters", t.name));
+            }
+            if config.tokens[..i].iter().any(|o| o.name == t.name) {
+                return Err(format!("[auth] token name '{}' is used twice", t.name));
+            }
+            if config.tokens[..i].iter().any(|o| o.token == t.token) {
+                return Err(format!("[auth] tokens '{}' and another share a secret", t.name));
+            }
+        }
+        Ok(Self { tokens: config.tokens.clone() })
+    }
+
+    /// Whether calls need a token at all.
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// The token `presented` matches, compared in constant time against each.
+    pub fn authenticate(&self, presented: &str) -> Option<&ApiToken> {
+        self.tokens.iter().find(|t| constant_time_eq(t.token.as_bytes(), presented.as_bytes()))
+    }
+}
+
+/// The scope `method` on `path` needs, or none for static files, CORS
+/// preflights and the Slack endpoint.
+pub fn required_scope(method: &str, path: &str) -> Option<Scope> {
+    if method == "OPTIONS" || path == SLACK_PATH || !(path == "/ws" || path.starts_with("/api/")) {
+        return None;
+    }
+    if method == "GET" || method == "HEAD" {
+        return Some(Scope::Read);
+    }
+    let review = path.strip_prefix("/api/alerts/").and_then(|rest| rest.strip_suffix("/review"));
+    if review.is_some_and(|id| id.parse::<u64>().is_ok()) {
+        return Some(Scope::Operate);
+    }
+    Some(Scope::Admin)
+}
+
+/// Compare every byte, so the time taken doesn't reveal a matching prefix.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::alerts::{Alert, AlertType};
+use crate::archive::AlertArchive;
+use crate::ml::MlScore;
+use crate::search::AlertIndex;
+use crate::types::{Order, Trade};
+
+/// Entries of each kind kept per account.
+pub const BLOTTER_DEPTH: usize = 100;
+
+/// Event time of raw trades and orders kept on the tape for evidence replay.
+pub const TAPE_RETENTION_MS: i64 = 60_000;
+
+/// Alerts of any kind kept for lookup by id when no archive is set.
+const ALERT_LOOKUP_DEPTH: usize = 1_000;
+
+/// One anomaly-model score for an account, at the cycle it was computed.
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskScore {
+    pub ts: i64,
+    pub score: f64,
+}
+
+/// An account's recent raw activity, oldest first in every list.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountBlotter {
+    pub account_id: String,
+    pub trades: VecDeque<Trade>,
+    pub orders: VecDeque<Order>,
+    pub alerts: VecDeque<Alert>,
+    pub risk_scores: VecDeque<RiskScore>,
+}
+
+/// The raw trades and orders in an alert's window that match its symbol
+/// and account, oldest first.
+#[derive(Debug, Clone, Serialize)]
+pub struct Evidence {
+    pub alert: Alert,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub trades: Vec<Trade>,
+    pub orders: Vec<Order>,
+}
+
+fn push_bounded<T>(list: &mut VecDeque<T>, item: T, depth: usize) {
+    if list.len() >= depth {
+        list.pop_front();
+    }
+    list.push_back(item);
+}
+
+#[derive(Default)]
+struct Inner {
+    accounts: HashMap<String, AccountBlotter>,
+    tape_trades: VecDeque<Trade>,
+    tape_orders: VecDeque<Order>,
+    alerts: VecDeque<Alert>,
+    archive: Option<AlertArchive>,
+    index: AlertIndex,
+}
+
+impl Inner {
+    fn entry(&mut self, account_id: &str) -> &mut AccountBlotter {
+        self.accounts
+            .entry(account_id.to_string())
+            .or_insert_with(|| AccountBlotter { account_id: account_id.to_string(), ..Default::default() })
+    }
+}
+
+/// Per-account trade blotter and recent tape behind the web dashboard's
+/// `/api/accounts/{id}/activity` and `/api/alerts/{id}/evidence`. The engine
+/// records into it each cycle; clones share the same state.
+#[derive(Clone, Default)]
+pub struct Blotter {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Blotter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve an account's alerts from `archive` instead of memory, so they
+    /// reach back past `BLOTTER_DEPTH` and earlier runs, and make its raw
+    /// alerts searchable.
+    pub fn set_archive(&self, archive: Option<AlertArchive>) {
+        let archived = match archive.as_ref().map(AlertArchive::alerts).transpose() {
+            Ok(alerts) => alerts.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("Alert archive not indexed for search: {e}");
+                Vec::new()
+            }
+        };
+        let mut inner = self.inner.lock().unwrap();
+        for alert in archived {
+            inner.index.insert(alert);
+        }
+        inner.archive = archive;
+    }
+
+    /// Replace the search index with `index`, e.g. one sized by `[search]`.
+    /// Call before [`Blotter::set_archive`], which fills it.
+    pub fn set_index(&self, index: AlertIndex) {
+        self.inner.lock().unwrap().index = index;
+    }
+
+    pub fn record_activity(&self, trades: &[Trade], orders: &[Order]) {
+        let mut inner = self.inner.lock().unwrap();
+        for t in trades {
+            push_bounded(&mut inner.entry(&t.account_id).trades, t.clone(), BLOTTER_DEPTH);
+        }
+        for o in orders {
+            push_bounded(&mut inner.entry(&o.account_id).orders, o.clone(), BLOTTER_DEPTH);
+        }
+
+        inner.tape_trades.extend(trades.iter().cloned());
+        inner.tape_orders.extend(orders.iter().cloned());
+        let newest = trades.iter().map(|t| t.ts).chain(orders.iter().map(|o| o.ts)).max();
+        if let Some(cutoff) = newest.map(|ts| ts - TAPE_RETENTION_MS) {
+            while inner.tape_trades.front().is_some_and(|t| t.ts < cutoff) {
+                inner.tape_trades.pop_front();
+            }
+            while inner.tape_orders.front().is_some_and(|o| o.ts < cutoff) {
+                inner.tape_orders.pop_front();
+            }
+        }
+    }
+
+    /// Record alerts for lookup by id, and on their account's blotter if
+    /// they name one.
+    pub fn record_alerts(&self, alerts: &[Alert]) {
+        let mut inner = self.inner.lock().unwrap();
+        for a in alerts {
+            if let Some(account_id) = &a.account_id {
+                push_bounded(&mut inner.entry(account_id).alerts, a.clone(), BLOTTER_DEPTH);
+            }
+            push_bounded(&mut inner.alerts, a.clone(), ALERT_LOOKUP_DEPTH);
+            inner.index.insert(a.clone());
+        }
+    }
+
+    pub fn record_scores(&self, ts: i64, scores: &[MlScore]) {
+        let mut inner = self.inner.lock().unwrap();
+        for s in scores {
+            push_bounded(&mut inner.entry(&s.account_id).risk_scores, RiskScore { ts, score: s.score }, BLOTTER_DEPTH);
+        }
+    }
+
+    /// Snapshot of everything recorded for `account_id`, or `None` if the
+    /// account has never been seen. Reads the archive when one is set.
+    pub fn activity(&self, account_id: &str) -> Result<Option<AccountBlotter>, Box<dyn std::error::Error>> {
+        let (snapshot, archive) = {
+            let inner = self.inner.lock().unwrap();
+            (inner.accounts.get(account_id).cloned(), inner.archive.clone())
+        };
+        let Some(mut snapshot) = snapshot else {
+            return Ok(None);
+        };
+        if let Some(archive) = archive {
+            snapshot.alerts = archive.account_alerts(account_id, BLOTTER_DEPTH)?.into();
+        }
+        Ok(Some(snapshot))
+    }
+
+    /// The alert with `id`, found in memory or else the archive; `None` if
+    /// neither has it.
+    pub fn alert(&self, id: u64) -> Result<Option<Alert>, Box<dyn std::error::Error>> {
+        let (alert, archive) = {
+            let inner = self.inner.lock().unwrap();
+            (inner.alerts.iter().rev().find(|a| a.id == id).cloned(), inner.archive.clone())
+        };
+        match (alert, archive) {
+            (Some(alert), _) => Ok(Some(alert)),
+            (None, Some(archive)) => archive.find_alert(id),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// The last alerts recorded, oldest first; up to 1,000, across accounts.
+    pub fn recent_alerts(&self) -> Vec<Alert> {
+        self.inner.lock().unwrap().alerts.iter().cloned().collect()
+    }
+
+    /// Indexed alerts matching every word of `query`, best match first; see
+    /// [`AlertIndex::search`].
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Alert> {
+        self.inner.lock().unwrap().index.search(query, limit).into_iter().cloned().collect()
+    }
+
+    /// Evidence for the alert with `id`, or `None` if [`Blotter::alert`]
+    /// can't find it.
+    pub fn evidence(&self, id: u64) -> Result<Option<Evidence>, Box<dyn std::error::Error>> {
+        Ok(self.alert(id)?.map(|alert| self.evidence_for(&alert)))
+    }
+
+    /// Replay the tape over `alert`'s window. Rows older than
+    /// `TAPE_RETENTION_MS` before the newest push are gone, so an old
+    /// alert's evidence comes back empty.
+    pub fn evidence_for(&self, alert: &Alert) -> Evidence {
+        let end_ms = alert.timestamp_ms;
+        let start_ms = end_ms - alert.alert_type.evidence_window_ms();
+        let in_window = |ts: i64| ts >= start_ms && ts <= end_ms;
+        let symbol_matches = |symbol: &str| alert.symbol.as_deref().is_none_or(|s| s == symbol);
+        let account_matches = |account: &str| alert.account_id.as_deref().is_none_or(|a| a == account);
+        // A front-running alert names the trading account; the order ahead of it is someone else's
+        let order_account_matches = |account: &str| matches!(alert.alert_type, AlertType::FrontRunning) || account_matches(account);
+
+        let inner = self.inner.lock().unwrap();
+        Evidence {
+            alert: alert.clone(),
+            start_ms,
+            end_ms,
+            trades: inner
+                .tape_trades
+                .iter()
+                .filter(|t| in_window(t.ts) && symbol_matches(&t.symbol) && account_matches(&t.account_id))
+                .cloned()
+                .collect(),
+            orders: inner
+                .tape_orders
+                .iter()
+                .filter(|o| in_window(o.ts) && symbol_matches(&o.symbol) && order_account_matches(&o.account_id))
+                .cloned()
+                .collect(),
+        }
+    }
+}
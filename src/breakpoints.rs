@@ -0,0 +1,74 @@
+//! Debugging breakpoints for a TUI `run` or `replay`: at the first alert of
+//! a given severity, or once event time reaches a given instant, the engine
+//! stops generating and pushing events (a replay stops playing recorded
+//! cycles) while the dashboard stays live, so the state at that moment can
+//! be inspected before resuming.
+
+use std::fmt;
+
+use chrono::DateTime;
+
+use crate::alerts::{Alert, AlertSeverity};
+use crate::config::BreakpointConfig;
+use crate::locale;
+
+/// Why a run paused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BreakReason {
+    /// The cycle raised this alert, the first at or above the breakpoint's severity.
+    Alert { id: u64, severity: AlertSeverity, description: String },
+    /// The next cycle would run at or past event time `at_ms`.
+    EventTime { at_ms: i64 },
+}
+
+impl fmt::Display for BreakReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakReason::Alert { id, severity, description } => write!(f, "{severity:?} alert #{id}: {description}"),
+            BreakReason::EventTime { at_ms } => write!(f, "event time {}", locale::timestamp(*at_ms)),
+        }
+    }
+}
+
+/// `[breakpoints]`, each firing at most once per run.
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints {
+    on_severity: Option<AlertSeverity>,
+    at_ms: Option<i64>,
+}
+
+impl Breakpoints {
+    pub fn from_config(config: &BreakpointConfig) -> Result<Self, String> {
+        let at_ms = config.at.as_deref().map(parse_event_time).transpose()?;
+        Ok(Self { on_severity: config.on_severity.map(AlertSeverity::from), at_ms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.on_severity.is_none() && self.at_ms.is_none()
+    }
+
+    /// Check before a cycle at event time `ts`; pauses without running it.
+    pub fn before_cycle(&mut self, ts: i64) -> Option<BreakReason> {
+        let at_ms = self.at_ms.filter(|&at| ts >= at)?;
+        self.at_ms = None;
+        Some(BreakReason::EventTime { at_ms })
+    }
+
+    /// Check a cycle's alerts; pauses with them in the feed.
+    pub fn after_cycle(&mut self, alerts: &[Alert]) -> Option<BreakReason> {
+        let min = self.on_severity.as_ref()?;
+        let alert = alerts.iter().find(|a| a.severity >= *min)?;
+        self.on_severity = None;
+        Some(BreakReason::Alert { id: alert.id, severity: alert.severity.clone(), description: alert.description().to_string() })
+    }
+}
+
+/// Epoch milliseconds, or an RFC 3339 timestamp such as `2026-10-16T09:30:00Z`.
+fn parse_event_time(s: &str) -> Result<i64, String> {
+    if let Ok(ms) = s.parse::<i64>() {
+        return Ok(ms);
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.timestamp_millis())
+        .map_err(|_| format!("[breakpoints] at: '{s}' is neither epoch milliseconds nor an RFC 3339 timestamp"))
+}
@@ -0,0 +1,179 @@
+//! Exchange trading calendar: regular session hours in the exchange's local
+//! time, weekends, holidays and half days. Local time follows a fixed
+//! standard offset plus a daylight-saving rule, so session times stay put
+//! in local time while their UTC times shift twice a year.
+//!
+//! The engine generates nothing while the market is closed, and VolumeAnomaly
+//! neither fires on nor learns from windows outside a session, so holiday and
+//! overnight quiet never reads as an anomaly against a full-day baseline.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use crate::config::{CalendarConfig, DstRule};
+
+/// How far `next_open` looks ahead before giving up.
+const MAX_CLOSED_DAYS: i64 = 30;
+
+/// What kind of trading day a local date is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayKind {
+    Regular,
+    /// Closes early, at `half_day_close`.
+    HalfDay,
+    Holiday,
+    Weekend,
+}
+
+impl DayKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DayKind::Regular => "regular",
+            DayKind::HalfDay => "half day",
+            DayKind::Holiday => "holiday",
+            DayKind::Weekend => "weekend",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    /// Standard-time offset from UTC.
+    offset_mins: i32,
+    dst: DstRule,
+    open: NaiveTime,
+    close: NaiveTime,
+    half_day_close: NaiveTime,
+    holidays: Vec<NaiveDate>,
+    half_days: Vec<NaiveDate>,
+}
+
+impl TradingCalendar {
+    /// `None` when `[calendar]` is disabled: the market never closes.
+    pub fn from_config(config: &CalendarConfig) -> Result<Option<Self>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.utc_offset_minutes.abs() > 14 * 60 {
+            return Err(format!("[calendar] utc_offset_minutes {} is more than 14 hours from UTC", config.utc_offset_minutes));
+        }
+        let time = |key: &str, s: &str| {
+            NaiveTime::parse_from_str(s, "%H:%M").map_err(|e| format!("[calendar] {key} {s:?} is not HH:MM: {e}"))
+        };
+        let dates = |key: &str, list: &[String]| {
+            list.iter()
+                .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("[calendar] {key} {s:?} is not YYYY-MM-DD: {e}")))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let calendar = Self {
+            offset_mins: config.utc_offset_minutes,
+            dst: config.dst,
+            open: time("open", &config.open)?,
+            close: time("close", &config.close)?,
+            half_day_close: time("half_day_close", &config.half_day_close)?,
+            holidays: dates("holidays", &config.holidays)?,
+            half_days: dates("half_days", &config.half_days)?,
+        };
+        if calendar.open >= calendar.close {
+            return Err(format!("[calendar] open {} must be before close {}", config.open, config.close));
+        }
+        if calendar.half_day_close <= calendar.open || calendar.half_day_close > calendar.close {
+            return Err(format!("[calendar] half_day_close {} must fall after open and no later than close", config.half_day_close));
+        }
+        if let Some(d) = calendar.half_days.iter().find(|d| calendar.holidays.contains(d)) {
+            return Err(format!("[calendar] {d} is listed as both a holiday and a half day"));
+        }
+        Ok(Some(calendar))
+    }
+
+    /// Local offset from UTC at `ts_ms`, daylight saving included.
+    pub fn offset_mins(&self, ts_ms: i64) -> i32 {
+        let Some(utc) = DateTime::from_timestamp_millis(ts_ms).map(|t| t.naive_utc()) else {
+            return self.offset_mins;
+        };
+        let year = utc.year();
+        let in_dst = match self.dst {
+            DstRule::None => false,
+            // 02:00 local on the second Sunday of March until 02:00 local
+            // (daylight) on the first Sunday of November
+            DstRule::Us => {
+                let start = nth_sunday(year, 3, 2).and_hms_opt(2, 0, 0).unwrap() - Duration::minutes(self.offset_mins as i64);
+                let end = nth_sunday(year, 11, 1).and_hms_opt(2, 0, 0).unwrap() - Duration::minutes(self.offset_mins as i64 + 60);
+                start <= utc && utc < end
+            }
+            // 01:00 UTC on the last Sunday of March until 01:00 UTC on the last Sunday of October
+            DstRule::Eu => {
+                let start = last_sunday(year, 3).and_hms_opt(1, 0, 0).unwrap();
+                let end = last_sunday(year, 10).and_hms_opt(1, 0, 0).unwrap();
+                start <= utc && utc < end
+            }
+        };
+        self.offset_mins + if in_dst { 60 } else { 0 }
+    }
+
+    /// Exchange-local wall-clock time at `ts_ms`.
+    pub fn local(&self, ts_ms: i64) -> NaiveDateTime {
+        let utc = DateTime::from_timestamp_millis(ts_ms).map(|t| t.naive_utc()).unwrap_or_default();
+        utc + Duration::minutes(self.offset_mins(ts_ms) as i64)
+    }
+
+    pub fn day_kind(&self, date: NaiveDate) -> DayKind {
+        if matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            DayKind::Weekend
+        } else if self.holidays.contains(&date) {
+            DayKind::Holiday
+        } else if self.half_days.contains(&date) {
+            DayKind::HalfDay
+        } else {
+            DayKind::Regular
+        }
+    }
+
+    /// The session on local `date` as UTC epoch milliseconds `[open, close)`,
+    /// or `None` when the market doesn't open that day.
+    pub fn session(&self, date: NaiveDate) -> Option<(i64, i64)> {
+        let close = match self.day_kind(date) {
+            DayKind::Regular => self.close,
+            DayKind::HalfDay => self.half_day_close,
+            DayKind::Holiday | DayKind::Weekend => return None,
+        };
+        Some((self.to_utc_ms(date.and_time(self.open)), self.to_utc_ms(date.and_time(close))))
+    }
+
+    pub fn is_open(&self, ts_ms: i64) -> bool {
+        self.session(self.local(ts_ms).date()).is_some_and(|(open, close)| open <= ts_ms && ts_ms < close)
+    }
+
+    /// The first session open at or after `ts_ms`, within `MAX_CLOSED_DAYS`.
+    pub fn next_open(&self, ts_ms: i64) -> Option<i64> {
+        let today = self.local(ts_ms).date();
+        (0..=MAX_CLOSED_DAYS)
+            .filter_map(|d| self.session(today + Duration::days(d)))
+            .map(|(open, _)| open)
+            .find(|&open| open >= ts_ms)
+    }
+
+    /// Length of a regular session, the day ADV is extrapolated to.
+    pub fn regular_session_secs(&self) -> u64 {
+        (self.close - self.open).num_seconds() as u64
+    }
+
+    /// Local wall-clock time to UTC milliseconds. Sessions never straddle a
+    /// DST switch, so the offset an hour either side of it is the same.
+    fn to_utc_ms(&self, local: NaiveDateTime) -> i64 {
+        let guess = (local - Duration::minutes(self.offset_mins as i64)).and_utc().timestamp_millis();
+        (local - Duration::minutes(self.offset_mins(guess) as i64)).and_utc().timestamp_millis()
+    }
+}
+
+/// The `n`th Sunday (1-based) of `month`.
+fn nth_sunday(year: i32, month: u32, n: u32) -> NaiveDate {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let to_sunday = (7 - first.weekday().num_days_from_sunday()) % 7;
+    first + Duration::days((to_sunday + 7 * (n - 1)) as i64)
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let next_month = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1) } else { NaiveDate::from_ymd_opt(year, month + 1, 1) };
+    let last = next_month.unwrap().pred_opt().unwrap();
+    last - Duration::days(last.weekday().num_days_from_sunday() as i64)
+}
@@ -0,0 +1,81 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::{Duration, Instant};
+
+pub type Sleep<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Source of event time and run time for the engine loop.
+///
+/// Latency measurements deliberately stay on `std::time::Instant`: they
+/// measure real work in the pipeline, which a virtual clock can't speed up.
+pub trait Clock: Send + Sync {
+    /// Event time in epoch milliseconds, stamped on generated trades and orders.
+    fn now_ms(&self) -> i64;
+    /// Time since the clock was created. Bounds run duration.
+    fn elapsed(&self) -> Duration;
+    /// Pause between engine cycles.
+    fn sleep(&self, d: Duration) -> Sleep<'_>;
+}
+
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, d: Duration) -> Sleep<'_> {
+        Box::pin(tokio::time::sleep(d))
+    }
+}
+
+/// Deterministic clock for tests: time only moves on `advance` or `sleep`,
+/// and `sleep` returns immediately after advancing.
+pub struct VirtualClock {
+    start_ms: i64,
+    offset_ms: AtomicI64,
+}
+
+impl VirtualClock {
+    pub fn new(start_ms: i64) -> Self {
+        Self { start_ms, offset_ms: AtomicI64::new(0) }
+    }
+
+    pub fn advance(&self, d: Duration) {
+        self.offset_ms.fetch_add(d.as_millis() as i64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now_ms(&self) -> i64 {
+        self.start_ms + self.offset_ms.load(Ordering::SeqCst)
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.offset_ms.load(Ordering::SeqCst) as u64)
+    }
+
+    fn sleep(&self, d: Duration) -> Sleep<'_> {
+        self.advance(d);
+        Box::pin(tokio::task::yield_now())
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alerts::AlertEngine;
+use crate::clock::SystemClock;
+use crate::config::Config;
+use crate::detection::{self, PipelineOptions};
+use crate::engine::{Challenger, Engine};
+use crate::generator::{self, FraudGenerator};
+use crate::runs::RunRecord;
+
+/// Run rule sets `a` and `b` side by side on one pipeline and one generated
+/// event stream, returning a run record for each. The pipeline (SESSION gaps)
+/// and join band come from `a`; from `b` only its rule settings apply. Both run unbounded
+/// and unarchived so neither side sheds or drops alerts the other keeps.
+pub async fn run(
+    a: &Config,
+    b: &Config,
+    fraud_rate: f64,
+    duration_secs: u64,
+) -> Result<(RunRecord, RunRecord), Box<dyn std::error::Error>> {
+    println!("=== laminardb-fraud-detect (compare) ===");
+    println!("Fraud rate: {:.0}%, Duration: {}s", fraud_rate * 100.0, duration_secs);
+    println!();
+
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(Duration::from_millis(200));
+    let join = detection::guard_join_band(&a.join, trades_per_sec, orders_per_sec);
+    let pipeline = detection::setup_with(&PipelineOptions { sessions: a.sessions.clone(), join, ..Default::default() }).await?;
+    println!();
+
+    let gen = FraudGenerator::new(fraud_rate).with_benign_rate(a.generator.benign_rate);
+    let mut engine = Engine::new(pipeline, gen, AlertEngine::from_config(a)?, Arc::new(SystemClock::new()));
+    engine.challenger = Some(Challenger::new(AlertEngine::from_config(b)?));
+
+    let run_duration = Duration::from_secs(duration_secs);
+    while engine.elapsed() < run_duration {
+        engine.cycle().await;
+        engine.sleep(Duration::from_millis(200)).await;
+    }
+
+    let mut challenger = engine.challenger.take().expect("challenger set above");
+    let record_a = RunRecord::capture(&mut engine, "compare", fraud_rate, a);
+    let record_b = challenger_record(&mut challenger, b, &record_a);
+    engine.shutdown().await;
+    Ok((record_a, record_b))
+}
+
+/// Side `b`'s run record: the challenger's alerts, latencies and quality
+/// over `record_a`'s shared event counts and timings.
+fn challenger_record(challenger: &mut Challenger, b: &Config, record_a: &RunRecord) -> RunRecord {
+    RunRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        config: b.clone(),
+        total_alerts: challenger.alert_engine.total_alerts(),
+        suppressed_alerts: challenger.alert_engine.suppressed_alerts(),
+        alert_counts: challenger.alert_engine.alert_counts().clone(),
+        severity_counts: challenger.alert_engine.severity_counts().clone(),
+        shadow_counts: challenger.alert_engine.shadow_alert_counts().clone(),
+        alert: challenger.latency.alert_stats(),
+        // The challenger's alert latencies aren't held to the [slo] objectives
+        slo: Vec::new(),
+        quality: challenger.quality.has_labels().then(|| challenger.quality.report()),
+        ..record_a.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::time::Instant;
+
+    use super::*;
+    use crate::latency::LatencyStats;
+    use crate::types::RapidFireBurst;
+
+    fn record_a() -> RunRecord {
+        RunRecord {
+            id: "a".into(),
+            started_at_ms: 1_000,
+            ui: "none".into(),
+            fraud_rate: 0.05,
+            config: Config::default(),
+            elapsed_secs: 10.0,
+            uptime_secs: 10.0,
+            total_trades: 500,
+            total_orders: 900,
+            total_alerts: 3,
+            suppressed_alerts: 1,
+            alert_counts: HashMap::from([("WashTrading".to_string(), 3)]),
+            severity_counts: HashMap::from([("High".to_string(), 3)]),
+            stream_counts: BTreeMap::from([("wash_score".to_string(), 7)]),
+            shadow_counts: HashMap::new(),
+            push: LatencyStats::default(),
+            processing: LatencyStats::default(),
+            alert: LatencyStats { count: 3, ..Default::default() },
+            tick_lag: LatencyStats::default(),
+            bar_lag: LatencyStats::default(),
+            drain: None,
+            slo: Vec::new(),
+            quality: None,
+        }
+    }
+
+    #[test]
+    fn challenger_record_keeps_shared_counts_and_its_own_alerts() {
+        let mut b = Config::default();
+        b.thresholds.rapid_fire_trades = 10;
+        let mut challenger = Challenger::new(AlertEngine::from_config(&b).unwrap());
+        let burst = RapidFireBurst { account_id: "A1".into(), burst_trades: 60, burst_volume: 1_000, low: 100.0, high: 100.0, burst_notional: 1e9 };
+        assert!(challenger.alert_engine.evaluate_rapid_fire(&burst, Instant::now()).is_some());
+        challenger.latency.record_alert_us(250);
+
+        let a = record_a();
+        let record = challenger_record(&mut challenger, &b, &a);
+        assert_ne!(record.id, a.id);
+        assert_eq!((record.started_at_ms, record.total_trades, record.total_orders), (1_000, 500, 900));
+        assert_eq!(record.stream_counts, a.stream_counts);
+        assert_eq!(record.config.thresholds.rapid_fire_trades, 10);
+        assert_eq!((record.total_alerts, record.suppressed_alerts), (1, 0));
+        assert_eq!(record.alert_counts, HashMap::from([("RapidFire".to_string(), 1)]));
+        assert_eq!(record.severity_counts, HashMap::from([("Critical".to_string(), 1)]));
+        assert_eq!((record.alert.count, record.alert.p50_us), (1, 250));
+    }
+
+    #[test]
+    fn challenger_record_without_alerts_is_empty() {
+        let b = Config::default();
+        let mut challenger = Challenger::new(AlertEngine::from_config(&b).unwrap());
+        let record = challenger_record(&mut challenger, &b, &record_a());
+        assert_eq!(record.total_alerts, 0);
+        assert!(record.alert_counts.is_empty() && record.severity_counts.is_empty());
+        assert_eq!(record.alert.count, 0);
+        assert!(record.quality.is_none(), "no labelled injections were seen");
+        assert!(record.slo.is_empty());
+    }
+}
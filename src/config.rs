@@ -0,0 +1,1057 @@
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::Shard;
+
+/// Optional TOML configuration loaded with `--config`. Every section and
+/// field has a default, so an empty file (or no file) is a valid config.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub scoring: ScoringConfig,
+    pub notify: NotifyConfig,
+    pub limits: LimitsConfig,
+    pub archive: ArchiveConfig,
+    pub sessions: SessionConfig,
+    pub notional: NotionalConfig,
+    pub volume: VolumeConfig,
+    pub thresholds: ThresholdsConfig,
+    pub shadow: ShadowConfig,
+    pub severity: SeverityConfig,
+    pub cooldown: CooldownConfig,
+    pub audit: AuditConfig,
+    pub join: JoinConfig,
+    pub keys: KeysConfig,
+    pub tui: TuiConfig,
+    pub web: WebConfig,
+    pub pacing: PacingConfig,
+    pub ids: IdsConfig,
+    pub ticks: TickConfig,
+    pub format: FormatConfig,
+    pub drain: DrainConfig,
+    pub watermark: WatermarkConfig,
+    pub standby: StandbyConfig,
+    pub stop: StopConfig,
+    pub retention: RetentionConfig,
+    pub search: SearchConfig,
+    pub slack: SlackConfig,
+    pub auth: AuthConfig,
+    pub breakpoints: BreakpointConfig,
+    pub reviews: ReviewConfig,
+    pub generator: GeneratorConfig,
+    pub coordinator: CoordinatorConfig,
+    pub calendar: CalendarConfig,
+    pub rollover: RolloverConfig,
+    pub recording: RecordingConfig,
+    pub slo: SloConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// ONNX model that scores each candidate alert (requires the `onnx` feature).
+    pub model_path: Option<PathBuf>,
+    /// Candidate alerts the model scores below this are suppressed.
+    pub min_score: f64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self { model_path: None, min_score: 0.0 }
+    }
+}
+
+/// Which alert severities raise a desktop notification in the local TUI
+/// (requires the `notify` feature). All off by default.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    pub medium: bool,
+    pub high: bool,
+    pub critical: bool,
+}
+
+/// TUI key bindings, used by `run --ui tui` and `attach`. Each action takes
+/// a list of keys: a single character, or one of `Up`, `Down`, `Left`,
+/// `Right`, `PageUp`, `PageDown`, `Home`, `End`, `Enter`, `Tab`, `Esc`,
+/// `Backspace`. Esc also closes whatever overlay is open.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeysConfig {
+    pub quit: Vec<String>,
+    pub help: Vec<String>,
+    pub evidence: Vec<String>,
+    pub scroll_up: Vec<String>,
+    pub scroll_down: Vec<String>,
+    pub search: Vec<String>,
+    pub next_match: Vec<String>,
+    pub prev_match: Vec<String>,
+    pub pin_account: Vec<String>,
+    pub pin_symbol: Vec<String>,
+    pub reset_stats: Vec<String>,
+    pub resume: Vec<String>,
+    pub true_positive: Vec<String>,
+    pub false_positive: Vec<String>,
+    pub benign: Vec<String>,
+    pub note: Vec<String>,
+    pub next_tab: Vec<String>,
+}
+
+impl Default for KeysConfig {
+    fn default() -> Self {
+        let keys = |k: &[&str]| k.iter().map(|s| s.to_string()).collect();
+        Self {
+            quit: keys(&["q", "Esc"]),
+            help: keys(&["?"]),
+            evidence: keys(&["e"]),
+            scroll_up: keys(&["Up"]),
+            scroll_down: keys(&["Down"]),
+            search: keys(&["/"]),
+            next_match: keys(&["n"]),
+            prev_match: keys(&["N"]),
+            pin_account: keys(&["p"]),
+            pin_symbol: keys(&["P"]),
+            reset_stats: keys(&["r"]),
+            resume: keys(&["c"]),
+            true_positive: keys(&["t"]),
+            false_positive: keys(&["f"]),
+            benign: keys(&["b"]),
+            note: keys(&["a"]),
+            next_tab: keys(&["Tab"]),
+        }
+    }
+}
+
+/// Dashboard WebSocket broadcast and keepalive. Each client is pinged every
+/// `ping_interval_secs` and closed once it has sent nothing, pongs included,
+/// for `idle_timeout_secs` (0 = never).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebConfig {
+    /// Gap between dashboard updates; cycles in between are coalesced into
+    /// the next one. 0 falls back to `[limits] max_ws_updates_per_sec`.
+    pub broadcast_interval_ms: u64,
+    pub ping_interval_secs: u64,
+    pub idle_timeout_secs: u64,
+    /// PEM certificate chain and private key; set both to serve HTTPS/WSS
+    /// (requires the `tls` feature).
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Path prefix everything is served under, e.g. `/fraud` behind a portal
+    /// proxy that forwards the prefix. Empty serves at the root.
+    pub base_path: String,
+    /// Origins allowed to call the REST API from a browser; `["*"]` allows
+    /// any. Empty sends no CORS headers.
+    pub cors_origins: Vec<String>,
+    /// Proxies whose `X-Forwarded-For` is believed; from any other peer the
+    /// header is ignored. `X-Forwarded-Proto` and `X-Forwarded-Prefix` are
+    /// not read: set `tls_cert`/`tls_key` and `base_path` to match instead.
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Recorded runs behind `/api/runs` and the compare page; unset uses
+    /// `run --runs-dir`.
+    pub runs_dir: Option<PathBuf>,
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            broadcast_interval_ms: 0,
+            ping_interval_secs: 15,
+            idle_timeout_secs: 45,
+            tls_cert: None,
+            tls_key: None,
+            base_path: String::new(),
+            cors_origins: Vec::new(),
+            trusted_proxies: Vec::new(),
+            runs_dir: None,
+        }
+    }
+}
+
+/// TUI appearance, used by `run --ui tui` and `attach`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TuiConfig {
+    pub theme: ThemeName,
+    /// What the terminal is assumed to support; see [`CompatMode`].
+    pub compat: CompatMode,
+}
+
+/// How much of the terminal the TUI relies on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatMode {
+    /// Detect from `TERM`, `COLORTERM`, the locale and, on Windows, the console host.
+    #[default]
+    Auto,
+    /// Alternate screen, mouse, Unicode borders and 256 colors.
+    Full,
+    /// No alternate screen or mouse, ASCII borders and the 16 ANSI colors,
+    /// for legacy consoles, serial lines and old multiplexers.
+    Basic,
+}
+
+/// Built-in TUI color themes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    /// For light terminal backgrounds.
+    Light,
+    HighContrast,
+    /// No color, for casting and recording.
+    Monochrome,
+}
+
+/// Overload bounds for `run` mode. Stress and tests run unbounded.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Skip generating while this many pushes have produced no stream output yet.
+    pub max_inflight_batches: u32,
+    /// Alerts handed to the frontend per cycle; the rest are counted as dropped.
+    pub max_alert_queue: usize,
+    /// Dashboard WebSocket updates per second; faster cycles are coalesced. 0 = unlimited.
+    pub max_ws_updates_per_sec: u32,
+    /// Smoothed push latency above which the engine degrades: it sheds every
+    /// other generator cycle and halves the dashboard update rate until push
+    /// latency falls back below half this.
+    pub degrade_push_us: u64,
+    /// A cycle, or a pause between cycles, longer than this raises a
+    /// Critical EngineStall alert. 0 = off.
+    pub cycle_budget_ms: u64,
+    /// Rows evaluated per stream per cycle; the rest carry over to the next
+    /// cycle, so a stream with explosive output (join fan-out) can't starve
+    /// the others. 0 = unlimited.
+    pub max_rows_per_stream: usize,
+    /// Run the rules on a worker thread fed by the poll loop, so slow rules
+    /// don't hold up polling the remaining streams.
+    pub eval_worker: bool,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_inflight_batches: 32,
+            max_alert_queue: 200,
+            max_ws_updates_per_sec: 10,
+            degrade_push_us: 5_000,
+            cycle_budget_ms: 2_000,
+            max_rows_per_stream: 5_000,
+            eval_worker: false,
+        }
+    }
+}
+
+impl LimitsConfig {
+    pub fn cycle_budget(&self) -> Option<Duration> {
+        (self.cycle_budget_ms > 0).then(|| Duration::from_millis(self.cycle_budget_ms))
+    }
+
+    pub fn poll_budget(&self) -> Option<usize> {
+        (self.max_rows_per_stream > 0).then_some(self.max_rows_per_stream)
+    }
+}
+
+/// How often `run` cycles the engine. Shorter intervals cut alert latency and
+/// cost CPU; each cycle generates about the same number of events, so the
+/// interval also sets the event rate.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PacingConfig {
+    /// Time from one cycle's start to the next.
+    pub cycle_interval_ms: u64,
+    /// Trades plus orders per second to hold by adapting the interval,
+    /// within `min_interval_ms`..`max_interval_ms`. Unset keeps it fixed.
+    pub target_events_per_sec: Option<f64>,
+    pub min_interval_ms: u64,
+    pub max_interval_ms: u64,
+}
+
+impl Default for PacingConfig {
+    fn default() -> Self {
+        Self { cycle_interval_ms: 200, target_events_per_sec: None, min_interval_ms: 20, max_interval_ms: 1_000 }
+    }
+}
+
+/// Conditions besides `--duration` that end a `run`, and the checkpoints an
+/// open-ended one writes. `--max-alerts` and `--max-trades` override these.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StopConfig {
+    /// Stop once this many alerts have been delivered.
+    pub max_alerts: Option<u64>,
+    /// Stop once this many trades have been pushed.
+    pub max_trades: Option<u64>,
+    /// How often a run rewrites its summary (`--summary`) while it runs, so
+    /// one that is killed still leaves its stats; 0 never does.
+    pub checkpoint_secs: u64,
+    /// Where checkpoints go; `run` sets it to `--summary` unless given.
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Default for StopConfig {
+    fn default() -> Self {
+        Self { max_alerts: None, max_trades: None, checkpoint_secs: 60, checkpoint_path: None }
+    }
+}
+
+/// Where a `run --ui tui` pauses for inspection: event generation stops, the
+/// TUI stays live, and the resume key carries on. `--break-on` and
+/// `--break-at` override these.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BreakpointConfig {
+    /// Pause after the first cycle that raises an alert this severe or more.
+    pub on_severity: Option<SeverityLevel>,
+    /// Pause before the first cycle at or past this event time: epoch
+    /// milliseconds or an RFC 3339 timestamp.
+    pub at: Option<String>,
+}
+
+/// Operator notes and dispositions on alerts, from the TUI or
+/// `/api/alerts/{id}/review`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReviewConfig {
+    /// JSON file reviews are loaded from and saved to; kept in memory for
+    /// the run only when unset.
+    pub path: Option<PathBuf>,
+}
+
+/// Synthetic market activity beyond the `--fraud-rate` injections.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GeneratorConfig {
+    /// Chance per cycle (0.0-1.0) of a benign look-alike: an index
+    /// rebalance, VWAP slicing or market making that resembles fraud but is
+    /// labelled legitimate, so each rule's false-positive rate on it lands in
+    /// the quality report. 0 injects none.
+    pub benign_rate: f64,
+    /// Generate only this symbol shard, e.g. `"0/3"`, for one of several
+    /// instances behind a `coordinate` fan-in. Unset generates every symbol.
+    pub shard: Option<Shard>,
+}
+
+/// `coordinate` fan-in. Each instance's per-account bursts and wash volume
+/// are added up with the others' while they are newer than
+/// `account_window_secs`, and judged by the rapid-fire and wash rules.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CoordinatorConfig {
+    pub account_window_secs: u64,
+}
+
+impl Default for CoordinatorConfig {
+    fn default() -> Self {
+        Self { account_window_secs: 10 }
+    }
+}
+
+/// Exchange trading calendar. When enabled, nothing is generated outside a
+/// session, VolumeAnomaly ignores windows outside one, and ADV is
+/// extrapolated to the regular session length instead of
+/// `[volume] trading_day_secs`. Defaults to NYSE hours; weekends are always
+/// closed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    pub enabled: bool,
+    /// The exchange's standard-time offset from UTC, e.g. -300 for New York.
+    pub utc_offset_minutes: i32,
+    /// Daylight-saving rule added on top of the standard offset.
+    pub dst: DstRule,
+    /// Session open and close, exchange-local `HH:MM`.
+    pub open: String,
+    pub close: String,
+    /// Close on `half_days`.
+    pub half_day_close: String,
+    /// Exchange-local dates, `YYYY-MM-DD`, with no session.
+    pub holidays: Vec<String>,
+    /// Exchange-local dates that close at `half_day_close`.
+    pub half_days: Vec<String>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            utc_offset_minutes: -300,
+            dst: DstRule::Us,
+            open: "09:30".into(),
+            close: "16:00".into(),
+            half_day_close: "13:00".into(),
+            holidays: Vec::new(),
+            half_days: Vec::new(),
+        }
+    }
+}
+
+/// When daylight saving moves local time an hour ahead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DstRule {
+    /// No daylight saving.
+    None,
+    /// Second Sunday of March to the first Sunday of November, 02:00 local.
+    Us,
+    /// Last Sunday of March to the last Sunday of October, 01:00 UTC.
+    Eu,
+}
+
+/// What each rule's learned state does when a new trading session starts.
+/// Only applies with `[calendar]` enabled. Intraday patterns reset; history
+/// a baseline is built from carries over.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RolloverConfig {
+    /// Recent window volumes and the ADV estimate.
+    pub volume_anomaly: Rollover,
+    /// Each account's distinct-symbol history.
+    pub diversification: Rollover,
+    /// Book windows and armed imbalances.
+    pub book_imbalance: Rollover,
+    /// Bursts waiting for their unwind.
+    pub momentum_ignition: Rollover,
+    /// Positions built ahead of news.
+    pub insider_trading: Rollover,
+    /// Each account pair's recent improved fills.
+    pub price_improvement: Rollover,
+    /// Each account's counterparty window in progress.
+    pub counterparty_concentration: Rollover,
+    /// The online anomaly model.
+    pub ml_anomaly: Rollover,
+    /// Every `[cooldown]` in progress.
+    pub cooldowns: Rollover,
+}
+
+impl Default for RolloverConfig {
+    fn default() -> Self {
+        Self {
+            volume_anomaly: Rollover::Carry,
+            diversification: Rollover::Carry,
+            book_imbalance: Rollover::Reset,
+            momentum_ignition: Rollover::Reset,
+            insider_trading: Rollover::Carry,
+            price_improvement: Rollover::Carry,
+            counterparty_concentration: Rollover::Carry,
+            ml_anomaly: Rollover::Carry,
+            cooldowns: Rollover::Reset,
+        }
+    }
+}
+
+impl RolloverConfig {
+    /// Keys set to `reset`, in declaration order.
+    pub fn reset(&self) -> Vec<&'static str> {
+        [
+            ("volume_anomaly", self.volume_anomaly),
+            ("diversification", self.diversification),
+            ("book_imbalance", self.book_imbalance),
+            ("momentum_ignition", self.momentum_ignition),
+            ("insider_trading", self.insider_trading),
+            ("price_improvement", self.price_improvement),
+            ("counterparty_concentration", self.counterparty_concentration),
+            ("ml_anomaly", self.ml_anomaly),
+            ("cooldowns", self.cooldowns),
+        ]
+        .into_iter()
+        .filter(|(_, r)| *r == Rollover::Reset)
+        .map(|(key, _)| key)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rollover {
+    /// Keep yesterday's state.
+    Carry,
+    /// Start the session empty.
+    Reset,
+}
+
+/// How much alert history is kept in memory: the engine's recent alerts, the
+/// TUI feed and the web dashboard's alert table. 0 disables that limit, but
+/// one of them must be set. The archive keeps its own `[archive]` limits.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Newest alerts kept; older ones are dropped as new ones arrive.
+    pub max_alerts: usize,
+    /// Drop alerts older than this.
+    pub max_age_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self { max_alerts: 200, max_age_secs: 0 }
+    }
+}
+
+/// Full-text alert search (`/api/alerts?q=`, the TUI `/` search): how many
+/// of the newest alerts, archived ones included, the index holds.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// 0 turns the index off; searches then find nothing.
+    pub max_alerts: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self { max_alerts: 200_000 }
+    }
+}
+
+/// Slack slash commands at `POST /api/slack/command`. Off until the Slack
+/// app's signing secret is set; requests it didn't sign are refused.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SlackConfig {
+    pub signing_secret: Option<String>,
+}
+
+/// How much of the web API a token may use. Each scope includes the ones
+/// before it: `operate` can also read, `admin` can do everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Every GET and the dashboard WebSocket.
+    Read,
+    /// Alert reviews and acknowledgements.
+    Operate,
+    /// Stats reset, standby pipelines and anything else that changes the run.
+    Admin,
+}
+
+impl Scope {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Operate => "operate",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiToken {
+    /// Who holds the token, recorded in the audit log.
+    pub name: String,
+    pub token: String,
+    pub scope: Scope,
+}
+
+/// Bearer tokens for the web API. With none, the default, the API is open as
+/// before; with any, every API call needs one of sufficient scope.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub tokens: Vec<ApiToken>,
+}
+
+/// End-of-run drain: when a `run` ends, every watermark is advanced past the
+/// pushed data and the streams polled until they stop emitting, so the last
+/// windows' rows and alerts are counted rather than lost.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DrainConfig {
+    /// Longest the drain may poll; 0 skips it.
+    pub timeout_ms: u64,
+}
+
+impl Default for DrainConfig {
+    fn default() -> Self {
+        Self { timeout_ms: 5_000 }
+    }
+}
+
+impl DrainConfig {
+    pub fn timeout(&self) -> Option<Duration> {
+        (self.timeout_ms > 0).then(|| Duration::from_millis(self.timeout_ms))
+    }
+}
+
+/// Which pushes move the sources' watermarks. By default every push does;
+/// a cadence or an event-time step batches them, so windows close in fewer,
+/// larger steps at the cost of some detection latency.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WatermarkConfig {
+    /// Emit on every Nth push; 0 leaves it to `min_advance_ms`.
+    pub every_pushes: u32,
+    /// Also emit once the watermark has gained this much event time; 0 = off.
+    pub min_advance_ms: i64,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self { every_pushes: 1, min_advance_ms: 0 }
+    }
+}
+
+/// Warm standby pipelines (`POST /api/pipeline/standby`): how much mirrored
+/// event time a standby needs before alert evaluation switches to it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StandbyConfig {
+    /// At least the longest window or baseline any stream keeps.
+    pub warmup_secs: u64,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self { warmup_secs: 60 }
+    }
+}
+
+/// Alert archive and its retention policy. Limits of 0 disable that limit.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// JSON-lines file every alert is appended to; no archive when unset.
+    pub path: Option<PathBuf>,
+    /// Drop alerts and hourly summaries older than this.
+    pub max_age_secs: u64,
+    /// Raw alerts kept after compaction; the oldest beyond this are dropped.
+    pub max_rows: usize,
+    /// Fold raw alerts older than this into per-hour counts.
+    pub summarize_after_secs: u64,
+    pub compact_interval_secs: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self { path: None, max_age_secs: 30 * 86_400, max_rows: 100_000, summarize_after_secs: 86_400, compact_interval_secs: 60 }
+    }
+}
+
+/// Alert latency objectives: each holds `percent` of alerts to within
+/// `within_ms` of the start of the cycle that pushed their events. Attainment
+/// is tracked since start and over the last `window_secs`; an objective is
+/// breached while the window's attainment is under target. An empty list
+/// tracks nothing.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SloConfig {
+    pub window_secs: u64,
+    pub objectives: Vec<SloObjective>,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self { window_secs: 300, objectives: vec![SloObjective { name: "alerts-250ms".into(), percent: 99.0, within_ms: 250 }] }
+    }
+}
+
+/// One `[[slo.objectives]]` entry, e.g. 99% of alerts within 250ms.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SloObjective {
+    pub name: String,
+    pub percent: f64,
+    pub within_ms: u64,
+}
+
+/// Recording of polled stream rows, for `replay` to feed the dashboards
+/// from without a pipeline.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// JSON-lines file each cycle's rows are appended to; no recording when unset.
+    pub path: Option<PathBuf>,
+}
+
+/// Alert ID continuity across restarts. With no ledger, every run numbers
+/// its alerts from 1.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IdsConfig {
+    /// JSON file holding the IDs reserved so far and each run's first ID.
+    pub path: Option<PathBuf>,
+    /// IDs reserved per ledger write; a crash can skip up to this many.
+    pub block: u64,
+    /// Globally unique ID given to each delivered alert as `uid`, beside the
+    /// numeric sequence.
+    pub format: IdFormat,
+}
+
+impl Default for IdsConfig {
+    fn default() -> Self {
+        Self { path: None, block: 1_000, format: IdFormat::Sequence }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdFormat {
+    /// The numeric sequence only.
+    #[default]
+    Sequence,
+    /// 26-character Crockford base32 ULID.
+    Ulid,
+    /// RFC 9562 UUID version 7.
+    UuidV7,
+}
+
+/// Append-only compliance log of config changes and rule firings.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// JSON-lines file entries are appended to; no audit log when unset.
+    pub path: Option<PathBuf>,
+}
+
+/// Band of the `suspicious_match` INNER JOIN and its fan-out guardrail. Each
+/// trade joins every same-symbol order within ±`band_ms`, so join output grows
+/// with the band and with the square of the event rate.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JoinConfig {
+    pub band_ms: u64,
+    /// Estimated join rows per second above which the band is flagged. 0 = no guardrail.
+    pub max_fanout_per_sec: f64,
+    /// At setup, narrow the band until the expected fan-out fits instead of only warning.
+    pub clamp: bool,
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        Self { band_ms: 2_000, max_fanout_per_sec: 10_000.0, clamp: false }
+    }
+}
+
+/// SESSION gaps for the rapid-fire stream. Each profile gets its own stream
+/// over its accounts; every other account uses `default_gap_ms`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub default_gap_ms: u64,
+    pub profiles: Vec<SessionProfile>,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self { default_gap_ms: 2_000, profiles: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SessionProfile {
+    /// Becomes the stream name suffix (`rapid_fire_<name>`): letters, digits and `_`.
+    pub name: String,
+    pub accounts: Vec<String>,
+    pub gap_ms: u64,
+}
+
+/// Minimum traded value (price × volume, in dollars) a stream row needs
+/// before its rule may fire, per alert type. 0 = no floor.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotionalConfig {
+    pub volume_anomaly: f64,
+    pub price_spike: f64,
+    pub rapid_fire: f64,
+    pub wash_trading: f64,
+    pub suspicious_match: f64,
+    pub front_running: f64,
+    pub diversification: f64,
+    pub momentum_ignition: f64,
+    pub insider_trading: f64,
+    pub price_improvement: f64,
+    pub counterparty_concentration: f64,
+}
+
+/// Rules running in shadow mode: they evaluate and count would-have-fired
+/// alerts, but those alerts never reach the feed, archive or notifications.
+/// All off by default.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShadowConfig {
+    pub volume_anomaly: bool,
+    pub price_spike: bool,
+    pub rapid_fire: bool,
+    pub wash_trading: bool,
+    pub suspicious_match: bool,
+    pub front_running: bool,
+    pub ml_anomaly: bool,
+    pub diversification: bool,
+    pub book_imbalance: bool,
+    pub momentum_ignition: bool,
+    pub insider_trading: bool,
+    pub price_improvement: bool,
+    pub counterparty_concentration: bool,
+    pub price_velocity: bool,
+}
+
+/// Seconds after an alert during which repeats of its type for the same
+/// account (or symbol, for symbol-level rules) are suppressed. 0 = off, the
+/// default for every type.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CooldownConfig {
+    pub volume_anomaly: u64,
+    pub price_spike: u64,
+    pub rapid_fire: u64,
+    pub wash_trading: u64,
+    pub suspicious_match: u64,
+    pub front_running: u64,
+    pub ml_anomaly: u64,
+    pub diversification: u64,
+    pub book_imbalance: u64,
+    pub momentum_ignition: u64,
+    pub insider_trading: u64,
+    pub price_improvement: u64,
+    pub counterparty_concentration: u64,
+    pub price_velocity: u64,
+}
+
+/// Alert severity as written in `[severity]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeverityLevel {
+    Medium,
+    High,
+    Critical,
+}
+
+/// Floor and ceiling for one alert type's severity; either may be unset.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SeverityBounds {
+    pub min: Option<SeverityLevel>,
+    pub max: Option<SeverityLevel>,
+}
+
+/// Per-deployment severity remapping by alert type, e.g.
+/// `wash_trading = { min = "critical" }` or `rapid_fire = { max = "medium" }`.
+/// Applied after a rule computes its severity and before the alert is
+/// counted, archived or notified; thresholds are untouched.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SeverityConfig {
+    pub volume_anomaly: SeverityBounds,
+    pub price_spike: SeverityBounds,
+    pub rapid_fire: SeverityBounds,
+    pub wash_trading: SeverityBounds,
+    pub suspicious_match: SeverityBounds,
+    pub front_running: SeverityBounds,
+    pub ml_anomaly: SeverityBounds,
+    pub diversification: SeverityBounds,
+    pub book_imbalance: SeverityBounds,
+    pub momentum_ignition: SeverityBounds,
+    pub insider_trading: SeverityBounds,
+    pub price_improvement: SeverityBounds,
+    pub counterparty_concentration: SeverityBounds,
+    pub price_velocity: SeverityBounds,
+}
+
+/// The engine's tick-level PriceVelocity detector: a move of `move_pct`
+/// (a fraction, like the `[thresholds]` `_pct` keys) within `window_ms` of
+/// trades in one symbol. 0 = off, the default.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TickConfig {
+    pub move_pct: f64,
+    pub window_ms: u64,
+}
+
+impl Default for TickConfig {
+    fn default() -> Self {
+        Self { move_pct: 0.0, window_ms: 2_000 }
+    }
+}
+
+/// How VolumeAnomaly decides a window is anomalous.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeMode {
+    /// Window volume over the symbol's recent average (`volume_ratio_threshold`).
+    #[default]
+    Ratio,
+    /// Window volume as a percentage of the symbol's estimated ADV.
+    Adv,
+}
+
+/// Average-daily-volume context for VolumeAnomaly. Each symbol's ADV is
+/// extrapolated from its 10s window volumes to a `trading_day_secs` day.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VolumeConfig {
+    pub mode: VolumeMode,
+    /// In `adv` mode, window volume above this percent of ADV fires.
+    pub adv_pct_threshold: f64,
+    pub trading_day_secs: u64,
+}
+
+impl Default for VolumeConfig {
+    fn default() -> Self {
+        // A 10s window is ~0.043% of a 6.5h session, so 0.25% is ~6x normal
+        Self { mode: VolumeMode::Ratio, adv_pct_threshold: 0.25, trading_day_secs: 23_400 }
+    }
+}
+
+/// Rule thresholds for `AlertEngine`; the defaults are its built-in values.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThresholdsConfig {
+    /// Per-symbol `total_volume` samples averaged for the volume baseline.
+    pub volume_history_len: usize,
+    pub volume_ratio: f64,
+    pub price_range_pct: f64,
+    pub rapid_fire_trades: i64,
+    pub wash_imbalance: f64,
+    pub match_price_diff: f64,
+    pub front_run_spread: f64,
+    pub ml_score: f64,
+    /// Distinct symbols in a window over the account's average that triggers Diversification.
+    pub diversification_ratio: f64,
+    pub diversification_min_symbols: i64,
+    pub diversification_min_history: usize,
+    /// One-sided book depth, `(bid - ask) / (bid + ask)`, that arms BookImbalance.
+    pub book_imbalance: f64,
+    /// Mid-price move toward the heavy side that confirms an armed imbalance.
+    pub book_move_pct: f64,
+    /// Consecutive windows at or over `book_imbalance`, on the same side,
+    /// that arm BookImbalance. At least 1.
+    pub book_enter_windows: usize,
+    /// Finished windows under `book_imbalance` in a row after a
+    /// BookImbalance alert before the symbol can arm again. 0 = none.
+    pub book_exit_windows: usize,
+    /// Fewest trades in a one-sided session that can arm MomentumIgnition.
+    pub momentum_min_trades: i64,
+    /// Share of a session's trades on one side for it to count as one-sided.
+    pub momentum_side_share: f64,
+    /// Move from the burst's first price to the unwind's VWAP that confirms an ignition.
+    pub momentum_displacement_pct: f64,
+    /// Fewest |impact| a news event needs before trading ahead of it is checked.
+    pub insider_min_impact: f64,
+    /// Volume traded in the news' direction ahead of it that fires InsiderTrading.
+    pub insider_min_volume: i64,
+    /// Share of the account's pre-news volume that must be on the news' side.
+    pub insider_side_share: f64,
+    /// How far through the quote, as a fraction of it, a fill must be to
+    /// count as improved for PriceImprovement.
+    pub price_improvement_pct: f64,
+    /// Improved fills between one account pair within the window that fire PriceImprovement.
+    pub price_improvement_fills: usize,
+    /// Share of an account's window volume against one counterparty that fires CounterpartyConcentration.
+    pub concentration_share: f64,
+    /// Fewest trades with a known counterparty in a window before its concentration is checked.
+    pub concentration_min_trades: i64,
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            volume_history_len: 20,
+            volume_ratio: 2.0,
+            price_range_pct: 0.002,
+            rapid_fire_trades: 5,
+            wash_imbalance: 0.3,
+            match_price_diff: 1.0,
+            front_run_spread: 0.5,
+            ml_score: 0.9,
+            diversification_ratio: 3.0,
+            diversification_min_symbols: 3,
+            diversification_min_history: 3,
+            book_imbalance: 0.6,
+            book_move_pct: 0.002,
+            book_enter_windows: 1,
+            book_exit_windows: 1,
+            momentum_min_trades: 8,
+            momentum_side_share: 0.8,
+            momentum_displacement_pct: 0.005,
+            insider_min_impact: 0.7,
+            insider_min_volume: 4_000,
+            insider_side_share: 0.8,
+            price_improvement_pct: 0.0015,
+            price_improvement_fills: 5,
+            concentration_share: 0.8,
+            concentration_min_trades: 10,
+        }
+    }
+}
+
+/// How counts, latencies and timestamps are written in the TUI, the
+/// headless summary and the printed reports. JSON output is unaffected.
+/// Applied once at startup; see `locale::install`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    pub grouping: Grouping,
+    pub timezone: Timezone,
+    pub timestamps: TimestampStyle,
+}
+
+/// Thousands separator for counts and latencies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Grouping {
+    /// `1234567`
+    #[default]
+    None,
+    /// `1,234,567`
+    Comma,
+    /// `1.234.567`
+    Period,
+    /// `1 234 567`
+    Space,
+    /// `1_234_567`
+    Underscore,
+    /// Whichever of the above `LC_ALL`, `LC_NUMERIC` or `LANG` uses.
+    Locale,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Timezone {
+    #[default]
+    Utc,
+    /// The system's local time zone.
+    Local,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampStyle {
+    /// `2026-10-16 09:30:00`
+    #[default]
+    Plain,
+    /// `2026-10-16T09:30:00.000Z`, or with the UTC offset in local time.
+    Iso8601,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read config {}: {e}", path.display()))?;
+        let config = Self::parse(&text)
+            .map_err(|e| format!("invalid config {}: {e}", path.display()))?;
+        Ok(config)
+    }
+
+    /// A config from TOML text; sections left out take their defaults.
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    /// This config with its secrets (API tokens, the Slack signing secret)
+    /// replaced, for copies written to the audit log or shared bundles.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for token in &mut config.auth.tokens {
+            token.token = REDACTED.to_string();
+        }
+        if config.slack.signing_secret.is_some() {
+            config.slack.signing_secret = Some(REDACTED.to_string());
+        }
+        config
+    }
+}
+
+const REDACTED: &str = "<redacted>";
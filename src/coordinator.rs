@@ -0,0 +1,247 @@
+//! Scale-out fan-in. Several `run --shard i/n --web-port ...` instances each
+//! detect on their own symbols; `coordinate` subscribes to every one's
+//! dashboard feed and serves the combined view on one dashboard, with the
+//! same `/ws` feed and read endpoints as a single engine. Account-level
+//! activity split across instances is added up and judged again here (see
+//! [`crate::risk`]).
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::alerts::{Alert, AlertEngine, SymbolAlerts};
+use crate::config::Config;
+use crate::ids::UidGenerator;
+use crate::latency::LatencyStats;
+use crate::risk::RiskMerger;
+use crate::slo;
+use crate::web::{self, DashboardUpdate, StreamStatus};
+
+/// Wait between attempts to reach an instance that isn't up or dropped.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// One dashboard update standing for all of `parts`, the latest from each
+/// instance. Counts add up, SLO attainment included; latency percentiles,
+/// lags and queue depths take the worst instance, since a percentile can't be
+/// combined exactly without the samples. Alerts and accounts are left empty
+/// for the caller to fill with whatever arrived new and the merged account
+/// risk. Feed integrity is kept per instance, keyed `feed[i]`.
+pub fn merge(parts: &[DashboardUpdate]) -> DashboardUpdate {
+    let mut merged = DashboardUpdate { retention: parts.first().map(|p| p.retention).unwrap_or_default(), ..Default::default() };
+    let mut streams: Vec<StreamStatus> = Vec::new();
+    let mut symbols: BTreeMap<String, SymbolAlerts> = BTreeMap::new();
+    for (i, p) in parts.iter().enumerate() {
+        merged.total_trades += p.total_trades;
+        merged.total_orders += p.total_orders;
+        merged.total_alerts += p.total_alerts;
+        merged.uptime_secs = merged.uptime_secs.max(p.uptime_secs);
+        merged.degraded |= p.degraded;
+        merged.shed_cycles += p.shed_cycles;
+        merged.dropped_alerts += p.dropped_alerts;
+        merged.alerts_generated += p.alerts_generated;
+        merged.alerts_delivered += p.alerts_delivered;
+        merged.suppressed_alerts += p.suppressed_alerts;
+        merged.model_suppressed += p.model_suppressed;
+        merged.cooldown_suppressed += p.cooldown_suppressed;
+        merged.join_fanout_per_sec += p.join_fanout_per_sec;
+        merged.join_overloaded |= p.join_overloaded;
+        merged.pipeline_switches += p.pipeline_switches;
+        merged.prices.extend(p.prices.clone());
+        add_counts(&mut merged.alert_counts, &p.alert_counts);
+        add_counts(&mut merged.shadow_counts, &p.shadow_counts);
+        merged.cooldowns.extend(p.cooldowns.iter().cloned());
+        merged.standby = merged.standby.take().or_else(|| p.standby.clone());
+        slo::merge(&mut merged.slo, &p.slo);
+
+        let l = &mut merged.latency;
+        let pl = &p.latency;
+        for (into, from) in [(&mut l.push, &pl.push), (&mut l.processing, &pl.processing), (&mut l.alert, &pl.alert), (&mut l.tick_lag, &pl.tick_lag), (&mut l.bar_lag, &pl.bar_lag)] {
+            worst(into, from);
+        }
+
+        for s in &p.streams {
+            match streams.iter_mut().find(|m| m.name == s.name) {
+                Some(m) => {
+                    m.count += s.count;
+                    m.active |= s.active;
+                    m.budget_exhausted += s.budget_exhausted;
+                    m.carried_rows += s.carried_rows;
+                }
+                None => streams.push(s.clone()),
+            }
+        }
+        for s in &p.symbol_alerts {
+            let entry = symbols.entry(s.symbol.clone()).or_insert_with(|| SymbolAlerts { symbol: s.symbol.clone(), total: 0, by_type: BTreeMap::new() });
+            entry.total += s.total;
+            for (t, n) in &s.by_type {
+                *entry.by_type.entry(t.clone()).or_default() += n;
+            }
+        }
+        for (feed, stats) in &p.feed_integrity {
+            merged.feed_integrity.insert(format!("{feed}[{i}]"), stats.clone());
+        }
+
+        let ps = &mut merged.push_stats;
+        ps.deferred_batches += p.push_stats.deferred_batches;
+        ps.rejected_batches += p.push_stats.rejected_batches;
+        ps.rejected_records += p.push_stats.rejected_records;
+        ps.buffer_pressure = ps.buffer_pressure.max(p.push_stats.buffer_pressure);
+
+        let e = &mut merged.eval_stats;
+        e.batches += p.eval_stats.batches;
+        e.rows += p.eval_stats.rows;
+        e.max_queue_depth = e.max_queue_depth.max(p.eval_stats.max_queue_depth);
+        e.last_queue_depth = e.last_queue_depth.max(p.eval_stats.last_queue_depth);
+        e.queue_full += p.eval_stats.queue_full;
+        e.wait_us += p.eval_stats.wait_us;
+
+        // The instance furthest behind sets the pace
+        let h = &mut merged.heartbeat;
+        h.seq = if i == 0 { p.heartbeat.seq } else { h.seq.min(p.heartbeat.seq) };
+        h.cycle_us = h.cycle_us.max(p.heartbeat.cycle_us);
+        h.gap_ms = h.gap_ms.max(p.heartbeat.gap_ms);
+        h.rows_polled += p.heartbeat.rows_polled;
+    }
+    merged.streams = streams;
+    merged.cooldowns.sort_by_key(|c| c.until_ms);
+    merged.symbol_alerts = symbols.into_values().collect();
+    merged.symbol_alerts.sort_by(|a, b| b.total.cmp(&a.total).then(a.symbol.cmp(&b.symbol)));
+    merged
+}
+
+fn add_counts(into: &mut HashMap<String, u64>, from: &HashMap<String, u64>) {
+    for (k, n) in from {
+        *into.entry(k.clone()).or_default() += n;
+    }
+}
+
+fn worst(into: &mut LatencyStats, from: &LatencyStats) {
+    if from.count == 0 {
+        return;
+    }
+    if into.count == 0 {
+        *into = from.clone();
+        return;
+    }
+    into.p50_us = into.p50_us.max(from.p50_us);
+    into.p95_us = into.p95_us.max(from.p95_us);
+    into.p99_us = into.p99_us.max(from.p99_us);
+    into.max_us = into.max_us.max(from.max_us);
+    into.min_us = into.min_us.min(from.min_us);
+    into.count += from.count;
+}
+
+/// Serve the combined dashboard of `upstreams` on `port` for `duration`
+/// seconds (0 = until stopped).
+pub async fn run(port: u16, upstreams: Vec<String>, duration: u64, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+    run_on(listener, upstreams, duration, config).await
+}
+
+/// [`run`] on an already-bound listener, e.g. an ephemeral port in tests.
+pub async fn run_on(listener: TcpListener, upstreams: Vec<String>, duration: u64, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    if upstreams.is_empty() {
+        return Err("coordinate needs at least one --upstream".into());
+    }
+    let mut web = web::serve_on(listener, &config).await?;
+    let blotter = web.blotter();
+    let mut alert_engine = AlertEngine::from_config(&config)?;
+    let mut uids = UidGenerator::from_config(&config.ids);
+    let mut risk = RiskMerger::from_config(&config.coordinator)?;
+    // When each account last had a RapidFire or WashTrading alert from an instance
+    let mut fired: HashMap<(&'static str, String), i64> = HashMap::new();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    for (i, url) in upstreams.iter().enumerate() {
+        tokio::spawn(follow(i, url.clone(), tx.clone()));
+    }
+    drop(tx);
+
+    let mut latest: Vec<Option<DashboardUpdate>> = vec![None; upstreams.len()];
+    let deadline = (duration > 0).then(|| Instant::now() + Duration::from_secs(duration));
+    loop {
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline.into(), rx.recv()).await {
+                Ok(next) => next,
+                Err(_) => break,
+            },
+            None => rx.recv().await,
+        };
+        let Some((i, mut update)) = next else { break };
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut alerts = std::mem::take(&mut update.alerts);
+        let shadow_alerts = std::mem::take(&mut update.shadow_alerts);
+        for a in &alerts {
+            if let Some(account_id) = &a.account_id {
+                fired.insert((a.alert_type.label(), account_id.clone()), now_ms);
+            }
+        }
+
+        // Activity no single instance alerted on may still fire once added up
+        let (bursts, washes) = risk.observe(i, &std::mem::take(&mut update.accounts), now_ms);
+        let window_ms = risk.window_ms();
+        let recent = |label: &'static str, account_id: &str| fired.get(&(label, account_id.to_string())).is_some_and(|&at| now_ms - at <= window_ms);
+        let start = Instant::now();
+        let mut combined: Vec<Alert> = Vec::new();
+        for row in bursts.iter().filter(|r| !recent("RapidFire", &r.account_id)) {
+            combined.extend(alert_engine.evaluate_rapid_fire(row, start));
+        }
+        for row in washes.iter().filter(|r| !recent("WashTrading", &r.account_id)) {
+            combined.extend(alert_engine.evaluate_wash(row, start));
+        }
+        for a in combined.iter_mut() {
+            a.uid = uids.as_mut().map(|u| u.next(now_ms));
+        }
+        alerts.extend(combined);
+        blotter.record_alerts(&alerts);
+
+        latest[i] = Some(update);
+        let parts: Vec<DashboardUpdate> = latest.iter().flatten().cloned().collect();
+        let mut merged = merge(&parts);
+        merged.total_alerts += alert_engine.total_alerts();
+        add_counts(&mut merged.alert_counts, alert_engine.alert_counts());
+        web.publish(&DashboardUpdate { alerts, shadow_alerts, accounts: risk.accounts(now_ms), ..merged });
+    }
+    web.flush();
+    Ok(())
+}
+
+/// Forward every update from instance `i` at `url`, reconnecting whenever
+/// it isn't reachable, until the coordinator stops listening.
+async fn follow(i: usize, url: String, tx: mpsc::UnboundedSender<(usize, DashboardUpdate)>) {
+    let mut reported = false;
+    loop {
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((mut ws, _)) => {
+                eprintln!("Coordinator: following {url}");
+                reported = false;
+                while let Some(Ok(msg)) = ws.next().await {
+                    match msg {
+                        // Frames from a different build may not parse; skip rather than drop the feed
+                        Message::Text(json) => {
+                            let Ok(update) = serde_json::from_str::<DashboardUpdate>(&json) else { continue };
+                            if tx.send((i, update)).is_err() {
+                                return;
+                            }
+                        }
+                        Message::Close(_) => break,
+                        _ => {}
+                    }
+                }
+                eprintln!("Coordinator: lost {url}, reconnecting");
+            }
+            Err(e) if !reported => {
+                eprintln!("Coordinator: can't reach {url} yet ({e}), retrying");
+                reported = true;
+            }
+            Err(_) => {}
+        }
+        if tx.is_closed() {
+            return;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
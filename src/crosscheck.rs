@@ -0,0 +1,368 @@
+//! Cross-check mode: recompute `ohlc_vol`, `vol_baseline` and `wash_score`
+//! in plain Rust from the trades pushed, and diff the pipeline's rows against
+//! them window by window.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use laminar_db::{FromBatch, TypedSubscription};
+use serde::Serialize;
+
+use crate::detection::{self, DetectionPipeline, OHLC_BAR_MS};
+use crate::generator::FraudGenerator;
+use crate::locale;
+use crate::testkit;
+use crate::types::*;
+
+/// `vol_baseline` HOP slide and size.
+const VOL_SLIDE_MS: i64 = 2_000;
+const VOL_WINDOW_MS: i64 = 10_000;
+/// `wash_score` TUMBLE size.
+const WASH_WINDOW_MS: i64 = 5_000;
+
+/// One row the reference and the stream disagree on. `expected` is `None`
+/// for a stream row with no reference window, `actual` for a reference
+/// window the stream never emitted.
+#[derive(Debug, Clone, Serialize)]
+pub struct Discrepancy {
+    pub key: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// One stream's result: reference windows, how many the stream matched, and
+/// every disagreement.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamCheck {
+    pub stream: &'static str,
+    pub windows: usize,
+    pub rows: usize,
+    pub matched: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossCheckReport {
+    pub seed: u64,
+    pub cycles: u64,
+    pub trades: usize,
+    pub streams: Vec<StreamCheck>,
+}
+
+impl CrossCheckReport {
+    pub fn is_clean(&self) -> bool {
+        self.streams.iter().all(|s| s.discrepancies.is_empty())
+    }
+
+    pub fn print(&self) {
+        println!("  Cross-check: {} trades over {} cycles (seed {})", locale::count(self.trades as u64), self.cycles, self.seed);
+        for s in &self.streams {
+            println!(
+                "    {:<14} {:>6} windows  {:>6} rows  {:>6} matched  {} discrepancies",
+                s.stream,
+                s.windows,
+                s.rows,
+                s.matched,
+                s.discrepancies.len()
+            );
+            for d in s.discrepancies.iter().take(10) {
+                println!(
+                    "      {}: expected {} got {}",
+                    d.key,
+                    d.expected.as_deref().unwrap_or("nothing"),
+                    d.actual.as_deref().unwrap_or("nothing")
+                );
+            }
+            if s.discrepancies.len() > 10 {
+                println!("      ... {} more", s.discrepancies.len() - 10);
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Generate `cycles` cycles `cycle_ms` apart in event time, push them through
+/// a fresh pipeline, and check its aggregates against the reference. The
+/// watermark trails each cycle's event time, so no generated trade is late.
+pub async fn run(cycles: u64, cycle_ms: i64, fraud_rate: f64, seed: u64) -> Result<CrossCheckReport, Box<dyn std::error::Error>> {
+    let pipeline = detection::setup().await?;
+    let ohlc_sub = pipeline.ohlc_vol_sub.as_ref().ok_or("ohlc_vol stream is not running")?;
+    let vol_sub = pipeline.vol_baseline_sub.as_ref().ok_or("vol_baseline stream is not running")?;
+    let wash_sub = pipeline.wash_score_sub.as_ref().ok_or("wash_score stream is not running")?;
+
+    let mut gen = FraudGenerator::with_seed(fraud_rate, seed);
+    let mut pushed = Vec::new();
+    let (mut ohlc, mut vol, mut wash) = (Vec::new(), Vec::new(), Vec::new());
+    let mut ts = 100_000;
+    for _ in 0..cycles {
+        let (trades, _) = gen.generate_cycle(ts);
+        pushed.extend(trades.iter().cloned());
+        pipeline.trade_source.push_batch(trades);
+        advance(&pipeline, ts);
+        drain(ohlc_sub, &mut ohlc);
+        drain(vol_sub, &mut vol);
+        drain(wash_sub, &mut wash);
+        ts += cycle_ms;
+    }
+
+    // Close every window the generator's future-dated trades reach into
+    advance(&pipeline, pushed.iter().map(|t| t.ts).max().unwrap_or(ts) + VOL_WINDOW_MS * 2);
+    // The pipeline works through the backlog only while this task waits, and
+    // a micro-batch can outlast the quiet period, so wait out the source first
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while pipeline.trade_source.pending() > 0 && Instant::now() < deadline {
+        drain(ohlc_sub, &mut ohlc);
+        drain(vol_sub, &mut vol);
+        drain(wash_sub, &mut wash);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    ohlc.extend(testkit::collect(ohlc_sub, Duration::from_secs(5)).await);
+    vol.extend(testkit::collect(vol_sub, Duration::from_secs(5)).await);
+    wash.extend(testkit::collect(wash_sub, Duration::from_secs(5)).await);
+    let _ = pipeline.db.shutdown().await;
+
+    Ok(CrossCheckReport {
+        seed,
+        cycles,
+        trades: pushed.len(),
+        streams: vec![check_ohlc(&pushed, &ohlc), check_vol_baseline(&pushed, &vol), check_wash(&pushed, &wash)],
+    })
+}
+
+fn advance(pipeline: &DetectionPipeline, watermark: i64) {
+    pipeline.trade_source.watermark(watermark);
+    pipeline.order_source.watermark(watermark);
+    pipeline.book_source.watermark(watermark);
+    pipeline.news_source.watermark(watermark);
+}
+
+fn drain<T: Clone + FromBatch>(sub: &TypedSubscription<T>, into: &mut Vec<T>) {
+    while let Some(rows) = sub.poll() {
+        into.extend(rows);
+    }
+}
+
+/// Equal to within float summation error.
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-6 * a.abs().max(b.abs()).max(1.0)
+}
+
+/// Reference `ohlc_vol` bars by symbol and bar start. Open and close follow
+/// push order, as `first_value`/`last_value` do.
+pub fn reference_ohlc(trades: &[Trade]) -> BTreeMap<(String, i64), OhlcVolatility> {
+    let mut bars: BTreeMap<(String, i64), OhlcVolatility> = BTreeMap::new();
+    for t in trades {
+        let bar_start = t.ts - t.ts.rem_euclid(OHLC_BAR_MS);
+        let notional = t.price * t.volume as f64;
+        bars.entry((t.symbol.clone(), bar_start))
+            .and_modify(|b| {
+                b.high = b.high.max(t.price);
+                b.low = b.low.min(t.price);
+                b.close = t.price;
+                b.volume += t.volume;
+                b.notional += notional;
+                b.price_range = b.high - b.low;
+            })
+            .or_insert_with(|| OhlcVolatility {
+                symbol: t.symbol.clone(),
+                bar_start,
+                open: t.price,
+                high: t.price,
+                low: t.price,
+                close: t.price,
+                volume: t.volume,
+                price_range: 0.0,
+                notional,
+            });
+    }
+    bars
+}
+
+/// Fold `row` into `bar`, a later part of the same bar.
+fn merge_bar(bar: &mut OhlcVolatility, row: &OhlcVolatility) {
+    bar.high = bar.high.max(row.high);
+    bar.low = bar.low.min(row.low);
+    bar.close = row.close;
+    bar.volume += row.volume;
+    bar.notional += row.notional;
+    bar.price_range = bar.high - bar.low;
+}
+
+/// Diff `ohlc_vol` rows against the reference, keyed by symbol and bar start.
+/// The rows of one bar are folded in arrival order first.
+pub fn check_ohlc(trades: &[Trade], rows: &[OhlcVolatility]) -> StreamCheck {
+    let expected = reference_ohlc(trades);
+    let same = |a: &OhlcVolatility, b: &OhlcVolatility| {
+        close(a.open, b.open)
+            && close(a.high, b.high)
+            && close(a.low, b.low)
+            && close(a.close, b.close)
+            && a.volume == b.volume
+            && close(a.notional, b.notional)
+            && close(a.price_range, b.price_range)
+    };
+    let fmt = |b: &OhlcVolatility| {
+        format!("O={} H={} L={} C={} vol={} notional={:.2}", b.open, b.high, b.low, b.close, b.volume, b.notional)
+    };
+    let window = |b: &OhlcVolatility| (format!("{} bar", b.symbol), b.bar_start);
+    match_by_window("ohlc_vol", expected.into_values(), rows, merge_bar, same, fmt, window)
+}
+
+/// Reference `vol_baseline` windows by symbol and window start: every
+/// 10s HOP window, sliding by 2s, that holds at least one trade.
+pub fn reference_vol_baseline(trades: &[Trade]) -> BTreeMap<(String, i64), VolumeBaseline> {
+    let mut windows: BTreeMap<(String, i64), (VolumeBaseline, f64)> = BTreeMap::new();
+    for t in trades {
+        let last_start = t.ts - t.ts.rem_euclid(VOL_SLIDE_MS);
+        let mut start = last_start - VOL_WINDOW_MS + VOL_SLIDE_MS;
+        while start <= last_start {
+            let (w, price_sum) = windows.entry((t.symbol.clone(), start)).or_insert_with(|| {
+                let empty = VolumeBaseline { symbol: t.symbol.clone(), window_start: start, total_volume: 0, trade_count: 0, avg_price: 0.0, total_notional: 0.0 };
+                (empty, 0.0)
+            });
+            w.total_volume += t.volume;
+            w.trade_count += 1;
+            w.total_notional += t.price * t.volume as f64;
+            *price_sum += t.price;
+            w.avg_price = *price_sum / w.trade_count as f64;
+            start += VOL_SLIDE_MS;
+        }
+    }
+    windows.into_iter().map(|(k, (w, _))| (k, w)).collect()
+}
+
+/// Fold `row` into `w`, a later part of the same window.
+fn merge_vol(w: &mut VolumeBaseline, row: &VolumeBaseline) {
+    let price_sum = w.avg_price * w.trade_count as f64 + row.avg_price * row.trade_count as f64;
+    w.total_volume += row.total_volume;
+    w.trade_count += row.trade_count;
+    w.total_notional += row.total_notional;
+    w.avg_price = price_sum / w.trade_count.max(1) as f64;
+}
+
+/// Diff `vol_baseline` rows against the reference, keyed by symbol and
+/// window start.
+pub fn check_vol_baseline(trades: &[Trade], rows: &[VolumeBaseline]) -> StreamCheck {
+    let expected = reference_vol_baseline(trades);
+    let same = |a: &VolumeBaseline, b: &VolumeBaseline| {
+        a.total_volume == b.total_volume
+            && a.trade_count == b.trade_count
+            && close(a.avg_price, b.avg_price)
+            && close(a.total_notional, b.total_notional)
+    };
+    let fmt = |w: &VolumeBaseline| {
+        format!("vol={} trades={} avg={:.4} notional={:.2}", w.total_volume, w.trade_count, w.avg_price, w.total_notional)
+    };
+    let window = |w: &VolumeBaseline| (format!("{} window", w.symbol), w.window_start);
+    match_by_window("vol_baseline", expected.into_values(), rows, merge_vol, same, fmt, window)
+}
+
+/// Reference `wash_score` windows by account, symbol and 5s window start.
+pub fn reference_wash(trades: &[Trade]) -> BTreeMap<(String, i64), WashScore> {
+    let mut windows: BTreeMap<(String, i64), WashScore> = BTreeMap::new();
+    for t in trades {
+        let start = t.ts - t.ts.rem_euclid(WASH_WINDOW_MS);
+        let w = windows.entry((format!("{}/{}", t.account_id, t.symbol), start)).or_insert_with(|| WashScore {
+            account_id: t.account_id.clone(),
+            symbol: t.symbol.clone(),
+            window_start: start,
+            buy_volume: 0,
+            sell_volume: 0,
+            buy_count: 0,
+            sell_count: 0,
+            buy_notional: 0.0,
+            sell_notional: 0.0,
+        });
+        let notional = t.price * t.volume as f64;
+        match t.side.as_str() {
+            "buy" => {
+                w.buy_volume += t.volume;
+                w.buy_count += 1;
+                w.buy_notional += notional;
+            }
+            "sell" => {
+                w.sell_volume += t.volume;
+                w.sell_count += 1;
+                w.sell_notional += notional;
+            }
+            _ => {}
+        }
+    }
+    windows
+}
+
+/// Fold `row` into `w`, a later part of the same window.
+fn merge_wash(w: &mut WashScore, row: &WashScore) {
+    w.buy_volume += row.buy_volume;
+    w.sell_volume += row.sell_volume;
+    w.buy_count += row.buy_count;
+    w.sell_count += row.sell_count;
+    w.buy_notional += row.buy_notional;
+    w.sell_notional += row.sell_notional;
+}
+
+/// Diff `wash_score` rows against the reference, keyed by account, symbol
+/// and window start.
+pub fn check_wash(trades: &[Trade], rows: &[WashScore]) -> StreamCheck {
+    let expected = reference_wash(trades);
+    let same = |a: &WashScore, b: &WashScore| {
+        a.buy_volume == b.buy_volume
+            && a.sell_volume == b.sell_volume
+            && a.buy_count == b.buy_count
+            && a.sell_count == b.sell_count
+            && close(a.buy_notional, b.buy_notional)
+            && close(a.sell_notional, b.sell_notional)
+    };
+    let fmt = |w: &WashScore| {
+        format!(
+            "buy={}x{} sell={}x{} notional={:.2}/{:.2}",
+            w.buy_count, w.buy_volume, w.sell_count, w.sell_volume, w.buy_notional, w.sell_notional
+        )
+    };
+    let window = |w: &WashScore| (format!("{}/{} window", w.account_id, w.symbol), w.window_start);
+    match_by_window("wash_score", expected.into_values(), rows, merge_wash, same, fmt, window)
+}
+
+/// Fold the stream rows by key and window start, then compare them window
+/// by window with the reference; a window on only one side is a
+/// discrepancy. The pipeline emits a window's aggregate in parts, one per
+/// micro-batch, so rows are never compared unfolded.
+fn match_by_window<T: Clone>(
+    stream: &'static str,
+    expected: impl Iterator<Item = T>,
+    rows: &[T],
+    merge: impl Fn(&mut T, &T),
+    same: impl Fn(&T, &T) -> bool,
+    fmt: impl Fn(&T) -> String,
+    window: impl Fn(&T) -> (String, i64),
+) -> StreamCheck {
+    let mut expected: BTreeMap<(String, i64), T> = expected.map(|w| (window(&w), w)).collect();
+    let mut folded: BTreeMap<(String, i64), T> = BTreeMap::new();
+    for row in rows {
+        match folded.get_mut(&window(row)) {
+            Some(acc) => merge(acc, row),
+            None => {
+                folded.insert(window(row), row.clone());
+            }
+        }
+    }
+    let mut check = StreamCheck { stream, windows: expected.len(), rows: rows.len(), matched: 0, discrepancies: Vec::new() };
+    for ((key, start), row) in folded {
+        let w = expected.remove(&(key.clone(), start));
+        let key = format!("{key} {start}");
+        match w {
+            Some(w) if same(&w, &row) => check.matched += 1,
+            Some(w) => check.discrepancies.push(Discrepancy { key, expected: Some(fmt(&w)), actual: Some(fmt(&row)) }),
+            None => check.discrepancies.push(Discrepancy { key, expected: None, actual: Some(fmt(&row)) }),
+        }
+    }
+    for ((key, start), w) in expected {
+        check.discrepancies.push(Discrepancy { key: format!("{key} {start}"), expected: Some(fmt(&w)), actual: None });
+    }
+    check
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::AlertEngine;
+use crate::config::{Config, JoinConfig};
+use crate::detection::{self, PipelineOptions, SetupStatement};
+use crate::engine::WatermarkStrategy;
+use crate::generator;
+use crate::pacing::Pacer;
+
+/// What a `run` with one config would execute, for reproducing it or
+/// attaching to a support ticket: the config with every default filled in,
+/// the settings setup derives from it, and each CREATE statement as issued.
+/// Secrets in the config are redacted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bundle {
+    pub version: String,
+    pub config: Config,
+    /// The cycle interval the pacer starts at.
+    pub cycle_interval_ms: u64,
+    /// `[join]` after the fan-out guardrail; narrower than `config.join` when
+    /// `clamp` applied.
+    pub effective_join: JoinConfig,
+    pub watermark: WatermarkStrategy,
+    pub statements: Vec<SetupStatement>,
+    /// Streams setup was asked for that were not created.
+    pub failed_streams: Vec<String>,
+}
+
+/// Check `config` as `run` would, then set up the pipeline it describes and
+/// record every statement executed. The pipeline is shut down again before
+/// returning; nothing is pushed through it.
+pub async fn bundle(config: &Config) -> Result<Bundle, Box<dyn std::error::Error>> {
+    AlertEngine::from_config(config)?;
+    let watermark = WatermarkStrategy::from_config(&config.watermark)?;
+    let interval = Pacer::from_config(config)?.interval();
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(interval);
+    let join = detection::guard_join_band(&config.join, trades_per_sec, orders_per_sec);
+    let pipeline = detection::setup_with(&PipelineOptions { sessions: config.sessions.clone(), join: join.clone(), ..Default::default() }).await?;
+    let bundle = Bundle {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        config: config.redacted(),
+        cycle_interval_ms: interval.as_millis() as u64,
+        effective_join: join,
+        watermark,
+        statements: pipeline.statements.clone(),
+        failed_streams: pipeline.streams_created.iter().filter(|(_, ok)| !ok).map(|(name, _)| name.clone()).collect(),
+    };
+    let _ = pipeline.db.shutdown().await;
+    Ok(bundle)
+}
+
+impl Bundle {
+    /// The statements as one script, each ending in `;`.
+    pub fn sql(&self) -> String {
+        self.statements.iter().map(|s| format!("{};\n", s.sql)).collect::<Vec<_>>().join("\n")
+    }
+}
@@ -1,41 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
 use laminar_db::LaminarDB;
+use serde::{Deserialize, Serialize};
 
+use crate::config::{JoinConfig, SessionConfig};
+use crate::generator::SYMBOLS;
 use crate::types::*;
 
 pub struct DetectionPipeline {
     pub db: LaminarDB,
     pub trade_source: laminar_db::SourceHandle<Trade>,
     pub order_source: laminar_db::SourceHandle<Order>,
+    pub book_source: laminar_db::SourceHandle<BookLevel>,
+    pub news_source: laminar_db::SourceHandle<NewsEvent>,
     pub vol_baseline_sub: Option<laminar_db::TypedSubscription<VolumeBaseline>>,
     pub ohlc_vol_sub: Option<laminar_db::TypedSubscription<OhlcVolatility>>,
     pub rapid_fire_sub: Option<laminar_db::TypedSubscription<RapidFireBurst>>,
+    /// One `rapid_fire_<profile>` stream per configured session profile.
+    pub rapid_fire_profile_subs: Vec<laminar_db::TypedSubscription<RapidFireBurst>>,
     pub wash_score_sub: Option<laminar_db::TypedSubscription<WashScore>>,
     pub suspicious_match_sub: Option<laminar_db::TypedSubscription<SuspiciousMatch>>,
     pub asof_match_sub: Option<laminar_db::TypedSubscription<AsofMatch>>,
+    pub account_activity_sub: Option<laminar_db::TypedSubscription<AccountActivity>>,
+    pub book_imbalance_sub: Option<laminar_db::TypedSubscription<BookImbalance>>,
+    pub momentum_burst_sub: Option<laminar_db::TypedSubscription<MomentumBurst>>,
+    pub pre_news_sub: Option<laminar_db::TypedSubscription<PreNewsTrade>>,
+    pub price_improvement_sub: Option<laminar_db::TypedSubscription<PriceImprovement>>,
+    pub counterparty_volume_sub: Option<laminar_db::TypedSubscription<CounterpartyVolume>>,
+    pub account_features_sub: Option<laminar_db::TypedSubscription<AccountWindow>>,
     pub streams_created: Vec<(String, bool)>,
+    /// Capacity of each source's buffer in records, as built.
+    pub buffer_size: usize,
+    /// Every CREATE statement setup issued, in order, with its outcome.
+    pub statements: Vec<SetupStatement>,
+    pub setup_timing: SetupTiming,
+}
+
+/// One CREATE SOURCE/STREAM/SINK statement as [`setup_with`] executed it,
+/// with `[sessions]` and `[join]` already substituted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupStatement {
+    pub sql: String,
+    /// `None` when it succeeded.
+    pub error: Option<String>,
+}
+
+impl SetupStatement {
+    fn record(statements: &mut Vec<SetupStatement>, sql: &str, result: &Result<(), String>) {
+        statements.push(SetupStatement { sql: dedent(sql), error: result.as_ref().err().cloned() });
+    }
+}
+
+impl DetectionPipeline {
+    /// Poll every subscription until it's empty and drop the rows, returning
+    /// how many there were.
+    pub fn discard_output(&self) -> u64 {
+        fn drain<T: laminar_db::FromBatch>(sub: Option<&laminar_db::TypedSubscription<T>>) -> u64 {
+            let mut rows = 0;
+            while let Some(batch) = sub.and_then(|s| s.poll()) {
+                rows += batch.len() as u64;
+            }
+            rows
+        }
+        drain(self.vol_baseline_sub.as_ref())
+            + drain(self.ohlc_vol_sub.as_ref())
+            + drain(self.rapid_fire_sub.as_ref())
+            + self.rapid_fire_profile_subs.iter().map(|s| drain(Some(s))).sum::<u64>()
+            + drain(self.wash_score_sub.as_ref())
+            + drain(self.suspicious_match_sub.as_ref())
+            + drain(self.asof_match_sub.as_ref())
+            + drain(self.account_activity_sub.as_ref())
+            + drain(self.book_imbalance_sub.as_ref())
+            + drain(self.momentum_burst_sub.as_ref())
+            + drain(self.pre_news_sub.as_ref())
+            + drain(self.price_improvement_sub.as_ref())
+            + drain(self.counterparty_volume_sub.as_ref())
+            + drain(self.account_features_sub.as_ref())
+    }
+}
+
+/// Wall time of each phase of [`setup_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SetupTiming {
+    pub sources: Duration,
+    pub streams: Duration,
+    /// Sinks and subscriptions.
+    pub sinks: Duration,
+    pub start: Duration,
+    /// Including building the database.
+    pub total: Duration,
+}
+
+impl fmt::Display for SetupTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}ms (sources {}ms, streams {}ms, sinks {}ms, start {}ms)",
+            self.total.as_millis(),
+            self.sources.as_millis(),
+            self.streams.as_millis(),
+            self.sinks.as_millis(),
+            self.start.as_millis()
+        )
+    }
+}
+
+/// Optional pipeline pieces beyond the twelve detection streams.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOptions {
+    /// Create the `account_features` stream used for training-data export.
+    /// Off by default: nothing polls it unless an export is requested.
+    pub feature_stream: bool,
+    /// Extra `(name, CREATE STREAM sql)` pairs, created with a sink after the
+    /// built-in streams. Subscribe to them by name once the pipeline is up.
+    pub extra_streams: Vec<(String, String)>,
+    /// Built-in detection streams to skip, by name. Used to measure the
+    /// marginal cost of each stream.
+    pub disabled_streams: Vec<String>,
+    /// SESSION gaps for `rapid_fire`, per account profile.
+    pub sessions: SessionConfig,
+    /// `suspicious_match` band; pass it through [`guard_join_band`] first.
+    pub join: JoinConfig,
+    /// Source buffer capacity in records; `None` uses [`DEFAULT_BUFFER_SIZE`].
+    pub buffer_size: Option<usize>,
+    /// Issue the sources', streams' and sinks' statements one at a time
+    /// rather than together, to compare setup time.
+    pub sequential_setup: bool,
 }
 
+pub const DEFAULT_BUFFER_SIZE: usize = 65536;
+
+/// Length of an `ohlc_vol` bar.
+pub const OHLC_BAR_MS: i64 = 5_000;
+
+/// How far ahead of a news event `pre_news` looks for trades in its symbol.
+pub const NEWS_LOOKBACK_MS: i64 = 20_000;
+
+/// Oldest book snapshot `price_improvement` prices a trade against. Shorter
+/// than the shortest cycle, so a trade only meets the quote of its own cycle.
+pub const QUOTE_MAX_AGE_MS: i64 = 10;
+
 pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
+    setup_with(&PipelineOptions::default()).await
+}
+
+pub async fn setup_with(options: &PipelineOptions) -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let buffer_size = options.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
     let db = LaminarDB::builder()
-        .buffer_size(65536)
+        .buffer_size(buffer_size)
         .build()
         .await?;
 
     // ── Sources ──
-    db.execute(
+    let mut phase = Instant::now();
+    let mut timing = SetupTiming::default();
+    let sources = [
         "CREATE SOURCE trades (
             account_id VARCHAR NOT NULL,
+            counterparty VARCHAR NOT NULL,
             symbol     VARCHAR NOT NULL,
             side       VARCHAR NOT NULL,
             price      DOUBLE NOT NULL,
             volume     BIGINT NOT NULL,
             order_ref  VARCHAR NOT NULL,
+            seq        BIGINT NOT NULL,
             ts         BIGINT NOT NULL
         )",
-    )
-    .await?;
-
-    db.execute(
         "CREATE SOURCE orders (
             order_id   VARCHAR NOT NULL,
             account_id VARCHAR NOT NULL,
@@ -43,27 +177,54 @@ pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
             side       VARCHAR NOT NULL,
             quantity   BIGINT NOT NULL,
             price      DOUBLE NOT NULL,
+            seq        BIGINT NOT NULL,
             ts         BIGINT NOT NULL
         )",
-    )
-    .await?;
+        "CREATE SOURCE book (
+            symbol     VARCHAR NOT NULL,
+            side       VARCHAR NOT NULL,
+            level      BIGINT NOT NULL,
+            price      DOUBLE NOT NULL,
+            size       BIGINT NOT NULL,
+            ts         BIGINT NOT NULL
+        )",
+        "CREATE SOURCE news_events (
+            symbol     VARCHAR NOT NULL,
+            headline   VARCHAR NOT NULL,
+            impact     DOUBLE NOT NULL,
+            ts         BIGINT NOT NULL
+        )",
+    ];
+    let mut statements = Vec::new();
+    for (sql, result) in sources.iter().zip(execute_all(&db, sources.iter().copied(), options.sequential_setup).await) {
+        SetupStatement::record(&mut statements, sql, &result);
+        result?;
+    }
+    timing.sources = phase.elapsed();
 
-    let mut streams_created = Vec::new();
+    // ── Detection streams ──
+    // Each reads only the sources, so they are gathered here and created
+    // together below. A disabled stream is recorded as not created.
+    phase = Instant::now();
+    let enabled = |name: &str| !options.disabled_streams.iter().any(|s| s == name);
+    let mut streams: Vec<(String, Option<String>)> = Vec::new();
+    let mut define = |name: &str, sql: String| streams.push((name.to_string(), enabled(name).then_some(sql)));
 
     // ── Stream 1: Volume Baseline (HOP window) ──
-    let vol_ok = try_create(&db, "vol_baseline",
+    define("vol_baseline",
         "CREATE STREAM vol_baseline AS
          SELECT symbol,
+                CAST(hop(ts, INTERVAL '2' SECOND, INTERVAL '10' SECOND) AS BIGINT) AS window_start,
                 SUM(volume) AS total_volume,
                 COUNT(*) AS trade_count,
-                AVG(price) AS avg_price
+                AVG(price) AS avg_price,
+                SUM(price * CAST(volume AS DOUBLE)) AS total_notional
          FROM trades
-         GROUP BY symbol, HOP(ts, INTERVAL '2' SECOND, INTERVAL '10' SECOND)"
-    ).await;
-    streams_created.push(("vol_baseline".into(), vol_ok));
+         GROUP BY symbol, hop(ts, INTERVAL '2' SECOND, INTERVAL '10' SECOND)".into()
+    );
 
     // ── Stream 2: OHLC + Volatility (TUMBLE window) ──
-    let ohlc_ok = try_create(&db, "ohlc_vol",
+    define("ohlc_vol",
         "CREATE STREAM ohlc_vol AS
          SELECT symbol,
                 CAST(tumble(ts, INTERVAL '5' SECOND) AS BIGINT) AS bar_start,
@@ -72,42 +233,42 @@ pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
                 MIN(price) AS low,
                 last_value(price) AS close,
                 SUM(volume) AS volume,
-                MAX(price) - MIN(price) AS price_range
+                MAX(price) - MIN(price) AS price_range,
+                SUM(price * CAST(volume AS DOUBLE)) AS notional
          FROM trades
-         GROUP BY symbol, tumble(ts, INTERVAL '5' SECOND)"
-    ).await;
-    streams_created.push(("ohlc_vol".into(), ohlc_ok));
+         GROUP BY symbol, tumble(ts, INTERVAL '5' SECOND)".into()
+    );
 
     // ── Stream 3: Rapid-Fire Burst (SESSION window) ──
-    let rapid_ok = try_create(&db, "rapid_fire",
-        "CREATE STREAM rapid_fire AS
-         SELECT account_id,
-                COUNT(*) AS burst_trades,
-                SUM(volume) AS burst_volume,
-                MIN(price) AS low,
-                MAX(price) AS high
-         FROM trades
-         GROUP BY account_id, SESSION(ts, INTERVAL '2' SECOND)"
-    ).await;
-    streams_created.push(("rapid_fire".into(), rapid_ok));
+    let (rapid_fire, profiles) = rapid_fire_streams(&options.sessions)?;
+    define("rapid_fire", rapid_fire);
+
+    let mut profile_streams = Vec::new();
+    for (name, sql) in profiles.into_iter().filter(|_| enabled("rapid_fire")) {
+        define(&name, sql);
+        profile_streams.push(name);
+    }
 
     // ── Stream 4: Wash Score (TUMBLE + CASE WHEN) ──
-    let wash_ok = try_create(&db, "wash_score",
+    define("wash_score",
         "CREATE STREAM wash_score AS
          SELECT account_id,
                 symbol,
+                CAST(tumble(ts, INTERVAL '5' SECOND) AS BIGINT) AS window_start,
                 SUM(CASE WHEN side = 'buy' THEN volume ELSE CAST(0 AS BIGINT) END) AS buy_volume,
                 SUM(CASE WHEN side = 'sell' THEN volume ELSE CAST(0 AS BIGINT) END) AS sell_volume,
                 SUM(CASE WHEN side = 'buy' THEN 1 ELSE 0 END) AS buy_count,
-                SUM(CASE WHEN side = 'sell' THEN 1 ELSE 0 END) AS sell_count
+                SUM(CASE WHEN side = 'sell' THEN 1 ELSE 0 END) AS sell_count,
+                SUM(CASE WHEN side = 'buy' THEN price * CAST(volume AS DOUBLE) ELSE CAST(0 AS DOUBLE) END) AS buy_notional,
+                SUM(CASE WHEN side = 'sell' THEN price * CAST(volume AS DOUBLE) ELSE CAST(0 AS DOUBLE) END) AS sell_notional
          FROM trades
-         GROUP BY account_id, symbol, TUMBLE(ts, INTERVAL '5' SECOND)"
-    ).await;
-    streams_created.push(("wash_score".into(), wash_ok));
+         GROUP BY account_id, symbol, tumble(ts, INTERVAL '5' SECOND)".into()
+    );
 
     // ── Stream 5: Suspicious Match (INNER JOIN) ──
-    let match_ok = try_create(&db, "suspicious_match",
-        "CREATE STREAM suspicious_match AS
+    let band = options.join.band_ms;
+    define("suspicious_match",
+        format!("CREATE STREAM suspicious_match AS
          SELECT t.symbol,
                 t.price AS trade_price,
                 t.volume,
@@ -115,16 +276,16 @@ pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
                 o.account_id,
                 o.side,
                 o.price AS order_price,
-                t.price - o.price AS price_diff
+                t.price - o.price AS price_diff,
+                t.price * CAST(t.volume AS DOUBLE) AS notional
          FROM trades t
          INNER JOIN orders o
          ON t.symbol = o.symbol
-         AND o.ts BETWEEN t.ts - 2000 AND t.ts + 2000"
-    ).await;
-    streams_created.push(("suspicious_match".into(), match_ok));
+         AND o.ts BETWEEN t.ts - {band} AND t.ts + {band}")
+    );
 
     // ── Stream 6: ASOF Match (ASOF JOIN — front-running detection) ──
-    let asof_ok = try_create(&db, "asof_match",
+    define("asof_match",
         "CREATE STREAM asof_match AS
          SELECT t.symbol,
                 t.price AS trade_price,
@@ -133,19 +294,188 @@ pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
                 o.order_id,
                 o.account_id AS order_account,
                 o.price AS order_price,
-                t.price - o.price AS price_spread
+                o.quantity AS order_quantity,
+                t.ts AS trade_ts,
+                o.ts AS order_ts,
+                t.price - o.price AS price_spread,
+                t.price * CAST(t.volume AS DOUBLE) AS notional
          FROM trades t
          ASOF JOIN orders o
          MATCH_CONDITION(t.ts >= o.ts)
-         ON t.symbol = o.symbol"
-    ).await;
-    streams_created.push(("asof_match".into(), asof_ok));
+         ON t.symbol = o.symbol".into()
+    );
+
+    // ── Stream 7: Account Activity (TUMBLE, across all symbols) ──
+    define("account_activity",
+        "CREATE STREAM account_activity AS
+         SELECT account_id,
+                CAST(tumble(ts, INTERVAL '5' SECOND) AS BIGINT) AS window_start,
+                COUNT(DISTINCT symbol) AS distinct_symbols,
+                COUNT(*) AS trade_count,
+                SUM(price * CAST(volume AS DOUBLE)) AS total_notional
+         FROM trades
+         GROUP BY account_id, tumble(ts, INTERVAL '5' SECOND)".into()
+    );
+
+    // ── Stream 8: Book Imbalance (TUMBLE over order-book snapshots) ──
+    define("book_imbalance",
+        "CREATE STREAM book_imbalance AS
+         SELECT symbol,
+                CAST(tumble(ts, INTERVAL '1' SECOND) AS BIGINT) AS window_start,
+                SUM(CASE WHEN side = 'bid' THEN size ELSE CAST(0 AS BIGINT) END) AS bid_depth,
+                SUM(CASE WHEN side = 'ask' THEN size ELSE CAST(0 AS BIGINT) END) AS ask_depth,
+                SUM(CASE WHEN level = 1 THEN price ELSE CAST(0 AS DOUBLE) END)
+                    / CAST(SUM(CASE WHEN level = 1 THEN 1 ELSE 0 END) AS DOUBLE) AS mid_price
+         FROM book
+         GROUP BY symbol, tumble(ts, INTERVAL '1' SECOND)".into()
+    );
+
+    // ── Stream 9: Momentum Burst (SESSION per account and symbol) ──
+    define("momentum_burst",
+        "CREATE STREAM momentum_burst AS
+         SELECT account_id, symbol,
+                MIN(ts) AS start_ts,
+                MAX(ts) AS end_ts,
+                SUM(CASE WHEN side = 'buy' THEN 1 ELSE 0 END) AS buy_count,
+                SUM(CASE WHEN side = 'sell' THEN 1 ELSE 0 END) AS sell_count,
+                SUM(volume) AS volume,
+                SUM(price * CAST(volume AS DOUBLE)) / CAST(SUM(volume) AS DOUBLE) AS vwap,
+                MIN(price) AS low,
+                MAX(price) AS high,
+                SUM(price * CAST(volume AS DOUBLE)) AS notional
+         FROM trades
+         GROUP BY account_id, symbol, SESSION(ts, INTERVAL '1' SECOND)".into()
+    );
+
+    // ── Stream 10: Pre-News Trades (INNER JOIN, trades × later news) ──
+    // Pairs each trade with its symbol's news in the following
+    // NEWS_LOOKBACK_MS. A forward ASOF JOIN would keep only the next event,
+    // but ASOF output is still pending (see asof_match); news is sparse
+    // enough that the interval join rarely pairs a trade twice.
+    define("pre_news",
+        format!("CREATE STREAM pre_news AS
+         SELECT t.account_id,
+                t.symbol,
+                t.side,
+                t.volume,
+                t.price AS trade_price,
+                t.ts AS trade_ts,
+                n.ts AS news_ts,
+                n.headline,
+                n.impact,
+                t.price * CAST(t.volume AS DOUBLE) AS notional
+         FROM trades t
+         INNER JOIN news_events n
+         ON t.symbol = n.symbol
+         AND n.ts BETWEEN t.ts AND t.ts + {NEWS_LOOKBACK_MS}")
+    );
+
+    // ── Stream 11: Price Improvement (INNER JOIN, trades × top of book) ──
+    // Prices each trade with a known counterparty against the level-1 quote
+    // it traded into. The snapshot at or just before the trade is the quote
+    // in force; an ASOF JOIN would say that directly but its output is still
+    // pending (see asof_match).
+    define("price_improvement",
+        format!("CREATE STREAM price_improvement AS
+         SELECT t.account_id,
+                t.counterparty,
+                t.symbol,
+                t.side,
+                t.order_ref,
+                t.price AS trade_price,
+                b.price AS quote_price,
+                CASE WHEN t.side = 'buy' THEN b.price - t.price ELSE t.price - b.price END / b.price AS improvement,
+                t.volume,
+                t.ts AS trade_ts,
+                t.price * CAST(t.volume AS DOUBLE) AS notional
+         FROM trades t
+         INNER JOIN book b
+         ON t.symbol = b.symbol
+         AND b.ts BETWEEN t.ts - {QUOTE_MAX_AGE_MS} AND t.ts
+         WHERE b.level = 1
+           AND t.counterparty <> ''
+           AND ((t.side = 'buy' AND b.side = 'ask') OR (t.side = 'sell' AND b.side = 'bid'))")
+    );
+
+    // ── Stream 12: Counterparty Volume (TUMBLE per account and counterparty) ──
+    // Each account's volume against each counterparty it traded with; the
+    // rule compares the largest against the account's total for the window.
+    define("counterparty_volume",
+        "CREATE STREAM counterparty_volume AS
+         SELECT account_id,
+                counterparty,
+                CAST(tumble(ts, INTERVAL '10' SECOND) AS BIGINT) AS window_start,
+                COUNT(*) AS trade_count,
+                SUM(volume) AS volume,
+                SUM(price * CAST(volume AS DOUBLE)) AS notional
+         FROM trades
+         WHERE counterparty <> ''
+         GROUP BY account_id, counterparty, tumble(ts, INTERVAL '10' SECOND)".into()
+    );
+
+    // ── Feature stream: per-account TUMBLE window (training-data export only) ──
+    if options.feature_stream {
+        define("account_features",
+            "CREATE STREAM account_features AS
+             SELECT account_id,
+                    CAST(tumble(ts, INTERVAL '5' SECOND) AS BIGINT) AS window_start,
+                    COUNT(*) AS trade_count,
+                    SUM(volume) AS total_volume,
+                    SUM(CASE WHEN side = 'buy' THEN volume ELSE CAST(0 AS BIGINT) END) AS buy_volume,
+                    SUM(CASE WHEN side = 'sell' THEN volume ELSE CAST(0 AS BIGINT) END) AS sell_volume
+             FROM trades
+             GROUP BY account_id, tumble(ts, INTERVAL '5' SECOND)".into()
+        );
+    }
+
+    let enabled_sql = streams.iter().filter_map(|(_, sql)| sql.as_deref());
+    let mut results = execute_all(&db, enabled_sql, options.sequential_setup).await.into_iter();
+    let mut streams_created = Vec::new();
+    for (name, sql) in &streams {
+        let result = sql.as_ref().map(|_| results.next().expect("one result per enabled stream"));
+        if let (Some(sql), Some(result)) = (sql, &result) {
+            SetupStatement::record(&mut statements, sql, result);
+        }
+        let ok = match result {
+            Some(Ok(())) => {
+                eprintln!("  [OK] {} created", name);
+                true
+            }
+            Some(Err(e)) => {
+                eprintln!("  [WARN] {} failed: {e}", name);
+                false
+            }
+            None => false,
+        };
+        streams_created.push((name.clone(), ok));
+    }
+
+    // Extra streams may read the built-in ones, so they follow in order
+    for (name, sql) in &options.extra_streams {
+        let result = try_create(&db, name, sql).await;
+        SetupStatement::record(&mut statements, sql, &result);
+        let ok = result.is_ok();
+        if ok {
+            let sink = format!("CREATE SINK {}_sink FROM {}", name, name);
+            let result = db.execute(&sink).await.map(|_| ()).map_err(|e| e.to_string());
+            SetupStatement::record(&mut statements, &sink, &result);
+        }
+        streams_created.push((name.clone(), ok));
+    }
+    timing.streams = phase.elapsed();
 
     // ── Create sinks + subscribe ──
+    phase = Instant::now();
+    let created = |name: &str| streams_created.iter().any(|(n, ok)| n == name && *ok);
+    let sinks: Vec<String> = streams.iter().filter(|(name, _)| created(name)).map(|(name, _)| format!("CREATE SINK {name}_sink FROM {name}")).collect();
+    // A stream whose sink fails still subscribes; the subscription reports it
+    for (sql, result) in sinks.iter().zip(execute_all(&db, sinks.iter().map(String::as_str), options.sequential_setup).await) {
+        SetupStatement::record(&mut statements, sql, &result);
+    }
+
     macro_rules! setup_sub {
-        ($db:expr, $name:expr, $ok:expr, $ty:ty) => {
-            if $ok {
-                let _ = $db.execute(&format!("CREATE SINK {}_sink FROM {}", $name, $name)).await;
+        ($db:expr, $name:expr, $ty:ty) => {
+            if created($name) {
                 match $db.subscribe::<$ty>($name) {
                     Ok(sub) => Some(sub),
                     Err(e) => {
@@ -159,41 +489,270 @@ pub async fn setup() -> Result<DetectionPipeline, Box<dyn std::error::Error>> {
         };
     }
 
-    let vol_baseline_sub = setup_sub!(db, "vol_baseline", vol_ok, VolumeBaseline);
-    let ohlc_vol_sub = setup_sub!(db, "ohlc_vol", ohlc_ok, OhlcVolatility);
-    let rapid_fire_sub = setup_sub!(db, "rapid_fire", rapid_ok, RapidFireBurst);
-    let wash_score_sub = setup_sub!(db, "wash_score", wash_ok, WashScore);
-    let suspicious_match_sub = setup_sub!(db, "suspicious_match", match_ok, SuspiciousMatch);
-    let asof_match_sub = setup_sub!(db, "asof_match", asof_ok, AsofMatch);
+    let vol_baseline_sub = setup_sub!(db, "vol_baseline", VolumeBaseline);
+    let ohlc_vol_sub = setup_sub!(db, "ohlc_vol", OhlcVolatility);
+    let rapid_fire_sub = setup_sub!(db, "rapid_fire", RapidFireBurst);
+    let wash_score_sub = setup_sub!(db, "wash_score", WashScore);
+    let suspicious_match_sub = setup_sub!(db, "suspicious_match", SuspiciousMatch);
+    let asof_match_sub = setup_sub!(db, "asof_match", AsofMatch);
+    let account_activity_sub = setup_sub!(db, "account_activity", AccountActivity);
+    let book_imbalance_sub = setup_sub!(db, "book_imbalance", BookImbalance);
+    let momentum_burst_sub = setup_sub!(db, "momentum_burst", MomentumBurst);
+    let pre_news_sub = setup_sub!(db, "pre_news", PreNewsTrade);
+    let price_improvement_sub = setup_sub!(db, "price_improvement", PriceImprovement);
+    let counterparty_volume_sub = setup_sub!(db, "counterparty_volume", CounterpartyVolume);
+    let account_features_sub = setup_sub!(db, "account_features", AccountWindow);
+    let mut rapid_fire_profile_subs = Vec::new();
+    for name in &profile_streams {
+        rapid_fire_profile_subs.extend(setup_sub!(db, name.as_str(), RapidFireBurst));
+    }
+    timing.sinks = phase.elapsed();
 
+    phase = Instant::now();
     db.start().await?;
+    timing.start = phase.elapsed();
+    timing.total = started.elapsed();
+    eprintln!("  [OK] pipeline set up in {timing}");
 
     let trade_source = db.source::<Trade>("trades")?;
     let order_source = db.source::<Order>("orders")?;
+    let book_source = db.source::<BookLevel>("book")?;
+    let news_source = db.source::<NewsEvent>("news_events")?;
 
     Ok(DetectionPipeline {
         db,
         trade_source,
         order_source,
+        book_source,
+        news_source,
         vol_baseline_sub,
         ohlc_vol_sub,
         rapid_fire_sub,
+        rapid_fire_profile_subs,
         wash_score_sub,
         suspicious_match_sub,
         asof_match_sub,
+        account_activity_sub,
+        book_imbalance_sub,
+        momentum_burst_sub,
+        pre_news_sub,
+        price_improvement_sub,
+        counterparty_volume_sub,
+        account_features_sub,
         streams_created,
+        buffer_size,
+        statements,
+        setup_timing: timing,
     })
 }
 
-async fn try_create(db: &LaminarDB, name: &str, sql: &str) -> bool {
+/// Estimated `suspicious_match` rows per second of event time: each trade
+/// joins the same-symbol orders within ±`band_ms`, with symbols equally busy.
+pub fn join_fanout_per_sec(band_ms: u64, trades_per_sec: f64, orders_per_sec: f64) -> f64 {
+    let orders_per_symbol_sec = orders_per_sec / SYMBOLS.len() as f64;
+    trades_per_sec * orders_per_symbol_sec * 2.0 * band_ms as f64 / 1000.0
+}
+
+/// Check `join` against the event rates a run expects. Over
+/// `max_fanout_per_sec` it warns, and with `clamp` returns the band narrowed
+/// to the widest that fits; otherwise the band is returned unchanged.
+pub fn guard_join_band(join: &JoinConfig, trades_per_sec: f64, orders_per_sec: f64) -> JoinConfig {
+    let fanout = join_fanout_per_sec(join.band_ms, trades_per_sec, orders_per_sec);
+    if join.max_fanout_per_sec <= 0.0 || fanout <= join.max_fanout_per_sec {
+        return join.clone();
+    }
+    let fitting_ms = ((join.band_ms as f64 * join.max_fanout_per_sec / fanout) as u64).max(1);
+    eprintln!(
+        "  [WARN] join band ±{}ms at {:.0} trades/s implies ~{:.0} join rows/s (limit {:.0})",
+        join.band_ms, trades_per_sec, fanout, join.max_fanout_per_sec
+    );
+    if join.clamp {
+        eprintln!("  [WARN] join band clamped to ±{fitting_ms}ms");
+        JoinConfig { band_ms: fitting_ms, ..join.clone() }
+    } else {
+        eprintln!("  [WARN] set [join] band_ms <= {fitting_ms} or clamp = true");
+        join.clone()
+    }
+}
+
+/// Check `[sessions]` as setup will use it: each profile named for a stream
+/// of its own, with at least one account that no other profile lists, and
+/// every SESSION gap above zero.
+pub fn check_sessions(sessions: &SessionConfig) -> Result<(), String> {
+    if sessions.default_gap_ms == 0 {
+        return Err("sessions: default_gap_ms must be greater than 0".into());
+    }
+    let mut names = HashSet::new();
+    let mut owners: HashMap<&str, &str> = HashMap::new();
+    for profile in &sessions.profiles {
+        if !profile.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("session profile name '{}' must be letters, digits and '_'", profile.name));
+        }
+        if !names.insert(profile.name.as_str()) {
+            return Err(format!("session profile '{}' is defined twice", profile.name));
+        }
+        if profile.accounts.is_empty() {
+            return Err(format!("session profile '{}' lists no accounts", profile.name));
+        }
+        if profile.gap_ms == 0 {
+            return Err(format!("session profile '{}': gap_ms must be greater than 0", profile.name));
+        }
+        for account in &profile.accounts {
+            if let Some(other) = owners.insert(account, &profile.name) {
+                return Err(format!("account '{account}' is in both session profiles '{other}' and '{}'", profile.name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The rapid-fire streams `sessions` asks for: the default stream's SQL, and
+/// each profile's stream name and SQL. Profiled accounts get their own
+/// stream with their own gap, and are excluded from the default one so each
+/// burst is counted once.
+fn rapid_fire_streams(sessions: &SessionConfig) -> Result<(String, Vec<(String, String)>), String> {
+    check_sessions(sessions)?;
+    let profiled: Vec<&String> = sessions.profiles.iter().flat_map(|p| &p.accounts).collect();
+    let default_filter = if profiled.is_empty() { String::new() } else { format!("WHERE account_id NOT IN ({})", sql_list(profiled)) };
+    let profiles = sessions
+        .profiles
+        .iter()
+        .map(|profile| {
+            let name = format!("rapid_fire_{}", profile.name);
+            let filter = format!("WHERE account_id IN ({})", sql_list(&profile.accounts));
+            let sql = rapid_fire_sql(&name, &filter, profile.gap_ms);
+            (name, sql)
+        })
+        .collect();
+    Ok((rapid_fire_sql("rapid_fire", &default_filter, sessions.default_gap_ms), profiles))
+}
+
+fn rapid_fire_sql(name: &str, filter: &str, gap_ms: u64) -> String {
+    // Whole seconds keep the `INTERVAL 'n' SECOND` form the default stream has always used
+    let gap = if gap_ms.is_multiple_of(1000) { format!("INTERVAL '{}' SECOND", gap_ms / 1000) } else { format!("INTERVAL '{gap_ms} milliseconds'") };
+    format!(
+        "CREATE STREAM {name} AS
+         SELECT account_id,
+                COUNT(*) AS burst_trades,
+                SUM(volume) AS burst_volume,
+                MIN(price) AS low,
+                MAX(price) AS high,
+                SUM(price * CAST(volume AS DOUBLE)) AS burst_notional
+         FROM trades
+         {filter}
+         GROUP BY account_id, SESSION(ts, {gap})"
+    )
+}
+
+/// Quoted, comma-separated SQL string literals.
+fn sql_list<'a>(values: impl IntoIterator<Item = &'a String>) -> String {
+    values.into_iter().map(|v| format!("'{}'", v.replace('\'', "''"))).collect::<Vec<_>>().join(", ")
+}
+
+async fn try_create(db: &LaminarDB, name: &str, sql: &str) -> Result<(), String> {
     match db.execute(sql).await {
         Ok(_) => {
             eprintln!("  [OK] {} created", name);
-            true
+            Ok(())
         }
         Err(e) => {
             eprintln!("  [WARN] {} failed: {e}", name);
-            false
+            Err(e.to_string())
+        }
+    }
+}
+
+/// `sql` with the indentation of its continuation lines, which comes from
+/// where the statement sits in this file, removed.
+fn dedent(sql: &str) -> String {
+    let mut lines = sql.trim().lines();
+    let first = lines.next().unwrap_or_default();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest.iter().filter(|l| !l.trim().is_empty()).map(|l| l.len() - l.trim_start().len()).min().unwrap_or(0);
+    std::iter::once(first.to_string()).chain(rest.iter().map(|l| format!("    {}", l.get(indent..).unwrap_or("").trim_end()))).collect::<Vec<_>>().join("\n")
+}
+
+/// Execute `statements`, all at once unless `sequential`, and return each
+/// outcome in order.
+async fn execute_all<'a>(db: &LaminarDB, statements: impl IntoIterator<Item = &'a str>, sequential: bool) -> Vec<Result<(), String>> {
+    let run = |sql: &'a str| async move { db.execute(sql).await.map(|_| ()).map_err(|e| e.to_string()) };
+    if sequential {
+        let mut results = Vec::new();
+        for sql in statements {
+            results.push(run(sql).await);
         }
+        results
+    } else {
+        join_all(statements.into_iter().map(run)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SessionProfile;
+
+    fn profile(name: &str, accounts: &[&str], gap_ms: u64) -> SessionProfile {
+        SessionProfile { name: name.into(), accounts: accounts.iter().map(|a| a.to_string()).collect(), gap_ms }
+    }
+
+    #[test]
+    fn without_profiles_there_is_one_unfiltered_stream() {
+        let (default, profiles) = rapid_fire_streams(&SessionConfig::default()).unwrap();
+        assert!(profiles.is_empty());
+        assert!(!default.contains("WHERE"));
+        assert!(default.contains("SESSION(ts, INTERVAL '2' SECOND)"));
+    }
+
+    #[test]
+    fn profiled_accounts_get_their_own_gap_and_leave_the_default_stream() {
+        let sessions = SessionConfig {
+            default_gap_ms: 2_000,
+            profiles: vec![profile("hft", &["HFT-1", "HFT-2"], 250), profile("retail", &["O'BRIEN"], 30_000)],
+        };
+        let (default, profiles) = rapid_fire_streams(&sessions).unwrap();
+        assert!(default.contains("WHERE account_id NOT IN ('HFT-1', 'HFT-2', 'O''BRIEN')"));
+
+        let names: Vec<&str> = profiles.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["rapid_fire_hft", "rapid_fire_retail"]);
+        let (_, hft) = &profiles[0];
+        assert!(hft.starts_with("CREATE STREAM rapid_fire_hft AS"));
+        assert!(hft.contains("WHERE account_id IN ('HFT-1', 'HFT-2')"));
+        // Sub-second gaps are given in milliseconds, whole seconds as seconds
+        assert!(hft.contains("SESSION(ts, INTERVAL '250 milliseconds')"));
+        assert!(profiles[1].1.contains("SESSION(ts, INTERVAL '30' SECOND)"));
+    }
+
+    #[test]
+    fn profile_names_must_be_identifiers() {
+        let sessions = SessionConfig { profiles: vec![profile("hft; DROP", &["HFT-1"], 250)], ..Default::default() };
+        assert!(rapid_fire_streams(&sessions).unwrap_err().contains("letters, digits and '_'"));
+    }
+
+    fn rejected(profiles: Vec<SessionProfile>) -> String {
+        check_sessions(&SessionConfig { profiles, ..Default::default() }).unwrap_err()
+    }
+
+    #[test]
+    fn profiles_need_accounts() {
+        assert!(rejected(vec![profile("hft", &[], 250)]).contains("lists no accounts"));
+    }
+
+    #[test]
+    fn profile_names_are_unique() {
+        assert!(rejected(vec![profile("hft", &["HFT-1"], 250), profile("hft", &["HFT-2"], 500)]).contains("defined twice"));
+    }
+
+    #[test]
+    fn an_account_belongs_to_one_profile() {
+        let err = rejected(vec![profile("hft", &["HFT-1", "MM-1"], 250), profile("mm", &["MM-1"], 500)]);
+        assert_eq!(err, "account 'MM-1' is in both session profiles 'hft' and 'mm'");
+    }
+
+    #[test]
+    fn session_gaps_are_positive() {
+        assert!(rejected(vec![profile("hft", &["HFT-1"], 0)]).contains("gap_ms must be greater than 0"));
+        let sessions = SessionConfig { default_gap_ms: 0, ..Default::default() };
+        assert!(check_sessions(&sessions).unwrap_err().contains("default_gap_ms"));
     }
 }
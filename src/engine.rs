@@ -0,0 +1,983 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertEngine};
+use crate::archive::AlertArchive;
+use crate::audit::AuditLog;
+use crate::blotter::Blotter;
+use crate::clock::Clock;
+use crate::config::WatermarkConfig;
+use crate::detection::{DetectionPipeline, OHLC_BAR_MS};
+use crate::evaluation::{EvalStats, EvalWorker, Evaluator, RowBatch};
+use crate::features::FeatureExtractor;
+use crate::generator::FraudGenerator;
+use crate::ids::{IdLedger, UidGenerator};
+use crate::latency::LatencyTracker;
+use crate::limits::LoadGovernor;
+use crate::quality::QualityTracker;
+use crate::recording::Recorder;
+use crate::reviews::AlertReviews;
+use crate::sequence::SequenceMonitor;
+use crate::slo::SloTracker;
+use crate::source::Batch;
+use crate::standby::Standby;
+use crate::ticks::TickDetector;
+use crate::types::*;
+
+pub const STREAM_NAMES: [&str; 12] = [
+    "vol_baseline", "ohlc_vol", "rapid_fire", "wash_score", "suspicious_match", "asof_match", "account_activity", "book_imbalance",
+    "momentum_burst", "pre_news", "price_improvement", "counterparty_volume",
+];
+
+/// Event time an end-of-run drain moves every watermark past the last one
+/// pushed: more than the longest window or lookback any stream holds open.
+pub const DRAIN_ADVANCE_MS: i64 = 30_000;
+/// Time without a new row after which the drain counts the streams as settled.
+const DRAIN_QUIET_PERIOD: Duration = Duration::from_millis(300);
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Where each push sets the watermark relative to the newest event time,
+/// and which pushes move it: every `every_pushes`th push, or any push that
+/// takes it `min_advance_ms` past the last one emitted. Pushes in between
+/// hold it back, so windows close in fewer, larger steps.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WatermarkStrategy {
+    pub lead_ms: i64,
+    /// 0 leaves emission to `min_advance_ms`.
+    pub every_pushes: u32,
+    /// 0 = no event-time threshold.
+    pub min_advance_ms: i64,
+}
+
+impl Default for WatermarkStrategy {
+    fn default() -> Self {
+        Self { lead_ms: 10_000, every_pushes: 1, min_advance_ms: 0 }
+    }
+}
+
+impl WatermarkStrategy {
+    pub fn from_config(config: &WatermarkConfig) -> Result<Self, String> {
+        if config.min_advance_ms < 0 {
+            return Err(format!("watermark: min_advance_ms must not be negative, got {}", config.min_advance_ms));
+        }
+        if config.every_pushes == 0 && config.min_advance_ms == 0 {
+            return Err("watermark: every_pushes and min_advance_ms can't both be 0, or the watermark never moves".into());
+        }
+        Ok(Self { every_pushes: config.every_pushes, min_advance_ms: config.min_advance_ms, ..Self::default() })
+    }
+
+    pub fn watermark_for(&self, max_event_ts: i64) -> i64 {
+        max_event_ts + self.lead_ms
+    }
+
+    /// Whether a push `pushes` after the last emission, at `watermark`,
+    /// moves the sources' watermark. The first push always does.
+    pub fn due(&self, pushes: u32, last_emitted: Option<i64>, watermark: i64) -> bool {
+        let Some(last) = last_emitted else { return true };
+        (self.every_pushes > 0 && pushes >= self.every_pushes) || (self.min_advance_ms > 0 && watermark - last >= self.min_advance_ms)
+    }
+}
+
+/// Watermark emissions against pushes, and how coarsely the streams'
+/// output arrives as a result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatermarkStats {
+    pub pushes: u64,
+    pub emitted: u64,
+    /// Polls that returned rows, across every stream.
+    pub output_batches: u64,
+    /// Rows those polls returned.
+    pub output_rows: u64,
+}
+
+impl WatermarkStats {
+    /// Rows per non-empty poll: larger with fewer, bigger watermark steps.
+    pub fn rows_per_batch(&self) -> f64 {
+        self.output_rows as f64 / self.output_batches.max(1) as f64
+    }
+}
+
+/// How `Engine::push` retries a source that doesn't take a whole batch
+/// because its buffer is full: up to `max_retries` more attempts, doubling
+/// the backoff from `initial_backoff` each time.
+#[derive(Debug, Clone, Copy)]
+pub struct PushRetry {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for PushRetry {
+    fn default() -> Self {
+        Self { max_retries: 5, initial_backoff: Duration::from_micros(50) }
+    }
+}
+
+impl PushRetry {
+    /// Doublings after which the backoff stops growing, so a high
+    /// `max_retries` can't overflow it.
+    const MAX_DOUBLINGS: u32 = 16;
+
+    /// Wait before retry number `retry`, counting from 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff.saturating_mul(1 << retry.min(Self::MAX_DOUBLINGS))
+    }
+}
+
+/// Where [`push_with_retry`] pushes: a pipeline source, or a stand-in in
+/// tests.
+trait PushTarget<T> {
+    /// Push from the front of `records` until the buffer is full, returning
+    /// how many it took. The record it stopped on is left for the next push.
+    fn push_from(&self, records: &[T]) -> usize;
+}
+
+impl<T: laminar_core::streaming::Record> PushTarget<T> for laminar_db::SourceHandle<T> {
+    // `push` and `push_batch` consume the record a full buffer refuses, so
+    // each record goes in as its own one-row batch, built from a borrow
+    fn push_from(&self, records: &[T]) -> usize {
+        records.iter().take_while(|r| self.push_arrow(r.to_record_batch()).is_ok()).count()
+    }
+}
+
+/// Push `records` to `source`, retrying what a full buffer refused as
+/// `retry` allows, and count the outcome in `stats`. The backoff yields to
+/// the runtime rather than blocking its worker.
+async fn push_with_retry<T>(source: &impl PushTarget<T>, records: Vec<T>, retry: &PushRetry, stats: &mut PushStats) {
+    let total = records.len();
+    let mut accepted = 0;
+    let mut retries = 0;
+    loop {
+        accepted += source.push_from(&records[accepted..]);
+        if accepted == total || retries == retry.max_retries {
+            break;
+        }
+        tokio::time::sleep(retry.backoff(retries)).await;
+        retries += 1;
+    }
+    if accepted < total {
+        stats.rejected_batches += 1;
+        stats.rejected_records += (total - accepted) as u64;
+    } else if retries > 0 {
+        stats.deferred_batches += 1;
+    }
+}
+
+/// Push outcomes per source batch, so a full buffer shows up as numbers
+/// rather than silently lost rows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushStats {
+    /// Batches that were only fully accepted after retrying.
+    pub deferred_batches: u64,
+    /// Batches with records still refused when retries ran out.
+    pub rejected_batches: u64,
+    /// Records dropped from rejected batches.
+    pub rejected_records: u64,
+    /// Fill of the fullest source buffer after the last push, 0.0-1.0.
+    pub buffer_pressure: f64,
+}
+
+/// What an end-of-run drain recovered from windows still open at the end.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrainStats {
+    /// Stream rows polled during the drain.
+    pub rows: u64,
+    /// Alerts those rows raised, before the governor's cap.
+    pub alerts: u64,
+    pub polls: u64,
+    pub took_ms: u64,
+    /// The timeout ran out before the streams had gone quiet.
+    pub timed_out: bool,
+}
+
+/// One cycle's liveness record, published with the dashboard stats.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// Cycles run since start.
+    pub seq: u64,
+    /// Wall time from generate through evaluate.
+    pub cycle_us: u64,
+    /// Wall time between the end of the previous cycle and the start of this one.
+    pub gap_ms: u64,
+    pub rows_polled: u64,
+}
+
+/// A second rule set evaluated on the same stream rows as the engine's own
+/// `alert_engine`, for A/B threshold comparison. Its alerts are scored and
+/// timed but never returned, archived, or counted against limits.
+pub struct Challenger {
+    pub alert_engine: AlertEngine,
+    pub latency: LatencyTracker,
+    pub quality: QualityTracker,
+}
+
+impl Challenger {
+    pub fn new(alert_engine: AlertEngine) -> Self {
+        Self { alert_engine, latency: LatencyTracker::new(), quality: QualityTracker::new() }
+    }
+}
+
+/// Rows a stream's subscription handed over beyond its per-cycle polling
+/// budget, evaluated first the next cycle.
+#[derive(Default)]
+struct PollBacklog {
+    vol_baseline: VecDeque<VolumeBaseline>,
+    ohlc_vol: VecDeque<OhlcVolatility>,
+    rapid_fire: VecDeque<RapidFireBurst>,
+    /// One per `rapid_fire_<profile>` stream, in `rapid_fire_profile_subs` order.
+    rapid_fire_profiles: Vec<VecDeque<RapidFireBurst>>,
+    wash_score: VecDeque<WashScore>,
+    suspicious_match: VecDeque<SuspiciousMatch>,
+    asof_match: VecDeque<AsofMatch>,
+    account_activity: VecDeque<AccountActivity>,
+    book_imbalance: VecDeque<BookImbalance>,
+    momentum_burst: VecDeque<MomentumBurst>,
+    pre_news: VecDeque<PreNewsTrade>,
+    price_improvement: VecDeque<PriceImprovement>,
+    counterparty_volume: VecDeque<CounterpartyVolume>,
+}
+
+/// The generate → push → poll → evaluate loop shared by every frontend.
+pub struct Engine {
+    pub pipeline: DetectionPipeline,
+    pub gen: FraudGenerator,
+    pub alert_engine: AlertEngine,
+    pub latency: LatencyTracker,
+    /// Alert latency against the `[slo]` objectives.
+    pub slo: SloTracker,
+    pub quality: QualityTracker,
+    /// Set to collect training-data features (requires `PipelineOptions::feature_stream`).
+    pub features: Option<FeatureExtractor>,
+    /// Set to bound load and degrade under overload; `None` runs unbounded.
+    pub governor: Option<LoadGovernor>,
+    /// Set to append every alert the engine hands out to an archive file.
+    pub archive: Option<AlertArchive>,
+    /// Set to log every alert, delivered or shadow, with its rule's thresholds.
+    pub audit: Option<AuditLog>,
+    /// Set to record every cycle's polled rows for `replay`.
+    pub recorder: Option<Recorder>,
+    /// Set to continue alert IDs from earlier runs.
+    pub ids: Option<IdLedger>,
+    /// Set to give every alert handed out a globally unique `uid`.
+    pub uids: Option<UidGenerator>,
+    /// Set to keep each account's recent activity for the web drill-down API.
+    pub blotter: Option<Blotter>,
+    /// Set to fold operator dispositions into the quality report.
+    pub reviews: Option<AlertReviews>,
+    /// Set to watch pushed trade prices for PriceVelocity moves.
+    pub ticks: Option<TickDetector>,
+    /// Gap and duplicate checks on pushed trade and order sequence numbers.
+    pub sequences: SequenceMonitor,
+    /// Set to evaluate a second rule set alongside `alert_engine`.
+    pub challenger: Option<Challenger>,
+    /// Would-have-fired alerts from shadow-mode rules in the last cycle.
+    pub shadow_alerts: Vec<Alert>,
+    pub clock: Arc<dyn Clock>,
+    pub watermark: WatermarkStrategy,
+    pub push_retry: PushRetry,
+    pub push_stats: PushStats,
+    pub stream_counts: [u64; STREAM_NAMES.len()],
+    /// Set to evaluate at most this many rows per stream per cycle; the rest
+    /// carry over to the next cycle, so one stream's burst of output can't
+    /// hold back the others. `None` evaluates everything polled.
+    pub poll_budget: Option<usize>,
+    /// Cycles in which each stream had rows left over after its budget.
+    pub budget_exhausted: [u64; STREAM_NAMES.len()],
+    /// Rows each stream is carrying over to the next cycle.
+    pub carried_rows: [u64; STREAM_NAMES.len()],
+    backlog: PollBacklog,
+    /// Set to run the rules on a worker thread while the poll loop drains
+    /// the remaining streams, so slow rules (ML scoring, a scoring model)
+    /// don't hold up polling.
+    pub eval_worker: bool,
+    /// Queue depth and wait time of the evaluation worker.
+    pub eval_stats: EvalStats,
+    /// Started by the first poll with `eval_worker` set.
+    worker: Option<EvalWorker>,
+    /// Set by [`Engine::start_standby`]: a pipeline fed a copy of every push
+    /// until it's warm, then switched in for the live one.
+    pub standby: Option<Standby>,
+    /// Standbys switched in so far.
+    pub pipeline_switches: u64,
+    /// Pipelines replaced by a standby, shut down with the engine.
+    retired: Vec<DetectionPipeline>,
+    pub total_trades: u64,
+    pub total_orders: u64,
+    /// `elapsed()` at the last `reset_stats`; throughput is measured from here.
+    pub stats_since: Duration,
+    /// The last cycle's heartbeat.
+    pub heartbeat: Heartbeat,
+    /// Set to raise an EngineStall alert when a cycle, or the gap before it,
+    /// runs longer than this.
+    pub cycle_budget: Option<Duration>,
+    /// Set by [`Engine::drain`].
+    pub drained: Option<DrainStats>,
+    last_cycle_end: Option<Instant>,
+    /// Watermark last emitted to the sources.
+    last_watermark: Option<i64>,
+    /// Newest watermark a push held back, not yet emitted; the drain
+    /// advances from here when there is one.
+    held_watermark: Option<i64>,
+    pushes_since_watermark: u32,
+    pub watermark_stats: WatermarkStats,
+    /// Alerts raised at push by the tick detector and sequence checks,
+    /// handed out by the next poll.
+    push_alerts: Vec<Alert>,
+}
+
+impl Engine {
+    pub fn new(pipeline: DetectionPipeline, gen: FraudGenerator, alert_engine: AlertEngine, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            pipeline,
+            gen,
+            alert_engine,
+            latency: LatencyTracker::new(),
+            slo: SloTracker::default(),
+            quality: QualityTracker::new(),
+            features: None,
+            governor: None,
+            archive: None,
+            audit: None,
+            recorder: None,
+            ids: None,
+            uids: None,
+            blotter: None,
+            reviews: None,
+            ticks: None,
+            sequences: SequenceMonitor::new(),
+            challenger: None,
+            shadow_alerts: Vec::new(),
+            clock,
+            watermark: WatermarkStrategy::default(),
+            push_retry: PushRetry::default(),
+            push_stats: PushStats::default(),
+            stream_counts: [0; STREAM_NAMES.len()],
+            poll_budget: None,
+            budget_exhausted: [0; STREAM_NAMES.len()],
+            carried_rows: [0; STREAM_NAMES.len()],
+            backlog: PollBacklog::default(),
+            eval_worker: false,
+            eval_stats: EvalStats::default(),
+            worker: None,
+            standby: None,
+            pipeline_switches: 0,
+            retired: Vec::new(),
+            total_trades: 0,
+            total_orders: 0,
+            stats_since: Duration::ZERO,
+            heartbeat: Heartbeat::default(),
+            cycle_budget: None,
+            drained: None,
+            last_cycle_end: None,
+            last_watermark: None,
+            held_watermark: None,
+            pushes_since_watermark: 0,
+            watermark_stats: WatermarkStats::default(),
+            push_alerts: Vec::new(),
+        }
+    }
+
+    /// One generator cycle at the clock's current event time: generate, push,
+    /// poll every stream, and return the alerts raised. With a governor, the
+    /// generate and push steps may be shed and the alerts capped; with a
+    /// `cycle_budget`, a stall adds an EngineStall alert after the cap. With
+    /// a trading calendar, nothing is generated while the market is closed.
+    pub async fn cycle(&mut self) -> Vec<Alert> {
+        self.cover_ids();
+        self.promote_standby();
+        let ts = self.clock.now_ms();
+        let gen_instant = Instant::now();
+        self.alert_engine.advance_to(ts);
+        if let Some(c) = self.challenger.as_mut() {
+            c.alert_engine.advance_to(ts);
+        }
+
+        let closed = self.alert_engine.calendar.as_ref().is_some_and(|c| !c.is_open(ts));
+        let shed = !closed && self.governor.as_mut().is_some_and(|g| g.should_shed());
+        if shed || closed {
+            self.quality.start_cycle(ts, Vec::new());
+            if let Some(c) = self.challenger.as_mut() {
+                c.quality.start_cycle(ts, Vec::new());
+            }
+            // Event time still passes while closed, so the last windows before the close emit
+            if closed {
+                let watermark = self.watermark.watermark_for(ts);
+                self.advance_watermarks(watermark);
+            }
+        } else {
+            let Batch { trades, orders, book, news } = self.gen.batch(ts);
+            let labels = self.gen.drain_labels();
+            let benign = self.gen.drain_benign_labels();
+            if let Some(f) = self.features.as_mut() {
+                f.observe_labels(labels.clone());
+            }
+            if let Some(c) = self.challenger.as_mut() {
+                c.quality.start_cycle(ts, labels.clone());
+                c.quality.observe_benign(benign.clone());
+            }
+            self.quality.start_cycle(ts, labels);
+            self.quality.observe_benign(benign);
+            if let Some(b) = &self.blotter {
+                b.record_activity(&trades, &orders);
+            }
+
+            let push_start = Instant::now();
+            let watermark = self.watermark.watermark_for(ts);
+            self.push_market(trades, orders, book, news, watermark).await;
+            let elapsed = self.stats_elapsed();
+            if let Some(g) = self.governor.as_mut() {
+                g.observe_push(push_start.elapsed().as_micros() as u64);
+                g.observe_rates(self.total_trades, self.total_orders, elapsed);
+            }
+        }
+
+        self.finish_cycle(ts, gen_instant)
+    }
+
+    /// One cycle on `batch` from an [`EventSource`](crate::source::EventSource)
+    /// instead of the generator: pushed with the watermark strategy's lead
+    /// ahead of its newest event, then polled and recorded like
+    /// [`Engine::cycle`]. An empty batch only polls.
+    pub async fn cycle_with(&mut self, batch: Batch) -> Vec<Alert> {
+        self.cover_ids();
+        self.promote_standby();
+        let gen_instant = Instant::now();
+        let ts = batch.max_event_ts().unwrap_or_else(|| self.clock.now_ms());
+        self.alert_engine.advance_to(ts);
+        if let Some(c) = self.challenger.as_mut() {
+            c.alert_engine.advance_to(ts);
+        }
+        if let Some(b) = &self.blotter {
+            b.record_activity(&batch.trades, &batch.orders);
+        }
+        if let Some(watermark) = batch.watermark(&self.watermark) {
+            let Batch { trades, orders, book, news } = batch;
+            let push_start = Instant::now();
+            self.push_market(trades, orders, book, news, watermark).await;
+            let elapsed = self.stats_elapsed();
+            if let Some(g) = self.governor.as_mut() {
+                g.observe_push(push_start.elapsed().as_micros() as u64);
+                g.observe_rates(self.total_trades, self.total_orders, elapsed);
+            }
+        }
+        self.finish_cycle(ts, gen_instant)
+    }
+
+    /// Poll everything the cycle's push produced, then cap, beat and record.
+    fn finish_cycle(&mut self, ts: i64, gen_instant: Instant) -> Vec<Alert> {
+        let rows_before: u64 = self.stream_counts.iter().sum();
+        let mut alerts = self.poll(ts, gen_instant);
+        let rows_polled = self.stream_counts.iter().sum::<u64>() - rows_before;
+        self.shadow_alerts = self.alert_engine.take_shadow_alerts();
+        if let Some(g) = self.governor.as_mut() {
+            g.observe_poll(rows_polled);
+            g.cap_alerts(&mut alerts);
+        }
+        alerts.extend(self.beat(gen_instant, rows_polled));
+        self.record(ts, &mut alerts);
+        alerts
+    }
+
+    /// At the end of a run, advance every watermark `DRAIN_ADVANCE_MS` past
+    /// the last push and poll until the streams stop emitting or `timeout`
+    /// runs out, returning the alerts the closing windows raise. Waits in
+    /// wall time, whatever the clock, since it waits on the pipeline.
+    /// The outcome is kept in `drained`.
+    pub async fn drain(&mut self, timeout: Duration) -> Vec<Alert> {
+        let mut stats = DrainStats::default();
+        let mut drained = Vec::new();
+        let Some(last) = self.held_watermark.or(self.last_watermark) else {
+            self.drained = Some(stats);
+            return drained;
+        };
+        self.advance_watermarks(last + DRAIN_ADVANCE_MS);
+
+        let start = Instant::now();
+        let mut last_row = start;
+        while last_row.elapsed() < DRAIN_QUIET_PERIOD {
+            if start.elapsed() >= timeout {
+                stats.timed_out = true;
+                break;
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            let ts = self.clock.now_ms();
+            let rows_before: u64 = self.stream_counts.iter().sum();
+            let mut alerts = self.poll(ts, Instant::now());
+            let rows = self.stream_counts.iter().sum::<u64>() - rows_before;
+            stats.polls += 1;
+            stats.rows += rows;
+            stats.alerts += alerts.len() as u64;
+            if rows > 0 || !alerts.is_empty() {
+                last_row = Instant::now();
+            }
+
+            self.shadow_alerts = self.alert_engine.take_shadow_alerts();
+            if let Some(g) = self.governor.as_mut() {
+                g.cap_alerts(&mut alerts);
+            }
+            self.record(ts, &mut alerts);
+            drained.extend(alerts);
+        }
+        stats.took_ms = start.elapsed().as_millis() as u64;
+        self.drained = Some(stats);
+        drained
+    }
+
+    /// Give a cycle's alerts their `uid`s and hand them to the ID ledger,
+    /// archive, audit log and blotter, and close the cycle's row recording.
+    fn record(&mut self, ts: i64, alerts: &mut [Alert]) {
+        if let Some(uids) = self.uids.as_mut() {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            for alert in alerts.iter_mut() {
+                alert.uid = Some(uids.next(now_ms));
+            }
+        }
+        self.cover_ids();
+        if let Some(archive) = &self.archive {
+            if let Err(e) = archive.append(alerts) {
+                eprintln!("Alert archive write failed: {e}");
+            }
+        }
+        if let Some(audit) = &self.audit {
+            if let Err(e) = audit.record_firings(&self.alert_engine, alerts, &self.shadow_alerts) {
+                eprintln!("Audit log write failed: {e}");
+            }
+        }
+        if let Some(b) = &self.blotter {
+            b.record_alerts(alerts);
+            b.record_scores(ts, self.alert_engine.last_ml_scores());
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.finish_cycle(ts, self.total_trades, self.total_orders, self.gen.current_prices()) {
+                eprintln!("Row recording write failed: {e}");
+            }
+        }
+    }
+
+    /// Record the heartbeat of a cycle that started at `start`, and an
+    /// EngineStall alert for each of the cycle and the gap before it that ran
+    /// over `cycle_budget`. A loop that stops cycling is caught when it
+    /// resumes; nothing inside a hung cycle can raise an alert.
+    fn beat(&mut self, start: Instant, rows_polled: u64) -> Vec<Alert> {
+        let end = Instant::now();
+        let took = end - start;
+        let gap = self.last_cycle_end.map_or(Duration::ZERO, |prev| start.saturating_duration_since(prev));
+        self.last_cycle_end = Some(end);
+        let seq = self.heartbeat.seq + 1;
+        self.heartbeat = Heartbeat { seq, cycle_us: took.as_micros() as u64, gap_ms: gap.as_millis() as u64, rows_polled };
+
+        let Some(budget) = self.cycle_budget else {
+            return Vec::new();
+        };
+        let mut stalls = Vec::new();
+        if gap > budget {
+            stalls.push(self.alert_engine.engine_stall(&format!("Gap before cycle #{seq}"), gap, budget));
+        }
+        if took > budget {
+            stalls.push(self.alert_engine.engine_stall(&format!("Cycle #{seq}"), took, budget));
+        }
+        stalls
+    }
+
+    /// Call when a deliberate pause, such as a breakpoint, ends, so the next
+    /// heartbeat doesn't report it as a stall.
+    pub fn resume(&mut self) {
+        self.last_cycle_end = None;
+    }
+
+    fn cover_ids(&mut self) {
+        if let Some(ids) = self.ids.as_mut() {
+            if let Err(e) = ids.cover(&mut self.alert_engine) {
+                eprintln!("Alert ID ledger write failed: {e}");
+            }
+        }
+    }
+
+    /// Zero the latency trackers, SLO attainment and throughput counters
+    /// (trades, orders, stream rows, push stats) so measurements start after
+    /// warm-up. Alert counts and history are kept.
+    pub fn reset_stats(&mut self) {
+        self.latency.reset();
+        self.slo.reset();
+        if let Some(c) = self.challenger.as_mut() {
+            c.latency.reset();
+        }
+        self.push_stats = PushStats::default();
+        self.stream_counts = [0; STREAM_NAMES.len()];
+        self.budget_exhausted = [0; STREAM_NAMES.len()];
+        self.eval_stats = EvalStats::default();
+        self.watermark_stats = WatermarkStats::default();
+        self.total_trades = 0;
+        self.total_orders = 0;
+        self.stats_since = self.elapsed();
+    }
+
+    /// Time the throughput counters cover: since start or the last reset.
+    pub fn stats_elapsed(&self) -> Duration {
+        self.elapsed().saturating_sub(self.stats_since)
+    }
+
+    /// Whether the governor currently has the engine degraded.
+    pub fn degraded(&self) -> bool {
+        self.governor.as_ref().is_some_and(|g| g.degraded())
+    }
+
+    /// Alerts the rules raised, before model suppression, cooldowns and the
+    /// governor's alert cap. Shadow-mode alerts are counted separately.
+    pub fn alerts_generated(&self) -> u64 {
+        self.alert_engine.total_alerts() + self.alert_engine.suppressed_alerts()
+    }
+
+    /// Alerts that reached the feed: generated, less those the scoring model
+    /// suppressed and those dropped over `max_alert_queue`.
+    pub fn alerts_delivered(&self) -> u64 {
+        let dropped = self.governor.as_ref().map_or(0, |g| g.dropped_alerts);
+        self.alert_engine.total_alerts().saturating_sub(dropped)
+    }
+
+    /// Whether the governor sees join fan-out over its guardrail.
+    pub fn join_overloaded(&self) -> bool {
+        self.governor.as_ref().is_some_and(|g| g.join_overloaded())
+    }
+
+    pub async fn push(&mut self, trades: Vec<Trade>, orders: Vec<Order>, watermark: i64) {
+        self.push_market(trades, orders, Vec::new(), Vec::new(), watermark).await;
+    }
+
+    /// [`Engine::push`] plus one cycle's order-book snapshots and news events.
+    /// Trade and order sequence numbers are checked first, and with a tick
+    /// detector the trades are checked for moves, before they reach the
+    /// pipeline.
+    pub async fn push_market(&mut self, trades: Vec<Trade>, orders: Vec<Order>, book: Vec<BookLevel>, news: Vec<NewsEvent>, watermark: i64) {
+        self.total_trades += trades.len() as u64;
+        self.total_orders += orders.len() as u64;
+        self.observe_sequences(&trades, &orders);
+        self.observe_ticks(&trades);
+        if let Some(standby) = self.standby.as_mut() {
+            standby.mirror(&trades, &orders, &book, &news);
+        }
+
+        let push_start = self.latency.record_push_start();
+        let Engine { pipeline, push_retry, push_stats, .. } = self;
+        push_with_retry(&pipeline.trade_source, trades, push_retry, push_stats).await;
+        if !orders.is_empty() {
+            push_with_retry(&pipeline.order_source, orders, push_retry, push_stats).await;
+        }
+        if !book.is_empty() {
+            push_with_retry(&pipeline.book_source, book, push_retry, push_stats).await;
+        }
+        if !news.is_empty() {
+            push_with_retry(&pipeline.news_source, news, push_retry, push_stats).await;
+        }
+        let pending = pipeline
+            .trade_source
+            .pending()
+            .max(pipeline.order_source.pending())
+            .max(pipeline.book_source.pending())
+            .max(pipeline.news_source.pending());
+        push_stats.buffer_pressure = pending as f64 / pipeline.buffer_size.max(1) as f64;
+
+        self.offer_watermark(watermark);
+        self.latency.record_push_end(push_start);
+    }
+
+    /// A push's watermark: emitted if the strategy says it's due, else held.
+    fn offer_watermark(&mut self, watermark: i64) {
+        self.watermark_stats.pushes += 1;
+        self.pushes_since_watermark += 1;
+        if self.watermark.due(self.pushes_since_watermark, self.last_watermark, watermark) {
+            self.advance_watermarks(watermark);
+        } else {
+            self.held_watermark = Some(watermark);
+        }
+    }
+
+    fn advance_watermarks(&mut self, watermark: i64) {
+        self.pipeline.trade_source.watermark(watermark);
+        self.pipeline.order_source.watermark(watermark);
+        self.pipeline.book_source.watermark(watermark);
+        self.pipeline.news_source.watermark(watermark);
+        if let Some(standby) = self.standby.as_mut() {
+            standby.watermark(watermark);
+        }
+        self.last_watermark = Some(watermark);
+        self.held_watermark = None;
+        self.pushes_since_watermark = 0;
+        self.watermark_stats.emitted += 1;
+    }
+
+    fn observe_sequences(&mut self, trades: &[Trade], orders: &[Order]) {
+        let breaks = [
+            self.sequences.observe("trades", trades.iter().map(|t| t.seq)),
+            self.sequences.observe("orders", orders.iter().map(|o| o.seq)),
+        ];
+        for brk in breaks.into_iter().flatten() {
+            let alert = self.alert_engine.feed_integrity(&brk);
+            self.push_alerts.push(alert);
+        }
+    }
+
+    fn observe_ticks(&mut self, trades: &[Trade]) {
+        let Some(detector) = self.ticks.as_mut() else {
+            return;
+        };
+        let seen = Instant::now();
+        let now_ms = self.clock.now_ms();
+        for m in detector.observe(trades) {
+            self.latency.record_tick_lag(now_ms - m.ts);
+            if let Some(alert) = self.alert_engine.evaluate_tick(&m, seen) {
+                self.latency.record_alert(seen);
+                self.quality.observe_alert(&alert);
+                self.push_alerts.push(alert);
+            }
+        }
+    }
+
+    /// Drain every subscription once and evaluate each row, up to
+    /// `poll_budget` rows per stream with rows carried over from the last
+    /// cycle first. With `eval_worker`, the rules run on the engine's
+    /// evaluation worker while the remaining streams are polled. `cycle_ts`
+    /// attributes timestamp-less rows (bursts, matches) to a feature window.
+    pub fn poll(&mut self, cycle_ts: i64, gen_instant: Instant) -> Vec<Alert> {
+        let Engine {
+            pipeline, alert_engine, latency, slo, quality, features, challenger, stream_counts, poll_budget, budget_exhausted, carried_rows,
+            backlog, eval_worker, worker, eval_stats, watermark_stats, push_alerts, recorder, ..
+        } = self;
+        let mut fired = std::mem::take(push_alerts);
+        let budget = poll_budget.unwrap_or(usize::MAX);
+        *carried_rows = [0; STREAM_NAMES.len()];
+        backlog.rapid_fire_profiles.resize_with(pipeline.rapid_fire_profile_subs.len(), VecDeque::new);
+        let mut worker = eval_worker.then(|| worker.get_or_insert_with(EvalWorker::spawn));
+        let mut evaluator = match worker.as_mut() {
+            Some(w) => {
+                w.begin(alert_engine, quality, challenger, gen_instant);
+                None
+            }
+            None => Some(Evaluator::new(alert_engine, quality, challenger.as_mut(), gen_instant)),
+        };
+        let mut dispatch = |batch: RowBatch| {
+            if batch.is_empty() {
+                return;
+            }
+            if let Some(r) = recorder.as_mut() {
+                r.observe(&batch);
+            }
+            match (evaluator.as_mut(), worker.as_mut()) {
+                (Some(ev), _) => ev.evaluate(batch),
+                (None, Some(w)) => w.send(batch, eval_stats),
+                (None, None) => unreachable!("evaluated inline or on the worker"),
+            }
+        };
+
+        macro_rules! poll_stream {
+            ($sub:expr, $backlog:expr, $idx:expr, $batch:ident, |$row:ident| $observe:block) => {
+                if let Some(ref sub) = $sub {
+                    let pending = &mut $backlog;
+                    let mut left = budget;
+                    while left > 0 {
+                        if pending.is_empty() {
+                            let Some(rows) = sub.poll() else { break };
+                            latency.record_poll();
+                            watermark_stats.output_batches += 1;
+                            watermark_stats.output_rows += rows.len() as u64;
+                            pending.extend(rows);
+                        }
+                        let take = pending.len().min(left);
+                        left -= take;
+                        let rows: Vec<_> = pending.drain(..take).collect();
+                        stream_counts[$idx] += rows.len() as u64;
+                        for $row in &rows $observe
+                        dispatch(RowBatch::$batch(rows));
+                    }
+                    // Spent exactly: anything still queued is carried over too
+                    if left == 0 && pending.is_empty() {
+                        if let Some(rows) = sub.poll() {
+                            latency.record_poll();
+                            watermark_stats.output_batches += 1;
+                            watermark_stats.output_rows += rows.len() as u64;
+                            pending.extend(rows);
+                        }
+                    }
+                    if !pending.is_empty() {
+                        budget_exhausted[$idx] += 1;
+                        carried_rows[$idx] += pending.len() as u64;
+                    }
+                }
+            };
+        }
+
+        poll_stream!(pipeline.vol_baseline_sub, backlog.vol_baseline, 0, VolumeBaseline, |_row| {});
+        poll_stream!(pipeline.ohlc_vol_sub, backlog.ohlc_vol, 1, OhlcVolatility, |row| {
+            latency.record_bar_lag(cycle_ts - (row.bar_start + OHLC_BAR_MS));
+        });
+        poll_stream!(pipeline.rapid_fire_sub, backlog.rapid_fire, 2, RapidFire, |row| {
+            if let Some(f) = features.as_mut() {
+                f.observe_burst(&row.account_id, cycle_ts);
+            }
+        });
+        for (sub, pending) in pipeline.rapid_fire_profile_subs.iter().zip(backlog.rapid_fire_profiles.iter_mut()) {
+            poll_stream!(Some(sub), *pending, 2, RapidFire, |row| {
+                if let Some(f) = features.as_mut() {
+                    f.observe_burst(&row.account_id, cycle_ts);
+                }
+            });
+        }
+        poll_stream!(pipeline.wash_score_sub, backlog.wash_score, 3, WashScore, |_row| {});
+        poll_stream!(pipeline.suspicious_match_sub, backlog.suspicious_match, 4, SuspiciousMatch, |row| {
+            if let Some(f) = features.as_mut() {
+                f.observe_match(&row.account_id, cycle_ts);
+            }
+        });
+        poll_stream!(pipeline.asof_match_sub, backlog.asof_match, 5, AsofMatch, |_row| {});
+        poll_stream!(pipeline.account_activity_sub, backlog.account_activity, 6, AccountActivity, |_row| {});
+        poll_stream!(pipeline.book_imbalance_sub, backlog.book_imbalance, 7, BookImbalance, |_row| {});
+        poll_stream!(pipeline.momentum_burst_sub, backlog.momentum_burst, 8, MomentumBurst, |_row| {});
+        poll_stream!(pipeline.pre_news_sub, backlog.pre_news, 9, PreNews, |_row| {});
+        poll_stream!(pipeline.price_improvement_sub, backlog.price_improvement, 10, PriceImprovement, |_row| {});
+        poll_stream!(pipeline.counterparty_volume_sub, backlog.counterparty_volume, 11, CounterpartyVolume, |_row| {});
+        let evaluated = match evaluator {
+            Some(ev) => ev.finish(),
+            None => worker.expect("evaluated on the worker").finish(alert_engine, quality, challenger, eval_stats),
+        };
+        for alert in &evaluated {
+            latency.record_alert_us(alert.latency_us);
+        }
+        fired.extend(evaluated);
+        slo.observe(&fired, chrono::Utc::now().timestamp_millis());
+
+        if let (Some(f), Some(sub)) = (features.as_mut(), pipeline.account_features_sub.as_ref()) {
+            while let Some(rows) = sub.poll() {
+                for row in &rows {
+                    f.observe_window(row);
+                }
+            }
+        }
+        if let Some(standby) = self.standby.as_mut() {
+            standby.discard_output();
+        }
+
+        fired
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    pub async fn sleep(&self, d: Duration) {
+        self.clock.sleep(d).await;
+    }
+
+    pub async fn shutdown(mut self) {
+        if let Some(ids) = self.ids.as_mut() {
+            if let Err(e) = ids.close(&self.alert_engine) {
+                eprintln!("Alert ID ledger write failed: {e}");
+            }
+        }
+        let _ = self.pipeline.db.shutdown().await;
+        for pipeline in self.retired.into_iter().chain(self.standby.map(|s| s.pipeline)) {
+            let _ = pipeline.db.shutdown().await;
+        }
+    }
+
+    /// Start mirroring every push into `pipeline`, built with the new stream
+    /// SQL; once it has seen `warmup` of event time, the next cycle evaluates
+    /// its rows instead of the live pipeline's. A standby already warming is
+    /// replaced.
+    pub fn start_standby(&mut self, pipeline: DetectionPipeline, warmup: Duration) {
+        if let Some(old) = self.standby.replace(Standby::new(pipeline, warmup)) {
+            self.retired.push(old.pipeline);
+        }
+    }
+
+    /// Switch a warm standby in for the live pipeline, between cycles and
+    /// only once no stream is carrying rows over. The rule engine is kept,
+    /// so its baselines carry on over the new pipeline's rows. Returns
+    /// whether it switched.
+    fn promote_standby(&mut self) -> bool {
+        if !self.standby.as_ref().is_some_and(Standby::is_warm) || self.carried_rows.iter().any(|&n| n > 0) {
+            return false;
+        }
+        let standby = self.standby.take().expect("checked above");
+        let old = std::mem::replace(&mut self.pipeline, standby.pipeline);
+        self.retired.push(old);
+        self.backlog = PollBacklog::default();
+        self.pipeline_switches += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+
+    /// A source whose buffer takes at most `room` records per push and is
+    /// drained between pushes. A refused record stays with the caller.
+    struct StubSource {
+        room: usize,
+        pushes: Cell<u32>,
+        received: RefCell<Vec<u32>>,
+    }
+
+    impl StubSource {
+        fn new(room: usize) -> Self {
+            Self { room, pushes: Cell::new(0), received: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl PushTarget<u32> for StubSource {
+        fn push_from(&self, records: &[u32]) -> usize {
+            self.pushes.set(self.pushes.get() + 1);
+            let taken = records.len().min(self.room);
+            self.received.borrow_mut().extend_from_slice(&records[..taken]);
+            taken
+        }
+    }
+
+    const RETRY: PushRetry = PushRetry { max_retries: 2, initial_backoff: Duration::from_micros(10) };
+
+    #[tokio::test]
+    async fn full_buffer_retries_then_drops_the_rest() {
+        let source = StubSource::new(0);
+        let mut stats = PushStats::default();
+        push_with_retry(&source, (0..10).collect::<Vec<u32>>(), &RETRY, &mut stats).await;
+        assert_eq!(source.pushes.get(), 3, "the first push and max_retries more");
+        assert_eq!((stats.rejected_batches, stats.rejected_records, stats.deferred_batches), (1, 10, 0));
+    }
+
+    #[tokio::test]
+    async fn a_refused_record_is_retried_not_lost() {
+        let source = StubSource::new(4);
+        let mut stats = PushStats::default();
+        push_with_retry(&source, (0..10).collect::<Vec<u32>>(), &RETRY, &mut stats).await;
+        assert_eq!(source.pushes.get(), 3, "4, 4, then the last 2");
+        assert_eq!(*source.received.borrow(), (0..10).collect::<Vec<u32>>(), "every record, in order");
+        assert_eq!((stats.rejected_batches, stats.rejected_records, stats.deferred_batches), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn rejected_records_count_only_what_was_refused() {
+        let source = StubSource::new(3);
+        let mut stats = PushStats::default();
+        push_with_retry(&source, (0..10).collect::<Vec<u32>>(), &RETRY, &mut stats).await;
+        assert_eq!(source.pushes.get(), 3, "3 taken per push, the last record still refused");
+        assert_eq!(source.received.borrow().len(), 9);
+        assert_eq!((stats.rejected_batches, stats.rejected_records, stats.deferred_batches), (1, 1, 0));
+
+        push_with_retry(&StubSource::new(100), (0..10).collect::<Vec<u32>>(), &RETRY, &mut stats).await;
+        assert_eq!((stats.rejected_batches, stats.rejected_records, stats.deferred_batches), (1, 1, 0), "a batch that fits counts nothing");
+        push_with_retry(&source, Vec::<u32>::new(), &RETRY, &mut stats).await;
+        assert_eq!(stats.rejected_batches, 1, "nor does an empty one");
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        let retry = PushRetry { max_retries: 100, initial_backoff: Duration::from_micros(50) };
+        assert_eq!(retry.backoff(0), Duration::from_micros(50));
+        assert_eq!(retry.backoff(3), Duration::from_micros(400));
+        assert_eq!(retry.backoff(99), retry.backoff(PushRetry::MAX_DOUBLINGS), "no overflow at high max_retries");
+    }
+}
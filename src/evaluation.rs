@@ -0,0 +1,318 @@
+//! Rule evaluation for polled stream rows, inline or on a worker thread.
+//! The worker is started once and lives as long as the engine. Each cycle
+//! lends it the rule state, then the poll loop queues each stream's rows as
+//! soon as it has them and goes on draining the other subscriptions while
+//! the rules run. The queue is bounded: a poll loop that gets
+//! `EVAL_QUEUE_BATCHES` ahead waits for room. Once everything is polled,
+//! the rules come back over a results channel with the cycle's alerts. Rows
+//! reach the rules in the order they were polled either way, so the alerts
+//! are the same.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertEngine};
+use crate::engine::Challenger;
+use crate::quality::QualityTracker;
+use crate::types::*;
+
+/// One poll's rows from one stream, on their way to the rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RowBatch {
+    VolumeBaseline(Vec<VolumeBaseline>),
+    OhlcVolatility(Vec<OhlcVolatility>),
+    RapidFire(Vec<RapidFireBurst>),
+    WashScore(Vec<WashScore>),
+    SuspiciousMatch(Vec<SuspiciousMatch>),
+    AsofMatch(Vec<AsofMatch>),
+    AccountActivity(Vec<AccountActivity>),
+    BookImbalance(Vec<BookImbalance>),
+    MomentumBurst(Vec<MomentumBurst>),
+    PreNews(Vec<PreNewsTrade>),
+    PriceImprovement(Vec<PriceImprovement>),
+    CounterpartyVolume(Vec<CounterpartyVolume>),
+}
+
+impl RowBatch {
+    pub fn len(&self) -> usize {
+        match self {
+            RowBatch::VolumeBaseline(rows) => rows.len(),
+            RowBatch::OhlcVolatility(rows) => rows.len(),
+            RowBatch::RapidFire(rows) => rows.len(),
+            RowBatch::WashScore(rows) => rows.len(),
+            RowBatch::SuspiciousMatch(rows) => rows.len(),
+            RowBatch::AsofMatch(rows) => rows.len(),
+            RowBatch::AccountActivity(rows) => rows.len(),
+            RowBatch::BookImbalance(rows) => rows.len(),
+            RowBatch::MomentumBurst(rows) => rows.len(),
+            RowBatch::PreNews(rows) => rows.len(),
+            RowBatch::PriceImprovement(rows) => rows.len(),
+            RowBatch::CounterpartyVolume(rows) => rows.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index of the stream the rows came from, into `STREAM_NAMES`.
+    pub fn stream(&self) -> usize {
+        match self {
+            RowBatch::VolumeBaseline(_) => 0,
+            RowBatch::OhlcVolatility(_) => 1,
+            RowBatch::RapidFire(_) => 2,
+            RowBatch::WashScore(_) => 3,
+            RowBatch::SuspiciousMatch(_) => 4,
+            RowBatch::AsofMatch(_) => 5,
+            RowBatch::AccountActivity(_) => 6,
+            RowBatch::BookImbalance(_) => 7,
+            RowBatch::MomentumBurst(_) => 8,
+            RowBatch::PreNews(_) => 9,
+            RowBatch::PriceImprovement(_) => 10,
+            RowBatch::CounterpartyVolume(_) => 11,
+        }
+    }
+}
+
+/// Batches the poll loop may queue for the worker before it waits for room.
+pub const EVAL_QUEUE_BATCHES: usize = 32;
+
+/// How the evaluation worker kept up, since start or the last stats reset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvalStats {
+    /// Batches handed to the worker.
+    pub batches: u64,
+    pub rows: u64,
+    /// Most batches queued or being evaluated at once.
+    pub max_queue_depth: u64,
+    /// Batches queued or being evaluated when the last cycle's polling finished.
+    pub last_queue_depth: u64,
+    /// Batches that found the queue full and waited for room.
+    #[serde(default)]
+    pub queue_full: u64,
+    /// Total time the poll loop waited on the worker: for room in the
+    /// queue, and for the rules to finish once polling was done.
+    pub wait_us: u64,
+}
+
+/// A cycle's rule evaluation: the main rule set, the quality tracker and
+/// any challenger, borrowed for the cycle.
+pub struct Evaluator<'a> {
+    alert_engine: &'a mut AlertEngine,
+    quality: &'a mut QualityTracker,
+    challenger: Option<&'a mut Challenger>,
+    gen_instant: Instant,
+    fired: Vec<Alert>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(alert_engine: &'a mut AlertEngine, quality: &'a mut QualityTracker, challenger: Option<&'a mut Challenger>, gen_instant: Instant) -> Self {
+        Self { alert_engine, quality, challenger, gen_instant, fired: Vec::new() }
+    }
+
+    pub fn evaluate(&mut self, batch: RowBatch) {
+        let Evaluator { alert_engine, quality, challenger, gen_instant, fired } = self;
+        let gen_instant = *gen_instant;
+        macro_rules! evaluate_rows {
+            ($rows:expr, $eval:ident) => {
+                for row in &$rows {
+                    if let Some(alert) = alert_engine.$eval(row, gen_instant) {
+                        quality.observe_alert(&alert);
+                        fired.push(alert);
+                    }
+                    if let Some(c) = challenger.as_mut() {
+                        if let Some(alert) = c.alert_engine.$eval(row, gen_instant) {
+                            c.latency.record_alert(gen_instant);
+                            c.quality.observe_alert(&alert);
+                        }
+                    }
+                }
+            };
+        }
+        match batch {
+            RowBatch::VolumeBaseline(rows) => evaluate_rows!(rows, evaluate_volume),
+            RowBatch::OhlcVolatility(rows) => evaluate_rows!(rows, evaluate_ohlc),
+            RowBatch::RapidFire(rows) => evaluate_rows!(rows, evaluate_rapid_fire),
+            RowBatch::WashScore(rows) => evaluate_rows!(rows, evaluate_wash),
+            RowBatch::SuspiciousMatch(rows) => evaluate_rows!(rows, evaluate_match),
+            RowBatch::AsofMatch(rows) => evaluate_rows!(rows, evaluate_asof),
+            RowBatch::AccountActivity(rows) => evaluate_rows!(rows, evaluate_activity),
+            RowBatch::BookImbalance(rows) => evaluate_rows!(rows, evaluate_book),
+            RowBatch::MomentumBurst(rows) => evaluate_rows!(rows, evaluate_momentum),
+            RowBatch::PreNews(rows) => evaluate_rows!(rows, evaluate_news),
+            RowBatch::PriceImprovement(rows) => evaluate_rows!(rows, evaluate_improvement),
+            RowBatch::CounterpartyVolume(rows) => evaluate_rows!(rows, evaluate_concentration),
+        }
+    }
+
+    /// Once every stream is polled: score the accounts the rows touched
+    /// with the ML model, and hand back every alert raised, in order.
+    pub fn finish(self) -> Vec<Alert> {
+        let Evaluator { alert_engine, quality, challenger, gen_instant, mut fired } = self;
+        for alert in alert_engine.evaluate_ml(gen_instant) {
+            quality.observe_alert(&alert);
+            fired.push(alert);
+        }
+        if let Some(c) = challenger {
+            for alert in c.alert_engine.evaluate_ml(gen_instant) {
+                c.latency.record_alert(gen_instant);
+                c.quality.observe_alert(&alert);
+            }
+        }
+        fired
+    }
+}
+
+/// The rule state lent to the worker for a cycle.
+struct Rules {
+    alert_engine: AlertEngine,
+    quality: QualityTracker,
+    challenger: Option<Challenger>,
+}
+
+enum Job {
+    /// Start a cycle on these rules.
+    Begin(Box<Rules>, Instant),
+    Rows(RowBatch),
+    /// Run the ML step and send the rules back with every alert raised.
+    Finish,
+}
+
+/// The long-lived evaluation thread and its two channels: a bounded queue
+/// of jobs in, and each cycle's rules and alerts back.
+pub struct EvalWorker {
+    jobs: SyncSender<Job>,
+    results: Receiver<(Box<Rules>, Vec<Alert>)>,
+    /// Batches evaluated, counted by the worker against `sent`.
+    done: Arc<AtomicU64>,
+    sent: u64,
+    /// Stands in for the engine's rule set while the worker has it, so
+    /// lending doesn't build a new one every cycle.
+    spare: Option<AlertEngine>,
+}
+
+impl EvalWorker {
+    pub fn spawn() -> Self {
+        let (jobs, rx) = mpsc::sync_channel(EVAL_QUEUE_BATCHES);
+        let (tx, results) = mpsc::channel();
+        let done = Arc::new(AtomicU64::new(0));
+        let counter = done.clone();
+        std::thread::Builder::new()
+            .name("eval-worker".into())
+            .spawn(move || Self::run(rx, tx, counter))
+            .expect("failed to start the evaluation worker");
+        Self { jobs, results, done, sent: 0, spare: Some(AlertEngine::new()) }
+    }
+
+    /// Until the engine drops its end: take a cycle's rules, evaluate every
+    /// batch queued for it, then hand the rules back with the alerts.
+    fn run(jobs: Receiver<Job>, results: mpsc::Sender<(Box<Rules>, Vec<Alert>)>, done: Arc<AtomicU64>) {
+        while let Ok(Job::Begin(mut rules, gen_instant)) = jobs.recv() {
+            let Rules { alert_engine, quality, challenger } = &mut *rules;
+            let mut ev = Evaluator::new(alert_engine, quality, challenger.as_mut(), gen_instant);
+            loop {
+                match jobs.recv() {
+                    Ok(Job::Rows(batch)) => {
+                        ev.evaluate(batch);
+                        done.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok(Job::Finish) => break,
+                    Ok(Job::Begin(..)) | Err(_) => return,
+                }
+            }
+            let alerts = ev.finish();
+            if results.send((rules, alerts)).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Batches handed over and not yet evaluated.
+    fn depth(&self) -> u64 {
+        self.sent - self.done.load(Ordering::Relaxed)
+    }
+
+    /// Lend the worker the engine's rules for a cycle; [`EvalWorker::finish`]
+    /// puts them back.
+    pub fn begin(&mut self, alert_engine: &mut AlertEngine, quality: &mut QualityTracker, challenger: &mut Option<Challenger>, gen_instant: Instant) {
+        let spare = self.spare.take().expect("the rules are lent one cycle at a time");
+        let rules = Rules { alert_engine: std::mem::replace(alert_engine, spare), quality: std::mem::take(quality), challenger: challenger.take() };
+        self.jobs.send(Job::Begin(Box::new(rules), gen_instant)).expect("evaluation worker panicked");
+    }
+
+    /// Queue `batch`, waiting for room if the worker is `EVAL_QUEUE_BATCHES` behind.
+    pub fn send(&mut self, batch: RowBatch, stats: &mut EvalStats) {
+        stats.batches += 1;
+        stats.rows += batch.len() as u64;
+        self.sent += 1;
+        stats.max_queue_depth = stats.max_queue_depth.max(self.depth());
+        let job = match self.jobs.try_send(Job::Rows(batch)) {
+            Ok(()) => return,
+            Err(TrySendError::Full(job)) => job,
+            Err(TrySendError::Disconnected(_)) => panic!("evaluation worker panicked"),
+        };
+        stats.queue_full += 1;
+        let wait = Instant::now();
+        self.jobs.send(job).expect("evaluation worker panicked");
+        stats.wait_us += wait.elapsed().as_micros() as u64;
+    }
+
+    /// Once every stream is polled: wait for the worker to get through the
+    /// queue and score, put the rules back, and return the cycle's alerts.
+    pub fn finish(&mut self, alert_engine: &mut AlertEngine, quality: &mut QualityTracker, challenger: &mut Option<Challenger>, stats: &mut EvalStats) -> Vec<Alert> {
+        stats.last_queue_depth = self.depth();
+        let wait = Instant::now();
+        self.jobs.send(Job::Finish).expect("evaluation worker panicked");
+        let (rules, alerts) = self.results.recv().expect("evaluation worker panicked");
+        stats.wait_us += wait.elapsed().as_micros() as u64;
+        let Rules { alert_engine: lent, quality: q, challenger: c } = *rules;
+        self.spare = Some(std::mem::replace(alert_engine, lent));
+        *quality = q;
+        *challenger = c;
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wash(account_id: &str) -> WashScore {
+        WashScore {
+            account_id: account_id.into(),
+            symbol: "AAPL".into(),
+            window_start: 100_000,
+            buy_volume: 500,
+            sell_volume: 500,
+            buy_count: 3,
+            sell_count: 3,
+            buy_notional: 75_000.0,
+            sell_notional: 75_000.0,
+        }
+    }
+
+    #[test]
+    fn the_worker_hands_back_the_rules_with_each_cycles_alerts() {
+        let mut worker = EvalWorker::spawn();
+        let mut alert_engine = AlertEngine::new();
+        let mut quality = QualityTracker::new();
+        let mut challenger = None;
+        let mut stats = EvalStats::default();
+        for (cycle, accounts) in [["FRAUD-01", "FRAUD-02"], ["FRAUD-03", "FRAUD-04"]].iter().enumerate() {
+            worker.begin(&mut alert_engine, &mut quality, &mut challenger, Instant::now());
+            for account in accounts {
+                worker.send(RowBatch::WashScore(vec![wash(account)]), &mut stats);
+            }
+            let alerts = worker.finish(&mut alert_engine, &mut quality, &mut challenger, &mut stats);
+            let fired: Vec<_> = alerts.iter().filter_map(|a| a.account_id.as_deref()).collect();
+            assert_eq!(fired, accounts.to_vec(), "cycle {cycle}: alerts in the order queued");
+            assert_eq!(alert_engine.total_alerts(), 2 * (cycle as u64 + 1), "the rules carry over between cycles");
+        }
+        assert_eq!((stats.batches, stats.rows), (4, 4));
+        assert!(stats.max_queue_depth >= 1 && stats.max_queue_depth <= 2);
+    }
+}
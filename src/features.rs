@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::generator::FraudLabel;
+use crate::types::AccountWindow;
+
+/// Matches the 5s TUMBLE in the `account_features` stream.
+pub const FEATURE_WINDOW_MS: i64 = 5_000;
+
+/// One training example: an account's activity in one 5s window.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureRow {
+    pub account_id: String,
+    pub window_start: i64,
+    pub trade_count: i64,
+    pub total_volume: i64,
+    /// Volume relative to the account's own mean/stddev over the whole run.
+    pub volume_zscore: f64,
+    /// |buy - sell| / (buy + sell); 1.0 when the window has no volume.
+    pub imbalance: f64,
+    pub burst_count: u64,
+    pub match_count: u64,
+    /// 1 if a fraud scenario was injected for this account in this window.
+    pub label: u8,
+    pub scenario: String,
+    buy_volume: i64,
+    sell_volume: i64,
+}
+
+pub fn window_start(ts: i64) -> i64 {
+    ts - ts.rem_euclid(FEATURE_WINDOW_MS)
+}
+
+/// Collects `account_features` rows, rapid_fire bursts, suspicious matches, and
+/// generator labels, then joins them per (window, account) in `finish`.
+///
+/// Windows are upserted because the stream may emit a window more than once as
+/// it fills; the last emission wins.
+#[derive(Default)]
+pub struct FeatureExtractor {
+    windows: BTreeMap<(i64, String), FeatureRow>,
+    labels: Vec<FraudLabel>,
+}
+
+impl FeatureExtractor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entry(&mut self, window_start: i64, account_id: &str) -> &mut FeatureRow {
+        self.windows
+            .entry((window_start, account_id.to_string()))
+            .or_insert_with(|| FeatureRow {
+                account_id: account_id.to_string(),
+                window_start,
+                ..Default::default()
+            })
+    }
+
+    pub fn observe_window(&mut self, row: &AccountWindow) {
+        let f = self.entry(row.window_start, &row.account_id);
+        f.trade_count = row.trade_count;
+        f.total_volume = row.total_volume;
+        f.buy_volume = row.buy_volume;
+        f.sell_volume = row.sell_volume;
+    }
+
+    /// rapid_fire rows carry no timestamp, so they are attributed to the
+    /// window of the cycle that polled them.
+    pub fn observe_burst(&mut self, account_id: &str, cycle_ts: i64) {
+        self.entry(window_start(cycle_ts), account_id).burst_count += 1;
+    }
+
+    pub fn observe_match(&mut self, account_id: &str, cycle_ts: i64) {
+        self.entry(window_start(cycle_ts), account_id).match_count += 1;
+    }
+
+    pub fn observe_labels(&mut self, labels: Vec<FraudLabel>) {
+        self.labels.extend(labels);
+    }
+
+    /// Compute z-scores, imbalance, and labels. Rows are ordered by window, then account.
+    pub fn finish(self) -> Vec<FeatureRow> {
+        let mut rows: Vec<FeatureRow> = self.windows.into_values().collect();
+
+        let mut volumes: HashMap<&str, Vec<f64>> = HashMap::new();
+        for r in &rows {
+            volumes.entry(r.account_id.as_str()).or_default().push(r.total_volume as f64);
+        }
+        let stats: HashMap<String, (f64, f64)> = volumes
+            .into_iter()
+            .map(|(acct, v)| {
+                let n = v.len() as f64;
+                let mean = v.iter().sum::<f64>() / n;
+                let var = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+                (acct.to_string(), (mean, var.sqrt()))
+            })
+            .collect();
+
+        for r in &mut rows {
+            let (mean, std) = stats[&r.account_id];
+            r.volume_zscore = if std > 0.0 { (r.total_volume as f64 - mean) / std } else { 0.0 };
+
+            let total = r.buy_volume + r.sell_volume;
+            r.imbalance = if total > 0 {
+                (r.buy_volume - r.sell_volume).unsigned_abs() as f64 / total as f64
+            } else {
+                1.0
+            };
+
+            let end = r.window_start + FEATURE_WINDOW_MS;
+            if let Some(l) = self.labels.iter().find(|l| {
+                l.account_id.as_deref() == Some(r.account_id.as_str()) && l.start_ts < end && l.end_ts >= r.window_start
+            }) {
+                r.label = 1;
+                r.scenario = l.scenario.to_string();
+            }
+        }
+        rows
+    }
+}
+
+const CSV_HEADER: &str = "account_id,window_start,trade_count,total_volume,volume_zscore,imbalance,burst_count,match_count,label,scenario";
+
+pub fn write_csv(path: &Path, rows: &[FeatureRow]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "{CSV_HEADER}")?;
+    for r in rows {
+        writeln!(
+            w,
+            "{},{},{},{},{:.6},{:.6},{},{},{},{}",
+            r.account_id, r.window_start, r.trade_count, r.total_volume, r.volume_zscore,
+            r.imbalance, r.burst_count, r.match_count, r.label, r.scenario
+        )?;
+    }
+    w.flush()
+}
+
+#[cfg(feature = "parquet")]
+pub fn write_parquet(path: &Path, rows: &[FeatureRow]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray, UInt64Array, UInt8Array};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+
+    let batch = RecordBatch::try_from_iter(vec![
+        ("account_id", Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.account_id.as_str()))) as ArrayRef),
+        ("window_start", Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.window_start)))),
+        ("trade_count", Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.trade_count)))),
+        ("total_volume", Arc::new(Int64Array::from_iter_values(rows.iter().map(|r| r.total_volume)))),
+        ("volume_zscore", Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.volume_zscore)))),
+        ("imbalance", Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.imbalance)))),
+        ("burst_count", Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.burst_count)))),
+        ("match_count", Arc::new(UInt64Array::from_iter_values(rows.iter().map(|r| r.match_count)))),
+        ("label", Arc::new(UInt8Array::from_iter_values(rows.iter().map(|r| r.label)))),
+        ("scenario", Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.scenario.as_str())))),
+    ])?;
+
+    let mut writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Write `rows` as Parquet if `path` ends in `.parquet`, otherwise as CSV.
+pub fn export(path: &Path, rows: &[FeatureRow]) -> Result<(), Box<dyn std::error::Error>> {
+    let is_parquet = path.extension().is_some_and(|e| e == "parquet");
+    if is_parquet {
+        #[cfg(feature = "parquet")]
+        return write_parquet(path, rows);
+        #[cfg(not(feature = "parquet"))]
+        return Err("Parquet export requires building with --features parquet".into());
+    }
+    write_csv(path, rows)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(account: &str, start: i64, buy: i64, sell: i64) -> AccountWindow {
+        AccountWindow { account_id: account.into(), window_start: start, trade_count: 2, total_volume: buy + sell, buy_volume: buy, sell_volume: sell }
+    }
+
+    #[test]
+    fn finish_joins_windows_bursts_matches_and_labels() {
+        let mut x = FeatureExtractor::new();
+        x.observe_window(&window("A1", 10_000, 100, 0));
+        x.observe_window(&window("A1", 15_000, 150, 150));
+        x.observe_window(&window("B1", 10_000, 0, 0));
+        x.observe_burst("A1", 11_000);
+        x.observe_burst("A1", 14_999);
+        x.observe_match("A1", 16_000);
+        x.observe_labels(vec![FraudLabel { scenario: "wash_trading", account_id: Some("A1".into()), symbol: "AAPL".into(), start_ts: 16_000, end_ts: 17_000 }]);
+
+        let rows = x.finish();
+        let keys: Vec<(i64, &str)> = rows.iter().map(|r| (r.window_start, r.account_id.as_str())).collect();
+        assert_eq!(keys, [(10_000, "A1"), (10_000, "B1"), (15_000, "A1")]);
+        let (a1, b1, a2) = (&rows[0], &rows[1], &rows[2]);
+        assert_eq!((a1.volume_zscore, a2.volume_zscore, b1.volume_zscore), (-1.0, 1.0, 0.0), "A1 averages 200 +/- 100; B1 has one window");
+        assert_eq!((a1.imbalance, a2.imbalance, b1.imbalance), (1.0, 0.0, 1.0), "no volume counts as one-sided");
+        assert_eq!((a1.burst_count, a1.match_count, a2.burst_count, a2.match_count), (2, 0, 0, 1));
+        assert_eq!((a1.label, a2.label, a2.scenario.as_str()), (0, 1, "wash_trading"));
+    }
+
+    #[test]
+    fn a_window_emitted_again_replaces_the_last() {
+        let mut x = FeatureExtractor::new();
+        x.observe_window(&window("A1", 10_000, 10, 0));
+        x.observe_window(&window("A1", 10_000, 60, 40));
+        let rows = x.finish();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].total_volume, rows[0].imbalance), (100, 0.2));
+        assert!(FeatureExtractor::new().finish().is_empty());
+    }
+
+    #[test]
+    fn csv_export_has_a_header_and_one_line_per_row() {
+        let mut x = FeatureExtractor::new();
+        x.observe_window(&window("A1", 10_000, 100, 0));
+        let path = std::env::temp_dir().join(format!("features-{}.csv", uuid::Uuid::new_v4()));
+        export(&path, &x.finish()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(text, format!("{CSV_HEADER}\nA1,10000,2,100,0.000000,1.000000,0,0,0,\n"));
+    }
+}
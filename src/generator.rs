@@ -1,7 +1,14 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Duration;
 
-use crate::types::{Order, Trade};
+use crate::source::Batch;
+use crate::types::{BookLevel, NewsEvent, Order, Trade};
 
 pub const SYMBOLS: &[(&str, f64)] = &[
     ("AAPL", 150.0),
@@ -11,15 +18,207 @@ pub const SYMBOLS: &[(&str, f64)] = &[
     ("TSLA", 250.0),
 ];
 
+/// One of `count` detector instances splitting the market by symbol, written
+/// `index/count` (e.g. `0/3`). Shard `index` takes the symbols at positions
+/// `index`, `index + count`, ... of `SYMBOLS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Shard {
+    pub fn owns(&self, symbol: &str) -> bool {
+        SYMBOLS.iter().position(|(s, _)| *s == symbol).is_some_and(|i| i % self.count == self.index)
+    }
+
+    pub fn symbols(&self) -> Vec<&'static str> {
+        SYMBOLS.iter().map(|(s, _)| *s).filter(|s| self.owns(s)).collect()
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid shard '{s}': expected index/count, e.g. 0/3");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let (index, count): (usize, usize) = (index.trim().parse().map_err(|_| invalid())?, count.trim().parse().map_err(|_| invalid())?);
+        if count == 0 || index >= count {
+            return Err(format!("invalid shard '{s}': index must be below count"));
+        }
+        if count > SYMBOLS.len() {
+            return Err(format!("invalid shard '{s}': {} symbols can't be split {count} ways", SYMBOLS.len()));
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl TryFrom<String> for Shard {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+impl From<Shard> for String {
+    fn from(shard: Shard) -> Self {
+        shard.to_string()
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Chance that a normal or stress trade comes with a matching order.
+pub const ORDER_PROBABILITY: f64 = 0.3;
+
+/// Price levels on each side of a symbol's simulated order book.
+pub const BOOK_LEVELS: usize = 5;
+
+/// Gap between adjacent book levels, as a fraction of price.
+const BOOK_TICK: f64 = 0.0005;
+
+/// Spoofing lasts this many cycles; in the last `SPOOF_MOVE_CYCLES` of them
+/// the price drifts toward the stacked side by `SPOOF_DRIFT` per cycle.
+const SPOOF_CYCLES: u32 = 10;
+const SPOOF_MOVE_CYCLES: u32 = 5;
+const SPOOF_DRIFT: f64 = 0.0015;
+/// Resting size on the spoofed side relative to the honest book.
+const SPOOF_SIZE_FACTOR: i64 = 8;
+
+/// Momentum ignition: a burst of same-side trades `IGNITION_STEP_MS` apart,
+/// each `IGNITION_PRICE_STEP` further from the start, unwound by the same
+/// account `IGNITION_UNWIND_MS` after the burst began.
+const IGNITION_STEP_MS: i64 = 40;
+const IGNITION_PRICE_STEP: f64 = 0.001;
+const IGNITION_UNWIND_MS: i64 = 3_000;
+
+/// Insider trading: the account trades this long (event time) before its
+/// news breaks, and the news is at least this market-moving.
+const INSIDER_LEAD_MS: Range<i64> = 4_000..10_000;
+const INSIDER_MIN_IMPACT: f64 = 0.7;
+
+/// Price move per unit of impact when news breaks.
+const NEWS_PRICE_REACTION: f64 = 0.03;
+
+/// Chance of a background headline per cycle, and its largest |impact|.
+const BACKGROUND_NEWS_PROBABILITY: f64 = 0.02;
+const BACKGROUND_NEWS_MAX_IMPACT: f64 = 0.5;
+
+/// Mixed into the seed of the book's own RNG, so simulating the book never
+/// changes the trades and orders a seed produces.
+const BOOK_SEED_SALT: u64 = 0xB00C;
+
+/// Likewise for background news.
+const NEWS_SEED_SALT: u64 = 0x4E57;
+
+/// Likewise for benign look-alikes, so a seed produces the same fraud with
+/// or without them.
+const BENIGN_SEED_SALT: u64 = 0xB3A1;
+
+/// Likewise for the counterparties of normal trades.
+const COUNTERPARTY_SEED_SALT: u64 = 0xC7B7;
+
+/// Price improvement: how far through the market, as a fraction of price,
+/// a colluding counterparty fills its partner; the quote is `BOOK_TICK` away.
+const IMPROVEMENT_THROUGH: Range<f64> = 0.0015..0.003;
+
+/// VWAP slicing: child orders of one parent, `VWAP_SLICE_MS` apart with jitter.
+const VWAP_SLICE_MS: Range<i64> = 80..120;
+
+/// Market making: the maker's half-spread as a fraction of price.
+const MM_HALF_SPREAD: f64 = 0.0005;
+
 const NORMAL_ACCOUNTS: &[&str] = &["ACCT-001", "ACCT-002", "ACCT-003", "ACCT-004", "ACCT-005"];
 const FRAUD_ACCOUNTS: &[&str] = &["FRAUD-01", "FRAUD-02", "FRAUD-03"];
+const INDEX_ACCOUNT: &str = "INDEX-01";
+const ALGO_ACCOUNT: &str = "ALGO-01";
+const MARKET_MAKER_ACCOUNT: &str = "MM-01";
 
-#[derive(Debug, Clone, Copy)]
-enum FraudScenario {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FraudScenario {
     VolumeSpike,
     PriceManipulation,
     RapidFire,
     WashTrading,
+    Spoofing,
+    MomentumIgnition,
+    InsiderTrading,
+    PriceImprovement,
+}
+
+impl FraudScenario {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FraudScenario::VolumeSpike => "VolumeSpike",
+            FraudScenario::PriceManipulation => "PriceManipulation",
+            FraudScenario::RapidFire => "RapidFire",
+            FraudScenario::WashTrading => "WashTrading",
+            FraudScenario::Spoofing => "Spoofing",
+            FraudScenario::MomentumIgnition => "MomentumIgnition",
+            FraudScenario::InsiderTrading => "InsiderTrading",
+            FraudScenario::PriceImprovement => "PriceImprovement",
+        }
+    }
+}
+
+/// Legitimate activity that superficially looks like one of the fraud
+/// scenarios, injected so each rule's false-positive rate on it can be measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenignScenario {
+    /// An index fund rebalancing into one name: a VolumeSpike look-alike.
+    IndexRebalance,
+    /// One parent order worked as evenly spaced same-side child orders: a RapidFire look-alike.
+    VwapSlicing,
+    /// A market maker buying at the bid and selling at the ask in matched size: a WashTrading look-alike.
+    MarketMaking,
+}
+
+impl BenignScenario {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BenignScenario::IndexRebalance => "IndexRebalance",
+            BenignScenario::VwapSlicing => "VwapSlicing",
+            BenignScenario::MarketMaking => "MarketMaking",
+        }
+    }
+}
+
+pub const ALL_BENIGN_SCENARIOS: &[BenignScenario] =
+    &[BenignScenario::IndexRebalance, BenignScenario::VwapSlicing, BenignScenario::MarketMaking];
+
+/// Ground truth for one injected benign look-alike.
+#[derive(Debug, Clone)]
+pub struct BenignLabel {
+    pub scenario: &'static str,
+    pub account_id: String,
+    pub symbol: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+/// Ground truth for one injected fraud scenario.
+#[derive(Debug, Clone)]
+pub struct FraudLabel {
+    pub scenario: &'static str,
+    /// `None` for PriceManipulation, which moves the price of normal-account trades.
+    pub account_id: Option<String>,
+    pub symbol: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+/// Trades and orders per second of event time that `generate_cycle` produces,
+/// fraud aside, when called once every `cycle`.
+pub fn normal_rates(cycle: Duration) -> (f64, f64) {
+    let trades_per_sec = SYMBOLS.len() as f64 / cycle.as_secs_f64();
+    (trades_per_sec, trades_per_sec * ORDER_PROBABILITY)
 }
 
 const ALL_SCENARIOS: &[FraudScenario] = &[
@@ -27,30 +226,144 @@ const ALL_SCENARIOS: &[FraudScenario] = &[
     FraudScenario::PriceManipulation,
     FraudScenario::RapidFire,
     FraudScenario::WashTrading,
+    FraudScenario::Spoofing,
+    FraudScenario::MomentumIgnition,
+    FraudScenario::InsiderTrading,
+    FraudScenario::PriceImprovement,
 ];
 
+/// Resting size at each level of one symbol's book, best level first. Prices
+/// are not stored: they sit `BOOK_TICK` apart around the symbol's price.
+struct Book {
+    bids: [i64; BOOK_LEVELS],
+    asks: [i64; BOOK_LEVELS],
+}
+
+/// A spoofer stacking one side of a symbol's book to move its price.
+struct Spoof {
+    symbol: String,
+    /// Bids stacked to push the price up, or asks to push it down.
+    bid_side: bool,
+    remaining: u32,
+}
+
 pub struct FraudGenerator {
+    rng: StdRng,
     prices: HashMap<String, f64>,
     order_seq: u64,
     trade_seq: u64,
     pub fraud_rate: f64,
+    /// Chance per cycle of a benign look-alike, independent of `fraud_rate`.
+    pub benign_rate: f64,
     manipulation_remaining: u32,
     manipulation_symbol: Option<String>,
+    book_rng: StdRng,
+    books: HashMap<String, Book>,
+    spoof: Option<Spoof>,
+    news_rng: StdRng,
+    /// Insider news waiting for its `ts`; it moves the price when it breaks.
+    scheduled_news: Vec<NewsEvent>,
+    released_news: Vec<NewsEvent>,
+    labels: Vec<FraudLabel>,
+    benign_rng: StdRng,
+    benign_labels: Vec<BenignLabel>,
+    counterparty_rng: StdRng,
+    /// Symbols kept when sharded, with each feed renumbered from 1.
+    shard: Option<Shard>,
+    shard_trade_seq: i64,
+    shard_order_seq: i64,
 }
 
 impl FraudGenerator {
     pub fn new(fraud_rate: f64) -> Self {
+        Self::with_rngs(
+            fraud_rate,
+            StdRng::from_entropy(),
+            StdRng::from_entropy(),
+            StdRng::from_entropy(),
+            StdRng::from_entropy(),
+            StdRng::from_entropy(),
+        )
+    }
+
+    /// Same seed and same timestamps produce the same trades, orders, and injections.
+    pub fn with_seed(fraud_rate: f64, seed: u64) -> Self {
+        Self::with_rngs(
+            fraud_rate,
+            StdRng::seed_from_u64(seed),
+            StdRng::seed_from_u64(seed ^ BOOK_SEED_SALT),
+            StdRng::seed_from_u64(seed ^ NEWS_SEED_SALT),
+            StdRng::seed_from_u64(seed ^ BENIGN_SEED_SALT),
+            StdRng::seed_from_u64(seed ^ COUNTERPARTY_SEED_SALT),
+        )
+    }
+
+    /// Also inject benign look-alikes at `benign_rate` per cycle.
+    pub fn with_benign_rate(mut self, benign_rate: f64) -> Self {
+        self.benign_rate = benign_rate;
+        self
+    }
+
+    /// Keep only `shard`'s symbols in every cycle, book, headline and label.
+    /// The whole market is still simulated, so with the same seed the shards
+    /// of one market add up to the unsharded generator's output, apart from
+    /// sequence numbers: each shard's feeds count their own records.
+    pub fn with_shard(mut self, shard: Option<Shard>) -> Self {
+        self.shard = shard;
+        self
+    }
+
+    fn owns(&self, symbol: &str) -> bool {
+        self.shard.is_none_or(|s| s.owns(symbol))
+    }
+
+    /// Drop other shards' records and renumber the rest, so the ones dropped
+    /// don't read as sequence gaps.
+    fn keep_shard(&mut self, trades: &mut Vec<Trade>, orders: &mut Vec<Order>) {
+        let Some(shard) = self.shard else {
+            return;
+        };
+        trades.retain(|t| shard.owns(&t.symbol));
+        orders.retain(|o| shard.owns(&o.symbol));
+        for t in trades.iter_mut().filter(|t| t.seq != 0) {
+            self.shard_trade_seq += 1;
+            t.seq = self.shard_trade_seq;
+        }
+        for o in orders.iter_mut().filter(|o| o.seq != 0) {
+            self.shard_order_seq += 1;
+            o.seq = self.shard_order_seq;
+        }
+    }
+
+    fn with_rngs(fraud_rate: f64, rng: StdRng, book_rng: StdRng, news_rng: StdRng, benign_rng: StdRng, counterparty_rng: StdRng) -> Self {
         let mut prices = HashMap::new();
+        let mut books = HashMap::new();
         for (sym, base) in SYMBOLS {
             prices.insert(sym.to_string(), *base);
+            books.insert(sym.to_string(), Book { bids: [500; BOOK_LEVELS], asks: [500; BOOK_LEVELS] });
         }
         Self {
+            rng,
             prices,
             order_seq: 0,
             trade_seq: 0,
             fraud_rate,
+            benign_rate: 0.0,
             manipulation_remaining: 0,
             manipulation_symbol: None,
+            book_rng,
+            books,
+            spoof: None,
+            news_rng,
+            scheduled_news: Vec::new(),
+            released_news: Vec::new(),
+            labels: Vec::new(),
+            benign_rng,
+            benign_labels: Vec::new(),
+            counterparty_rng,
+            shard: None,
+            shard_trade_seq: 0,
+            shard_order_seq: 0,
         }
     }
 
@@ -68,36 +381,110 @@ impl FraudGenerator {
         &self.prices
     }
 
-    /// Generate trades + optional orders for one cycle. Returns (trades, orders).
-    pub fn generate_cycle(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
+    /// Take the ground-truth labels for scenarios injected since the last call.
+    pub fn drain_labels(&mut self) -> Vec<FraudLabel> {
+        let mut labels = std::mem::take(&mut self.labels);
+        labels.retain(|l| self.owns(&l.symbol));
+        labels
+    }
 
+    /// Take the labels for benign look-alikes injected since the last call.
+    pub fn drain_benign_labels(&mut self) -> Vec<BenignLabel> {
+        let mut labels = std::mem::take(&mut self.benign_labels);
+        labels.retain(|l| self.owns(&l.symbol));
+        labels
+    }
+
+    fn record_label(&mut self, scenario: FraudScenario, account_id: Option<&str>, symbol: &str, start_ts: i64, end_ts: i64) {
+        self.labels.push(FraudLabel {
+            scenario: scenario.label(),
+            account_id: account_id.map(str::to_string),
+            symbol: symbol.to_string(),
+            start_ts,
+            end_ts,
+        });
+    }
+
+    /// Generate trades + optional orders for one cycle. Returns (trades, orders),
+    /// of the shard's symbols only when sharded.
+    pub fn generate_cycle(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
         // Check if we should inject fraud this cycle
-        let inject_fraud = rng.gen_bool(self.fraud_rate.min(1.0));
-
-        if inject_fraud {
-            let scenario = ALL_SCENARIOS[rng.gen_range(0..ALL_SCENARIOS.len())];
-            match scenario {
-                FraudScenario::VolumeSpike => return self.inject_volume_spike(ts),
-                FraudScenario::PriceManipulation => {
-                    self.manipulation_remaining = 3;
-                    let idx = rng.gen_range(0..SYMBOLS.len());
-                    self.manipulation_symbol = Some(SYMBOLS[idx].0.to_string());
-                }
-                FraudScenario::RapidFire => return self.inject_rapid_fire(ts),
-                FraudScenario::WashTrading => return self.inject_wash_trading(ts),
-            }
+        let inject_fraud = self.rng.gen_bool(self.fraud_rate.min(1.0));
+
+        let (mut trades, mut orders) = if inject_fraud {
+            let scenario = ALL_SCENARIOS[self.rng.gen_range(0..ALL_SCENARIOS.len())];
+            self.generate_scenario_cycle(ts, scenario)
+        } else {
+            // Normal cycle (or price manipulation continuation)
+            self.generate_normal(ts)
+        };
+
+        if self.benign_rate > 0.0 && self.benign_rng.gen_bool(self.benign_rate.min(1.0)) {
+            let scenario = ALL_BENIGN_SCENARIOS[self.benign_rng.gen_range(0..ALL_BENIGN_SCENARIOS.len())];
+            let (mut benign_trades, mut benign_orders) = self.inject_benign(ts, scenario);
+            trades.append(&mut benign_trades);
+            orders.append(&mut benign_orders);
         }
+        self.keep_shard(&mut trades, &mut orders);
+        (trades, orders)
+    }
+
+    /// One cycle's trades and orders with the book snapshot and news due at
+    /// `ts`, as a source [`Batch`].
+    pub fn batch(&mut self, ts: i64) -> Batch {
+        let (trades, orders) = self.generate_cycle(ts);
+        Batch { trades, orders, book: self.book_snapshot(ts), news: self.news(ts) }
+    }
+
+    /// Generate one normal cycle plus the benign look-alike `scenario`,
+    /// ignoring `benign_rate`.
+    pub fn generate_benign_cycle(&mut self, ts: i64, scenario: BenignScenario) -> (Vec<Trade>, Vec<Order>) {
+        let (mut trades, mut orders) = self.generate_normal(ts);
+        let (mut benign_trades, mut benign_orders) = self.inject_benign(ts, scenario);
+        trades.append(&mut benign_trades);
+        orders.append(&mut benign_orders);
+        (trades, orders)
+    }
 
-        // Normal cycle (or price manipulation continuation)
-        self.generate_normal(ts)
+    /// Generate one cycle that always injects `scenario`, ignoring `fraud_rate`.
+    pub fn generate_scenario_cycle(&mut self, ts: i64, scenario: FraudScenario) -> (Vec<Trade>, Vec<Order>) {
+        match scenario {
+            FraudScenario::VolumeSpike => self.inject_volume_spike(ts),
+            FraudScenario::PriceManipulation => {
+                self.manipulation_remaining = 3;
+                let idx = self.rng.gen_range(0..SYMBOLS.len());
+                self.manipulation_symbol = Some(SYMBOLS[idx].0.to_string());
+                self.record_label(scenario, None, SYMBOLS[idx].0, ts, ts);
+                self.generate_normal(ts)
+            }
+            FraudScenario::RapidFire => self.inject_rapid_fire(ts),
+            FraudScenario::WashTrading => self.inject_wash_trading(ts),
+            FraudScenario::Spoofing => {
+                let idx = self.rng.gen_range(0..SYMBOLS.len());
+                let bid_side = self.rng.gen_bool(0.5);
+                self.spoof = Some(Spoof { symbol: SYMBOLS[idx].0.to_string(), bid_side, remaining: SPOOF_CYCLES });
+                self.record_label(scenario, None, SYMBOLS[idx].0, ts, ts);
+                self.generate_normal(ts)
+            }
+            FraudScenario::MomentumIgnition => self.inject_momentum_ignition(ts),
+            FraudScenario::InsiderTrading => self.inject_insider_trading(ts),
+            FraudScenario::PriceImprovement => self.inject_price_improvement(ts),
+        }
     }
 
     fn generate_normal(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
         let mut trades = Vec::with_capacity(SYMBOLS.len());
         let mut orders = Vec::new();
 
+        // Scheduled news breaks and the market reprices on it
+        let (due, later): (Vec<NewsEvent>, Vec<NewsEvent>) =
+            std::mem::take(&mut self.scheduled_news).into_iter().partition(|n| n.ts <= ts);
+        self.scheduled_news = later;
+        for news in due {
+            *self.prices.get_mut(&news.symbol).unwrap() *= 1.0 + news.impact * NEWS_PRICE_REACTION;
+            self.released_news.push(news);
+        }
+
         for (sym, _) in SYMBOLS {
             let symbol = sym.to_string();
             let price = self.prices.get_mut(&symbol).unwrap();
@@ -106,7 +493,7 @@ impl FraudGenerator {
             if self.manipulation_remaining > 0
                 && self.manipulation_symbol.as_deref() == Some(sym)
             {
-                let push = *price * rng.gen_range(0.02..0.04);
+                let push = *price * self.rng.gen_range(0.02..0.04);
                 *price += push;
                 self.manipulation_remaining -= 1;
                 if self.manipulation_remaining == 0 {
@@ -115,31 +502,40 @@ impl FraudGenerator {
                     self.manipulation_symbol = None;
                 }
             } else {
-                let change = *price * rng.gen_range(-0.005..0.005);
+                let change = *price * self.rng.gen_range(-0.005..0.005);
                 *price += change;
+                // Spoofing: once the stacked side has sat a while, price drifts toward it
+                if let Some(s) = self.spoof.as_ref().filter(|s| s.symbol == *sym && s.remaining <= SPOOF_MOVE_CYCLES) {
+                    *price *= if s.bid_side { 1.0 + SPOOF_DRIFT } else { 1.0 - SPOOF_DRIFT };
+                }
             }
 
-            let account = NORMAL_ACCOUNTS[rng.gen_range(0..NORMAL_ACCOUNTS.len())];
-            let side = if rng.gen_bool(0.5) { "buy" } else { "sell" };
-            let volume = rng.gen_range(10..500);
+            let account = NORMAL_ACCOUNTS[self.rng.gen_range(0..NORMAL_ACCOUNTS.len())];
+            let side = if self.rng.gen_bool(0.5) { "buy" } else { "sell" };
+            let volume = self.rng.gen_range(10..500);
 
             self.trade_seq += 1;
             let order_ref = format!("T-{:06}", self.trade_seq);
+            // Any other normal account; drawn from its own RNG so the trades a seed produces don't change
+            let others: Vec<&str> = NORMAL_ACCOUNTS.iter().copied().filter(|a| *a != account).collect();
+            let counterparty = others[self.counterparty_rng.gen_range(0..others.len())];
 
             trades.push(Trade {
                 account_id: account.to_string(),
+                counterparty: counterparty.to_string(),
                 symbol: symbol.clone(),
                 side: side.to_string(),
                 price: *price,
                 volume,
                 order_ref: order_ref.clone(),
+                seq: self.trade_seq as i64,
                 ts,
             });
 
             // ~30% chance to generate a matching order
-            if rng.gen_bool(0.3) {
+            if self.rng.gen_bool(ORDER_PROBABILITY) {
                 self.order_seq += 1;
-                let offset = *price * rng.gen_range(-0.002..0.002);
+                let offset = *price * self.rng.gen_range(-0.002..0.002);
                 orders.push(Order {
                     order_id: format!("ORD-{:06}", self.order_seq),
                     account_id: account.to_string(),
@@ -147,23 +543,77 @@ impl FraudGenerator {
                     side: side.to_string(),
                     quantity: volume,
                     price: *price + offset,
+                    seq: self.order_seq as i64,
                     ts,
                 });
             }
         }
 
+        if let Some(s) = self.spoof.as_mut() {
+            s.remaining -= 1;
+            if s.remaining == 0 {
+                self.spoof = None;
+            }
+        }
+
         (trades, orders)
     }
 
+    /// One order-book snapshot per symbol at `ts`: `BOOK_LEVELS` bids and asks
+    /// around the current price, their sizes random-walking between 100 and
+    /// 1000. Call once per cycle, after `generate_cycle`. Draws only from the
+    /// book's own RNG, so a seeded generator's trades are the same with or
+    /// without it.
+    pub fn book_snapshot(&mut self, ts: i64) -> Vec<BookLevel> {
+        let mut levels = Vec::with_capacity(SYMBOLS.len() * BOOK_LEVELS * 2);
+        for (sym, _) in SYMBOLS {
+            let price = self.prices[*sym];
+            let book = self.books.get_mut(*sym).unwrap();
+            let spoofed = self.spoof.as_ref().filter(|s| s.symbol == *sym).map(|s| s.bid_side);
+            for (side, sizes, sign) in [("bid", &mut book.bids, -1.0), ("ask", &mut book.asks, 1.0)] {
+                let factor = if spoofed == Some(side == "bid") { SPOOF_SIZE_FACTOR } else { 1 };
+                for (i, size) in sizes.iter_mut().enumerate() {
+                    *size = (*size + self.book_rng.gen_range(-100..=100)).clamp(100, 1000);
+                    let level = i as i64 + 1;
+                    levels.push(BookLevel {
+                        symbol: sym.to_string(),
+                        side: side.to_string(),
+                        level,
+                        price: price * (1.0 + sign * BOOK_TICK * level as f64),
+                        size: *size * factor,
+                        ts,
+                    });
+                }
+            }
+        }
+        levels.retain(|l| self.owns(&l.symbol));
+        levels
+    }
+
+    /// News at or before `ts`: scheduled headlines that broke this cycle, plus
+    /// an occasional low-impact background headline. Call once per cycle,
+    /// after `generate_cycle`. Background news draws from its own RNG, so a
+    /// seeded generator's trades are the same with or without it.
+    pub fn news(&mut self, ts: i64) -> Vec<NewsEvent> {
+        let mut news = std::mem::take(&mut self.released_news);
+        if self.news_rng.gen_bool(BACKGROUND_NEWS_PROBABILITY) {
+            let (sym, _) = SYMBOLS[self.news_rng.gen_range(0..SYMBOLS.len())];
+            let impact = self.news_rng.gen_range(-BACKGROUND_NEWS_MAX_IMPACT..BACKGROUND_NEWS_MAX_IMPACT);
+            news.push(NewsEvent { symbol: sym.to_string(), headline: headline(sym, impact), impact, ts });
+        }
+        news.retain(|n| self.owns(&n.symbol));
+        news
+    }
+
     /// Generate a stress-test cycle with a configurable number of trades.
-    /// No fraud injection — measures pure pipeline throughput.
+    /// At a zero fraud rate nothing is injected, to measure pure pipeline
+    /// throughput; otherwise a scenario cycle is added at `fraud_rate`.
     ///
     /// Uses a constant 50ms step between consecutive trades so that the JOIN
     /// fan-out ratio stays the same regardless of batch size. The caller must
     /// provide a `base_ts` that advances between cycles (see stress.rs) to
     /// prevent event-time overlap between batches.
     pub fn generate_stress_cycle(&mut self, base_ts: i64, count: usize) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
         let mut trades = Vec::with_capacity(count);
         let mut orders = Vec::new();
 
@@ -181,30 +631,32 @@ impl FraudGenerator {
             let price = self.prices.get_mut(&symbol).unwrap();
 
             // Small random walk
-            let change = *price * rng.gen_range(-0.005..0.005);
+            let change = *price * self.rng.gen_range(-0.005..0.005);
             *price += change;
 
-            let account = NORMAL_ACCOUNTS[rng.gen_range(0..NORMAL_ACCOUNTS.len())];
-            let side = if rng.gen_bool(0.5) { "buy" } else { "sell" };
-            let volume = rng.gen_range(10..500);
+            let account = NORMAL_ACCOUNTS[self.rng.gen_range(0..NORMAL_ACCOUNTS.len())];
+            let side = if self.rng.gen_bool(0.5) { "buy" } else { "sell" };
+            let volume = self.rng.gen_range(10..500);
 
             self.trade_seq += 1;
             let order_ref = format!("T-{:06}", self.trade_seq);
 
             trades.push(Trade {
                 account_id: account.to_string(),
+                counterparty: String::new(),
                 symbol: symbol.clone(),
                 side: side.to_string(),
                 price: *price,
                 volume,
                 order_ref,
+                seq: self.trade_seq as i64,
                 ts: trade_ts,
             });
 
             // ~30% chance to generate a matching order
-            if rng.gen_bool(0.3) {
+            if self.rng.gen_bool(ORDER_PROBABILITY) {
                 self.order_seq += 1;
-                let offset = *price * rng.gen_range(-0.002..0.002);
+                let offset = *price * self.rng.gen_range(-0.002..0.002);
                 orders.push(Order {
                     order_id: format!("ORD-{:06}", self.order_seq),
                     account_id: account.to_string(),
@@ -212,39 +664,51 @@ impl FraudGenerator {
                     side: side.to_string(),
                     quantity: volume,
                     price: *price + offset,
+                    seq: self.order_seq as i64,
                     ts: trade_ts,
                 });
             }
         }
 
+        // At `fraud_rate`, a scenario rides on the load, labelled like a normal cycle's
+        if self.fraud_rate > 0.0 && self.rng.gen_bool(self.fraud_rate.min(1.0)) {
+            let scenario = ALL_SCENARIOS[self.rng.gen_range(0..ALL_SCENARIOS.len())];
+            let (mut fraud_trades, mut fraud_orders) = self.generate_scenario_cycle(base_ts, scenario);
+            trades.append(&mut fraud_trades);
+            orders.append(&mut fraud_orders);
+        }
+
         (trades, orders)
     }
 
     fn inject_volume_spike(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
-        let idx = rng.gen_range(0..SYMBOLS.len());
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
         let (sym, _) = SYMBOLS[idx];
         let symbol = sym.to_string();
         let price = *self.prices.get(&symbol).unwrap();
-        let fraud_acct = FRAUD_ACCOUNTS[rng.gen_range(0..FRAUD_ACCOUNTS.len())];
+        let fraud_acct = FRAUD_ACCOUNTS[self.rng.gen_range(0..FRAUD_ACCOUNTS.len())];
 
         let mut trades = Vec::new();
         // Generate 5-10 trades with 10-50x volume
-        let count = rng.gen_range(5..=10);
+        let count = self.rng.gen_range(5..=10);
         for _ in 0..count {
             self.trade_seq += 1;
-            let spike_vol = rng.gen_range(10..500) * rng.gen_range(10..50);
+            let spike_vol = self.rng.gen_range(10..500) * self.rng.gen_range(10..50);
             trades.push(Trade {
                 account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
                 symbol: symbol.clone(),
-                side: if rng.gen_bool(0.5) { "buy" } else { "sell" }.to_string(),
-                price: price + price * rng.gen_range(-0.001..0.001),
+                side: if self.rng.gen_bool(0.5) { "buy" } else { "sell" }.to_string(),
+                price: price + price * self.rng.gen_range(-0.001..0.001),
                 volume: spike_vol,
                 order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
                 ts,
             });
         }
 
+        self.record_label(FraudScenario::VolumeSpike, Some(fraud_acct), &symbol, ts, ts);
+
         // Also include normal trades for other symbols
         let (mut normal, orders) = self.generate_normal(ts);
         trades.append(&mut normal);
@@ -252,29 +716,33 @@ impl FraudGenerator {
     }
 
     fn inject_rapid_fire(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
-        let idx = rng.gen_range(0..SYMBOLS.len());
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
         let (sym, _) = SYMBOLS[idx];
         let symbol = sym.to_string();
         let price = *self.prices.get(&symbol).unwrap();
-        let fraud_acct = FRAUD_ACCOUNTS[rng.gen_range(0..FRAUD_ACCOUNTS.len())];
+        let fraud_acct = FRAUD_ACCOUNTS[self.rng.gen_range(0..FRAUD_ACCOUNTS.len())];
 
         let mut trades = Vec::new();
         // 20-30 trades spaced 50-100ms apart
-        let count = rng.gen_range(20..=30);
+        let count = self.rng.gen_range(20..=30);
+        let mut end_ts = ts;
         for i in 0..count {
             self.trade_seq += 1;
-            let t = ts + (i as i64) * rng.gen_range(50..100);
+            let t = ts + (i as i64) * self.rng.gen_range(50..100);
+            end_ts = end_ts.max(t);
             trades.push(Trade {
                 account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
                 symbol: symbol.clone(),
-                side: if rng.gen_bool(0.5) { "buy" } else { "sell" }.to_string(),
-                price: price + price * rng.gen_range(-0.001..0.001),
-                volume: rng.gen_range(10..100),
+                side: if self.rng.gen_bool(0.5) { "buy" } else { "sell" }.to_string(),
+                price: price + price * self.rng.gen_range(-0.001..0.001),
+                volume: self.rng.gen_range(10..100),
                 order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
                 ts: t,
             });
         }
+        self.record_label(FraudScenario::RapidFire, Some(fraud_acct), &symbol, ts, end_ts);
 
         let (mut normal, orders) = self.generate_normal(ts);
         trades.append(&mut normal);
@@ -282,42 +750,302 @@ impl FraudGenerator {
     }
 
     fn inject_wash_trading(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
-        let mut rng = rand::thread_rng();
-        let idx = rng.gen_range(0..SYMBOLS.len());
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
         let (sym, _) = SYMBOLS[idx];
         let symbol = sym.to_string();
         let price = *self.prices.get(&symbol).unwrap();
-        let fraud_acct = FRAUD_ACCOUNTS[rng.gen_range(0..FRAUD_ACCOUNTS.len())];
+        let fraud_acct = FRAUD_ACCOUNTS[self.rng.gen_range(0..FRAUD_ACCOUNTS.len())];
 
         let mut trades = Vec::new();
         // Generate equal buy/sell pairs from same account
-        let pairs = rng.gen_range(3..=6);
+        let pairs = self.rng.gen_range(3..=6);
         for _ in 0..pairs {
-            let vol = rng.gen_range(50..200);
+            let vol = self.rng.gen_range(50..200);
             self.trade_seq += 1;
             trades.push(Trade {
                 account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
                 symbol: symbol.clone(),
                 side: "buy".to_string(),
                 price,
                 volume: vol,
                 order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
                 ts,
             });
             self.trade_seq += 1;
             trades.push(Trade {
                 account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
                 symbol: symbol.clone(),
                 side: "sell".to_string(),
-                price: price + rng.gen_range(-0.01..0.01),
+                price: price + self.rng.gen_range(-0.01..0.01),
                 volume: vol,
                 order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
                 ts,
             });
         }
+        self.record_label(FraudScenario::WashTrading, Some(fraud_acct), &symbol, ts, ts);
 
         let (mut normal, orders) = self.generate_normal(ts);
         trades.append(&mut normal);
         (trades, orders)
     }
+
+    fn inject_momentum_ignition(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
+        let (sym, _) = SYMBOLS[idx];
+        let symbol = sym.to_string();
+        let start_price = *self.prices.get(&symbol).unwrap();
+        let fraud_acct = FRAUD_ACCOUNTS[self.rng.gen_range(0..FRAUD_ACCOUNTS.len())];
+        let buy = self.rng.gen_bool(0.5);
+        let (side, unwind_side, direction) = if buy { ("buy", "sell", 1.0) } else { ("sell", "buy", -1.0) };
+
+        let mut trades = Vec::new();
+        // 10-14 aggressive trades, each taking the next price level
+        let count = self.rng.gen_range(10..=14);
+        let mut price = start_price;
+        let mut burst_volume = 0;
+        for i in 0..count {
+            self.trade_seq += 1;
+            price = start_price * (1.0 + direction * IGNITION_PRICE_STEP * (i + 1) as f64);
+            let volume = self.rng.gen_range(200..500);
+            burst_volume += volume;
+            trades.push(Trade {
+                account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
+                symbol: symbol.clone(),
+                side: side.to_string(),
+                price,
+                volume,
+                order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
+                ts: ts + i as i64 * IGNITION_STEP_MS,
+            });
+        }
+        // The market follows the move
+        self.prices.insert(symbol.clone(), price);
+
+        // Unwind the position into the displaced price in 3-4 clips
+        let clips = self.rng.gen_range(3..=4);
+        let unwind_ts = ts + IGNITION_UNWIND_MS;
+        let mut end_ts = unwind_ts;
+        for i in 0..clips {
+            self.trade_seq += 1;
+            end_ts = unwind_ts + i * 100;
+            let volume = if i == clips - 1 { burst_volume - burst_volume / clips * (clips - 1) } else { burst_volume / clips };
+            trades.push(Trade {
+                account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
+                symbol: symbol.clone(),
+                side: unwind_side.to_string(),
+                price: price + price * self.rng.gen_range(-0.0005..0.0005),
+                volume,
+                order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
+                ts: end_ts,
+            });
+        }
+        self.record_label(FraudScenario::MomentumIgnition, Some(fraud_acct), &symbol, ts, end_ts);
+
+        let (mut normal, orders) = self.generate_normal(ts);
+        trades.append(&mut normal);
+        (trades, orders)
+    }
+
+    fn inject_insider_trading(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
+        let (sym, _) = SYMBOLS[idx];
+        let symbol = sym.to_string();
+        let price = *self.prices.get(&symbol).unwrap();
+        let fraud_acct = FRAUD_ACCOUNTS[self.rng.gen_range(0..FRAUD_ACCOUNTS.len())];
+        let positive = self.rng.gen_bool(0.5);
+        let impact = self.rng.gen_range(INSIDER_MIN_IMPACT..1.0) * if positive { 1.0 } else { -1.0 };
+        let news_ts = ts + self.rng.gen_range(INSIDER_LEAD_MS);
+
+        let mut trades = Vec::new();
+        // 6-10 heavy trades on the side the news will favour
+        let count = self.rng.gen_range(6..=10);
+        for i in 0..count {
+            self.trade_seq += 1;
+            trades.push(Trade {
+                account_id: fraud_acct.to_string(),
+                counterparty: String::new(),
+                symbol: symbol.clone(),
+                side: if positive { "buy" } else { "sell" }.to_string(),
+                price: price + price * self.rng.gen_range(-0.001..0.001),
+                volume: self.rng.gen_range(800..2000),
+                order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
+                ts: ts + i as i64 * 100,
+            });
+        }
+        self.scheduled_news.push(NewsEvent { symbol: symbol.clone(), headline: headline(sym, impact), impact, ts: news_ts });
+        self.record_label(FraudScenario::InsiderTrading, Some(fraud_acct), &symbol, ts, news_ts);
+
+        let (mut normal, orders) = self.generate_normal(ts);
+        trades.append(&mut normal);
+        (trades, orders)
+    }
+
+    /// 6-8 fills of one fraud account by another, all
+    /// `IMPROVEMENT_THROUGH` past the market in the first one's favour: buys
+    /// below the bid, sells above the ask. Each fill names its counterparty.
+    fn inject_price_improvement(&mut self, ts: i64) -> (Vec<Trade>, Vec<Order>) {
+        // Normal trading first: the fills are priced off the market the book
+        // snapshot will show, and follow it in sequence order
+        let (mut trades, orders) = self.generate_normal(ts);
+        let idx = self.rng.gen_range(0..SYMBOLS.len());
+        let (sym, _) = SYMBOLS[idx];
+        let symbol = sym.to_string();
+        let price = *self.prices.get(&symbol).unwrap();
+        let receiver = self.rng.gen_range(0..FRAUD_ACCOUNTS.len());
+        let giver = (receiver + self.rng.gen_range(1..FRAUD_ACCOUNTS.len())) % FRAUD_ACCOUNTS.len();
+        let (fraud_acct, counterparty) = (FRAUD_ACCOUNTS[receiver], FRAUD_ACCOUNTS[giver]);
+
+        let count = self.rng.gen_range(6..=8);
+        for _ in 0..count {
+            self.trade_seq += 1;
+            let buy = self.rng.gen_bool(0.5);
+            let through = self.rng.gen_range(IMPROVEMENT_THROUGH);
+            trades.push(Trade {
+                account_id: fraud_acct.to_string(),
+                counterparty: counterparty.to_string(),
+                symbol: symbol.clone(),
+                side: if buy { "buy" } else { "sell" }.to_string(),
+                price: price * if buy { 1.0 - through } else { 1.0 + through },
+                volume: self.rng.gen_range(100..400),
+                order_ref: format!("T-{:06}", self.trade_seq),
+                seq: self.trade_seq as i64,
+                ts,
+            });
+        }
+        self.record_label(FraudScenario::PriceImprovement, Some(fraud_acct), &symbol, ts, ts);
+        (trades, orders)
+    }
+
+    /// The trades (and orders) of one benign look-alike, drawn only from the
+    /// benign RNG and leaving prices where they were.
+    fn inject_benign(&mut self, ts: i64, scenario: BenignScenario) -> (Vec<Trade>, Vec<Order>) {
+        let (sym, _) = SYMBOLS[self.benign_rng.gen_range(0..SYMBOLS.len())];
+        let price = self.prices[sym];
+        let (account, trades, orders) = match scenario {
+            BenignScenario::IndexRebalance => (INDEX_ACCOUNT, self.index_rebalance(sym, price, ts), Vec::new()),
+            BenignScenario::VwapSlicing => {
+                let (trades, orders) = self.vwap_slicing(sym, price, ts);
+                (ALGO_ACCOUNT, trades, orders)
+            }
+            BenignScenario::MarketMaking => {
+                let (trades, orders) = self.market_making(sym, price, ts);
+                (MARKET_MAKER_ACCOUNT, trades, orders)
+            }
+        };
+        let end_ts = trades.iter().map(|t| t.ts).max().unwrap_or(ts);
+        self.benign_labels.push(BenignLabel {
+            scenario: scenario.label(),
+            account_id: account.to_string(),
+            symbol: sym.to_string(),
+            start_ts: ts,
+            end_ts,
+        });
+        (trades, orders)
+    }
+
+    fn benign_trade(&mut self, account: &str, symbol: &str, side: &str, price: f64, volume: i64, ts: i64) -> Trade {
+        self.trade_seq += 1;
+        Trade {
+            account_id: account.to_string(),
+            counterparty: String::new(),
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            price,
+            volume,
+            order_ref: format!("T-{:06}", self.trade_seq),
+            seq: self.trade_seq as i64,
+            ts,
+        }
+    }
+
+    /// 5-10 same-side prints at 10-50x normal size, all at the market: heavy
+    /// volume, but one-directional and with no price pressure.
+    fn index_rebalance(&mut self, symbol: &str, price: f64, ts: i64) -> Vec<Trade> {
+        let side = if self.benign_rng.gen_bool(0.5) { "buy" } else { "sell" };
+        let count = self.benign_rng.gen_range(5..=10);
+        (0..count)
+            .map(|_| {
+                let volume = self.benign_rng.gen_range(10..500) * self.benign_rng.gen_range(10..50);
+                let fill = price + price * self.benign_rng.gen_range(-0.0005..0.0005);
+                self.benign_trade(INDEX_ACCOUNT, symbol, side, fill, volume, ts)
+            })
+            .collect()
+    }
+
+    /// 20-30 small same-side child orders `VWAP_SLICE_MS` apart, each filled
+    /// against a matching order: a RapidFire count without the churn.
+    fn vwap_slicing(&mut self, symbol: &str, price: f64, ts: i64) -> (Vec<Trade>, Vec<Order>) {
+        let side = if self.benign_rng.gen_bool(0.5) { "buy" } else { "sell" };
+        let count = self.benign_rng.gen_range(20..=30);
+        let mut trades = Vec::with_capacity(count);
+        let mut orders = Vec::with_capacity(count);
+        let mut t = ts;
+        for _ in 0..count {
+            let volume = self.benign_rng.gen_range(20..80);
+            let fill = price + price * self.benign_rng.gen_range(-0.0005..0.0005);
+            self.order_seq += 1;
+            orders.push(Order {
+                order_id: format!("ORD-{:06}", self.order_seq),
+                account_id: ALGO_ACCOUNT.to_string(),
+                symbol: symbol.to_string(),
+                side: side.to_string(),
+                quantity: volume,
+                price: fill,
+                seq: self.order_seq as i64,
+                ts: t,
+            });
+            trades.push(self.benign_trade(ALGO_ACCOUNT, symbol, side, fill, volume, t));
+            t += self.benign_rng.gen_range(VWAP_SLICE_MS);
+        }
+        (trades, orders)
+    }
+
+    /// 4-8 round trips, each buying at the bid and selling the same size at
+    /// the ask `MM_HALF_SPREAD` either side of the market: balanced like a
+    /// wash, but earning the spread against other accounts' quotes.
+    fn market_making(&mut self, symbol: &str, price: f64, ts: i64) -> (Vec<Trade>, Vec<Order>) {
+        let (bid, ask) = (price * (1.0 - MM_HALF_SPREAD), price * (1.0 + MM_HALF_SPREAD));
+        let round_trips = self.benign_rng.gen_range(4..=8);
+        let mut trades = Vec::with_capacity(round_trips * 2);
+        let mut orders = Vec::with_capacity(round_trips * 2);
+        for i in 0..round_trips {
+            let volume = self.benign_rng.gen_range(100..300);
+            let t = ts + i as i64 * 150;
+            for (side, quote) in [("buy", bid), ("sell", ask)] {
+                self.order_seq += 1;
+                orders.push(Order {
+                    order_id: format!("ORD-{:06}", self.order_seq),
+                    account_id: MARKET_MAKER_ACCOUNT.to_string(),
+                    symbol: symbol.to_string(),
+                    side: side.to_string(),
+                    quantity: volume,
+                    price: quote,
+                    seq: self.order_seq as i64,
+                    ts: t,
+                });
+                trades.push(self.benign_trade(MARKET_MAKER_ACCOUNT, symbol, side, quote, volume, t));
+            }
+        }
+        (trades, orders)
+    }
+}
+
+/// A headline matching `impact`'s direction and size.
+fn headline(symbol: &str, impact: f64) -> String {
+    let text = match (impact >= 0.0, impact.abs() >= INSIDER_MIN_IMPACT) {
+        (true, true) => "agrees to be acquired at a premium",
+        (false, true) => "withdraws full-year guidance",
+        (true, false) => "beats consensus on quarterly revenue",
+        (false, false) => "delays product launch",
+    };
+    format!("{symbol} {text}")
 }
@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::{ContextV7, Timestamp, Uuid};
+
+use crate::alerts::AlertEngine;
+use crate::config::{IdFormat, IdsConfig};
+
+/// One process that drew alert IDs from the ledger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunLineage {
+    /// 1 for the first run against this ledger, then counting up.
+    pub run: u64,
+    pub started_at_ms: i64,
+    pub first_id: u64,
+    /// Last ID issued as of the last ledger write; a crash leaves it behind
+    /// the true last ID, never ahead.
+    pub last_id: u64,
+}
+
+/// The ledger file: IDs reserved so far, and every run that drew from them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdState {
+    pub reserved_through: u64,
+    pub runs: Vec<RunLineage>,
+}
+
+/// Keeps alert IDs unique and increasing across restarts. IDs are reserved a
+/// block at a time and the reservation is written before any ID in it is
+/// issued, so a run that crashes mid-block leaves a gap, never a reuse.
+pub struct IdLedger {
+    path: PathBuf,
+    block: u64,
+    state: IdState,
+    resumed: bool,
+}
+
+impl IdLedger {
+    /// Open the ledger `config` names, if any, and record this run in it.
+    pub fn open(config: &IdsConfig) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let Some(path) = &config.path else {
+            return Ok(None);
+        };
+        let mut state: IdState = match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("invalid alert ID ledger {}: {e}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => IdState::default(),
+            Err(e) => return Err(format!("failed to read alert ID ledger {}: {e}", path.display()).into()),
+        };
+        let first_id = state.reserved_through + 1;
+        state.runs.push(RunLineage {
+            run: state.runs.last().map_or(1, |r| r.run + 1),
+            started_at_ms: chrono::Utc::now().timestamp_millis(),
+            first_id,
+            last_id: first_id - 1,
+        });
+        state.reserved_through += config.block.max(1);
+        let ledger = Self { path: path.clone(), block: config.block.max(1), state, resumed: false };
+        ledger.write()?;
+        Ok(Some(ledger))
+    }
+
+    /// This run's lineage entry.
+    pub fn run(&self) -> &RunLineage {
+        self.state.runs.last().expect("open records the run")
+    }
+
+    /// Call around every cycle: the first call starts `engine`'s IDs after
+    /// every earlier run's reservation, and later calls reserve another block
+    /// when fewer than half of this one remain.
+    pub fn cover(&mut self, engine: &mut AlertEngine) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.resumed {
+            engine.resume_ids_after(self.run().first_id - 1);
+            self.resumed = true;
+        }
+        let last_id = engine.last_id();
+        if last_id + self.block / 2 < self.state.reserved_through {
+            return Ok(());
+        }
+        // A cycle that overran the block has already issued past it; reserve beyond those
+        self.state.reserved_through = self.state.reserved_through.max(last_id) + self.block;
+        self.state.runs.last_mut().expect("open records the run").last_id = last_id;
+        self.write()
+    }
+
+    /// Record the last ID this run issued, e.g. at shutdown.
+    pub fn close(&mut self, engine: &AlertEngine) -> Result<(), Box<dyn std::error::Error>> {
+        self.state.runs.last_mut().expect("open records the run").last_id = engine.last_id();
+        self.write()
+    }
+
+    /// Replace the file in one rename, so a crash mid-write keeps the old ledger.
+    fn write(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&self.state)?)
+            .map_err(|e| format!("failed to write alert ID ledger {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Read a ledger file without opening a run in it.
+pub fn load(path: &Path) -> Result<IdState, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read alert ID ledger {}: {e}", path.display()))?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// Mints the `[ids] format` IDs: 48 bits of Unix milliseconds followed by
+/// random bits, so IDs from other instances and restarts don't collide.
+/// Within one millisecond, or if the clock steps back, the random part
+/// counts up from the last ID instead, so one generator's IDs sort in the
+/// order they were given. UUIDv7s get that from `uuid`'s `ContextV7`.
+pub struct UidGenerator {
+    uuid: Option<ContextV7>,
+    last_ms: i64,
+    /// The 80 random bits of the last ULID.
+    last_random: u128,
+}
+
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+impl UidGenerator {
+    /// A generator for `config.format`; `None` for plain sequence IDs.
+    pub fn from_config(config: &IdsConfig) -> Option<Self> {
+        let uuid = match config.format {
+            IdFormat::Sequence => return None,
+            IdFormat::Ulid => None,
+            IdFormat::UuidV7 => Some(ContextV7::new()),
+        };
+        Some(Self { uuid, last_ms: 0, last_random: 0 })
+    }
+
+    pub fn next(&mut self, now_ms: i64) -> String {
+        if let Some(context) = &self.uuid {
+            let ms = now_ms.max(0) as u64;
+            return Uuid::new_v7(Timestamp::from_unix(context, ms / 1000, (ms % 1000) as u32 * 1_000_000)).to_string();
+        }
+        let mask = (1u128 << 80) - 1;
+        if now_ms > self.last_ms {
+            self.last_ms = now_ms;
+            // The top bit starts clear, leaving room to count within the millisecond
+            self.last_random = rand::random::<u128>() & (mask >> 1);
+        } else if self.last_random == mask {
+            self.last_ms += 1;
+            self.last_random = 0;
+        } else {
+            self.last_random += 1;
+        }
+        let ms = self.last_ms as u128 & ((1 << 48) - 1);
+        let value = (ms << 80) | self.last_random;
+        (0..26).rev().map(|i| CROCKFORD[((value >> (5 * i)) & 31) as usize] as char).collect()
+    }
+}
@@ -1,11 +1,11 @@
 use std::collections::VecDeque;
 use std::time::Instant;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 const WINDOW_SIZE: usize = 1000;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LatencyStats {
     pub p50_us: u64,
     pub p95_us: u64,
@@ -15,25 +15,32 @@ pub struct LatencyStats {
     pub count: usize,
 }
 
-impl Default for LatencyStats {
-    fn default() -> Self {
-        Self { p50_us: 0, p95_us: 0, p99_us: 0, min_us: 0, max_us: 0, count: 0 }
-    }
-}
-
 pub struct LatencyTracker {
     push_latencies: VecDeque<u64>,
     processing_latencies: VecDeque<u64>,
     alert_latencies: VecDeque<u64>,
+    /// Event time from a price move to its detection, in microseconds: on
+    /// the tick path, from the tick completing a move; on the bar path, from
+    /// the close of the `ohlc_vol` bar holding it.
+    tick_lags: VecDeque<u64>,
+    bar_lags: VecDeque<u64>,
     last_push_instant: Option<Instant>,
 }
 
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LatencyTracker {
     pub fn new() -> Self {
         Self {
             push_latencies: VecDeque::with_capacity(WINDOW_SIZE),
             processing_latencies: VecDeque::with_capacity(WINDOW_SIZE),
             alert_latencies: VecDeque::with_capacity(WINDOW_SIZE),
+            tick_lags: VecDeque::with_capacity(WINDOW_SIZE),
+            bar_lags: VecDeque::with_capacity(WINDOW_SIZE),
             last_push_instant: None,
         }
     }
@@ -42,6 +49,8 @@ impl LatencyTracker {
         self.push_latencies.clear();
         self.processing_latencies.clear();
         self.alert_latencies.clear();
+        self.tick_lags.clear();
+        self.bar_lags.clear();
         self.last_push_instant = None;
     }
 
@@ -67,6 +76,20 @@ impl LatencyTracker {
         push_capped(&mut self.alert_latencies, us);
     }
 
+    /// An alert latency measured where the alert was raised, e.g. on the
+    /// evaluation worker.
+    pub fn record_alert_us(&mut self, us: u64) {
+        push_capped(&mut self.alert_latencies, us);
+    }
+
+    pub fn record_tick_lag(&mut self, lag_ms: i64) {
+        push_capped(&mut self.tick_lags, lag_ms.max(0) as u64 * 1000);
+    }
+
+    pub fn record_bar_lag(&mut self, lag_ms: i64) {
+        push_capped(&mut self.bar_lags, lag_ms.max(0) as u64 * 1000);
+    }
+
     pub fn push_stats(&self) -> LatencyStats {
         compute_stats(&self.push_latencies)
     }
@@ -78,6 +101,14 @@ impl LatencyTracker {
     pub fn alert_stats(&self) -> LatencyStats {
         compute_stats(&self.alert_latencies)
     }
+
+    pub fn tick_lag_stats(&self) -> LatencyStats {
+        compute_stats(&self.tick_lags)
+    }
+
+    pub fn bar_lag_stats(&self) -> LatencyStats {
+        compute_stats(&self.bar_lags)
+    }
 }
 
 fn push_capped(q: &mut VecDeque<u64>, val: u64) {
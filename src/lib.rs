@@ -1,8 +1,48 @@
 pub mod alerts;
+pub mod archive;
+pub mod audit;
+pub mod auth;
+pub mod blotter;
+pub mod breakpoints;
+pub mod calendar;
+pub mod clock;
+pub mod compare;
+pub mod crosscheck;
+pub mod config;
+pub mod coordinator;
+pub mod describe;
 pub mod detection;
+pub mod engine;
+pub mod evaluation;
+pub mod features;
 pub mod generator;
+pub mod ids;
 pub mod latency;
+pub mod limits;
+pub mod locale;
+pub mod ml;
+pub mod notify;
+pub mod pacing;
+pub mod quality;
+pub mod recording;
+pub mod reviews;
+pub mod risk;
+pub mod runs;
+pub mod scoring;
+pub mod search;
+pub mod simulate;
+pub mod slack;
+pub mod slo;
+pub mod source;
+pub mod sequence;
+pub mod standby;
+pub mod stop;
 pub mod stress;
+pub mod terminal;
+pub mod testkit;
+pub mod theme;
+pub mod ticks;
 pub mod tui;
+pub mod tuning;
 pub mod types;
 pub mod web;
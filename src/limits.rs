@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use crate::alerts::Alert;
+use crate::config::{JoinConfig, LimitsConfig};
+use crate::detection;
+
+/// Smoothing factor for the push-latency average that drives degradation.
+const PUSH_EWMA_ALPHA: f64 = 0.2;
+
+/// Enforces [`LimitsConfig`] on an `Engine`: decides each cycle whether to
+/// shed generator load, caps the alerts handed to the frontend, and tracks
+/// whether the engine is degraded, instead of letting work queue unbounded.
+/// Given the join band in effect, it also watches the join fan-out that the
+/// measured event rate implies.
+pub struct LoadGovernor {
+    limits: LimitsConfig,
+    join: Option<JoinConfig>,
+    join_fanout: f64,
+    push_ewma_us: f64,
+    inflight: u32,
+    degraded: bool,
+    skip_next: bool,
+    pub shed_cycles: u64,
+    pub dropped_alerts: u64,
+}
+
+impl LoadGovernor {
+    pub fn new(limits: LimitsConfig) -> Self {
+        Self {
+            limits,
+            join: None,
+            join_fanout: 0.0,
+            push_ewma_us: 0.0,
+            inflight: 0,
+            degraded: false,
+            skip_next: false,
+            shed_cycles: 0,
+            dropped_alerts: 0,
+        }
+    }
+
+    /// Watch the fan-out of `join`, the band the pipeline was created with.
+    pub fn with_join(mut self, join: JoinConfig) -> Self {
+        self.join = Some(join);
+        self
+    }
+
+    pub fn degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Estimated join rows per second at the event rate measured so far.
+    pub fn join_fanout_per_sec(&self) -> f64 {
+        self.join_fanout
+    }
+
+    /// Whether the measured rate puts join fan-out over `max_fanout_per_sec`.
+    pub fn join_overloaded(&self) -> bool {
+        self.join.as_ref().is_some_and(|j| j.max_fanout_per_sec > 0.0 && self.join_fanout > j.max_fanout_per_sec)
+    }
+
+    /// Re-estimate join fan-out from the trades and orders pushed over
+    /// `elapsed`. The first second is too short a sample and is ignored.
+    pub fn observe_rates(&mut self, trades: u64, orders: u64, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        if let Some(join) = self.join.as_ref().filter(|_| secs >= 1.0) {
+            self.join_fanout = detection::join_fanout_per_sec(join.band_ms, trades as f64 / secs, orders as f64 / secs);
+        }
+    }
+
+    /// Whether to skip generating and pushing this cycle. Polling still runs so
+    /// the pipeline can drain.
+    pub fn should_shed(&mut self) -> bool {
+        let shed = self.inflight >= self.limits.max_inflight_batches || (self.degraded && self.skip_next);
+        self.skip_next = !self.skip_next;
+        if shed {
+            self.shed_cycles += 1;
+        }
+        shed
+    }
+
+    /// Record a push that took `push_us`, entering or leaving degraded state.
+    pub fn observe_push(&mut self, push_us: u64) {
+        self.inflight += 1;
+        self.push_ewma_us = PUSH_EWMA_ALPHA * push_us as f64 + (1.0 - PUSH_EWMA_ALPHA) * self.push_ewma_us;
+        let threshold = self.limits.degrade_push_us as f64;
+        if self.push_ewma_us > threshold {
+            self.degraded = true;
+        } else if self.push_ewma_us < threshold / 2.0 {
+            self.degraded = false;
+        }
+    }
+
+    /// Record a poll; any output means the pushes so far have been processed.
+    pub fn observe_poll(&mut self, rows: u64) {
+        if rows > 0 {
+            self.inflight = 0;
+        }
+    }
+
+    /// Keep at most `max_alert_queue` of this cycle's alerts, counting the rest.
+    pub fn cap_alerts(&mut self, alerts: &mut Vec<Alert>) {
+        if alerts.len() > self.limits.max_alert_queue {
+            self.dropped_alerts += (alerts.len() - self.limits.max_alert_queue) as u64;
+            alerts.truncate(self.limits.max_alert_queue);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity, AlertType};
+
+    fn governor(max_inflight_batches: u32, max_alert_queue: usize, degrade_push_us: u64) -> LoadGovernor {
+        LoadGovernor::new(LimitsConfig { max_inflight_batches, max_alert_queue, degrade_push_us, ..Default::default() })
+    }
+
+    fn alerts(n: u64) -> Vec<Alert> {
+        (1..=n)
+            .map(|id| Alert {
+                id,
+                alert_type: AlertType::RapidFire,
+                severity: AlertSeverity::Medium,
+                symbol: None,
+                account_id: None,
+                detail: AlertDetail::Text(String::new()),
+                latency_us: 0,
+                timestamp_ms: 0,
+                score: None,
+                gap_ms: None,
+                metric: None,
+                uid: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sheds_once_inflight_batches_reach_the_limit_until_a_poll_returns_rows() {
+        let mut g = governor(2, 200, 5_000);
+        g.observe_push(100);
+        assert!(!g.should_shed());
+        g.observe_push(100);
+        assert!(g.should_shed());
+        assert!(g.should_shed());
+        g.observe_poll(0);
+        assert!(g.should_shed(), "an empty poll doesn't prove the pushes were processed");
+        g.observe_poll(5);
+        assert!(!g.should_shed());
+        assert_eq!(g.shed_cycles, 3);
+    }
+
+    #[test]
+    fn degrades_above_the_push_threshold_and_recovers_below_half() {
+        let mut g = governor(u32::MAX, 200, 1_000);
+        for _ in 0..20 {
+            g.observe_push(2_000);
+        }
+        assert!(g.degraded());
+        // Degraded: every other cycle is shed
+        let shed: Vec<bool> = (0..4).map(|_| g.should_shed()).collect();
+        assert_eq!(shed.iter().filter(|s| **s).count(), 2);
+
+        // Between half the threshold and the threshold, it stays degraded
+        for _ in 0..40 {
+            g.observe_push(700);
+        }
+        assert!(g.degraded());
+        for _ in 0..40 {
+            g.observe_push(100);
+        }
+        assert!(!g.degraded());
+        assert!(!g.should_shed() && !g.should_shed());
+    }
+
+    #[test]
+    fn caps_alerts_at_the_queue_limit_and_counts_the_rest() {
+        let mut g = governor(32, 3, 5_000);
+        let mut at_limit = alerts(3);
+        g.cap_alerts(&mut at_limit);
+        assert_eq!(at_limit.len(), 3);
+        assert_eq!(g.dropped_alerts, 0);
+
+        let mut over = alerts(5);
+        g.cap_alerts(&mut over);
+        assert_eq!(over.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(g.dropped_alerts, 2);
+
+        let mut none = Vec::new();
+        g.cap_alerts(&mut none);
+        assert_eq!(g.dropped_alerts, 2);
+    }
+}
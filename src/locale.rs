@@ -0,0 +1,126 @@
+//! Number and timestamp formatting for human-readable output, chosen once
+//! by `[format]` at startup so every call site writes counts, latencies and
+//! times the same way.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::config::{FormatConfig, Grouping, TimestampStyle, Timezone};
+
+static FORMATS: OnceLock<Formats> = OnceLock::new();
+
+/// `[format]` with the locale resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Formats {
+    /// Thousands separator; `None` writes digits ungrouped.
+    pub separator: Option<char>,
+    pub timezone: Timezone,
+    pub timestamps: TimestampStyle,
+}
+
+impl Formats {
+    /// The formats `config` asks for, reading the locale through `env` for
+    /// `grouping = "locale"`.
+    pub fn from_config(config: &FormatConfig, env: impl Fn(&str) -> Option<String>) -> Self {
+        let separator = match config.grouping {
+            Grouping::None => None,
+            Grouping::Comma => Some(','),
+            Grouping::Period => Some('.'),
+            Grouping::Space => Some(' '),
+            Grouping::Underscore => Some('_'),
+            Grouping::Locale => {
+                let locale = ["LC_ALL", "LC_NUMERIC", "LANG"].into_iter().find_map(|name| env(name).filter(|v| !v.is_empty()));
+                locale.and_then(|l| locale_separator(&l))
+            }
+        };
+        Self { separator, timezone: config.timezone, timestamps: config.timestamps }
+    }
+
+    /// `n` with the thousands separator.
+    pub fn count(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let Some(sep) = self.separator else {
+            return digits;
+        };
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, d) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(sep);
+            }
+            out.push(d);
+        }
+        out
+    }
+
+    /// A latency in microseconds, e.g. `1,250us`.
+    pub fn micros(&self, us: u64) -> String {
+        format!("{}us", self.count(us))
+    }
+
+    /// Epoch milliseconds as a time of day in the configured zone and style.
+    pub fn timestamp(&self, ms: i64) -> String {
+        let Some(utc) = DateTime::<Utc>::from_timestamp_millis(ms) else {
+            return "-".to_string();
+        };
+        match (self.timezone, self.timestamps) {
+            (Timezone::Utc, TimestampStyle::Plain) => utc.format("%Y-%m-%d %H:%M:%S").to_string(),
+            (Timezone::Utc, TimestampStyle::Iso8601) => utc.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            (Timezone::Local, TimestampStyle::Plain) => utc.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+            (Timezone::Local, TimestampStyle::Iso8601) => utc.with_timezone(&Local).format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+        }
+    }
+
+    /// Epoch milliseconds as a time of day to the millisecond, for event
+    /// times that sit close together, such as an alert's evidence tape.
+    pub fn time(&self, ms: i64) -> String {
+        let Some(utc) = DateTime::<Utc>::from_timestamp_millis(ms) else {
+            return "-".to_string();
+        };
+        match (self.timezone, self.timestamps) {
+            (Timezone::Utc, TimestampStyle::Plain) => utc.format("%H:%M:%S%.3f").to_string(),
+            (Timezone::Utc, TimestampStyle::Iso8601) => utc.format("%H:%M:%S%.3fZ").to_string(),
+            (Timezone::Local, TimestampStyle::Plain) => utc.with_timezone(&Local).format("%H:%M:%S%.3f").to_string(),
+            (Timezone::Local, TimestampStyle::Iso8601) => utc.with_timezone(&Local).format("%H:%M:%S%.3f%:z").to_string(),
+        }
+    }
+}
+
+/// Thousands separator of a POSIX locale name such as `de_DE.UTF-8`; `None`
+/// for `C`, `POSIX` and languages not listed.
+fn locale_separator(locale: &str) -> Option<char> {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    match language {
+        "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" => Some(','),
+        "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" => Some('.'),
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "uk" | "hu" => Some(' '),
+        _ => None,
+    }
+}
+
+/// Set the process-wide formats from `[format]`. Call once, before any
+/// output; later calls are ignored.
+pub fn install(config: &FormatConfig) {
+    let _ = FORMATS.set(Formats::from_config(config, |name| std::env::var(name).ok()));
+}
+
+/// The installed formats, or the defaults (ungrouped, plain UTC) if none were.
+pub fn formats() -> &'static Formats {
+    FORMATS.get_or_init(Formats::default)
+}
+
+pub fn count(n: u64) -> String {
+    formats().count(n)
+}
+
+pub fn micros(us: u64) -> String {
+    formats().micros(us)
+}
+
+pub fn timestamp(ms: i64) -> String {
+    formats().timestamp(ms)
+}
+
+pub fn time(ms: i64) -> String {
+    formats().time(ms)
+}
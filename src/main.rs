@@ -1,197 +1,1102 @@
-use std::time::{Duration, Instant};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use laminardb_fraud_detect::alerts::AlertEngine;
-use laminardb_fraud_detect::detection;
-use laminardb_fraud_detect::generator::FraudGenerator;
-use laminardb_fraud_detect::latency::LatencyTracker;
-use laminardb_fraud_detect::stress;
+use laminardb_fraud_detect::archive::{self, AlertArchive};
+use laminardb_fraud_detect::audit::{self, AuditLog};
+use laminardb_fraud_detect::auth::Authorizer;
+use laminardb_fraud_detect::breakpoints::Breakpoints;
+use laminardb_fraud_detect::calendar::TradingCalendar;
+use laminardb_fraud_detect::clock::SystemClock;
+use laminardb_fraud_detect::compare;
+use laminardb_fraud_detect::coordinator;
+use laminardb_fraud_detect::crosscheck;
+use laminardb_fraud_detect::describe;
+use laminardb_fraud_detect::config::{Config, SeverityLevel};
+use laminardb_fraud_detect::detection::{self, PipelineOptions};
+use laminardb_fraud_detect::engine::{Engine, WatermarkStrategy, STREAM_NAMES};
+use laminardb_fraud_detect::features::{self, FeatureExtractor};
+use laminardb_fraud_detect::generator::{self, FraudGenerator, Shard};
+use laminardb_fraud_detect::ids::{IdLedger, UidGenerator};
+use laminardb_fraud_detect::limits::LoadGovernor;
+use laminardb_fraud_detect::locale;
+use laminardb_fraud_detect::notify::Notifier;
+use laminardb_fraud_detect::pacing::Pacer;
+use laminardb_fraud_detect::quality::QualityReport;
+use laminardb_fraud_detect::recording::{self, Player, Recorder};
+use laminardb_fraud_detect::reviews;
+use laminardb_fraud_detect::risk::RiskMerger;
+use laminardb_fraud_detect::runs::{self, RunRecord};
+use laminardb_fraud_detect::simulate::{self, SimulationOptions};
+use laminardb_fraud_detect::slo::{self, SloTracker};
+use laminardb_fraud_detect::stop::StopConditions;
+use laminardb_fraud_detect::stress::{self, StressLevel};
+use laminardb_fraud_detect::terminal;
+use laminardb_fraud_detect::ticks::TickDetector;
 use laminardb_fraud_detect::tui;
+use laminardb_fraud_detect::tuning;
 use laminardb_fraud_detect::web;
 
 #[derive(Parser)]
 #[command(name = "laminardb-fraud-detect", about = "Real-time fraud detection with LaminarDB")]
 struct Cli {
-    /// Run mode: tui, web, or headless
-    #[arg(long, default_value = "tui")]
-    mode: String,
+    /// Defaults to `run` with the TUI
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate trades and detect fraud live
+    Run(Box<RunArgs>),
+    /// Ramp load across levels and report throughput and latency
+    Stress(StressArgs),
+    /// Render the TUI from a remote engine's dashboard feed instead of running one
+    Attach {
+        /// WebSocket URL of a `run --ui web` or `run --web-port` engine
+        #[arg(long, default_value = "ws://localhost:3000/ws")]
+        url: String,
+
+        /// Path to a TOML config file; only `[keys]`, `[tui]` and `[format]` apply
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        refresh: RefreshArgs,
+    },
+    /// Play a row recording back to the TUI or web dashboard, without LaminarDB
+    Replay {
+        /// Row recording from `run --record-rows` or `[recording] path`
+        recording: PathBuf,
+
+        /// Frontend to play it to
+        #[arg(long, value_enum, default_value = "tui")]
+        ui: Ui,
+
+        /// Web dashboard port for --ui web
+        #[arg(long, default_value = "3000")]
+        web_port: u16,
+
+        /// Play this many times as fast as recorded
+        #[arg(long, default_value = "1.0")]
+        speed: f64,
+
+        /// Path to a TOML config file; its rules judge the recorded rows
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        #[command(flatten)]
+        refresh: RefreshArgs,
+    },
+    /// Combine the dashboard feeds of sharded `run --shard` instances into one dashboard
+    Coordinate {
+        /// WebSocket URL of one instance's feed, e.g. ws://host:3001/ws; repeat for each shard
+        #[arg(long = "upstream", required = true)]
+        upstreams: Vec<String>,
+
+        /// Port to serve the combined dashboard on
+        #[arg(long, default_value = "3000")]
+        port: u16,
+
+        /// Run duration in seconds (0 = until stopped)
+        #[arg(long, default_value = "0")]
+        duration: u64,
+
+        /// Path to a TOML config file; `[web]`, `[auth]`, `[search]` and `[retention]` apply
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Run two rule configs on the same event stream and compare their alerts
+    Compare {
+        /// Baseline config (A); its pipeline settings are used for both
+        a: PathBuf,
+        /// Candidate config (B); only its rule settings apply
+        b: PathBuf,
+
+        /// Fraud injection rate (0.0-1.0)
+        #[arg(long, default_value = "0.1")]
+        fraud_rate: f64,
+
+        /// Run duration in seconds
+        #[arg(long, default_value = "60")]
+        duration: u64,
+
+        /// Directory both runs are recorded in
+        #[arg(long, default_value = "runs")]
+        runs_dir: PathBuf,
+    },
+    /// Recompute OHLC, volume and wash aggregates in plain Rust and diff the SQL streams against them
+    CrossCheck {
+        /// Generator cycles to push
+        #[arg(long, default_value = "150")]
+        cycles: u64,
+
+        /// Event time between cycles, in milliseconds
+        #[arg(long, default_value = "200")]
+        cycle_ms: i64,
+
+        /// Fraud injection rate (0.0-1.0)
+        #[arg(long, default_value = "0.1")]
+        fraud_rate: f64,
+
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Write the report, every discrepancy included, as JSON
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+    /// Check a config file, including loading any scoring model it names
+    Validate {
+        /// Path to a TOML config file
+        config: PathBuf,
+    },
+    /// Print the resolved config and every CREATE statement setup executes, as JSON
+    Describe {
+        /// Path to a TOML config file
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Print only the statements, as a SQL script
+        #[arg(long)]
+        sql: bool,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// List recorded runs or compare two of them
+    Runs {
+        #[command(subcommand)]
+        action: Option<RunsAction>,
+
+        /// Directory runs are recorded in
+        #[arg(long, default_value = "runs", global = true)]
+        dir: PathBuf,
+
+        /// Path to a TOML config file; only `[format]` applies
+        #[arg(long, global = true)]
+        config: Option<PathBuf>,
+    },
+    /// Export an audit log (`[audit]`) as CSV for compliance review
+    Audit {
+        /// Path to the JSON-lines audit log
+        log: PathBuf,
+        /// Write the CSV here instead of stdout
+        #[arg(long)]
+        csv: Option<PathBuf>,
+    },
+    /// Summarize an alert archive (`[archive]`) by type, severity, symbol and account
+    Analyze {
+        /// Path to the JSON-lines alert archive
+        archive: PathBuf,
+
+        /// Symbols and accounts to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+
+        /// Path to a TOML config file; only `[format]` applies
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Suggest rule thresholds from reviewed alert dispositions
+    Tune {
+        /// Reviews file; defaults to the config's `[reviews] path`
+        #[arg(long)]
+        reviews: Option<PathBuf>,
+
+        /// Path to a TOML config file holding the current thresholds
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Write the config with every suggestion applied here
+        #[arg(long)]
+        write: Option<PathBuf>,
+    },
+    /// Simulate consecutive trading days from [calendar] on a virtual clock, with an end-of-day report each
+    Simulate {
+        /// Trading sessions to simulate; weekends and holidays are skipped
+        #[arg(long, default_value = "5")]
+        days: u32,
+
+        /// First date (YYYY-MM-DD, exchange-local); defaults to today
+        #[arg(long)]
+        start: Option<String>,
+
+        /// Event time between cycles, in milliseconds
+        #[arg(long, default_value = "1000")]
+        cycle_ms: u64,
 
-    /// Web server port (web mode only)
-    #[arg(long, default_value = "3000")]
-    port: u16,
+        /// Fraud injection rate (0.0-1.0)
+        #[arg(long, default_value = "0.05")]
+        fraud_rate: f64,
+
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Path to a TOML config file; [calendar] is enabled even if it is off
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Directory each day's report is written to as eod-<date>.json
+        #[arg(long, default_value = "daily-reports")]
+        reports_dir: PathBuf,
+    },
+    /// Print a saved detection-quality report
+    Report {
+        #[arg(default_value = "quality-report.json")]
+        path: PathBuf,
+
+        /// Path to a TOML config file; only `[format]` applies
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunsAction {
+    /// One line per recorded run, oldest first (the default)
+    List,
+    /// Compare quality, throughput and latency of run B against run A
+    Diff {
+        /// Run id or unambiguous prefix
+        a: String,
+        b: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Ui {
+    Tui,
+    Web,
+    /// Headless: alerts and a summary on stdout
+    #[value(name = "none")]
+    Headless,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BreakOn {
+    Medium,
+    High,
+    Critical,
+}
+
+impl From<BreakOn> for SeverityLevel {
+    fn from(b: BreakOn) -> Self {
+        match b {
+            BreakOn::Medium => SeverityLevel::Medium,
+            BreakOn::High => SeverityLevel::High,
+            BreakOn::Critical => SeverityLevel::Critical,
+        }
+    }
+}
+
+#[derive(Parser)]
+struct RunArgs {
+    /// Frontend to drive the engine with
+    #[arg(long, value_enum, default_value = "tui")]
+    ui: Ui,
+
+    /// Web dashboard port. With --ui tui, also serves the dashboard from the same engine
+    #[arg(long)]
+    web_port: Option<u16>,
+
+    /// PEM certificate chain to serve the dashboard over HTTPS/WSS (requires --features tls)
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// Serve the dashboard under this path prefix, e.g. /fraud behind a proxy
+    #[arg(long)]
+    base_path: Option<String>,
+
+    /// Milliseconds from one engine cycle to the next: lower cuts alert latency, higher saves CPU
+    #[arg(long)]
+    cycle_interval: Option<u64>,
+
+    /// Adapt the cycle interval to hold this many trades plus orders per second
+    #[arg(long)]
+    target_eps: Option<f64>,
 
     /// Fraud injection rate (0.0-1.0)
     #[arg(long, default_value = "0.05")]
     fraud_rate: f64,
 
-    /// Run duration in seconds (0 = infinite)
+    /// Benign look-alike injection rate (0.0-1.0), overriding [generator] benign_rate
+    #[arg(long)]
+    benign_rate: Option<f64>,
+
+    /// Generate only this symbol shard (index/count, e.g. 0/3), overriding [generator] shard
+    #[arg(long)]
+    shard: Option<Shard>,
+
+    /// Run duration in seconds (0 = until stopped)
     #[arg(long, default_value = "0")]
     duration: u64,
 
-    /// Duration per stress test level in seconds (stress mode only)
+    /// Stop once this many alerts have been delivered
+    #[arg(long)]
+    max_alerts: Option<u64>,
+
+    /// Stop once this many trades have been pushed
+    #[arg(long)]
+    max_trades: Option<u64>,
+
+    /// TUI: pause at the first alert this severe or more, until the resume key
+    #[arg(long, value_enum)]
+    break_on: Option<BreakOn>,
+
+    /// TUI: pause once event time reaches this (epoch milliseconds or RFC 3339)
+    #[arg(long)]
+    break_at: Option<String>,
+
+    /// Path to a TOML config file
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Export labelled per-account feature vectors to CSV or .parquet (--ui none only)
+    #[arg(long)]
+    export_features: Option<PathBuf>,
+
+    /// Where to persist the detection-quality report when fraud was injected
+    #[arg(long, default_value = "quality-report.json")]
+    quality_report: PathBuf,
+
+    /// Directory to record this run in, for `runs list` and `runs diff`
+    #[arg(long, default_value = "runs")]
+    runs_dir: PathBuf,
+
+    /// Record every cycle's polled stream rows here, for `replay`, overriding [recording] path
+    #[arg(long)]
+    record_rows: Option<PathBuf>,
+
+    /// Where to write the machine-readable summary of this run on exit
+    #[arg(long, default_value = "run-summary.json")]
+    summary: PathBuf,
+
+    #[command(flatten)]
+    refresh: RefreshArgs,
+}
+
+#[derive(Args)]
+struct RefreshArgs {
+    /// TUI: redraw at most this often, in milliseconds
+    #[arg(long, default_value = "150")]
+    refresh_ms: u64,
+
+    /// TUI: skip redraws when nothing on screen changed
+    #[arg(long)]
+    low_cpu: bool,
+}
+
+impl RefreshArgs {
+    fn refresh(&self) -> tui::Refresh {
+        tui::Refresh { interval: Duration::from_millis(self.refresh_ms), low_cpu: self.low_cpu }
+    }
+}
+
+#[derive(Args)]
+struct StressArgs {
+    /// Duration per stress test level in seconds
     #[arg(long, default_value = "60")]
     level_duration: u64,
+
+    /// Seconds of load before each level's measurement starts; samples are discarded
+    #[arg(long, default_value = "2")]
+    warmup: u64,
+
+    /// Write a gnuplot script of throughput and p99 per level
+    #[arg(long)]
+    plot: Option<PathBuf>,
+
+    /// Levels to ramp through instead of the default seven, as TRADES:SLEEP_MS per level, e.g. 100:50,500:10
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["stream_cost", "push_budget_us", "matrix", "watermarks"])]
+    levels: Option<Vec<StressLevel>>,
+
+    /// Inject fraud scenarios on top of the load (0.0-1.0) and report detection per level
+    #[arg(long, default_value = "0.0", conflicts_with_all = ["stream_cost", "push_budget_us", "matrix", "watermarks"])]
+    fraud_rate: f64,
+
+    /// Instead of ramping, measure the marginal cost of each detection stream
+    #[arg(long)]
+    stream_cost: bool,
+
+    /// Instead of ramping, tune trades per cycle for --level-duration seconds to keep push p99 under this many microseconds
+    #[arg(long, conflicts_with = "stream_cost")]
+    push_budget_us: Option<u64>,
+
+    /// Instead of ramping, run the top level for each --threads x --buffer-sizes pair
+    #[arg(long, conflicts_with_all = ["stream_cost", "push_budget_us"])]
+    matrix: bool,
+
+    /// Instead of ramping, compare watermark emission strategies at one mid level
+    #[arg(long, conflicts_with_all = ["stream_cost", "push_budget_us", "matrix"])]
+    watermarks: bool,
+
+    /// Tokio worker thread counts for --matrix
+    #[arg(long, value_delimiter = ',', default_value = "1,2,4,8")]
+    threads: Vec<usize>,
+
+    /// LaminarDB source buffer sizes for --matrix
+    #[arg(long, value_delimiter = ',', default_value = "4096,16384,65536")]
+    buffer_sizes: Vec<usize>,
+
+    /// Path to a TOML config file; only `[slo]` applies, to the ramp's alert latency
+    #[arg(long)]
+    config: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
-    match cli.mode.as_str() {
-        "tui" => tui::run(cli.fraud_rate, cli.duration).await?,
-        "web" => web::run(cli.port, cli.fraud_rate, cli.duration).await?,
-        "headless" => run_headless(cli.fraud_rate, cli.duration).await?,
-        "stress" => stress::run(cli.level_duration).await?,
-        other => eprintln!("Unknown mode: {other}. Use --mode tui|web|headless|stress"),
+    match cli.command.unwrap_or_else(|| Command::Run(Box::new(RunArgs::parse_from(["run"])))) {
+        Command::Run(args) => run(*args).await?,
+        Command::Stress(args) if args.matrix => {
+            stress::run_matrix(args.level_duration, args.warmup, &args.threads, &args.buffer_sizes).await?
+        }
+        Command::Stress(args) if args.watermarks => stress::run_watermarks(args.level_duration, args.warmup).await?,
+        Command::Stress(args) if args.stream_cost => stress::run_stream_cost(args.level_duration, args.warmup).await?,
+        Command::Stress(StressArgs { push_budget_us: Some(budget), level_duration, warmup, .. }) => {
+            stress::run_autotune(level_duration, warmup, budget).await?
+        }
+        Command::Stress(args) => {
+            let levels = args.levels.as_deref().unwrap_or(stress::LEVELS);
+            let config = args.config.as_deref().map(Config::load).transpose()?.unwrap_or_default();
+            stress::run(levels, args.level_duration, args.warmup, args.fraud_rate, args.plot.as_deref(), &config.slo).await?
+        }
+        Command::Attach { url, config, refresh } => {
+            let config = config.as_deref().map(Config::load).transpose()?.unwrap_or_default();
+            locale::install(&config.format);
+            tui::attach(&url, &config, refresh.refresh()).await?
+        }
+        Command::Replay { recording, ui, web_port, speed, config, refresh } => {
+            replay_command(&recording, ui, web_port, speed, config.as_deref(), refresh.refresh()).await?
+        }
+        Command::Coordinate { upstreams, port, duration, config } => {
+            let config = config.as_deref().map(Config::load).transpose()?.unwrap_or_default();
+            locale::install(&config.format);
+            coordinator::run(port, upstreams, duration, config).await?
+        }
+        Command::Runs { action, dir, config } => {
+            install_format(config.as_deref())?;
+            runs_command(action.unwrap_or(RunsAction::List), &dir)?
+        }
+        Command::Compare { a, b, fraud_rate, duration, runs_dir } => compare_command(&a, &b, fraud_rate, duration, &runs_dir).await?,
+        Command::CrossCheck { cycles, cycle_ms, fraud_rate, seed, report } => {
+            cross_check_command(cycles, cycle_ms, fraud_rate, seed, report.as_deref()).await?
+        }
+        Command::Validate { config } => validate(&config)?,
+        Command::Describe { config, sql, out } => describe_command(config.as_deref(), sql, out.as_deref()).await?,
+        Command::Audit { log, csv } => audit_command(&log, csv.as_deref())?,
+        Command::Analyze { archive: path, top, config } => {
+            install_format(config.as_deref())?;
+            archive::analyze(&archive::read_entries(&path)?, top).print()
+        }
+        Command::Tune { reviews, config, write } => tune_command(reviews.as_deref(), config.as_deref(), write.as_deref())?,
+        Command::Simulate { days, start, cycle_ms, fraud_rate, seed, config, reports_dir } => {
+            let options = SimulationOptions {
+                days,
+                start: parse_start(start.as_deref())?,
+                cycle: Duration::from_millis(cycle_ms),
+                fraud_rate,
+                seed,
+                reports_dir: Some(reports_dir),
+            };
+            simulate_command(&options, config.as_deref()).await?
+        }
+        Command::Report { path, config } => {
+            install_format(config.as_deref())?;
+            QualityReport::load(&path)?.print()
+        }
     }
 
     Ok(())
 }
 
-async fn run_headless(fraud_rate: f64, duration_secs: u64) -> Result<(), Box<dyn std::error::Error>> {
-    println!("=== laminardb-fraud-detect (headless) ===");
-    println!("Fraud rate: {:.0}%, Duration: {}s", fraud_rate * 100.0, if duration_secs == 0 { "infinite".to_string() } else { duration_secs.to_string() });
-    println!();
+async fn run(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    if args.tls_cert.is_some() {
+        config.web.tls_cert = args.tls_cert.clone();
+        config.web.tls_key = args.tls_key.clone();
+    }
+    if let Some(base_path) = &args.base_path {
+        config.web.base_path = base_path.clone();
+    }
+    if let Some(ms) = args.cycle_interval {
+        config.pacing.cycle_interval_ms = ms;
+    }
+    if args.target_eps.is_some() {
+        config.pacing.target_events_per_sec = args.target_eps;
+    }
+    if let Some(rate) = args.benign_rate {
+        config.generator.benign_rate = rate;
+    }
+    if args.shard.is_some() {
+        config.generator.shard = args.shard;
+    }
+    if args.record_rows.is_some() {
+        config.recording.path = args.record_rows.clone();
+    }
+    if args.max_alerts.is_some() {
+        config.stop.max_alerts = args.max_alerts;
+    }
+    if args.max_trades.is_some() {
+        config.stop.max_trades = args.max_trades;
+    }
+    if config.web.runs_dir.is_none() {
+        config.web.runs_dir = Some(args.runs_dir.clone());
+    }
+    if config.stop.checkpoint_path.is_none() {
+        config.stop.checkpoint_path = Some(args.summary.clone());
+    }
+    if let Some(level) = args.break_on {
+        config.breakpoints.on_severity = Some(level.into());
+    }
+    if args.break_at.is_some() {
+        config.breakpoints.at = args.break_at.clone();
+    }
+    locale::install(&config.format);
+    let breakpoints = !Breakpoints::from_config(&config.breakpoints)?.is_empty();
+    let interactive = args.ui == Ui::Tui && terminal::raw_mode_available();
+    if breakpoints && !interactive {
+        eprintln!("Breakpoints need the TUI to pause and resume; running without them");
+    }
+
+    let record = match args.ui {
+        // No raw mode (piped output, a service, an old console): run without the TUI
+        Ui::Tui if !interactive => {
+            eprintln!("Terminal does not support raw mode; running without the TUI");
+            match args.web_port {
+                Some(port) => web::run(port, args.fraud_rate, args.duration, config).await?,
+                None => run_headless(args.fraud_rate, args.duration, &config, args.export_features.as_deref()).await?,
+            }
+        }
+        Ui::Tui => {
+            let web = match args.web_port {
+                Some(port) => Some(web::serve(port, &config).await?),
+                None => None,
+            };
+            tui::run(args.fraud_rate, args.duration, &config, web, args.refresh.refresh()).await?
+        }
+        Ui::Web => web::run(args.web_port.unwrap_or(3000), args.fraud_rate, args.duration, config).await?,
+        Ui::Headless => run_headless(args.fraud_rate, args.duration, &config, args.export_features.as_deref()).await?,
+    };
 
-    let pipeline = detection::setup().await?;
+    if !record.slo.is_empty() {
+        println!();
+        slo::print(&record.slo);
+    }
+    if let Some(report) = &record.quality {
+        println!();
+        report.print();
+        report.save(&args.quality_report)?;
+        println!("    Saved to {}", args.quality_report.display());
+    }
+    let path = record.save(&args.runs_dir)?;
     println!();
+    println!("  Run {} recorded to {}", record.id, path.display());
+    record.write(&args.summary)?;
+    println!("  Summary written to {}", args.summary.display());
+    Ok(())
+}
 
-    let mut gen = FraudGenerator::new(fraud_rate);
-    let mut alert_engine = AlertEngine::new();
-    let mut latency = LatencyTracker::new();
-    let mut total_trades = 0u64;
-    let mut total_orders = 0u64;
-    let mut stream_counts: [u64; 6] = [0; 6];
-
-    let run_duration = if duration_secs == 0 { Duration::from_secs(3600) } else { Duration::from_secs(duration_secs) };
-    let start = Instant::now();
-
-    while start.elapsed() < run_duration {
-        let ts = FraudGenerator::now_ms();
-        let gen_instant = Instant::now();
-
-        let (trades, orders) = gen.generate_cycle(ts);
-        total_trades += trades.len() as u64;
-        total_orders += orders.len() as u64;
-
-        let push_start = latency.record_push_start();
-        pipeline.trade_source.push_batch(trades);
-        if !orders.is_empty() {
-            pipeline.order_source.push_batch(orders);
-        }
-        pipeline.trade_source.watermark(ts + 10_000);
-        pipeline.order_source.watermark(ts + 10_000);
-        latency.record_push_end(push_start);
-
-        // Poll all streams
-        if let Some(ref sub) = pipeline.vol_baseline_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[0] += 1;
-                    if let Some(alert) = alert_engine.evaluate_volume(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
+async fn replay_command(
+    path: &Path,
+    ui: Ui,
+    web_port: u16,
+    speed: f64,
+    config: Option<&Path>,
+    refresh: tui::Refresh,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err("--speed must be above 0".into());
+    }
+    let config = config.map(Config::load).transpose()?.unwrap_or_default();
+    locale::install(&config.format);
+    if ui != Ui::Tui && !Breakpoints::from_config(&config.breakpoints)?.is_empty() {
+        eprintln!("Breakpoints need the TUI to pause and resume; replaying without them");
+    }
+    let cycles = recording::load(path)?;
+    let mut player = Player::from_config(&config)?;
+    let name = path.display().to_string();
+    match ui {
+        Ui::Tui => tui::replay(&name, cycles, player, speed, &config, refresh).await?,
+        Ui::Web => {
+            let mut web = web::serve(web_port, &config).await?;
+            let blotter = web.blotter();
+            println!("Replaying {} cycles from {name} on port {web_port}", locale::count(cycles.len() as u64));
+            recording::play(cycles, &mut player, speed, |update| {
+                blotter.record_alerts(&update.alerts);
+                web.publish(&update);
+                true
+            })
+            .await;
+            web.flush();
+            println!("Replay finished; serving the last update until stopped");
+            tokio::signal::ctrl_c().await?;
+        }
+        Ui::Headless => {
+            let played = recording::play(cycles, &mut player, speed, |update| {
+                for alert in &update.alerts {
+                    println!("  ALERT | {:?} | {}", alert.severity, alert.description());
                 }
-            }
+                true
+            })
+            .await;
+            println!();
+            println!("  Cycles replayed:    {}", locale::count(played));
+            println!("  Alerts:             {}", locale::count(player.alert_engine.total_alerts()));
         }
+    }
+    Ok(())
+}
 
-        if let Some(ref sub) = pipeline.ohlc_vol_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[1] += 1;
-                    if let Some(alert) = alert_engine.evaluate_ohlc(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
-                }
+fn runs_command(action: RunsAction, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        RunsAction::List => {
+            let recorded = runs::list(dir)?;
+            if recorded.is_empty() {
+                println!("No runs recorded in {}", dir.display());
+            } else {
+                runs::print_list(&recorded);
             }
         }
+        RunsAction::Diff { a, b } => runs::print_diff(&runs::find(dir, &a)?, &runs::find(dir, &b)?),
+    }
+    Ok(())
+}
 
-        if let Some(ref sub) = pipeline.rapid_fire_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[2] += 1;
-                    if let Some(alert) = alert_engine.evaluate_rapid_fire(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
-                }
-            }
+/// Apply `[format]` from the config at `path`, for commands that only print.
+fn install_format(path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = path {
+        locale::install(&Config::load(path)?.format);
+    }
+    Ok(())
+}
+
+async fn compare_command(a: &Path, b: &Path, fraud_rate: f64, duration: u64, runs_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config_a = Config::load(a)?;
+    locale::install(&config_a.format);
+    let (record_a, record_b) = compare::run(&config_a, &Config::load(b)?, fraud_rate, duration).await?;
+    println!("  A = {} ({})", a.display(), &record_a.id[..8]);
+    println!("  B = {} ({})", b.display(), &record_b.id[..8]);
+    println!();
+    runs::print_diff(&record_a, &record_b);
+    record_a.save(runs_dir)?;
+    record_b.save(runs_dir)?;
+    println!();
+    println!("  Both runs recorded to {}", runs_dir.display());
+    Ok(())
+}
+
+async fn cross_check_command(
+    cycles: u64,
+    cycle_ms: i64,
+    fraud_rate: f64,
+    seed: u64,
+    report_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = crosscheck::run(cycles, cycle_ms, fraud_rate, seed).await?;
+    println!();
+    report.print();
+    if let Some(path) = report_path {
+        report.save(path)?;
+        println!("    Saved to {}", path.display());
+    }
+    if !report.is_clean() {
+        let total: usize = report.streams.iter().map(|s| s.discrepancies.len()).sum();
+        return Err(format!("cross-check found {total} discrepancies").into());
+    }
+    Ok(())
+}
+
+async fn describe_command(config: Option<&Path>, sql: bool, out: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config.map(Config::load).transpose()?.unwrap_or_default();
+    let bundle = describe::bundle(&config).await?;
+    let text = if sql { bundle.sql() } else { serde_json::to_string_pretty(&bundle)? + "\n" };
+    match out {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            eprintln!("Wrote {} statements to {}", bundle.statements.len(), path.display());
         }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
 
-        if let Some(ref sub) = pipeline.wash_score_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[3] += 1;
-                    if let Some(alert) = alert_engine.evaluate_wash(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
-                }
-            }
+fn audit_command(log: &Path, csv: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = audit::read_entries(log)?;
+    match csv {
+        Some(path) => {
+            let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+            audit::export_csv(&entries, &mut out)?;
+            out.flush()?;
+            println!("Exported {} audit entries to {}", entries.len(), path.display());
         }
+        None => audit::export_csv(&entries, &mut std::io::stdout().lock())?,
+    }
+    Ok(())
+}
 
-        if let Some(ref sub) = pipeline.suspicious_match_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[4] += 1;
-                    if let Some(alert) = alert_engine.evaluate_match(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
-                }
-            }
+fn tune_command(reviews: Option<&Path>, config: Option<&Path>, write: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config.map(Config::load).transpose()?.unwrap_or_default();
+    let path = reviews
+        .or(config.reviews.path.as_deref())
+        .ok_or("no reviews file: pass --reviews or set [reviews] path in --config")?;
+    let reviewed = reviews::load(path)?;
+    let suggestions = tuning::suggest(&reviewed, &config);
+    println!("  {} reviews in {}", reviewed.len(), path.display());
+    if suggestions.is_empty() {
+        println!("  No rule has both a reviewed true positive and false positive to tune between");
+    }
+    for s in &suggestions {
+        println!("  {s}");
+    }
+    if let Some(out) = write {
+        std::fs::write(out, toml::to_string_pretty(&tuning::apply(&suggestions, &config))?)?;
+        println!("  Tuned config written to {}", out.display());
+    }
+    Ok(())
+}
+
+fn parse_start(start: Option<&str>) -> Result<chrono::NaiveDate, String> {
+    match start {
+        Some(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("--start {s:?} is not YYYY-MM-DD: {e}")),
+        None => Ok(chrono::Utc::now().date_naive()),
+    }
+}
+
+async fn simulate_command(options: &SimulationOptions, config: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let config = config.map(Config::load).transpose()?.unwrap_or_default();
+    locale::install(&config.format);
+    println!("=== laminardb-fraud-detect (simulate) ===");
+    println!("Fraud rate: {:.0}%, Days: {}, starting {}", options.fraud_rate * 100.0, options.days, options.start);
+    println!();
+    let reports = simulate::run(options, &config).await?;
+    let trades: u64 = reports.iter().map(|r| r.trades).sum();
+    let alerts: u64 = reports.iter().map(|r| r.alerts).sum();
+    println!("Simulated {} trading day(s): {} trades, {} alerts", reports.len(), locale::count(trades), locale::count(alerts));
+    Ok(())
+}
+
+fn validate(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(path)?;
+    AlertEngine::from_config(&config)?;
+    Notifier::from_config(&config.notify)?;
+    tui::Keymap::from_config(&config.keys)?;
+    Pacer::from_config(&config)?;
+    TickDetector::from_config(&config.ticks)?;
+    WatermarkStrategy::from_config(&config.watermark)?;
+    Breakpoints::from_config(&config.breakpoints)?;
+    Authorizer::from_config(&config.auth)?;
+    RiskMerger::from_config(&config.coordinator)?;
+    SloTracker::from_config(&config.slo)?;
+    detection::check_sessions(&config.sessions)?;
+    let calendar = TradingCalendar::from_config(&config.calendar)?;
+    println!("{}: OK", path.display());
+    if let Some(model) = &config.scoring.model_path {
+        println!("  Scoring model: {} (min score {})", model.display(), config.scoring.min_score);
+    }
+    if let Some(calendar) = calendar {
+        let now = chrono::Utc::now().timestamp_millis();
+        match calendar.next_open(now) {
+            Some(open) => println!("  Calendar: next session opens {} (local {})", locale::timestamp(open), calendar.local(open)),
+            None => println!("  Calendar: no session in the next 30 days"),
         }
+    }
+    Ok(())
+}
 
-        if let Some(ref sub) = pipeline.asof_match_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[5] += 1;
-                    if let Some(alert) = alert_engine.evaluate_asof(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        println!("  ALERT | {:?} | {} | {}us", alert.severity, alert.description, alert.latency_us);
-                    }
-                }
+async fn run_headless(
+    fraud_rate: f64,
+    duration_secs: u64,
+    config: &Config,
+    export_features: Option<&Path>,
+) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    println!("=== laminardb-fraud-detect (headless) ===");
+    println!(
+        "Fraud rate: {:.0}%, Duration: {}",
+        fraud_rate * 100.0,
+        if duration_secs == 0 { "until stopped".to_string() } else { format!("{duration_secs}s") }
+    );
+    println!();
+
+    let mut pacer = Pacer::from_config(config)?;
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(pacer.interval());
+    let join = detection::guard_join_band(&config.join, trades_per_sec, orders_per_sec);
+    let options = PipelineOptions {
+        feature_stream: export_features.is_some(),
+        sessions: config.sessions.clone(),
+        join: join.clone(),
+        ..Default::default()
+    };
+    let pipeline = detection::setup_with(&options).await?;
+    println!();
+
+    let gen = FraudGenerator::new(fraud_rate).with_benign_rate(config.generator.benign_rate).with_shard(config.generator.shard);
+    let alert_engine = AlertEngine::from_config(config)?;
+    let mut engine = Engine::new(pipeline, gen, alert_engine, Arc::new(SystemClock::new()));
+    engine.features = export_features.map(|_| FeatureExtractor::new());
+    engine.governor = Some(LoadGovernor::new(config.limits.clone()).with_join(join));
+    engine.cycle_budget = config.limits.cycle_budget();
+    engine.poll_budget = config.limits.poll_budget();
+    engine.eval_worker = config.limits.eval_worker;
+    engine.watermark = WatermarkStrategy::from_config(&config.watermark)?;
+    engine.archive = AlertArchive::open(&config.archive)?;
+    engine.ids = IdLedger::open(&config.ids)?;
+    engine.uids = UidGenerator::from_config(&config.ids);
+    engine.ticks = TickDetector::from_config(&config.ticks)?;
+    engine.audit = AuditLog::open(config)?;
+    engine.recorder = Recorder::open(&config.recording)?;
+    engine.slo = SloTracker::from_config(&config.slo)?;
+
+    let mut stop = StopConditions::new(duration_secs, &config.stop);
+
+    let mut degraded = false;
+    let mut join_overloaded = false;
+    while !stop.reached(&engine) {
+        let start = engine.elapsed();
+        for alert in engine.cycle().await {
+            println!("  ALERT | {:?} | {} | {}", alert.severity, alert.description(), locale::micros(alert.latency_us));
+        }
+        pacer.record(start, &engine);
+        if engine.degraded() != degraded {
+            degraded = engine.degraded();
+            if degraded {
+                println!("  DEGRADED | push latency over limit, shedding load");
+            } else {
+                println!("  RECOVERED | push latency back under limit");
             }
         }
-
-        tokio::time::sleep(Duration::from_millis(200)).await;
+        if engine.join_overloaded() != join_overloaded {
+            join_overloaded = engine.join_overloaded();
+            let fanout = engine.governor.as_ref().map_or(0.0, |g| g.join_fanout_per_sec());
+            if join_overloaded {
+                println!("  JOIN FAN-OUT | ~{fanout:.0} join rows/s over [join] max_fanout_per_sec");
+            } else {
+                println!("  JOIN OK | ~{fanout:.0} join rows/s back under limit");
+            }
+        }
+        stop.checkpoint(&mut engine, "none", fraud_rate, config);
+        pacer.wait(&engine).await;
+    }
+    if let Some(reason) = stop.reason() {
+        println!("  STOP | {reason}");
+    }
+    if let Some(timeout) = config.drain.timeout() {
+        for alert in engine.drain(timeout).await {
+            println!("  ALERT | {:?} | {} | {} (drained)", alert.severity, alert.description(), locale::micros(alert.latency_us));
+        }
     }
 
     // Summary
     println!();
     println!("=== Results ===");
-    println!("  Trades pushed:      {}", total_trades);
-    println!("  Orders pushed:      {}", total_orders);
-    println!("  Alerts generated:   {}", alert_engine.total_alerts());
+    println!("  Trades pushed:      {}", locale::count(engine.total_trades));
+    println!("  Orders pushed:      {}", locale::count(engine.total_orders));
+    println!("  Alerts generated:   {}", locale::count(engine.alerts_generated()));
+    println!("  Alerts delivered:   {}", locale::count(engine.alerts_delivered()));
+    if engine.alert_engine.model_suppressed() > 0 {
+        println!("  Alerts suppressed:  {} (below model_min_score)", locale::count(engine.alert_engine.model_suppressed()));
+    }
+    if engine.alert_engine.cooldown_suppressed() > 0 {
+        println!("  Alerts held back:   {} (cooldown or silence)", locale::count(engine.alert_engine.cooldown_suppressed()));
+    }
+    let pushes = &engine.push_stats;
+    if pushes.deferred_batches > 0 || pushes.rejected_batches > 0 {
+        println!("  Batches deferred:   {} (accepted after retry)", locale::count(pushes.deferred_batches));
+        println!(
+            "  Batches rejected:   {} ({} records dropped at a full buffer)",
+            locale::count(pushes.rejected_batches),
+            locale::count(pushes.rejected_records)
+        );
+    }
+    if let Some(g) = engine.governor.as_ref().filter(|g| g.shed_cycles > 0 || g.dropped_alerts > 0) {
+        println!("  Cycles shed:        {} (overload)", locale::count(g.shed_cycles));
+        println!("  Alerts dropped:     {} (over max_alert_queue)", locale::count(g.dropped_alerts));
+    }
+    if engine.eval_worker {
+        let e = &engine.eval_stats;
+        println!(
+            "  Eval worker:        {} batches, {} rows, queue depth max {}, full {}x, waited {}",
+            locale::count(e.batches),
+            locale::count(e.rows),
+            e.max_queue_depth,
+            locale::count(e.queue_full),
+            locale::micros(e.wait_us)
+        );
+    }
+    if engine.watermark.every_pushes != 1 {
+        let w = &engine.watermark_stats;
+        println!(
+            "  Watermarks:         {} emitted over {} pushes, {:.1} rows per output batch",
+            locale::count(w.emitted),
+            locale::count(w.pushes),
+            w.rows_per_batch()
+        );
+    }
+    if let Some(d) = &engine.drained {
+        println!(
+            "  Drained at end:     {} rows, {} alerts in {}ms{}",
+            locale::count(d.rows),
+            locale::count(d.alerts),
+            d.took_ms,
+            if d.timed_out { " (timed out before the streams went quiet)" } else { "" }
+        );
+    }
+    for (feed, s) in engine.sequences.stats().iter().filter(|(_, s)| s.gaps > 0 || s.duplicates > 0) {
+        println!(
+            "  Feed {:<14} {} gaps ({} missing), {} duplicates",
+            format!("{feed}:"),
+            locale::count(s.gaps),
+            locale::count(s.missing),
+            locale::count(s.duplicates)
+        );
+    }
+    if let Some(archive) = &engine.archive {
+        let stats = archive.stats();
+        println!(
+            "  Alert archive:      {} alerts + {} hourly summaries, {} KiB, {} compactions",
+            stats.alert_rows, stats.summary_rows, stats.bytes / 1024, stats.compactions
+        );
+    }
     println!();
     println!("  Stream outputs:");
-    let names = ["vol_baseline", "ohlc_vol", "rapid_fire", "wash_score", "suspicious_match", "asof_match"];
-    for (i, name) in names.iter().enumerate() {
-        println!("    {:<20} {}", name, stream_counts[i]);
+    for (i, name) in STREAM_NAMES.iter().enumerate() {
+        let over = match engine.budget_exhausted[i] {
+            0 => String::new(),
+            n => format!(" (over max_rows_per_stream in {} cycles, {} rows carried)", locale::count(n), locale::count(engine.carried_rows[i])),
+        };
+        println!("    {:<20} {}{over}", name, locale::count(engine.stream_counts[i]));
     }
     println!();
-    let push = latency.push_stats();
-    let proc = latency.processing_stats();
-    let alert_lat = latency.alert_stats();
+    let push = engine.latency.push_stats();
+    let proc = engine.latency.processing_stats();
+    let alert_lat = engine.latency.alert_stats();
     println!("  Latency (microseconds):");
-    println!("    Push:       p50={} p95={} p99={} min={} max={}", push.p50_us, push.p95_us, push.p99_us, push.min_us, push.max_us);
-    println!("    Processing: p50={} p95={} p99={} min={} max={}", proc.p50_us, proc.p95_us, proc.p99_us, proc.min_us, proc.max_us);
-    println!("    Alert:      p50={} p95={} p99={} min={} max={}", alert_lat.p50_us, alert_lat.p95_us, alert_lat.p99_us, alert_lat.min_us, alert_lat.max_us);
+    let us = locale::count;
+    for (stage, s) in [("Push:      ", push), ("Processing:", proc), ("Alert:     ", alert_lat)] {
+        println!("    {stage} p50={} p95={} p99={} min={} max={}", us(s.p50_us), us(s.p95_us), us(s.p99_us), us(s.min_us), us(s.max_us));
+    }
+    let (tick_lag, bar_lag) = (engine.latency.tick_lag_stats(), engine.latency.bar_lag_stats());
+    println!("  Price move to detection (event-time ms):");
+    if tick_lag.count > 0 {
+        println!("    Tick path:  p50={} p95={} p99={} ({} moves)", tick_lag.p50_us / 1000, tick_lag.p95_us / 1000, tick_lag.p99_us / 1000, tick_lag.count);
+    }
+    println!("    Bar path:   p50={} p95={} p99={} (from bar close)", bar_lag.p50_us / 1000, bar_lag.p95_us / 1000, bar_lag.p99_us / 1000);
     println!();
 
-    for (name, count) in alert_engine.alert_counts() {
-        println!("  {}: {}", name, count);
+    for (name, count) in engine.alert_engine.alert_counts() {
+        println!("  {}: {}", name, locale::count(*count));
+    }
+    for (name, count) in engine.alert_engine.shadow_alert_counts() {
+        println!("  {}: {} (shadow, not delivered)", name, locale::count(*count));
+    }
+    let by_symbol = engine.alert_engine.symbol_breakdown();
+    if !by_symbol.is_empty() {
+        println!();
+        println!("  Alerts by symbol:");
+        for s in &by_symbol {
+            let types: Vec<String> = s.by_type.iter().map(|(t, n)| format!("{t} {}", locale::count(*n))).collect();
+            println!("    {:<6} {:>7}  {}", s.symbol, locale::count(s.total), types.join(", "));
+        }
     }
 
-    let _ = pipeline.db.shutdown().await;
-    Ok(())
+    if let (Some(f), Some(path)) = (engine.features.take(), export_features) {
+        let rows = f.finish();
+        let positives = rows.iter().filter(|r| r.label == 1).count();
+        features::export(path, &rows)?;
+        println!();
+        println!("  Exported {} feature rows ({} labelled fraud) to {}", rows.len(), positives, path.display());
+    }
+
+    let record = RunRecord::capture(&mut engine, "none", fraud_rate, config);
+    engine.shutdown().await;
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn no_subcommand_runs_the_tui() {
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect"]).unwrap().command.is_none());
+        let args = RunArgs::parse_from(["run"]);
+        assert!(args.ui == Ui::Tui);
+        assert_eq!(args.fraud_rate, 0.05);
+        assert_eq!(args.duration, 0);
+    }
+
+    #[test]
+    fn subcommands_replace_mode() {
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "run", "--ui", "none", "--duration", "30"]).unwrap();
+        let Some(Command::Run(args)) = cli.command else { panic!("expected run") };
+        assert!(args.ui == Ui::Headless);
+        assert_eq!(args.duration, 30);
+
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "stress", "--levels", "100:50,500:10"]).unwrap();
+        let Some(Command::Stress(args)) = cli.command else { panic!("expected stress") };
+        let levels = args.levels.unwrap();
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[1], StressLevel { trades_per_cycle: 500, sleep_ms: 10, target_tps: 50_000 });
+
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "--mode", "stress"]).is_err());
+
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "analyze", "alerts.jsonl", "--top", "3"]).unwrap();
+        let Some(Command::Analyze { archive, top, .. }) = cli.command else { panic!("expected analyze") };
+        assert_eq!((archive, top), (PathBuf::from("alerts.jsonl"), 3));
+    }
+
+    #[test]
+    fn conflicting_and_missing_arguments_are_rejected() {
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "stress", "--stream-cost", "--levels", "100:50"]).is_err());
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "stress", "--levels", "100:0"]).is_err());
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "validate"]).is_err());
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "run", "--tls-cert", "cert.pem"]).is_err());
+    }
+
+    #[test]
+    fn refresh_flags_apply_to_run_and_attach() {
+        let refresh = RunArgs::parse_from(["run"]).refresh.refresh();
+        assert_eq!((refresh.interval, refresh.low_cpu), (Duration::from_millis(150), false));
+
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "attach", "--refresh-ms", "500", "--low-cpu"]).unwrap();
+        let Some(Command::Attach { refresh, .. }) = cli.command else { panic!("expected attach") };
+        let refresh = refresh.refresh();
+        assert_eq!((refresh.interval, refresh.low_cpu), (Duration::from_millis(500), true));
+
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "run", "--refresh-ms", "-1"]).is_err());
+    }
+
+    #[test]
+    fn stress_matrix_takes_comma_separated_sweeps() {
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "stress", "--matrix", "--threads", "1,3", "--buffer-sizes", "1024"]).unwrap();
+        let Some(Command::Stress(args)) = cli.command else { panic!("expected stress") };
+        assert!(args.matrix);
+        assert_eq!((args.threads, args.buffer_sizes), (vec![1, 3], vec![1024]));
+
+        let cli = Cli::try_parse_from(["laminardb-fraud-detect", "stress"]).unwrap();
+        let Some(Command::Stress(args)) = cli.command else { panic!("expected stress") };
+        assert_eq!((args.threads, args.buffer_sizes), (vec![1, 2, 4, 8], vec![4096, 16384, 65536]));
+        assert!(Cli::try_parse_from(["laminardb-fraud-detect", "stress", "--matrix", "--push-budget-us", "500"]).is_err());
+    }
 }
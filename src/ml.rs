@@ -0,0 +1,358 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub const NUM_FEATURES: usize = 4;
+
+const NUM_TREES: usize = 25;
+const TREE_DEPTH: usize = 8;
+const WINDOW_SIZE: u32 = 250;
+const MODEL_SEED: u64 = 0x5eed;
+
+// ── Per-account feature vectors ──
+
+/// Latest per-account signals derived from stream outputs.
+#[derive(Debug, Clone, Default)]
+pub struct AccountFeatures {
+    pub burst_trades: i64,
+    pub burst_volume: i64,
+    /// 1.0 = perfectly balanced buy/sell volume, 0.0 = one-sided or no data.
+    pub wash_balance: f64,
+    /// Suspicious matches observed since the account was last scored.
+    pub match_count: u64,
+}
+
+impl AccountFeatures {
+    /// Squash each raw feature into [0, 1) so the trees can use a fixed workspace.
+    pub fn to_vector(&self) -> [f64; NUM_FEATURES] {
+        [
+            squash(self.burst_trades as f64, 10.0),
+            squash(self.burst_volume as f64, 1_000.0),
+            self.wash_balance.clamp(0.0, 1.0),
+            squash(self.match_count as f64, 5.0),
+        ]
+    }
+}
+
+fn squash(x: f64, scale: f64) -> f64 {
+    let x = x.max(0.0) / scale;
+    x / (1.0 + x)
+}
+
+// ── Streaming Half-Space Trees ──
+//
+// Tan, Ting & Liu (2011). Each tree partitions a randomly perturbed unit
+// workspace. Nodes count how many points landed in them during the previous
+// window (reference mass) and the current window (latest mass). A point that
+// lands in a region with little reference mass is anomalous.
+
+#[derive(Debug, Clone)]
+struct Node {
+    split_dim: usize,
+    split_value: f64,
+    reference_mass: u32,
+    latest_mass: u32,
+}
+
+/// Complete binary tree in heap layout: children of `i` are `2i+1` and `2i+2`.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(depth: usize, rng: &mut StdRng) -> Self {
+        let mut lo = [0.0; NUM_FEATURES];
+        let mut hi = [0.0; NUM_FEATURES];
+        for (l, h) in lo.iter_mut().zip(hi.iter_mut()) {
+            let s: f64 = rng.gen();
+            let r = 2.0 * s.max(1.0 - s);
+            *l = s - r;
+            *h = s + r;
+        }
+
+        let total = (1usize << (depth + 1)) - 1;
+        let mut ranges = vec![(lo, hi); total];
+        let mut nodes = Vec::with_capacity(total);
+        for i in 0..total {
+            let (lo, hi) = ranges[i];
+            let dim = rng.gen_range(0..NUM_FEATURES);
+            let split = (lo[dim] + hi[dim]) / 2.0;
+            nodes.push(Node { split_dim: dim, split_value: split, reference_mass: 0, latest_mass: 0 });
+
+            let left = 2 * i + 1;
+            if left < total {
+                let mut left_hi = hi;
+                left_hi[dim] = split;
+                ranges[left] = (lo, left_hi);
+                let mut right_lo = lo;
+                right_lo[dim] = split;
+                ranges[left + 1] = (right_lo, hi);
+            }
+        }
+        Self { nodes }
+    }
+
+    fn child(&self, i: usize, x: &[f64; NUM_FEATURES]) -> Option<usize> {
+        let left = 2 * i + 1;
+        if left >= self.nodes.len() {
+            return None;
+        }
+        let node = &self.nodes[i];
+        Some(if x[node.split_dim] < node.split_value { left } else { left + 1 })
+    }
+
+    fn learn(&mut self, x: &[f64; NUM_FEATURES]) {
+        let mut i = 0;
+        loop {
+            self.nodes[i].latest_mass += 1;
+            match self.child(i, x) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+    }
+
+    fn mass_score(&self, x: &[f64; NUM_FEATURES], size_limit: u32) -> f64 {
+        let mut i = 0;
+        let mut level = 0u32;
+        loop {
+            let mass = self.nodes[i].reference_mass;
+            let next = if mass <= size_limit { None } else { self.child(i, x) };
+            match next {
+                Some(n) => {
+                    i = n;
+                    level += 1;
+                }
+                None => return mass as f64 * 2f64.powi(level as i32),
+            }
+        }
+    }
+
+    fn rotate(&mut self) {
+        for node in &mut self.nodes {
+            node.reference_mass = node.latest_mass;
+            node.latest_mass = 0;
+        }
+    }
+}
+
+pub struct HalfSpaceTrees {
+    trees: Vec<Tree>,
+    window_size: u32,
+    size_limit: u32,
+    window_count: u32,
+    warm: bool,
+}
+
+impl HalfSpaceTrees {
+    pub fn new(num_trees: usize, depth: usize, window_size: u32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let trees = (0..num_trees).map(|_| Tree::new(depth, &mut rng)).collect();
+        Self {
+            trees,
+            window_size,
+            size_limit: (window_size / 10).max(1),
+            window_count: 0,
+            warm: false,
+        }
+    }
+
+    /// True once the first full window has become the reference profile.
+    pub fn is_warm(&self) -> bool {
+        self.warm
+    }
+
+    /// Anomaly score in [0, 1]: 0 = dense region of the reference window, 1 = empty region.
+    /// Always 0 until the model is warm.
+    pub fn score(&self, x: &[f64; NUM_FEATURES]) -> f64 {
+        if !self.warm {
+            return 0.0;
+        }
+        let mass: f64 = self.trees.iter().map(|t| t.mass_score(x, self.size_limit)).sum();
+        let expected = self.trees.len() as f64 * self.window_size as f64;
+        1.0 - (mass / expected).min(1.0)
+    }
+
+    pub fn learn(&mut self, x: &[f64; NUM_FEATURES]) {
+        for tree in &mut self.trees {
+            tree.learn(x);
+        }
+        self.window_count += 1;
+        if self.window_count >= self.window_size {
+            for tree in &mut self.trees {
+                tree.rotate();
+            }
+            self.window_count = 0;
+            self.warm = true;
+        }
+    }
+}
+
+// ── Scorer used by AlertEngine ──
+
+#[derive(Debug, Clone)]
+pub struct MlScore {
+    pub account_id: String,
+    pub score: f64,
+    pub features: AccountFeatures,
+}
+
+pub struct AnomalyScorer {
+    model: HalfSpaceTrees,
+    features: HashMap<String, AccountFeatures>,
+    dirty: BTreeSet<String>,
+}
+
+impl Default for AnomalyScorer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnomalyScorer {
+    pub fn new() -> Self {
+        Self {
+            model: HalfSpaceTrees::new(NUM_TREES, TREE_DEPTH, WINDOW_SIZE, MODEL_SEED),
+            features: HashMap::new(),
+            dirty: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_warm(&self) -> bool {
+        self.model.is_warm()
+    }
+
+    pub fn features(&self, account_id: &str) -> AccountFeatures {
+        self.features.get(account_id).cloned().unwrap_or_default()
+    }
+
+    fn entry(&mut self, account_id: &str) -> &mut AccountFeatures {
+        if !self.dirty.contains(account_id) {
+            self.dirty.insert(account_id.to_string());
+        }
+        self.features.entry(account_id.to_string()).or_default()
+    }
+
+    pub fn observe_burst(&mut self, account_id: &str, trades: i64, volume: i64) {
+        let f = self.entry(account_id);
+        f.burst_trades = trades;
+        f.burst_volume = volume;
+    }
+
+    pub fn observe_wash(&mut self, account_id: &str, balance: f64) {
+        self.entry(account_id).wash_balance = balance;
+    }
+
+    pub fn observe_match(&mut self, account_id: &str) {
+        self.entry(account_id).match_count += 1;
+    }
+
+    /// Score every account touched since the last call, then learn from it.
+    /// Accounts are returned in sorted order so runs are reproducible.
+    pub fn score_dirty(&mut self) -> Vec<MlScore> {
+        let dirty = std::mem::take(&mut self.dirty);
+        let mut scores = Vec::with_capacity(dirty.len());
+        for account_id in dirty {
+            let Some(features) = self.features.get_mut(&account_id) else {
+                continue;
+            };
+            let x = features.to_vector();
+            let score = self.model.score(&x);
+            self.model.learn(&x);
+            scores.push(MlScore { account_id, score, features: features.clone() });
+            features.match_count = 0;
+        }
+        scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::alerts::{AlertEngine, AlertType};
+    use crate::types::{RapidFireBurst, SuspiciousMatch, WashScore};
+
+    /// A small, steady spread of accounts: 1-3 trade bursts of 100-140 shares.
+    fn steady(i: usize) -> [f64; NUM_FEATURES] {
+        AccountFeatures { burst_trades: 1 + (i % 3) as i64, burst_volume: 100 + (i % 5) as i64 * 10, wash_balance: 0.0, match_count: 0 }.to_vector()
+    }
+
+    fn outlier() -> [f64; NUM_FEATURES] {
+        AccountFeatures { burst_trades: 60, burst_volume: 20_000, wash_balance: 1.0, match_count: 8 }.to_vector()
+    }
+
+    fn trained(seed: u64) -> HalfSpaceTrees {
+        let mut model = HalfSpaceTrees::new(NUM_TREES, TREE_DEPTH, 100, seed);
+        for i in 0..150 {
+            model.learn(&steady(i));
+        }
+        model
+    }
+
+    #[test]
+    fn anomalies_score_above_the_steady_distribution() {
+        let mut model = HalfSpaceTrees::new(NUM_TREES, TREE_DEPTH, 100, MODEL_SEED);
+        assert_eq!(model.score(&outlier()), 0.0, "no score before the first window");
+        for i in 0..99 {
+            model.learn(&steady(i));
+        }
+        assert!(!model.is_warm());
+        model.learn(&steady(99));
+        assert!(model.is_warm());
+
+        let outlier = model.score(&outlier());
+        let steady_max = (0..15).map(|i| model.score(&steady(i))).fold(0.0, f64::max);
+        assert!(outlier > steady_max, "outlier {outlier} vs steady {steady_max}");
+        assert!((0.0..=1.0).contains(&outlier));
+    }
+
+    #[test]
+    fn same_seed_same_scores() {
+        let (a, b) = (trained(7), trained(7));
+        for x in [steady(0), steady(4), outlier()] {
+            assert_eq!(a.score(&x), b.score(&x));
+        }
+    }
+
+    /// An engine whose model has learned `WINDOW_SIZE` steady bursts, then
+    /// sees X1 burst, wash and match like `outlier()`; returns its MlAnomaly
+    /// alerts and X1's score.
+    fn score_outlier(threshold: f64) -> (Vec<crate::alerts::Alert>, f64) {
+        let mut engine = AlertEngine::new();
+        engine.ml_score_threshold = threshold;
+        let burst = |account: String, trades: i64, volume: i64| RapidFireBurst { account_id: account, burst_trades: trades, burst_volume: volume, low: 100.0, high: 100.0, burst_notional: 0.0 };
+        for i in 0..WINDOW_SIZE as usize {
+            engine.evaluate_rapid_fire(&burst(format!("S{}", i % 20), 1 + (i % 3) as i64, 100 + (i % 5) as i64 * 10), Instant::now());
+            engine.evaluate_ml(Instant::now());
+        }
+        engine.evaluate_rapid_fire(&burst("X1".into(), 60, 20_000), Instant::now());
+        let wash = WashScore { account_id: "X1".into(), symbol: "AAPL".into(), window_start: 100_000, buy_volume: 10_000, sell_volume: 10_000, buy_count: 5, sell_count: 5, buy_notional: 0.0, sell_notional: 0.0 };
+        engine.evaluate_wash(&wash, Instant::now());
+        for i in 0..8 {
+            let m = SuspiciousMatch { symbol: "AAPL".into(), trade_price: 100.0, volume: 100, order_id: format!("O{i}"), account_id: "X1".into(), side: "buy".into(), order_price: 100.0, price_diff: 0.0, notional: 0.0 };
+            engine.evaluate_match(&m, Instant::now());
+        }
+        let alerts = engine.evaluate_ml(Instant::now());
+        let score = engine.last_ml_scores().iter().find(|s| s.account_id == "X1").expect("X1 was scored").score;
+        (alerts, score)
+    }
+
+    #[test]
+    fn threshold_gates_ml_alerts() {
+        let (alerts, score) = score_outlier(1.0);
+        assert!(alerts.is_empty(), "no score is over 1.0");
+        assert!(score > 0.0, "the model is warm and X1 is unlike what it learned");
+
+        let (alerts, again) = score_outlier(score - 1e-9);
+        assert_eq!(again, score, "the same rows score the same");
+        assert_eq!(alerts.len(), 1);
+        assert!(matches!(alerts[0].alert_type, AlertType::MlAnomaly));
+        assert_eq!((alerts[0].account_id.as_deref(), alerts[0].score), (Some("X1"), Some(score)));
+
+        let (alerts, _) = score_outlier(score);
+        assert!(alerts.is_empty(), "a score at the threshold doesn't fire");
+    }
+}
@@ -0,0 +1,149 @@
+use crate::alerts::{Alert, AlertSeverity};
+use crate::config::NotifyConfig;
+
+/// Raises a desktop notification for the alerts of each cycle whose severity
+/// is enabled in `[notify]`. Several qualifying alerts in one cycle are
+/// coalesced into a single notification so a burst doesn't flood the desktop.
+pub struct Notifier {
+    config: NotifyConfig,
+}
+
+impl Notifier {
+    /// Build the notifier described by `config`, or `None` when every
+    /// severity is disabled.
+    pub fn from_config(config: &NotifyConfig) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !(config.medium || config.high || config.critical) {
+            return Ok(None);
+        }
+        #[cfg(not(feature = "notify"))]
+        return Err("desktop notifications require building with --features notify".into());
+        #[cfg(feature = "notify")]
+        Ok(Some(Self { config: config.clone() }))
+    }
+
+    pub fn enabled(&self, severity: &AlertSeverity) -> bool {
+        match severity {
+            AlertSeverity::Medium => self.config.medium,
+            AlertSeverity::High => self.config.high,
+            AlertSeverity::Critical => self.config.critical,
+        }
+    }
+
+    /// Notify about this cycle's `alerts`, if any are enabled. Delivery
+    /// failures are ignored: a missing notification daemon must not stall the
+    /// engine.
+    pub fn notify(&self, alerts: &[Alert]) {
+        if let Some((summary, body, severity)) = self.coalesce(alerts) {
+            show(summary, body, severity);
+        }
+    }
+
+    /// The one notification `alerts` raise: summary, body and the severity
+    /// its priority hints come from; `None` when no severity is enabled.
+    fn coalesce(&self, alerts: &[Alert]) -> Option<(String, String, AlertSeverity)> {
+        let shown: Vec<&Alert> = alerts.iter().filter(|a| self.enabled(&a.severity)).collect();
+        // Lead with the most severe alert; it also sets the priority hints
+        let top = shown.iter().max_by_key(|a| a.severity.clone() as u8)?;
+        let summary = if shown.len() == 1 {
+            format!("{:?} {}", top.severity, top.alert_type.label())
+        } else {
+            format!("{} fraud alerts", shown.len())
+        };
+        Some((summary, top.description().to_string(), top.severity.clone()))
+    }
+}
+
+/// Deliver on a blocking thread with urgency and sound hints mapped from
+/// `severity`.
+#[cfg(feature = "notify")]
+fn show(summary: String, body: String, severity: AlertSeverity) {
+    tokio::task::spawn_blocking(move || {
+        let mut n = notify_rust::Notification::new();
+        n.appname("laminardb-fraud-detect").summary(&summary).body(&body);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        n.urgency(match severity {
+            AlertSeverity::Critical => notify_rust::Urgency::Critical,
+            AlertSeverity::High => notify_rust::Urgency::Normal,
+            AlertSeverity::Medium => notify_rust::Urgency::Low,
+        });
+        if matches!(severity, AlertSeverity::Critical) {
+            n.sound_name("dialog-warning");
+        }
+        let _ = n.show();
+    });
+}
+
+// `Notifier::from_config` refuses to build one without the feature
+#[cfg(not(feature = "notify"))]
+fn show(_summary: String, _body: String, _severity: AlertSeverity) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertType};
+
+    fn alert(alert_type: AlertType, severity: AlertSeverity, detail: &str) -> Alert {
+        Alert {
+            id: 0,
+            alert_type,
+            severity,
+            symbol: Some("AAPL".into()),
+            account_id: None,
+            detail: AlertDetail::Text(detail.into()),
+            latency_us: 0,
+            timestamp_ms: 0,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    fn notifier(medium: bool, high: bool, critical: bool) -> Notifier {
+        Notifier { config: NotifyConfig { medium, high, critical } }
+    }
+
+    #[test]
+    fn no_enabled_severity_means_no_notifier() {
+        assert!(Notifier::from_config(&NotifyConfig::default()).unwrap().is_none());
+    }
+
+    #[cfg(not(feature = "notify"))]
+    #[test]
+    fn enabling_a_severity_needs_the_feature() {
+        let err = Notifier::from_config(&NotifyConfig { critical: true, ..Default::default() }).err().unwrap();
+        assert!(err.to_string().contains("--features notify"));
+    }
+
+    #[test]
+    fn one_alert_is_named_in_the_summary() {
+        let n = notifier(false, true, true);
+        let (summary, body, severity) = n.coalesce(&[alert(AlertType::WashTrading, AlertSeverity::High, "wash on AAPL")]).unwrap();
+        assert_eq!(summary, "High WashTrading");
+        assert_eq!(body, "wash on AAPL");
+        assert_eq!(severity, AlertSeverity::High);
+    }
+
+    #[test]
+    fn a_burst_is_one_notification_led_by_the_most_severe() {
+        let n = notifier(true, true, true);
+        let alerts = [
+            alert(AlertType::VolumeAnomaly, AlertSeverity::Medium, "volume"),
+            alert(AlertType::FrontRunning, AlertSeverity::Critical, "front-run"),
+            alert(AlertType::RapidFire, AlertSeverity::High, "burst"),
+        ];
+        let (summary, body, severity) = n.coalesce(&alerts).unwrap();
+        assert_eq!(summary, "3 fraud alerts");
+        assert_eq!(body, "front-run");
+        assert_eq!(severity, AlertSeverity::Critical);
+    }
+
+    #[test]
+    fn disabled_severities_are_left_out() {
+        let n = notifier(false, false, true);
+        assert!(n.coalesce(&[]).is_none());
+        assert!(n.coalesce(&[alert(AlertType::RapidFire, AlertSeverity::High, "burst")]).is_none());
+        let alerts = [alert(AlertType::RapidFire, AlertSeverity::High, "burst"), alert(AlertType::FrontRunning, AlertSeverity::Critical, "front-run")];
+        assert_eq!(n.coalesce(&alerts).unwrap().0, "Critical FrontRunning");
+    }
+}
@@ -0,0 +1,108 @@
+//! Cycle pacing shared by the `run` frontends: a fixed interval between
+//! engine cycles, or one adapted to hold a target event rate.
+
+use std::time::Duration;
+
+use crate::config::{Config, PacingConfig};
+use crate::engine::Engine;
+use crate::generator;
+
+/// Smoothing factor for the events-per-cycle average the adaptive interval follows.
+const EVENTS_EWMA_ALPHA: f64 = 0.2;
+
+/// Decides when the next engine cycle is due. Intervals run start to start,
+/// so a slow cycle eats into the pause after it rather than adding to it.
+/// With a target rate, the interval is sized so the events each cycle pushes
+/// add up to the target per second.
+pub struct Pacer {
+    pacing: PacingConfig,
+    interval: Duration,
+    events_ewma: f64,
+    last_start: Option<Duration>,
+    last_events: u64,
+}
+
+impl Pacer {
+    /// Check `[pacing]`, and that `[limits] cycle_budget_ms` leaves room for
+    /// the longest interval, or every pause would count as a stall.
+    pub fn from_config(config: &Config) -> Result<Self, String> {
+        let pacing = config.pacing.clone();
+        if pacing.cycle_interval_ms == 0 {
+            return Err("[pacing] cycle_interval_ms must be at least 1".into());
+        }
+        let mut longest = Duration::from_millis(pacing.cycle_interval_ms);
+        if let Some(target) = pacing.target_events_per_sec {
+            if target.is_nan() || target <= 0.0 {
+                return Err(format!("[pacing] target_events_per_sec must be positive, got {target}"));
+            }
+            if pacing.min_interval_ms == 0 || pacing.min_interval_ms > pacing.max_interval_ms {
+                return Err("[pacing] needs 0 < min_interval_ms <= max_interval_ms".into());
+            }
+            longest = Duration::from_millis(pacing.max_interval_ms);
+        }
+        if let Some(budget) = config.limits.cycle_budget().filter(|&b| b <= longest) {
+            return Err(format!(
+                "[limits] cycle_budget_ms ({}) must exceed the longest cycle interval ({}ms)",
+                budget.as_millis(),
+                longest.as_millis()
+            ));
+        }
+
+        // Start from what a cycle generates at normal rates, fraud aside
+        let (trades, orders) = generator::normal_rates(Duration::from_secs(1));
+        let mut pacer = Self {
+            interval: Duration::from_millis(pacing.cycle_interval_ms),
+            pacing,
+            events_ewma: trades + orders,
+            last_start: None,
+            last_events: 0,
+        };
+        pacer.interval = pacer.adapted();
+        Ok(pacer)
+    }
+
+    /// The current interval; with a target rate, the one it started from until
+    /// the first cycle is recorded.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Time from `now` (engine elapsed time) until the next cycle is due.
+    pub fn until_next(&self, now: Duration) -> Duration {
+        self.last_start.map_or(Duration::ZERO, |start| (start + self.interval).saturating_sub(now))
+    }
+
+    pub fn due(&self, now: Duration) -> bool {
+        self.until_next(now).is_zero()
+    }
+
+    /// Note a cycle of `engine` that started at `start`, adapting the interval
+    /// to the events it pushed.
+    pub fn record(&mut self, start: Duration, engine: &Engine) {
+        let total = engine.total_trades + engine.total_orders;
+        // `reset_stats` zeroes the totals between cycles
+        let events = total.checked_sub(self.last_events).unwrap_or(total);
+        self.last_events = total;
+        self.last_start = Some(start);
+        // Shed cycles push nothing; following them would speed up into the overload
+        if self.pacing.target_events_per_sec.is_some() && events > 0 {
+            self.events_ewma = EVENTS_EWMA_ALPHA * events as f64 + (1.0 - EVENTS_EWMA_ALPHA) * self.events_ewma;
+            self.interval = self.adapted();
+        }
+    }
+
+    /// Sleep on the engine's clock until the next cycle is due.
+    pub async fn wait(&self, engine: &Engine) {
+        engine.sleep(self.until_next(engine.elapsed())).await;
+    }
+
+    fn adapted(&self) -> Duration {
+        let Some(target) = self.pacing.target_events_per_sec else {
+            return self.interval;
+        };
+        // Clamp the seconds, not the Duration: a tiny target overflows one
+        let secs = (self.events_ewma / target)
+            .clamp(self.pacing.min_interval_ms as f64 / 1000.0, self.pacing.max_interval_ms as f64 / 1000.0);
+        Duration::from_secs_f64(secs)
+    }
+}
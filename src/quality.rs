@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertType};
+use crate::generator::{BenignLabel, FraudLabel, ALL_BENIGN_SCENARIOS};
+use crate::locale;
+use crate::reviews::ReviewedQuality;
+
+/// An alert up to this long (event time) after an injection starts counts as detecting it.
+pub const DETECTION_HORIZON_MS: i64 = 15_000;
+
+/// Scenario → the alert type whose job it is to catch it.
+const SCENARIO_ALERTS: &[(&str, &str)] = &[
+    ("VolumeSpike", "VolumeAnomaly"),
+    ("PriceManipulation", "PriceSpike"),
+    ("RapidFire", "RapidFire"),
+    ("WashTrading", "WashTrading"),
+    ("Spoofing", "BookImbalance"),
+    ("MomentumIgnition", "MomentumIgnition"),
+    ("InsiderTrading", "InsiderTrading"),
+    ("PriceImprovement", "PriceImprovement"),
+];
+
+/// Benign look-alike → the alert type it superficially resembles.
+const BENIGN_MIMICS: &[(&str, &str)] = &[
+    ("IndexRebalance", "VolumeAnomaly"),
+    ("VwapSlicing", "RapidFire"),
+    ("MarketMaking", "WashTrading"),
+];
+
+fn scenario_for(alert_type: &AlertType) -> Option<&'static str> {
+    SCENARIO_ALERTS
+        .iter()
+        .find(|(_, a)| *a == alert_type.label())
+        .map(|(s, _)| *s)
+}
+
+struct Injection {
+    label: FraudLabel,
+    detected_at: Option<i64>,
+}
+
+struct BenignInjection {
+    label: BenignLabel,
+    /// Alert types that fired on it.
+    fired: Vec<String>,
+}
+
+#[derive(Default)]
+struct ScenarioCounts {
+    false_positives: u64,
+    quiet_cycles: u64,
+}
+
+/// Scores alerts against the generator's ground-truth labels.
+///
+/// Call `start_cycle` once per generator cycle with that cycle's labels, then
+/// `observe_alert` for every alert raised in the cycle, and `report` at exit.
+/// Benign look-alikes passed to `observe_benign` are scored separately: any
+/// alert on one is a false positive of its rule.
+#[derive(Default)]
+pub struct QualityTracker {
+    injections: Vec<Injection>,
+    benign: Vec<BenignInjection>,
+    per_scenario: HashMap<&'static str, ScenarioCounts>,
+    cycle_ts: Option<i64>,
+    cycle_alerted: bool,
+    cycle_fp_scenarios: Vec<&'static str>,
+    clean_cycles: u64,
+    clean_cycles_with_alerts: u64,
+}
+
+impl QualityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_labels(&self) -> bool {
+        !self.injections.is_empty() || !self.benign.is_empty()
+    }
+
+    pub fn start_cycle(&mut self, ts: i64, labels: Vec<FraudLabel>) {
+        self.close_cycle();
+        self.injections.extend(labels.into_iter().map(|label| Injection { label, detected_at: None }));
+        self.cycle_ts = Some(ts);
+    }
+
+    /// Record this cycle's benign look-alikes; call after `start_cycle`.
+    pub fn observe_benign(&mut self, labels: Vec<BenignLabel>) {
+        self.benign.extend(labels.into_iter().map(|label| BenignInjection { label, fired: Vec::new() }));
+    }
+
+    fn active(&self, scenario: Option<&str>, ts: i64) -> bool {
+        self.injections.iter().any(|i| {
+            scenario.is_none_or(|s| i.label.scenario == s)
+                && i.label.start_ts <= ts
+                && ts <= i.label.start_ts + DETECTION_HORIZON_MS
+        })
+    }
+
+    fn close_cycle(&mut self) {
+        let Some(ts) = self.cycle_ts.take() else {
+            return;
+        };
+        if !self.active(None, ts) {
+            self.clean_cycles += 1;
+            if self.cycle_alerted {
+                self.clean_cycles_with_alerts += 1;
+            }
+        }
+        for (scenario, _) in SCENARIO_ALERTS {
+            if !self.active(Some(scenario), ts) && !self.cycle_fp_scenarios.contains(scenario) {
+                self.per_scenario.entry(*scenario).or_default().quiet_cycles += 1;
+            }
+        }
+        self.cycle_alerted = false;
+        self.cycle_fp_scenarios.clear();
+    }
+
+    pub fn observe_alert(&mut self, alert: &Alert) {
+        let Some(ts) = self.cycle_ts else {
+            return;
+        };
+        self.cycle_alerted = true;
+        let alert_type = alert.alert_type.label();
+        for b in self.benign.iter_mut().filter(|b| {
+            b.label.start_ts <= ts
+                && ts <= b.label.start_ts + DETECTION_HORIZON_MS
+                && match &alert.account_id {
+                    Some(a) => *a == b.label.account_id,
+                    None => alert.symbol.as_deref() == Some(b.label.symbol.as_str()),
+                }
+        }) {
+            if !b.fired.iter().any(|t| t == alert_type) {
+                b.fired.push(alert_type.to_string());
+            }
+        }
+        let Some(scenario) = scenario_for(&alert.alert_type) else {
+            return;
+        };
+
+        let matched = self.injections.iter_mut().find(|i| {
+            i.label.scenario == scenario
+                && i.label.start_ts <= ts
+                && ts <= i.label.start_ts + DETECTION_HORIZON_MS
+                && match (&alert.account_id, &i.label.account_id) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => alert.symbol.as_deref() == Some(i.label.symbol.as_str()),
+                }
+        });
+        match matched {
+            Some(i) => {
+                i.detected_at.get_or_insert(ts);
+            }
+            None => {
+                self.per_scenario.entry(scenario).or_default().false_positives += 1;
+                if !self.cycle_fp_scenarios.contains(&scenario) {
+                    self.cycle_fp_scenarios.push(scenario);
+                }
+            }
+        }
+    }
+
+    pub fn report(&mut self) -> QualityReport {
+        let last_ts = self.cycle_ts;
+        self.close_cycle();
+
+        let mut all_delays = Vec::new();
+        let mut scenarios = Vec::new();
+        for (scenario, alert_type) in SCENARIO_ALERTS {
+            let mut tp = 0;
+            let mut fn_ = 0;
+            let mut pending = 0;
+            let mut delays = Vec::new();
+            for i in self.injections.iter().filter(|i| i.label.scenario == *scenario) {
+                match i.detected_at {
+                    Some(at) => {
+                        tp += 1;
+                        delays.push(at - i.label.start_ts);
+                    }
+                    None if last_ts.is_some_and(|t| t < i.label.start_ts + DETECTION_HORIZON_MS) => pending += 1,
+                    None => fn_ += 1,
+                }
+            }
+            all_delays.extend_from_slice(&delays);
+            let counts = self.per_scenario.get(scenario);
+            let fp = counts.map_or(0, |c| c.false_positives);
+            scenarios.push(ScenarioQuality {
+                scenario: scenario.to_string(),
+                alert_type: alert_type.to_string(),
+                true_positives: tp,
+                false_negatives: fn_,
+                false_positives: fp,
+                true_negatives: counts.map_or(0, |c| c.quiet_cycles),
+                pending,
+                precision: ratio(tp, tp + fp),
+                recall: ratio(tp, tp + fn_),
+                median_delay_ms: median(&mut delays),
+            });
+        }
+
+        QualityReport {
+            injections: self.injections.len() as u64,
+            scenarios,
+            clean_cycles: self.clean_cycles,
+            clean_cycles_with_alerts: self.clean_cycles_with_alerts,
+            clean_fp_rate: ratio(self.clean_cycles_with_alerts, self.clean_cycles),
+            median_detection_delay_ms: median(&mut all_delays),
+            benign: self.benign_report(),
+            reviewed: Vec::new(),
+        }
+    }
+
+    fn benign_report(&self) -> Vec<BenignQuality> {
+        if self.benign.is_empty() {
+            return Vec::new();
+        }
+        ALL_BENIGN_SCENARIOS
+            .iter()
+            .map(|scenario| {
+                let scenario = scenario.label();
+                let mimics = BENIGN_MIMICS.iter().find(|(s, _)| *s == scenario).map_or("", |(_, a)| *a);
+                let injections: Vec<&BenignInjection> = self.benign.iter().filter(|b| b.label.scenario == scenario).collect();
+                let mut fired_by: BTreeMap<String, u64> = BTreeMap::new();
+                for t in injections.iter().flat_map(|b| &b.fired) {
+                    *fired_by.entry(t.clone()).or_default() += 1;
+                }
+                let n = injections.len() as u64;
+                BenignQuality {
+                    scenario: scenario.to_string(),
+                    mimics: mimics.to_string(),
+                    injections: n,
+                    alerted: injections.iter().filter(|b| !b.fired.is_empty()).count() as u64,
+                    mimic_fp_rate: ratio(fired_by.get(mimics).copied().unwrap_or(0), n),
+                    fired_by,
+                }
+            })
+            .collect()
+    }
+}
+
+fn ratio(num: u64, den: u64) -> Option<f64> {
+    (den > 0).then(|| num as f64 / den as f64)
+}
+
+fn median(values: &mut [i64]) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    Some(values[values.len() / 2])
+}
+
+/// Per-scenario confusion matrix. Units: TP/FN count injections, FP counts
+/// unattributed alerts, TN counts cycles with neither an injection nor an alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioQuality {
+    pub scenario: String,
+    pub alert_type: String,
+    pub true_positives: u64,
+    pub false_negatives: u64,
+    pub false_positives: u64,
+    pub true_negatives: u64,
+    /// Injections still inside the detection horizon when the run ended.
+    pub pending: u64,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+    pub median_delay_ms: Option<i64>,
+}
+
+/// How the rules fared on one kind of benign look-alike. Every alert on one
+/// is a false positive, so each rule's rate is the share of injections it
+/// fired on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenignQuality {
+    pub scenario: String,
+    /// The alert type the activity superficially resembles.
+    pub mimics: String,
+    pub injections: u64,
+    /// Injections that drew at least one alert of any type.
+    pub alerted: u64,
+    /// Alert type → injections it fired on.
+    pub fired_by: BTreeMap<String, u64>,
+    /// Share of injections the mimicked rule fired on.
+    pub mimic_fp_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub injections: u64,
+    pub scenarios: Vec<ScenarioQuality>,
+    /// Cycles with no injection of any scenario within the detection horizon.
+    pub clean_cycles: u64,
+    pub clean_cycles_with_alerts: u64,
+    pub clean_fp_rate: Option<f64>,
+    pub median_detection_delay_ms: Option<i64>,
+    /// Benign look-alikes by scenario; empty when none were injected and
+    /// absent from older reports.
+    #[serde(default)]
+    pub benign: Vec<BenignQuality>,
+    /// Operator dispositions given during the run, by alert type; absent
+    /// from older reports.
+    #[serde(default)]
+    pub reviewed: Vec<ReviewedQuality>,
+}
+
+impl QualityReport {
+    pub fn print(&self) {
+        let pct = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.0}%", v * 100.0));
+        let ms = |v: Option<i64>| v.map_or("-".to_string(), |v| format!("{v}ms"));
+
+        println!("  Detection quality ({} injections):", locale::count(self.injections));
+        println!(
+            "    {:<18} {:>4} {:>4} {:>4} {:>6} {:>5} {:>9} {:>7} {:>9}",
+            "Scenario", "TP", "FN", "FP", "TN", "Pend", "Precision", "Recall", "Delay p50"
+        );
+        for s in &self.scenarios {
+            println!(
+                "    {:<18} {:>4} {:>4} {:>4} {:>6} {:>5} {:>9} {:>7} {:>9}",
+                s.scenario, s.true_positives, s.false_negatives, s.false_positives, s.true_negatives,
+                s.pending, pct(s.precision), pct(s.recall), ms(s.median_delay_ms)
+            );
+        }
+        println!(
+            "    Clean-period FP rate: {} ({} of {} clean cycles alerted)",
+            pct(self.clean_fp_rate), locale::count(self.clean_cycles_with_alerts), locale::count(self.clean_cycles)
+        );
+        println!("    Median detection delay: {}", ms(self.median_detection_delay_ms));
+        if !self.benign.is_empty() {
+            println!("    Benign look-alikes:");
+            println!("      {:<16} {:<16} {:>4} {:>7} {:>8}  Fired by", "Scenario", "Mimics", "Runs", "Alerted", "Mimic FP");
+            for b in &self.benign {
+                let fired: Vec<String> = b.fired_by.iter().map(|(t, n)| format!("{t} {n}")).collect();
+                println!(
+                    "      {:<16} {:<16} {:>4} {:>7} {:>8}  {}",
+                    b.scenario, b.mimics, b.injections, b.alerted, pct(b.mimic_fp_rate),
+                    if fired.is_empty() { "-".to_string() } else { fired.join(", ") }
+                );
+            }
+        }
+        if !self.reviewed.is_empty() {
+            println!("    Operator review:");
+            println!("      {:<18} {:>4} {:>4} {:>6} {:>9}", "Alert type", "TP", "FP", "Benign", "Precision");
+            for r in &self.reviewed {
+                println!(
+                    "      {:<18} {:>4} {:>4} {:>6} {:>9}",
+                    r.alert_type, r.true_positives, r.false_positives, r.benign, pct(r.precision)
+                );
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read report {}: {e}", path.display()))?;
+        Ok(serde_json::from_str(&text)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity};
+
+    fn alert(alert_type: AlertType, account_id: Option<&str>, symbol: &str) -> Alert {
+        Alert {
+            id: 0,
+            alert_type,
+            severity: AlertSeverity::High,
+            symbol: Some(symbol.into()),
+            account_id: account_id.map(Into::into),
+            detail: AlertDetail::Text(String::new()),
+            latency_us: 0,
+            timestamp_ms: 0,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    fn label(scenario: &'static str, account_id: &str, start_ts: i64) -> FraudLabel {
+        FraudLabel { scenario, account_id: Some(account_id.into()), symbol: "AAPL".into(), start_ts, end_ts: start_ts + 1_000 }
+    }
+
+    fn scenario<'a>(report: &'a QualityReport, name: &str) -> &'a ScenarioQuality {
+        report.scenarios.iter().find(|s| s.scenario == name).unwrap()
+    }
+
+    #[test]
+    fn confusion_matrix_and_clean_period_fp_rate() {
+        let mut q = QualityTracker::new();
+        q.start_cycle(0, Vec::new());
+        q.start_cycle(1_000, vec![label("RapidFire", "R1", 1_000)]);
+        q.observe_alert(&alert(AlertType::RapidFire, Some("R1"), "AAPL"));
+        q.start_cycle(2_000, Vec::new());
+        q.observe_alert(&alert(AlertType::RapidFire, Some("R9"), "AAPL"));
+        q.start_cycle(20_000, Vec::new());
+        q.observe_alert(&alert(AlertType::VolumeAnomaly, None, "MSFT"));
+        let report = q.report();
+
+        let rf = scenario(&report, "RapidFire");
+        assert_eq!((rf.true_positives, rf.false_negatives, rf.false_positives, rf.true_negatives), (1, 0, 1, 2));
+        assert_eq!((rf.precision, rf.recall, rf.median_delay_ms), (Some(0.5), Some(1.0), Some(0)));
+        let vs = scenario(&report, "VolumeSpike");
+        assert_eq!((vs.false_positives, vs.true_negatives, vs.precision), (1, 3, Some(0.0)), "the cycle of its FP isn't a true negative");
+        assert_eq!((report.clean_cycles, report.clean_cycles_with_alerts, report.clean_fp_rate), (2, 1, Some(0.5)));
+        assert_eq!((report.injections, report.median_detection_delay_ms), (1, Some(0)));
+    }
+
+    #[test]
+    fn injections_inside_the_horizon_at_the_end_are_pending() {
+        let mut q = QualityTracker::new();
+        q.start_cycle(0, vec![label("WashTrading", "W1", 0)]);
+        q.start_cycle(10_000, vec![label("InsiderTrading", "I1", 10_000)]);
+        q.start_cycle(16_000, Vec::new());
+        let report = q.report();
+
+        let wash = scenario(&report, "WashTrading");
+        assert_eq!((wash.false_negatives, wash.pending, wash.recall), (1, 0, Some(0.0)), "15s passed undetected");
+        let insider = scenario(&report, "InsiderTrading");
+        assert_eq!((insider.false_negatives, insider.pending, insider.recall), (0, 1, None));
+        assert_eq!(report.median_detection_delay_ms, None);
+        assert!(report.benign.is_empty());
+        assert_eq!(QualityTracker::new().report().clean_fp_rate, None, "no cycles, no rate");
+    }
+
+    #[test]
+    fn alerts_on_benign_look_alikes_are_charged_to_the_mimicked_rule() {
+        let mut q = QualityTracker::new();
+        q.start_cycle(0, Vec::new());
+        q.observe_benign(vec![BenignLabel { scenario: "MarketMaking", account_id: "M1".into(), symbol: "AAPL".into(), start_ts: 0, end_ts: 1_000 }]);
+        q.observe_alert(&alert(AlertType::WashTrading, Some("M1"), "AAPL"));
+        q.observe_alert(&alert(AlertType::WashTrading, Some("M1"), "AAPL"));
+        assert!(q.has_labels());
+        let report = q.report();
+
+        let mm = report.benign.iter().find(|b| b.scenario == "MarketMaking").unwrap();
+        assert_eq!((mm.mimics.as_str(), mm.injections, mm.alerted, mm.mimic_fp_rate), ("WashTrading", 1, 1, Some(1.0)));
+        assert_eq!(mm.fired_by.get("WashTrading"), Some(&1), "one injection, however many alerts");
+        let rebalance = report.benign.iter().find(|b| b.scenario == "IndexRebalance").unwrap();
+        assert_eq!((rebalance.injections, rebalance.mimic_fp_rate), (0, None));
+    }
+}
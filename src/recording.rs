@@ -0,0 +1,225 @@
+//! Row recording and playback. With `[recording] path` set (or `run
+//! --record-rows`), the engine appends each cycle's polled stream rows to a
+//! JSON-lines file, with when the cycle ran. `replay` runs them back through
+//! the rules at the recorded pace and feeds the TUI or web dashboard from
+//! them, no LaminarDB needed: a fixed feed for UI work and UI tests, since
+//! the same recording plays back to the same alerts.
+//!
+//! Only the streams' rows are recorded. Alerts the engine raises at push
+//! (PriceVelocity, FeedIntegrity) or from its heartbeat (EngineStall) don't
+//! come from rows, so a playback doesn't have them.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::AlertEngine;
+use crate::config::{Config, RecordingConfig};
+use crate::engine::{Heartbeat, STREAM_NAMES};
+use crate::evaluation::{Evaluator, RowBatch};
+use crate::latency::LatencyTracker;
+use crate::quality::QualityTracker;
+use crate::slo::SloTracker;
+use crate::web::{DashboardUpdate, LatencyUpdate, StreamStatus};
+
+/// One engine cycle as recorded: a line of the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCycle {
+    /// Wall time since the recording started.
+    pub at_ms: u64,
+    pub cycle_ts: i64,
+    /// Pushed since start, as of the cycle.
+    pub total_trades: u64,
+    pub total_orders: u64,
+    pub prices: HashMap<String, f64>,
+    /// Every non-empty batch the cycle polled, in poll order.
+    pub batches: Vec<RowBatch>,
+}
+
+/// The engine's side: collects a cycle's batches as they are polled and
+/// writes them out when it ends.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    started: Instant,
+    batches: Vec<RowBatch>,
+    /// Cycles written so far.
+    pub cycles: u64,
+}
+
+impl Recorder {
+    /// Start the recording `config` names, if any.
+    pub fn open(config: &RecordingConfig) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        config.path.as_deref().map(Self::create).transpose()
+    }
+
+    /// Start a recording at `path`, replacing any file there.
+    pub fn create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let file = File::create(path).map_err(|e| format!("failed to create row recording {}: {e}", path.display()))?;
+        Ok(Self { writer: BufWriter::new(file), started: Instant::now(), batches: Vec::new(), cycles: 0 })
+    }
+
+    pub fn observe(&mut self, batch: &RowBatch) {
+        self.batches.push(batch.clone());
+    }
+
+    /// Write the cycle's batches out, flushed so a killed run keeps every
+    /// finished cycle, and start on the next.
+    pub fn finish_cycle(&mut self, cycle_ts: i64, total_trades: u64, total_orders: u64, prices: &HashMap<String, f64>) -> Result<(), Box<dyn std::error::Error>> {
+        let cycle = RecordedCycle {
+            at_ms: self.started.elapsed().as_millis() as u64,
+            cycle_ts,
+            total_trades,
+            total_orders,
+            prices: prices.clone(),
+            batches: std::mem::take(&mut self.batches),
+        };
+        serde_json::to_writer(&mut self.writer, &cycle)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        self.cycles += 1;
+        Ok(())
+    }
+}
+
+/// Read every cycle of the recording at `path`.
+pub fn load(path: &Path) -> Result<Vec<RecordedCycle>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read row recording {}: {e}", path.display()))?;
+    parse(&text).map_err(|e| format!("invalid row recording {} {e}", path.display()).into())
+}
+
+/// Every cycle in a recording's text, one JSON line each; blank lines are
+/// skipped. The error names the first line that isn't a cycle.
+pub fn parse(text: &str) -> Result<Vec<RecordedCycle>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {e}", i + 1)))
+        .collect()
+}
+
+/// The playback side: a rule set the recorded rows are evaluated by, and
+/// the running totals a dashboard shows.
+pub struct Player {
+    pub alert_engine: AlertEngine,
+    quality: QualityTracker,
+    latency: LatencyTracker,
+    slo: SloTracker,
+    stream_counts: [u64; STREAM_NAMES.len()],
+    cycles: u64,
+}
+
+impl Player {
+    /// Rules as `config` sets them; they may differ from the recording run's,
+    /// to see what other thresholds would have shown.
+    pub fn from_config(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            alert_engine: AlertEngine::from_config(config)?,
+            quality: QualityTracker::new(),
+            latency: LatencyTracker::new(),
+            slo: SloTracker::from_config(&config.slo)?,
+            stream_counts: [0; STREAM_NAMES.len()],
+            cycles: 0,
+        })
+    }
+
+    /// Evaluate `cycle`'s rows and return the dashboard update the cycle
+    /// would have published.
+    pub fn play(&mut self, cycle: RecordedCycle) -> DashboardUpdate {
+        let RecordedCycle { at_ms, cycle_ts, total_trades, total_orders, prices, batches } = cycle;
+        let start = Instant::now();
+        self.alert_engine.advance_to(cycle_ts);
+        let mut rows_polled = 0;
+        let mut evaluator = Evaluator::new(&mut self.alert_engine, &mut self.quality, None, start);
+        for batch in batches {
+            self.stream_counts[batch.stream()] += batch.len() as u64;
+            rows_polled += batch.len() as u64;
+            evaluator.evaluate(batch);
+        }
+        let alerts = evaluator.finish();
+        for alert in &alerts {
+            self.latency.record_alert_us(alert.latency_us);
+        }
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        self.slo.observe(&alerts, now_ms);
+        self.cycles += 1;
+
+        let streams = STREAM_NAMES
+            .iter()
+            .zip(self.stream_counts)
+            .map(|(name, count)| StreamStatus { name: name.to_string(), count, active: count > 0, budget_exhausted: 0, carried_rows: 0 })
+            .collect();
+        let engine = &mut self.alert_engine;
+        DashboardUpdate {
+            alerts,
+            latency: LatencyUpdate { alert: self.latency.alert_stats(), ..Default::default() },
+            streams,
+            alert_counts: engine.alert_counts().clone(),
+            total_trades,
+            total_orders,
+            total_alerts: engine.total_alerts(),
+            uptime_secs: at_ms / 1000,
+            prices,
+            alerts_generated: engine.total_alerts() + engine.suppressed_alerts(),
+            alerts_delivered: engine.total_alerts(),
+            suppressed_alerts: engine.suppressed_alerts(),
+            model_suppressed: engine.model_suppressed(),
+            cooldown_suppressed: engine.cooldown_suppressed(),
+            shadow_alerts: engine.take_shadow_alerts(),
+            shadow_counts: engine.shadow_alert_counts().clone(),
+            heartbeat: Heartbeat { seq: self.cycles, cycle_us: start.elapsed().as_micros() as u64, gap_ms: 0, rows_polled },
+            cooldowns: engine.active_cooldowns(cycle_ts),
+            symbol_alerts: engine.symbol_breakdown(),
+            retention: engine.retention(),
+            accounts: engine.account_risk().to_vec(),
+            slo: self.slo.status(now_ms),
+            ..Default::default()
+        }
+    }
+}
+
+/// The recorded pace, `speed` times as fast: when each cycle is due.
+pub struct Pace {
+    start: Instant,
+    speed: f64,
+}
+
+impl Pace {
+    pub fn new(speed: f64) -> Self {
+        Self { start: Instant::now(), speed }
+    }
+
+    /// How long until a cycle recorded `at_ms` into the run is due; zero
+    /// once it is.
+    pub fn until(&self, at_ms: u64) -> Duration {
+        (self.start + Duration::from_secs_f64(at_ms as f64 / 1000.0 / self.speed)).saturating_duration_since(Instant::now())
+    }
+
+    /// Push every later cycle back by `paused`, so playback picks up where
+    /// it stopped instead of racing to catch up.
+    pub fn hold(&mut self, paused: Duration) {
+        self.start += paused;
+    }
+}
+
+/// Play `cycles` through `player` into `out` at the recorded pace, `speed`
+/// times as fast, stopping early if `out` returns false. Returns the
+/// cycles played.
+pub async fn play(cycles: Vec<RecordedCycle>, player: &mut Player, speed: f64, mut out: impl FnMut(DashboardUpdate) -> bool) -> u64 {
+    let pace = Pace::new(speed);
+    let mut played = 0;
+    for cycle in cycles {
+        let wait = pace.until(cycle.at_ms);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        played += 1;
+        if !out(player.play(cycle)) {
+            break;
+        }
+    }
+    played
+}
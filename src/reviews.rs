@@ -0,0 +1,192 @@
+//! Operator review of alerts: free-text notes and a disposition per alert,
+//! set from the TUI or `/api/alerts/{id}/review`. Dispositions given during
+//! a run are folded into its quality report as operator-confirmed precision,
+//! next to the precision scored against the generator's labels.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::Alert;
+use crate::config::ReviewConfig;
+
+/// An operator's verdict on an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Disposition {
+    /// The alert caught abuse.
+    TruePositive,
+    /// Nothing happened that the rule meant to catch.
+    FalsePositive,
+    /// The rule fired as designed on activity that turned out to be legitimate.
+    Benign,
+}
+
+impl Disposition {
+    /// Short tag for the TUI feed.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Disposition::TruePositive => "TP",
+            Disposition::FalsePositive => "FP",
+            Disposition::Benign => "BN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertNote {
+    pub at_ms: i64,
+    pub text: String,
+}
+
+/// Everything operators recorded against one alert.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertReview {
+    pub alert_id: u64,
+    pub alert_type: String,
+    /// The alert's rule measurement, which `tune` sets thresholds against;
+    /// absent for engine-raised alerts and older reviews.
+    #[serde(default)]
+    pub metric: Option<f64>,
+    /// The latest disposition; a later one replaces it.
+    pub disposition: Option<Disposition>,
+    /// Oldest first.
+    pub notes: Vec<AlertNote>,
+    pub updated_at_ms: i64,
+}
+
+/// Body of `POST /api/alerts/{id}/review`; at least one of the two.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReviewUpdate {
+    pub disposition: Option<Disposition>,
+    /// Appended to the alert's notes.
+    pub note: Option<String>,
+}
+
+impl ReviewUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.disposition.is_none() && self.note.as_deref().is_none_or(|n| n.trim().is_empty())
+    }
+}
+
+/// Dispositions of one alert type's alerts, for the quality report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewedQuality {
+    pub alert_type: String,
+    pub true_positives: u64,
+    pub false_positives: u64,
+    pub benign: u64,
+    /// True positives over true and false positives; benign alerts did what
+    /// the rule asks and count for neither.
+    pub precision: Option<f64>,
+}
+
+#[derive(Default)]
+struct Inner {
+    path: Option<PathBuf>,
+    reviews: BTreeMap<u64, AlertReview>,
+    /// Alerts reviewed since open; only these count toward this run's quality.
+    this_run: BTreeSet<u64>,
+}
+
+/// Alert reviews, loaded from and written back to `[reviews] path` when set.
+/// Clones share the same state, so the TUI, the web API and the engine's
+/// quality report all see every review.
+#[derive(Clone, Default)]
+pub struct AlertReviews {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl AlertReviews {
+    /// Open the reviews file `config` names, if any; in memory only otherwise.
+    pub fn open(config: &ReviewConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut inner = Inner { path: config.path.clone(), ..Default::default() };
+        if let Some(path) = config.path.as_deref().filter(|p| p.exists()) {
+            inner.reviews = load(path)?.into_iter().map(|r| (r.alert_id, r)).collect();
+        }
+        Ok(Self { inner: Arc::new(Mutex::new(inner)) })
+    }
+
+    pub fn get(&self, alert_id: u64) -> Option<AlertReview> {
+        self.inner.lock().unwrap().reviews.get(&alert_id).cloned()
+    }
+
+    /// Every review, by alert ID.
+    pub fn all(&self) -> Vec<AlertReview> {
+        self.inner.lock().unwrap().reviews.values().cloned().collect()
+    }
+
+    /// Apply `update` to `alert`'s review and persist. The review is kept in
+    /// memory even when writing the file fails.
+    pub fn review(&self, alert: &Alert, update: ReviewUpdate) -> Result<AlertReview, Box<dyn std::error::Error>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let mut inner = self.inner.lock().unwrap();
+        let review = inner.reviews.entry(alert.id).or_insert_with(|| AlertReview {
+            alert_id: alert.id,
+            alert_type: alert.alert_type.label().to_string(),
+            metric: alert.metric,
+            disposition: None,
+            notes: Vec::new(),
+            updated_at_ms: now_ms,
+        });
+        if let Some(disposition) = update.disposition {
+            review.disposition = Some(disposition);
+        }
+        if let Some(text) = update.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) {
+            review.notes.push(AlertNote { at_ms: now_ms, text });
+        }
+        review.updated_at_ms = now_ms;
+        let review = review.clone();
+        inner.this_run.insert(alert.id);
+        inner.write()?;
+        Ok(review)
+    }
+
+    /// This run's dispositions by alert type.
+    pub fn quality(&self) -> Vec<ReviewedQuality> {
+        let inner = self.inner.lock().unwrap();
+        let mut by_type: BTreeMap<&str, ReviewedQuality> = BTreeMap::new();
+        for review in inner.this_run.iter().filter_map(|id| inner.reviews.get(id)) {
+            let Some(disposition) = review.disposition else { continue };
+            let q = by_type
+                .entry(&review.alert_type)
+                .or_insert_with(|| ReviewedQuality { alert_type: review.alert_type.clone(), ..Default::default() });
+            match disposition {
+                Disposition::TruePositive => q.true_positives += 1,
+                Disposition::FalsePositive => q.false_positives += 1,
+                Disposition::Benign => q.benign += 1,
+            }
+        }
+        by_type
+            .into_values()
+            .map(|mut q| {
+                let judged = q.true_positives + q.false_positives;
+                q.precision = (judged > 0).then(|| q.true_positives as f64 / judged as f64);
+                q
+            })
+            .collect()
+    }
+}
+
+impl Inner {
+    /// Replace the file in one rename, so a crash mid-write keeps the old reviews.
+    fn write(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let tmp = path.with_extension("tmp");
+        let reviews: Vec<&AlertReview> = self.reviews.values().collect();
+        std::fs::write(&tmp, serde_json::to_string_pretty(&reviews)?)
+            .map_err(|e| format!("failed to write alert reviews {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Read a reviews file without opening it for a run.
+pub fn load(path: &Path) -> Result<Vec<AlertReview>, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("failed to read alert reviews {}: {e}", path.display()))?;
+    Ok(serde_json::from_str(&text).map_err(|e| format!("invalid alert reviews {}: {e}", path.display()))?)
+}
@@ -0,0 +1,271 @@
+//! Account risk across shards. Each `run --shard` instance reports, every
+//! cycle, the rapid-fire bursts and wash volume its rules saw per account,
+//! with the account's anomaly score and alert count. The coordinator adds
+//! those up per account, so an account whose activity is split over shards
+//! is judged on all of it rather than on each slice.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::CoordinatorConfig;
+use crate::ml::MlScore;
+use crate::types::{RapidFireBurst, WashScore};
+
+/// One account's activity as one instance saw it, or the coordinator's sum
+/// over every instance.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountRisk {
+    pub account_id: String,
+    /// Largest rapid-fire burst.
+    pub burst_trades: i64,
+    pub burst_volume: i64,
+    pub burst_notional: f64,
+    /// Wash-score volume, summed over the account's symbols.
+    pub buy_volume: i64,
+    pub sell_volume: i64,
+    pub buy_count: i64,
+    pub sell_count: i64,
+    pub wash_notional: f64,
+    /// Symbols the wash volume came from.
+    pub symbols: Vec<String>,
+    /// Anomaly-model score, the highest across instances.
+    pub score: Option<f64>,
+    /// Delivered alerts naming the account since start.
+    pub alerts: u64,
+    /// Instances reporting the account; 1 on an instance itself.
+    #[serde(default)]
+    pub instances: u64,
+}
+
+impl AccountRisk {
+    fn has_burst(&self) -> bool {
+        self.burst_trades > 0
+    }
+
+    fn has_wash(&self) -> bool {
+        self.buy_count + self.sell_count > 0
+    }
+
+    /// The burst as a `rapid_fire` row, for the rapid-fire rule.
+    pub fn burst_row(&self) -> RapidFireBurst {
+        RapidFireBurst {
+            account_id: self.account_id.clone(),
+            burst_trades: self.burst_trades,
+            burst_volume: self.burst_volume,
+            low: 0.0,
+            high: 0.0,
+            burst_notional: self.burst_notional,
+        }
+    }
+
+    /// The wash volume as one `wash_score` row whose symbol lists every
+    /// symbol it came from, e.g. `AAPL+GOOGL`, for the wash rule. It sums
+    /// windows from several instances, so it carries no window start (0).
+    pub fn wash_row(&self) -> WashScore {
+        WashScore {
+            account_id: self.account_id.clone(),
+            symbol: self.symbols.join("+"),
+            window_start: 0,
+            buy_volume: self.buy_volume,
+            sell_volume: self.sell_volume,
+            buy_count: self.buy_count,
+            sell_count: self.sell_count,
+            buy_notional: self.wash_notional / 2.0,
+            sell_notional: self.wash_notional / 2.0,
+        }
+    }
+}
+
+/// An instance's side: what the rules saw this cycle, per account.
+#[derive(Default)]
+pub struct RiskTally {
+    bursts: HashMap<String, RapidFireBurst>,
+    washes: BTreeMap<(String, String), WashScore>,
+    alerts: HashMap<String, u64>,
+    last: Vec<AccountRisk>,
+}
+
+impl RiskTally {
+    /// Keep an account's largest burst of the cycle.
+    pub fn observe_burst(&mut self, row: &RapidFireBurst) {
+        let keep = self.bursts.get(&row.account_id).is_some_and(|b| b.burst_trades >= row.burst_trades);
+        if !keep {
+            self.bursts.insert(row.account_id.clone(), row.clone());
+        }
+    }
+
+    /// Keep the latest wash row per account and symbol.
+    pub fn observe_wash(&mut self, row: &WashScore) {
+        self.washes.insert((row.account_id.clone(), row.symbol.clone()), row.clone());
+    }
+
+    pub fn observe_alert(&mut self, account_id: &str) {
+        *self.alerts.entry(account_id.to_string()).or_insert(0) += 1;
+    }
+
+    /// Close the cycle: every account with a burst, wash row or score in it,
+    /// kept for [`RiskTally::last`] until the next.
+    pub fn finish(&mut self, scores: &[MlScore]) {
+        let mut accounts: BTreeMap<String, AccountRisk> = BTreeMap::new();
+        for (account_id, b) in self.bursts.drain() {
+            let a = account(&mut accounts, &self.alerts, &account_id);
+            a.burst_trades = b.burst_trades;
+            a.burst_volume = b.burst_volume;
+            a.burst_notional = b.burst_notional;
+        }
+        for ((account_id, symbol), w) in std::mem::take(&mut self.washes) {
+            let a = account(&mut accounts, &self.alerts, &account_id);
+            a.buy_volume += w.buy_volume;
+            a.sell_volume += w.sell_volume;
+            a.buy_count += w.buy_count;
+            a.sell_count += w.sell_count;
+            a.wash_notional += w.buy_notional + w.sell_notional;
+            a.symbols.push(symbol);
+        }
+        for s in scores {
+            account(&mut accounts, &self.alerts, &s.account_id).score = Some(s.score);
+        }
+        self.last = accounts.into_values().collect();
+    }
+
+    /// The accounts the last finished cycle touched.
+    pub fn last(&self) -> &[AccountRisk] {
+        &self.last
+    }
+}
+
+fn account<'a>(accounts: &'a mut BTreeMap<String, AccountRisk>, alerts: &HashMap<String, u64>, account_id: &str) -> &'a mut AccountRisk {
+    accounts.entry(account_id.to_string()).or_insert_with(|| AccountRisk {
+        account_id: account_id.to_string(),
+        alerts: alerts.get(account_id).copied().unwrap_or(0),
+        instances: 1,
+        ..Default::default()
+    })
+}
+
+/// One instance's latest report for an account, with when its burst and
+/// wash volume last arrived.
+struct Report {
+    risk: AccountRisk,
+    burst_ms: i64,
+    wash_ms: i64,
+    seen_ms: i64,
+}
+
+/// The coordinator's side: each instance's latest report per account, each
+/// part kept for `[coordinator] account_window_secs` after it arrived.
+pub struct RiskMerger {
+    window_ms: i64,
+    reports: HashMap<String, BTreeMap<usize, Report>>,
+}
+
+impl RiskMerger {
+    /// Instances never report at the same instant, so a zero window would
+    /// never add anything up.
+    pub fn from_config(config: &CoordinatorConfig) -> Result<Self, String> {
+        if config.account_window_secs == 0 {
+            return Err("[coordinator] account_window_secs must be at least 1".into());
+        }
+        Ok(Self { window_ms: config.account_window_secs as i64 * 1000, reports: HashMap::new() })
+    }
+
+    pub fn window_ms(&self) -> i64 {
+        self.window_ms
+    }
+
+    /// Take instance `i`'s reports at `now_ms`. Returns the summed burst and
+    /// wash rows of every account that reported one here and that more than
+    /// one instance has reported one for within the window, for the rules to
+    /// judge as a whole.
+    pub fn observe(&mut self, i: usize, reports: &[AccountRisk], now_ms: i64) -> (Vec<RapidFireBurst>, Vec<WashScore>) {
+        let window_ms = self.window_ms;
+        let fresh = |at: i64| now_ms.saturating_sub(at) <= window_ms;
+        let (mut bursts, mut washes) = (Vec::new(), Vec::new());
+        for r in reports {
+            let by_instance = self.reports.entry(r.account_id.clone()).or_default();
+            let report = by_instance.entry(i).or_insert_with(|| Report {
+                risk: AccountRisk { account_id: r.account_id.clone(), ..Default::default() },
+                burst_ms: i64::MIN,
+                wash_ms: i64::MIN,
+                seen_ms: now_ms,
+            });
+            report.seen_ms = now_ms;
+            let held = &mut report.risk;
+            if r.has_burst() {
+                (held.burst_trades, held.burst_volume, held.burst_notional) = (r.burst_trades, r.burst_volume, r.burst_notional);
+                report.burst_ms = now_ms;
+            }
+            if r.has_wash() {
+                (held.buy_volume, held.sell_volume, held.buy_count, held.sell_count) = (r.buy_volume, r.sell_volume, r.buy_count, r.sell_count);
+                held.wash_notional = r.wash_notional;
+                held.symbols = r.symbols.clone();
+                report.wash_ms = now_ms;
+            }
+            held.score = r.score.or(held.score);
+            held.alerts = held.alerts.max(r.alerts);
+
+            let sum = self.sum(&r.account_id, now_ms);
+            let parts = &self.reports[&r.account_id];
+            if r.has_burst() && parts.values().filter(|p| fresh(p.burst_ms)).count() > 1 {
+                bursts.push(sum.burst_row());
+            }
+            if r.has_wash() && parts.values().filter(|p| fresh(p.wash_ms)).count() > 1 {
+                washes.push(sum.wash_row());
+            }
+        }
+        self.reports.retain(|_, by_instance| {
+            by_instance.retain(|_, p| fresh(p.seen_ms));
+            !by_instance.is_empty()
+        });
+        (bursts, washes)
+    }
+
+    /// Every account reported within the window, summed over instances:
+    /// most alerts first, then highest score.
+    pub fn accounts(&self, now_ms: i64) -> Vec<AccountRisk> {
+        let mut accounts: Vec<AccountRisk> = self.reports.keys().map(|a| self.sum(a, now_ms)).filter(|a| a.instances > 0).collect();
+        accounts.sort_by(|a, b| {
+            b.alerts
+                .cmp(&a.alerts)
+                .then(b.score.unwrap_or(0.0).total_cmp(&a.score.unwrap_or(0.0)))
+                .then(a.account_id.cmp(&b.account_id))
+        });
+        accounts
+    }
+
+    /// `account_id`'s parts still within the window, added up: bursts and
+    /// wash volume summed, the highest score, alerts summed.
+    fn sum(&self, account_id: &str, now_ms: i64) -> AccountRisk {
+        let mut sum = AccountRisk { account_id: account_id.to_string(), ..Default::default() };
+        let fresh = |at: i64| now_ms.saturating_sub(at) <= self.window_ms;
+        for p in self.reports.get(account_id).into_iter().flat_map(|r| r.values()) {
+            if !fresh(p.seen_ms) {
+                continue;
+            }
+            let r = &p.risk;
+            sum.instances += 1;
+            sum.alerts += r.alerts;
+            sum.score = match (sum.score, r.score) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+            if fresh(p.burst_ms) {
+                sum.burst_trades += r.burst_trades;
+                sum.burst_volume += r.burst_volume;
+                sum.burst_notional += r.burst_notional;
+            }
+            if fresh(p.wash_ms) {
+                sum.buy_volume += r.buy_volume;
+                sum.sell_volume += r.sell_volume;
+                sum.buy_count += r.buy_count;
+                sum.sell_count += r.sell_count;
+                sum.wash_notional += r.wash_notional;
+                sum.symbols.extend(r.symbols.iter().cloned());
+            }
+        }
+        sum.symbols.sort();
+        sum.symbols.dedup();
+        sum
+    }
+}
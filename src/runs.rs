@@ -0,0 +1,476 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::engine::{DrainStats, Engine, STREAM_NAMES};
+use crate::latency::LatencyStats;
+use crate::locale;
+use crate::quality::{QualityReport, ScenarioQuality};
+use crate::reviews::AlertReviews;
+use crate::slo::SloStatus;
+
+/// Everything kept about one finished `run`: what it was asked to do, what it
+/// pushed, how fast, and how well it detected. Stored as `<dir>/<id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub started_at_ms: i64,
+    /// Frontend that drove the engine: `tui`, `web` or `none`.
+    pub ui: String,
+    pub fraud_rate: f64,
+    pub config: Config,
+    /// Seconds the throughput figures cover: since start or the last stats reset.
+    pub elapsed_secs: f64,
+    /// Seconds from start to shutdown; absent from older records.
+    #[serde(default)]
+    pub uptime_secs: f64,
+    pub total_trades: u64,
+    pub total_orders: u64,
+    pub total_alerts: u64,
+    pub suppressed_alerts: u64,
+    pub alert_counts: HashMap<String, u64>,
+    #[serde(default)]
+    pub severity_counts: HashMap<String, u64>,
+    /// Rows each detection stream emitted over `elapsed_secs`.
+    #[serde(default)]
+    pub stream_counts: BTreeMap<String, u64>,
+    /// Would-have-fired counts from shadow-mode rules; absent from older records.
+    #[serde(default)]
+    pub shadow_counts: HashMap<String, u64>,
+    pub push: LatencyStats,
+    pub processing: LatencyStats,
+    pub alert: LatencyStats,
+    /// Event-time lag from a price move to detection on the tick path and the
+    /// `ohlc_vol` bar path; absent from older records.
+    #[serde(default)]
+    pub tick_lag: LatencyStats,
+    #[serde(default)]
+    pub bar_lag: LatencyStats,
+    /// What the end-of-run drain recovered; absent when it was skipped and
+    /// from older records.
+    #[serde(default)]
+    pub drain: Option<DrainStats>,
+    /// Alert latency against each `[slo]` objective over the run; absent
+    /// from older records.
+    #[serde(default)]
+    pub slo: Vec<SloStatus>,
+    /// Present when the run injected fraud.
+    pub quality: Option<QualityReport>,
+}
+
+impl RunRecord {
+    /// Capture a finished run. Takes the engine mutably to close out its
+    /// quality report; call it once, just before `Engine::shutdown`.
+    pub fn capture(engine: &mut Engine, ui: &str, fraud_rate: f64, config: &Config) -> Self {
+        let elapsed = engine.elapsed();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            started_at_ms: chrono::Utc::now().timestamp_millis() - elapsed.as_millis() as i64,
+            ui: ui.to_string(),
+            fraud_rate,
+            config: config.clone(),
+            elapsed_secs: engine.stats_elapsed().as_secs_f64(),
+            uptime_secs: elapsed.as_secs_f64(),
+            total_trades: engine.total_trades,
+            total_orders: engine.total_orders,
+            total_alerts: engine.alert_engine.total_alerts(),
+            suppressed_alerts: engine.alert_engine.suppressed_alerts(),
+            alert_counts: engine.alert_engine.alert_counts().clone(),
+            severity_counts: engine.alert_engine.severity_counts().clone(),
+            stream_counts: STREAM_NAMES.iter().map(|n| n.to_string()).zip(engine.stream_counts).collect(),
+            shadow_counts: engine.alert_engine.shadow_alert_counts().clone(),
+            push: engine.latency.push_stats(),
+            processing: engine.latency.processing_stats(),
+            alert: engine.latency.alert_stats(),
+            tick_lag: engine.latency.tick_lag_stats(),
+            bar_lag: engine.latency.bar_lag_stats(),
+            drain: engine.drained.clone(),
+            slo: engine.slo.status(chrono::Utc::now().timestamp_millis()),
+            quality: engine.quality.has_labels().then(|| QualityReport {
+                reviewed: engine.reviews.as_ref().map(AlertReviews::quality).unwrap_or_default(),
+                ..engine.quality.report()
+            }),
+        }
+    }
+
+    pub fn trades_per_sec(&self) -> f64 {
+        if self.elapsed_secs > 0.0 {
+            self.total_trades as f64 / self.elapsed_secs
+        } else {
+            0.0
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", self.id));
+        self.write(&path)?;
+        Ok(path)
+    }
+
+    /// Write the record as pretty JSON to `path`, e.g. the `run-summary.json`
+    /// tooling reads after a run instead of parsing stdout.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Every run recorded in `dir`, oldest first. A missing directory is empty.
+pub fn list(dir: &Path) -> Result<Vec<RunRecord>, Box<dyn std::error::Error>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut runs = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            let text = std::fs::read_to_string(&path)?;
+            let run: RunRecord = serde_json::from_str(&text)
+                .map_err(|e| format!("invalid run record {}: {e}", path.display()))?;
+            runs.push(run);
+        }
+    }
+    runs.sort_by_key(|r| r.started_at_ms);
+    Ok(runs)
+}
+
+/// Find the run whose id starts with `prefix`; the prefix must be unambiguous.
+pub fn find(dir: &Path, prefix: &str) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    let mut matches: Vec<RunRecord> = list(dir)?.into_iter().filter(|r| r.id.starts_with(prefix)).collect();
+    match matches.len() {
+        0 => Err(format!("no run matching '{prefix}' in {}", dir.display()).into()),
+        1 => Ok(matches.remove(0)),
+        n => Err(format!("'{prefix}' matches {n} runs; use a longer prefix").into()),
+    }
+}
+
+pub fn print_list(runs: &[RunRecord]) {
+    println!(
+        "  {:<8} {:<19} {:<4} {:>5} {:>7} {:>9} {:>7} {:>10} {:>7}",
+        "Run", "Started", "UI", "Fraud", "Secs", "Trades/s", "Alerts", "Alert99", "Recall"
+    );
+    for r in runs {
+        println!(
+            "  {:<8} {:<19} {:<4} {:>4.0}% {:>7.0} {:>9.0} {:>7} {:>10} {:>7}",
+            &r.id[..8.min(r.id.len())],
+            locale::timestamp(r.started_at_ms),
+            r.ui,
+            r.fraud_rate * 100.0,
+            r.elapsed_secs,
+            r.trades_per_sec(),
+            locale::count(r.total_alerts),
+            locale::micros(r.alert.p99_us),
+            pct(r.quality.as_ref().and_then(overall_recall)),
+        );
+    }
+}
+
+/// The headline figures of a recorded run, as listed by `/api/runs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub id: String,
+    pub started_at_ms: i64,
+    pub ui: String,
+    pub fraud_rate: f64,
+    pub elapsed_secs: f64,
+    pub trades_per_sec: f64,
+    pub total_alerts: u64,
+    pub alert_p99_us: u64,
+    /// Recall across scenarios; absent when the run injected no fraud.
+    pub recall: Option<f64>,
+}
+
+impl From<&RunRecord> for RunSummary {
+    fn from(r: &RunRecord) -> Self {
+        Self {
+            id: r.id.clone(),
+            started_at_ms: r.started_at_ms,
+            ui: r.ui.clone(),
+            fraud_rate: r.fraud_rate,
+            elapsed_secs: r.elapsed_secs,
+            trades_per_sec: r.trades_per_sec(),
+            total_alerts: r.total_alerts,
+            alert_p99_us: r.alert.p99_us,
+            recall: r.quality.as_ref().and_then(overall_recall),
+        }
+    }
+}
+
+/// One metric of two runs side by side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRow {
+    pub metric: String,
+    /// Unit of `a` and `b`, e.g. `us`; empty for counts and rates.
+    pub unit: String,
+    pub a: Option<f64>,
+    pub b: Option<f64>,
+    /// `a` and `b` are fractions, so `change` is in percentage points.
+    pub ratio: bool,
+    /// From `a` to `b`: percent, or percentage points for a ratio. Absent
+    /// when either side is missing or a count starts from 0.
+    pub change: Option<f64>,
+}
+
+impl MetricRow {
+    fn value(metric: String, unit: &str, a: f64, b: f64) -> Self {
+        let change = (a != 0.0).then(|| (b - a) / a * 100.0);
+        Self { metric, unit: unit.to_string(), a: Some(a), b: Some(b), ratio: false, change }
+    }
+
+    fn ratio(metric: String, a: Option<f64>, b: Option<f64>) -> Self {
+        let change = a.zip(b).map(|(a, b)| (b - a) * 100.0);
+        Self { metric, unit: String::new(), a, b, ratio: true, change }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSection {
+    pub name: String,
+    pub rows: Vec<MetricRow>,
+}
+
+/// Two recorded runs compared metric by metric: throughput, latency, alert
+/// counts and detection quality. Printed by `runs diff`, served by
+/// `/api/runs/compare`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunComparison {
+    pub a: RunSummary,
+    pub b: RunSummary,
+    pub sections: Vec<MetricSection>,
+}
+
+/// Compare `b` against `a`.
+pub fn compare(a: &RunRecord, b: &RunRecord) -> RunComparison {
+    let throughput = vec![
+        MetricRow::value("Trades/sec".into(), "", a.trades_per_sec(), b.trades_per_sec()),
+        MetricRow::value("Alerts".into(), "", a.total_alerts as f64, b.total_alerts as f64),
+        MetricRow::value("Suppressed".into(), "", a.suppressed_alerts as f64, b.suppressed_alerts as f64),
+    ];
+
+    let mut latency = Vec::new();
+    for (stage, x, y) in [("Push", &a.push, &b.push), ("Processing", &a.processing, &b.processing), ("Alert", &a.alert, &b.alert)] {
+        latency.push(MetricRow::value(format!("{stage} p50"), "us", x.p50_us as f64, y.p50_us as f64));
+        latency.push(MetricRow::value(format!("{stage} p99"), "us", x.p99_us as f64, y.p99_us as f64));
+    }
+
+    let types: BTreeSet<&String> = a.alert_counts.keys().chain(b.alert_counts.keys()).collect();
+    let counts = types
+        .into_iter()
+        .map(|t| {
+            let count = |r: &RunRecord| r.alert_counts.get(t).copied().unwrap_or(0) as f64;
+            MetricRow::value(t.clone(), "", count(a), count(b))
+        })
+        .collect();
+
+    let (qa, qb) = (a.quality.as_ref(), b.quality.as_ref());
+    let mut quality = vec![
+        MetricRow::ratio("Recall (all scenarios)".into(), qa.and_then(overall_recall), qb.and_then(overall_recall)),
+        MetricRow::ratio("Clean-period FP rate".into(), qa.and_then(|q| q.clean_fp_rate), qb.and_then(|q| q.clean_fp_rate)),
+        MetricRow::ratio("Reviewed precision".into(), qa.and_then(reviewed_precision), qb.and_then(reviewed_precision)),
+    ];
+    let scenarios: BTreeSet<&String> = qa.iter().chain(qb.iter()).flat_map(|q| q.scenarios.iter().map(|s| &s.scenario)).collect();
+    for scenario in scenarios {
+        let get = |q: Option<&QualityReport>, f: fn(&ScenarioQuality) -> Option<f64>| {
+            q.and_then(|q| q.scenarios.iter().find(|s| &s.scenario == scenario)).and_then(f)
+        };
+        quality.push(MetricRow::ratio(format!("{scenario} precision"), get(qa, |s| s.precision), get(qb, |s| s.precision)));
+        quality.push(MetricRow::ratio(format!("{scenario} recall"), get(qa, |s| s.recall), get(qb, |s| s.recall)));
+    }
+
+    let section = |name: &str, rows| MetricSection { name: name.to_string(), rows };
+    RunComparison {
+        a: a.into(),
+        b: b.into(),
+        sections: vec![
+            section("Throughput", throughput),
+            section("Latency", latency),
+            section("Alert counts", counts),
+            section("Detection quality", quality),
+        ],
+    }
+}
+
+/// Print `b` against `a` metric by metric, with the change from `a` to `b`.
+pub fn print_diff(a: &RunRecord, b: &RunRecord) {
+    let short = |id: &str| id[..8.min(id.len())].to_string();
+    let comparison = compare(a, b);
+    println!("  {:<28} {:>12} {:>12} {:>10}", "Metric", short(&a.id), short(&b.id), "Change");
+    for section in &comparison.sections {
+        println!("  {}", section.name);
+        for row in &section.rows {
+            let name = format!("  {}", row.metric);
+            if row.ratio {
+                let change = row.change.map_or("-".to_string(), |c| format!("{c:+.0}pp"));
+                println!("  {:<28} {:>12} {:>12} {:>10}", name, pct(row.a), pct(row.b), change);
+            } else {
+                let change = row.change.map_or("-".to_string(), |c| format!("{c:+.1}%"));
+                let unit = &row.unit;
+                let (x, y) = (row.a.unwrap_or(0.0), row.b.unwrap_or(0.0));
+                println!("  {:<28} {:>10.0}{unit:<2} {:>10.0}{unit:<2} {:>10}", name, x, y, change);
+            }
+        }
+    }
+}
+
+/// Detected injections over all resolved injections, across scenarios.
+fn overall_recall(q: &QualityReport) -> Option<f64> {
+    let tp: u64 = q.scenarios.iter().map(|s| s.true_positives).sum();
+    let fn_: u64 = q.scenarios.iter().map(|s| s.false_negatives).sum();
+    (tp + fn_ > 0).then(|| tp as f64 / (tp + fn_) as f64)
+}
+
+/// Operator-confirmed true positives over confirmed true and false
+/// positives, across alert types.
+fn reviewed_precision(q: &QualityReport) -> Option<f64> {
+    let tp: u64 = q.reviewed.iter().map(|r| r.true_positives).sum();
+    let fp: u64 = q.reviewed.iter().map(|r| r.false_positives).sum();
+    (tp + fp > 0).then(|| tp as f64 / (tp + fp) as f64)
+}
+
+fn pct(v: Option<f64>) -> String {
+    v.map_or("-".to_string(), |v| format!("{:.0}%", v * 100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, started_at_ms: i64, total_trades: u64, elapsed_secs: f64) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            started_at_ms,
+            ui: "none".into(),
+            fraud_rate: 0.05,
+            config: Config::default(),
+            elapsed_secs,
+            uptime_secs: elapsed_secs,
+            total_trades,
+            total_orders: 0,
+            total_alerts: 0,
+            suppressed_alerts: 0,
+            alert_counts: HashMap::new(),
+            severity_counts: HashMap::new(),
+            stream_counts: BTreeMap::new(),
+            shadow_counts: HashMap::new(),
+            push: LatencyStats::default(),
+            processing: LatencyStats::default(),
+            alert: LatencyStats::default(),
+            tick_lag: LatencyStats::default(),
+            bar_lag: LatencyStats::default(),
+            drain: None,
+            slo: Vec::new(),
+            quality: None,
+        }
+    }
+
+    fn quality(true_positives: u64, false_negatives: u64) -> QualityReport {
+        QualityReport {
+            injections: true_positives + false_negatives,
+            scenarios: vec![ScenarioQuality {
+                scenario: "wash_trade".into(),
+                alert_type: "WashTrading".into(),
+                true_positives,
+                false_negatives,
+                false_positives: 0,
+                true_negatives: 0,
+                pending: 0,
+                precision: Some(1.0),
+                recall: Some(true_positives as f64 / (true_positives + false_negatives) as f64),
+                median_delay_ms: None,
+            }],
+            clean_cycles: 0,
+            clean_cycles_with_alerts: 0,
+            clean_fp_rate: None,
+            median_detection_delay_ms: None,
+            benign: Vec::new(),
+            reviewed: Vec::new(),
+        }
+    }
+
+    fn row<'a>(c: &'a RunComparison, section: &str, metric: &str) -> &'a MetricRow {
+        let section = c.sections.iter().find(|s| s.name == section).unwrap();
+        section.rows.iter().find(|r| r.metric == metric).unwrap()
+    }
+
+    #[test]
+    fn saved_runs_list_oldest_first_and_resolve_by_prefix() {
+        let dir = std::env::temp_dir().join(format!("runs-{}", uuid::Uuid::new_v4()));
+        assert!(list(&dir).unwrap().is_empty(), "a missing directory has no runs");
+
+        record("aaaa2222", 2_000, 10, 1.0).save(&dir).unwrap();
+        record("aaaa1111", 1_000, 10, 1.0).save(&dir).unwrap();
+        record("bbbb3333", 3_000, 10, 1.0).save(&dir).unwrap();
+        let ids: Vec<String> = list(&dir).unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, ["aaaa1111", "aaaa2222", "bbbb3333"]);
+
+        assert_eq!(find(&dir, "bbbb").unwrap().id, "bbbb3333");
+        assert!(find(&dir, "aaaa").unwrap_err().to_string().contains("matches 2 runs"));
+        assert!(find(&dir, "cccc").unwrap_err().to_string().contains("no run matching"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_summary_round_trips_with_stream_and_severity_counts() {
+        let path = std::env::temp_dir().join(format!("run-summary-{}.json", uuid::Uuid::new_v4()));
+        let mut run = record("summary", 1_000, 10, 2.0);
+        run.uptime_secs = 2.5;
+        run.severity_counts = HashMap::from([("High".to_string(), 2), ("Critical".to_string(), 1)]);
+        run.stream_counts = STREAM_NAMES.iter().map(|n| (n.to_string(), 0)).collect();
+        run.stream_counts.insert("wash_score".into(), 7);
+        run.write(&path).unwrap();
+
+        let read: RunRecord = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(read.uptime_secs, 2.5);
+        assert_eq!(read.severity_counts, run.severity_counts);
+        assert_eq!(read.stream_counts.len(), STREAM_NAMES.len());
+        assert_eq!(read.stream_counts.get("wash_score"), Some(&7));
+        std::fs::remove_file(&path).unwrap();
+        assert!(run.write(&path.join("nested")).is_err(), "the parent is not a directory");
+    }
+
+    #[test]
+    fn records_from_before_the_summary_fields_still_load() {
+        let mut json = serde_json::to_value(record("old", 1_000, 10, 2.0)).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        for field in ["uptime_secs", "severity_counts", "stream_counts"] {
+            fields.remove(field).unwrap();
+        }
+        let old: RunRecord = serde_json::from_value(json).unwrap();
+        assert_eq!(old.uptime_secs, 0.0);
+        assert!(old.severity_counts.is_empty() && old.stream_counts.is_empty());
+    }
+
+    #[test]
+    fn trades_per_sec_is_zero_without_elapsed_time() {
+        assert_eq!(record("a", 0, 5_000, 10.0).trades_per_sec(), 500.0);
+        assert_eq!(record("a", 0, 5_000, 0.0).trades_per_sec(), 0.0);
+    }
+
+    #[test]
+    fn compare_gives_the_change_from_a_to_b() {
+        let mut a = record("a", 0, 10_000, 10.0);
+        let mut b = record("b", 0, 15_000, 10.0);
+        a.alert_counts.insert("WashTrading".into(), 4);
+        b.alert_counts.insert("RapidFire".into(), 2);
+        a.quality = Some(quality(3, 1));
+        b.quality = Some(quality(4, 0));
+
+        let c = compare(&a, &b);
+        assert_eq!(row(&c, "Throughput", "Trades/sec").change, Some(50.0));
+        // Counts over the union of types; a count starting from 0 has no change
+        assert_eq!(row(&c, "Alert counts", "WashTrading").b, Some(0.0));
+        assert_eq!(row(&c, "Alert counts", "WashTrading").change, Some(-100.0));
+        assert_eq!(row(&c, "Alert counts", "RapidFire").change, None);
+        // Ratios change in percentage points; one missing side leaves it unset
+        assert_eq!(row(&c, "Detection quality", "Recall (all scenarios)").change, Some(25.0));
+        assert_eq!(row(&c, "Detection quality", "Reviewed precision").change, None);
+
+        b.quality = None;
+        let c = compare(&a, &b);
+        assert_eq!(row(&c, "Detection quality", "wash_trade recall").b, None);
+        assert_eq!(row(&c, "Detection quality", "wash_trade recall").change, None);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::alerts::Alert;
+use crate::config::ScoringConfig;
+use crate::ml::AccountFeatures;
+
+/// Length of the candidate-alert feature vector passed to the model.
+///
+/// Layout: `[alert_type, severity, metric, burst_trades, burst_volume,
+/// wash_balance, match_count]` — the first three come from the evidence row,
+/// the rest from the account's history (zeros for symbol-scoped alerts).
+pub const CANDIDATE_FEATURES: usize = 7;
+
+pub trait AlertScorer: Send {
+    /// Score one candidate alert. Higher means more likely to be real fraud.
+    fn score(&mut self, features: &[f32; CANDIDATE_FEATURES]) -> Result<f32, Box<dyn std::error::Error>>;
+}
+
+pub fn candidate_features(alert: &Alert, metric: f64, history: &AccountFeatures) -> [f32; CANDIDATE_FEATURES] {
+    [
+        alert.alert_type.clone() as u8 as f32,
+        alert.severity.clone() as u8 as f32,
+        metric as f32,
+        history.burst_trades as f32,
+        history.burst_volume as f32,
+        history.wash_balance as f32,
+        history.match_count as f32,
+    ]
+}
+
+/// Build the scorer described by `config`, if any.
+pub fn load(config: &ScoringConfig) -> Result<Option<Box<dyn AlertScorer>>, Box<dyn std::error::Error>> {
+    match &config.model_path {
+        None => Ok(None),
+        #[cfg(feature = "onnx")]
+        Some(path) => Ok(Some(Box::new(OnnxScorer::load(path)?))),
+        #[cfg(not(feature = "onnx"))]
+        Some(path) => Err(format!("scoring model {} requires building with --features onnx", path.display()).into()),
+    }
+}
+
+/// Runs a user-supplied ONNX model with one `[1, CANDIDATE_FEATURES]` float32
+/// input and reads the first element of the first output as the score.
+#[cfg(feature = "onnx")]
+pub struct OnnxScorer {
+    session: ort::session::Session,
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxScorer {
+    pub fn load(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let session = ort::session::Session::builder()?.commit_from_file(path)?;
+        Ok(Self { session })
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl AlertScorer for OnnxScorer {
+    fn score(&mut self, features: &[f32; CANDIDATE_FEATURES]) -> Result<f32, Box<dyn std::error::Error>> {
+        let input = ort::value::Tensor::from_array(([1usize, CANDIDATE_FEATURES], features.to_vec()))?;
+        let outputs = self.session.run(ort::inputs![input]?)?;
+        let (_, data) = outputs[0].try_extract_raw_tensor::<f32>()?;
+        data.first().copied().ok_or_else(|| "model returned an empty output".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity, AlertType};
+
+    #[test]
+    fn features_take_the_row_then_the_account_history() {
+        let alert = Alert {
+            id: 0,
+            alert_type: AlertType::WashTrading,
+            severity: AlertSeverity::High,
+            symbol: Some("AAPL".into()),
+            account_id: Some("W1".into()),
+            detail: AlertDetail::Text(String::new()),
+            latency_us: 0,
+            timestamp_ms: 0,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        };
+        let history = AccountFeatures { burst_trades: 12, burst_volume: 800, wash_balance: 0.5, match_count: 3 };
+        assert_eq!(candidate_features(&alert, 0.04, &history), [3.0, 1.0, 0.04, 12.0, 800.0, 0.5, 3.0]);
+        assert_eq!(candidate_features(&alert, 0.04, &AccountFeatures::default())[3..], [0.0; 4], "no history, zeros");
+    }
+
+    #[test]
+    fn no_model_path_no_scorer() {
+        assert!(load(&ScoringConfig::default()).unwrap().is_none());
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn model_path_needs_the_onnx_feature() {
+        let config = ScoringConfig { model_path: Some(PathBuf::from("model.onnx")), min_score: 0.5 };
+        let err = load(&config).err().expect("not built with onnx").to_string();
+        assert!(err.contains("--features onnx"), "{err}");
+    }
+
+    #[cfg(feature = "onnx")]
+    #[test]
+    fn missing_model_file_fails_to_load() {
+        let config = ScoringConfig { model_path: Some(PathBuf::from("does-not-exist.onnx")), min_score: 0.5 };
+        assert!(load(&config).is_err());
+    }
+}
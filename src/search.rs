@@ -0,0 +1,158 @@
+//! Inverted index over alert text behind `/api/alerts?q=` and the TUI
+//! search. Every alert the engine delivers is tokenized once, on its type,
+//! severity, symbol, account, uid and description, so a query touches only the
+//! postings of its terms rather than every alert.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::alerts::Alert;
+use crate::config::SearchConfig;
+
+/// Weight of a term found in the type, severity, symbol or account: those
+/// name what the alert is about, so they outrank a mention in the text.
+const FIELD_WEIGHT: f32 = 2.0;
+const DESCRIPTION_WEIGHT: f32 = 1.0;
+/// A query term that is only a prefix of the indexed term, e.g. `imb` for
+/// `imbalance`, counts for this much of a whole-word match.
+const PREFIX_WEIGHT: f64 = 0.5;
+
+/// The newest `capacity` alerts, searchable by word. A query matches an
+/// alert when each of its words starts some word of the alert's; matches are
+/// ranked by how rare the words are and where they appear, newest first on
+/// a tie.
+pub struct AlertIndex {
+    capacity: usize,
+    /// Alerts by document number, oldest first; `docs[0]` is `first_doc`.
+    docs: VecDeque<Alert>,
+    first_doc: u64,
+    /// Document numbers per term, ascending, with the term's weight in each.
+    /// Entries for evicted documents are skipped at query time and pruned
+    /// once they could make up half the index.
+    postings: BTreeMap<String, Vec<(u64, f32)>>,
+    evicted_since_prune: usize,
+}
+
+impl AlertIndex {
+    /// An index keeping the newest `capacity` alerts; 0 keeps none.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, docs: VecDeque::new(), first_doc: 0, postings: BTreeMap::new(), evicted_since_prune: 0 }
+    }
+
+    pub fn from_config(config: &SearchConfig) -> Self {
+        Self::new(config.max_alerts)
+    }
+
+    pub fn len(&self) -> usize {
+        self.docs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.docs.is_empty()
+    }
+
+    pub fn insert(&mut self, alert: Alert) {
+        if self.capacity == 0 {
+            return;
+        }
+        let doc = self.first_doc + self.docs.len() as u64;
+        let mut weights: HashMap<String, f32> = HashMap::new();
+        let fields = [
+            Some(alert.alert_type.label().to_string()),
+            Some(format!("{:?}", alert.severity)),
+            alert.symbol.clone(),
+            alert.account_id.clone(),
+            alert.uid.clone(),
+        ];
+        for field in fields.iter().flatten() {
+            for term in tokenize(field) {
+                *weights.entry(term).or_default() += FIELD_WEIGHT;
+            }
+        }
+        for term in tokenize(&alert.description().to_string()) {
+            *weights.entry(term).or_default() += DESCRIPTION_WEIGHT;
+        }
+        for (term, weight) in weights {
+            self.postings.entry(term).or_default().push((doc, weight));
+        }
+        self.docs.push_back(alert);
+
+        while self.docs.len() > self.capacity {
+            self.docs.pop_front();
+            self.first_doc += 1;
+            self.evicted_since_prune += 1;
+        }
+        if self.evicted_since_prune > self.capacity {
+            self.prune();
+        }
+    }
+
+    /// Alerts matching every word of `query`, best first, at most `limit`.
+    /// An empty query matches nothing.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&Alert> {
+        let words = tokenize(query);
+        if words.is_empty() || self.docs.is_empty() {
+            return Vec::new();
+        }
+        let total = self.docs.len() as f64;
+        let mut scores: Option<HashMap<u64, f64>> = None;
+        for word in &words {
+            // Best score per document over every indexed term the word starts
+            let mut word_scores: HashMap<u64, f64> = HashMap::new();
+            for (term, postings) in self.postings.range(word.clone()..).take_while(|(term, _)| term.starts_with(word.as_str())) {
+                let live = &postings[postings.partition_point(|&(doc, _)| doc < self.first_doc)..];
+                if live.is_empty() {
+                    continue;
+                }
+                let idf = (1.0 + total / live.len() as f64).ln();
+                let exact = if term == word { 1.0 } else { PREFIX_WEIGHT };
+                for &(doc, weight) in live {
+                    let score = idf * weight as f64 * exact;
+                    let best = word_scores.entry(doc).or_default();
+                    *best = best.max(score);
+                }
+            }
+            scores = Some(match scores {
+                None => word_scores,
+                Some(mut acc) => {
+                    acc.retain(|doc, score| match word_scores.get(doc) {
+                        Some(s) => {
+                            *score += s;
+                            true
+                        }
+                        None => false,
+                    });
+                    acc
+                }
+            });
+            if scores.as_ref().is_some_and(HashMap::is_empty) {
+                return Vec::new();
+            }
+        }
+
+        let mut ranked: Vec<(u64, f64)> = scores.unwrap_or_default().into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+        ranked.into_iter().take(limit).map(|(doc, _)| &self.docs[(doc - self.first_doc) as usize]).collect()
+    }
+
+    /// Drop postings of evicted documents, and terms left with none.
+    fn prune(&mut self) {
+        let first = self.first_doc;
+        self.postings.retain(|_, postings| {
+            postings.drain(..postings.partition_point(|&(doc, _)| doc < first));
+            !postings.is_empty()
+        });
+        self.evicted_since_prune = 0;
+    }
+}
+
+impl Default for AlertIndex {
+    fn default() -> Self {
+        Self::from_config(&SearchConfig::default())
+    }
+}
+
+/// Lowercase alphanumeric runs: `FRAUD-01` is `fraud` and `01`, so the query
+/// `fraud-01` finds it too.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
@@ -0,0 +1,81 @@
+//! Sequence-number checks on pushed trades and orders. A feed that skips
+//! or repeats sequence numbers has lost or replayed records, which the
+//! streams downstream can't tell from a quiet market.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// One feed's sequence record since start.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedStats {
+    /// Highest sequence number seen.
+    pub last_seq: i64,
+    /// Sequenced records checked.
+    pub records: u64,
+    /// Jumps past the next expected number.
+    pub gaps: u64,
+    /// Sequence numbers skipped by those jumps.
+    pub missing: u64,
+    /// Records at or below `last_seq`: repeated or arriving after a later one.
+    pub duplicates: u64,
+}
+
+/// What one batch of a feed broke; becomes a FeedIntegrity alert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceBreak {
+    pub feed: String,
+    pub gaps: u64,
+    pub missing: u64,
+    pub duplicates: u64,
+    /// Last sequence number before the batch's first gap, if it had one.
+    pub first_gap_after: Option<i64>,
+}
+
+/// Tracks the next expected sequence number of each feed. Records with
+/// sequence number 0 carry none and are not checked.
+#[derive(Debug, Default)]
+pub struct SequenceMonitor {
+    feeds: BTreeMap<String, FeedStats>,
+}
+
+impl SequenceMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check one batch of `feed`'s sequence numbers, in arrival order, and
+    /// return what it broke, if anything. A feed's first record sets where
+    /// it starts.
+    pub fn observe(&mut self, feed: &str, seqs: impl IntoIterator<Item = i64>) -> Option<SequenceBreak> {
+        let mut brk = SequenceBreak { feed: feed.to_string(), gaps: 0, missing: 0, duplicates: 0, first_gap_after: None };
+        let mut stats = self.feeds.get(feed).cloned();
+        for seq in seqs.into_iter().filter(|&s| s != 0) {
+            let s = stats.get_or_insert_with(|| FeedStats { last_seq: seq - 1, ..FeedStats::default() });
+            s.records += 1;
+            if seq <= s.last_seq {
+                s.duplicates += 1;
+                brk.duplicates += 1;
+                continue;
+            }
+            if seq > s.last_seq + 1 {
+                let missing = (seq - s.last_seq - 1) as u64;
+                s.gaps += 1;
+                s.missing += missing;
+                brk.gaps += 1;
+                brk.missing += missing;
+                brk.first_gap_after.get_or_insert(s.last_seq);
+            }
+            s.last_seq = seq;
+        }
+        if let Some(stats) = stats {
+            self.feeds.insert(feed.to_string(), stats);
+        }
+        (brk.gaps > 0 || brk.duplicates > 0).then_some(brk)
+    }
+
+    /// Every feed seen so far, by name.
+    pub fn stats(&self) -> &BTreeMap<String, FeedStats> {
+        &self.feeds
+    }
+}
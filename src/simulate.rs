@@ -0,0 +1,190 @@
+//! Multi-day simulation: the `[calendar]`'s trading sessions played back to
+//! back on a virtual clock, skipping nights, weekends and holidays, with an
+//! end-of-day report per session. Rules roll over between sessions as
+//! `[rollover]` says.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::{Alert, AlertEngine};
+use crate::calendar::TradingCalendar;
+use crate::clock::{Clock, VirtualClock};
+use crate::config::Config;
+use crate::detection::{self, PipelineOptions};
+use crate::engine::{Engine, WatermarkStrategy};
+use crate::generator::{self, FraudGenerator};
+use crate::locale;
+use crate::quality::QualityReport;
+
+/// Symbols listed in a day's report.
+const TOP_SYMBOLS: usize = 5;
+
+pub struct SimulationOptions {
+    /// Trading sessions to run; closed days don't count.
+    pub days: u32,
+    /// Exchange-local date the first session is on or after.
+    pub start: NaiveDate,
+    /// Event time between cycles.
+    pub cycle: Duration,
+    pub fraud_rate: f64,
+    pub seed: Option<u64>,
+    /// Each day's report is written here as `eod-<date>.json`.
+    pub reports_dir: Option<PathBuf>,
+}
+
+/// End-of-day report for one simulated session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyReport {
+    /// Exchange-local `YYYY-MM-DD`.
+    pub date: String,
+    /// "regular" or "half day".
+    pub day_kind: String,
+    /// Exchange-local session hours, e.g. `09:30-16:00`.
+    pub hours: String,
+    pub open_ms: i64,
+    pub close_ms: i64,
+    pub cycles: u64,
+    pub trades: u64,
+    pub orders: u64,
+    pub alerts: u64,
+    pub by_type: BTreeMap<String, u64>,
+    pub by_severity: BTreeMap<String, u64>,
+    /// Most-alerted symbols, most first.
+    pub top_symbols: Vec<(String, u64)>,
+    /// `[rollover]` keys reset at this session's open; empty on the first day.
+    pub reset: Vec<String>,
+    /// The day's alerts scored against its own injections; absent when
+    /// nothing was injected.
+    pub quality: Option<QualityReport>,
+}
+
+impl DailyReport {
+    pub fn print(&self) {
+        let counts = |m: &BTreeMap<String, u64>| {
+            let parts: Vec<String> = m.iter().map(|(k, n)| format!("{k} {}", locale::count(*n))).collect();
+            if parts.is_empty() { "-".to_string() } else { parts.join(", ") }
+        };
+        println!("=== {} ({}, {}) ===", self.date, self.day_kind, self.hours);
+        println!(
+            "  Cycles: {}  Trades: {}  Orders: {}  Alerts: {}",
+            locale::count(self.cycles), locale::count(self.trades), locale::count(self.orders), locale::count(self.alerts)
+        );
+        println!("  By type:     {}", counts(&self.by_type));
+        println!("  By severity: {}", counts(&self.by_severity));
+        if !self.top_symbols.is_empty() {
+            let top: Vec<String> = self.top_symbols.iter().map(|(s, n)| format!("{s} {}", locale::count(*n))).collect();
+            println!("  Top symbols: {}", top.join(", "));
+        }
+        if !self.reset.is_empty() {
+            println!("  Reset at open: {}", self.reset.join(", "));
+        }
+        if let Some(q) = &self.quality {
+            q.print();
+        }
+    }
+
+    /// Write the report as `eod-<date>.json` under `dir`.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        let path = dir.join(format!("eod-{}.json", self.date));
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Run `options.days` sessions and return their end-of-day reports. The
+/// calendar is enabled even if `config` leaves it off, with its hours.
+pub async fn run(options: &SimulationOptions, config: &Config) -> Result<Vec<DailyReport>, Box<dyn std::error::Error>> {
+    let mut config = config.clone();
+    config.calendar.enabled = true;
+    let calendar = TradingCalendar::from_config(&config.calendar)?.expect("the calendar was just enabled");
+    let start_ms = options.start.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis();
+    let mut open = calendar.next_open(start_ms).ok_or("no trading session within 30 days of the start date")?;
+
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(options.cycle);
+    let join = detection::guard_join_band(&config.join, trades_per_sec, orders_per_sec);
+    let pipeline = detection::setup_with(&PipelineOptions { sessions: config.sessions.clone(), join, ..Default::default() }).await?;
+    let gen = match options.seed {
+        Some(seed) => FraudGenerator::with_seed(options.fraud_rate, seed),
+        None => FraudGenerator::new(options.fraud_rate),
+    };
+    let clock = Arc::new(VirtualClock::new(open));
+    let mut engine = Engine::new(
+        pipeline,
+        gen.with_benign_rate(config.generator.benign_rate),
+        AlertEngine::from_config(&config)?,
+        clock.clone(),
+    );
+    engine.watermark = WatermarkStrategy::from_config(&config.watermark)?;
+
+    let mut reports = Vec::new();
+    for day in 0..options.days {
+        let date = calendar.local(open).date();
+        let (_, close) = calendar.session(date).expect("next_open lands on a session");
+        let (trades_before, orders_before) = (engine.total_trades, engine.total_orders);
+        let mut alerts = Vec::new();
+        let mut cycles = 0;
+        while clock.now_ms() < close {
+            alerts.extend(engine.cycle().await);
+            cycles += 1;
+            engine.sleep(options.cycle).await;
+        }
+        if let Some(timeout) = config.drain.timeout() {
+            alerts.extend(engine.drain(timeout).await);
+        }
+
+        let mut quality = std::mem::take(&mut engine.quality);
+        let report = DailyReport {
+            date: date.to_string(),
+            day_kind: calendar.day_kind(date).label().to_string(),
+            hours: format!("{}-{}", calendar.local(open).format("%H:%M"), calendar.local(close).format("%H:%M")),
+            open_ms: open,
+            close_ms: close,
+            cycles,
+            trades: engine.total_trades - trades_before,
+            orders: engine.total_orders - orders_before,
+            alerts: alerts.len() as u64,
+            by_type: tally(&alerts, |a| Some(a.alert_type.label().to_string())),
+            by_severity: tally(&alerts, |a| Some(format!("{:?}", a.severity))),
+            top_symbols: top_symbols(&alerts),
+            reset: if day == 0 { Vec::new() } else { config.rollover.reset().into_iter().map(String::from).collect() },
+            quality: quality.has_labels().then(|| quality.report()),
+        };
+        report.print();
+        if let Some(dir) = &options.reports_dir {
+            println!("  Report: {}", report.save(dir)?.display());
+        }
+        println!();
+        reports.push(report);
+
+        // Skip the night, and any weekend or holiday, to the next open
+        let Some(next) = calendar.next_open(clock.now_ms()) else {
+            eprintln!("No trading session within 30 days of {date}; stopping after {} day(s)", day + 1);
+            break;
+        };
+        clock.advance(Duration::from_millis((next - clock.now_ms()).max(0) as u64));
+        open = next;
+    }
+    engine.shutdown().await;
+    Ok(reports)
+}
+
+fn tally(alerts: &[Alert], key: impl Fn(&Alert) -> Option<String>) -> BTreeMap<String, u64> {
+    let mut counts = BTreeMap::new();
+    for k in alerts.iter().filter_map(key) {
+        *counts.entry(k).or_default() += 1;
+    }
+    counts
+}
+
+fn top_symbols(alerts: &[Alert]) -> Vec<(String, u64)> {
+    let mut by_symbol: Vec<(String, u64)> = tally(alerts, |a| a.symbol.clone()).into_iter().collect();
+    by_symbol.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    by_symbol.truncate(TOP_SYMBOLS);
+    by_symbol
+}
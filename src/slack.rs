@@ -0,0 +1,130 @@
+//! Slack slash commands (`/fraud status`, `/fraud ack <id>`, `/fraud silence
+//! <account> 10m`) posted to `POST /api/slack/command`. This module parses
+//! and authenticates them; the web server runs them against the same state
+//! the REST endpoints use.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::auth::constant_time_eq;
+use crate::config::SlackConfig;
+
+/// Requests signed further than this from now are refused, so a captured
+/// request can't be replayed later.
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// Longest silence a command may ask for.
+const MAX_SILENCE: Duration = Duration::from_secs(24 * 3600);
+
+/// The form fields Slack posts for a slash command that we use.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SlashCommand {
+    pub command: String,
+    pub text: String,
+    pub user_name: String,
+}
+
+/// What a slash command asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlackCommand {
+    Status,
+    /// Note on the alert's review that `user_name` acknowledged it.
+    Ack { id: u64 },
+    /// Suppress every alert naming the account for a while, as a cooldown
+    /// over all alert types.
+    Silence { account: String, duration: Duration },
+    Help,
+}
+
+impl SlackCommand {
+    /// Parse the text after the command name, e.g. `silence FRAUD-02 10m`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["help"] => Ok(Self::Help),
+            ["status"] => Ok(Self::Status),
+            ["ack", id] => id.trim_start_matches('#').parse().map(|id| Self::Ack { id }).map_err(|_| format!("'{id}' is not an alert id")),
+            ["silence", account, duration] => {
+                let duration = parse_duration(duration)?;
+                if duration > MAX_SILENCE {
+                    return Err("a silence can last at most 24h".into());
+                }
+                Ok(Self::Silence { account: account.to_string(), duration })
+            }
+            [verb, ..] => Err(format!("unknown command '{verb}'")),
+        }
+    }
+}
+
+/// A reply to a slash command. `ephemeral` replies are shown only to the
+/// user who ran the command; `in_channel` ones to everyone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlackReply {
+    pub response_type: String,
+    pub text: String,
+}
+
+impl SlackReply {
+    pub fn ephemeral(text: impl Into<String>) -> Self {
+        Self { response_type: "ephemeral".into(), text: text.into() }
+    }
+
+    pub fn in_channel(text: impl Into<String>) -> Self {
+        Self { response_type: "in_channel".into(), text: text.into() }
+    }
+}
+
+pub const HELP: &str = "Usage: `status` | `ack <alert id>` | `silence <account> <duration, e.g. 10m>`";
+
+/// `30s`, `10m`, `2h`, or bare seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let (digits, unit) = text.split_at(text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len()));
+    let n: u64 = digits.parse().map_err(|_| format!("'{text}' is not a duration like 10m"))?;
+    let secs = match unit {
+        "" | "s" => n,
+        "m" => n.saturating_mul(60),
+        "h" => n.saturating_mul(3600),
+        _ => return Err(format!("'{text}' is not a duration like 10m")),
+    };
+    if secs == 0 {
+        return Err("a silence needs a duration above 0".into());
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// Check Slack's request signature: `X-Slack-Signature` must be `v0=` and
+/// the hex HMAC-SHA256, under the signing secret, of `v0:<timestamp>:<body>`,
+/// with `X-Slack-Request-Timestamp` within five minutes of `now_secs`.
+pub fn verify(config: &SlackConfig, timestamp: &str, body: &str, signature: &str, now_secs: i64) -> Result<(), String> {
+    let secret = config.signing_secret.as_deref().ok_or("Slack commands are off: set [slack] signing_secret")?;
+    let ts: i64 = timestamp.parse().map_err(|_| "missing or invalid X-Slack-Request-Timestamp")?;
+    // `abs_diff` can't overflow on a hostile timestamp like i64::MIN
+    if now_secs.abs_diff(ts) > MAX_CLOCK_SKEW_SECS {
+        return Err("request timestamp too far from now".into());
+    }
+    if constant_time_eq(sign(secret, timestamp, body).as_bytes(), signature.as_bytes()) {
+        Ok(())
+    } else {
+        Err("invalid X-Slack-Signature".into())
+    }
+}
+
+/// The signature `verify` expects for `body` sent at `timestamp`, for
+/// clients and tests that post commands themselves.
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    format!("v0={}", hex(&hmac_sha256(secret.as_bytes(), format!("v0:{timestamp}:{body}").as_bytes())))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes a key of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,182 @@
+//! Alert latency SLOs (`[slo]`). Every alert's latency is checked against
+//! each objective's bound; attainment is the share of alerts within it,
+//! since start and over a rolling window, measured against the objective's
+//! target percentage.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::Alert;
+use crate::config::SloConfig;
+use crate::locale;
+
+/// One objective's compliance, as published with the dashboard stats and
+/// kept in the run summary. Counts only, so instances' statuses add up.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SloStatus {
+    pub name: String,
+    /// Target: this percentage of alerts within `within_ms`.
+    pub percent: f64,
+    pub within_ms: u64,
+    /// Alerts since start or the last stats reset, and how many were within.
+    pub alerts: u64,
+    pub met: u64,
+    /// The same over the last `[slo] window_secs`.
+    pub window_alerts: u64,
+    pub window_met: u64,
+}
+
+impl SloStatus {
+    /// Percentage of alerts within the bound since start; `None` before any.
+    pub fn attainment(&self) -> Option<f64> {
+        share(self.met, self.alerts)
+    }
+
+    pub fn window_attainment(&self) -> Option<f64> {
+        share(self.window_met, self.window_alerts)
+    }
+
+    /// Whether the window's attainment is under target. A window with no
+    /// alerts in it isn't a breach.
+    pub fn breached(&self) -> bool {
+        self.window_attainment().is_some_and(|a| a < self.percent)
+    }
+
+    /// Error budget left since start: how many more alerts may miss the
+    /// bound before attainment falls under target. Negative once overspent.
+    pub fn budget_left(&self) -> i64 {
+        let allowed = (self.alerts as f64 * (100.0 - self.percent) / 100.0).floor() as i64;
+        allowed - (self.alerts - self.met) as i64
+    }
+
+    /// Add another instance's counts for the same objective.
+    pub fn add(&mut self, other: &SloStatus) {
+        self.alerts += other.alerts;
+        self.met += other.met;
+        self.window_alerts += other.window_alerts;
+        self.window_met += other.window_met;
+    }
+}
+
+fn share(met: u64, alerts: u64) -> Option<f64> {
+    (alerts > 0).then(|| met as f64 / alerts as f64 * 100.0)
+}
+
+struct Objective {
+    status: SloStatus,
+    /// Per observation: when, alerts, and alerts within the bound.
+    recent: VecDeque<(i64, u64, u64)>,
+}
+
+/// Compliance with every `[slo]` objective, fed each poll's alerts.
+pub struct SloTracker {
+    window_ms: i64,
+    objectives: Vec<Objective>,
+}
+
+impl Default for SloTracker {
+    fn default() -> Self {
+        Self::from_config(&SloConfig::default()).expect("the default objectives are valid")
+    }
+}
+
+impl SloTracker {
+    pub fn from_config(config: &SloConfig) -> Result<Self, String> {
+        if config.window_secs == 0 && !config.objectives.is_empty() {
+            return Err("[slo] window_secs must be at least 1".into());
+        }
+        let mut objectives: Vec<Objective> = Vec::new();
+        for o in &config.objectives {
+            if o.name.is_empty() {
+                return Err("[[slo.objectives]] needs a name".into());
+            }
+            if objectives.iter().any(|other| other.status.name == o.name) {
+                return Err(format!("[[slo.objectives]] name {:?} is used twice", o.name));
+            }
+            if !(o.percent > 0.0 && o.percent <= 100.0) {
+                return Err(format!("[[slo.objectives]] {}: percent {} must be above 0 and at most 100", o.name, o.percent));
+            }
+            if o.within_ms == 0 {
+                return Err(format!("[[slo.objectives]] {}: within_ms must be at least 1", o.name));
+            }
+            let status = SloStatus { name: o.name.clone(), percent: o.percent, within_ms: o.within_ms, ..Default::default() };
+            objectives.push(Objective { status, recent: VecDeque::new() });
+        }
+        Ok(Self { window_ms: config.window_secs as i64 * 1000, objectives })
+    }
+
+    /// Count `alerts` raised at `now_ms` against every objective.
+    pub fn observe(&mut self, alerts: &[Alert], now_ms: i64) {
+        if alerts.is_empty() {
+            return;
+        }
+        let window_ms = self.window_ms;
+        for o in &mut self.objectives {
+            let bound_us = o.status.within_ms * 1000;
+            let met = alerts.iter().filter(|a| a.latency_us <= bound_us).count() as u64;
+            o.status.alerts += alerts.len() as u64;
+            o.status.met += met;
+            o.recent.push_back((now_ms, alerts.len() as u64, met));
+            while o.recent.front().is_some_and(|&(at, _, _)| now_ms - at > window_ms) {
+                o.recent.pop_front();
+            }
+        }
+    }
+
+    /// Every objective's compliance as of `now_ms`, in config order.
+    pub fn status(&self, now_ms: i64) -> Vec<SloStatus> {
+        self.objectives
+            .iter()
+            .map(|o| {
+                let recent = o.recent.iter().filter(|&&(at, _, _)| now_ms - at <= self.window_ms);
+                let (window_alerts, window_met) = recent.fold((0, 0), |(n, m), &(_, alerts, met)| (n + alerts, m + met));
+                SloStatus { window_alerts, window_met, ..o.status.clone() }
+            })
+            .collect()
+    }
+
+    /// Forget every alert counted so far, keeping the objectives.
+    pub fn reset(&mut self) {
+        for o in &mut self.objectives {
+            (o.status.alerts, o.status.met) = (0, 0);
+            o.recent.clear();
+        }
+    }
+}
+
+/// Add `from`'s counts into `into`, objective by objective, appending any
+/// objective `into` doesn't have yet.
+pub fn merge(into: &mut Vec<SloStatus>, from: &[SloStatus]) {
+    for s in from {
+        match into.iter_mut().find(|m| m.name == s.name) {
+            Some(m) => m.add(s),
+            None => into.push(s.clone()),
+        }
+    }
+}
+
+/// One line per objective, for the shutdown summary.
+pub fn print(statuses: &[SloStatus]) {
+    if statuses.is_empty() {
+        return;
+    }
+    println!("  Latency SLOs:");
+    for s in statuses {
+        let target = format!("{}% within {}ms", s.percent, s.within_ms);
+        match s.attainment() {
+            Some(a) => {
+                let verdict = if a >= s.percent { "met" } else { "MISSED" };
+                println!(
+                    "    {:<18} {:<22} {:>7.2}% of {} alerts ({verdict}, budget {} alerts)",
+                    s.name,
+                    target,
+                    a,
+                    locale::count(s.alerts),
+                    s.budget_left()
+                );
+            }
+            None => println!("    {:<18} {:<22} no alerts", s.name, target),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Pluggable market data. An [`EventSource`] hands the engine one batch of
+//! trades, orders, book levels and news at a time; [`ingest`] drives an
+//! [`Engine`] from one until it runs dry. The generator is one source
+//! ([`GeneratorSource`]); a proprietary feed handler only has to fill a
+//! [`Batch`] to be another.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::clock::Clock;
+use crate::engine::{Engine, WatermarkStrategy};
+use crate::generator::FraudGenerator;
+use crate::types::{BookLevel, NewsEvent, Order, Trade};
+
+/// One push's worth of market data, in any order.
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    pub trades: Vec<Trade>,
+    pub orders: Vec<Order>,
+    pub book: Vec<BookLevel>,
+    pub news: Vec<NewsEvent>,
+}
+
+impl Batch {
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty() && self.orders.is_empty() && self.book.is_empty() && self.news.is_empty()
+    }
+
+    /// Newest event time of any record, or `None` for an empty batch.
+    pub fn max_event_ts(&self) -> Option<i64> {
+        let trades = self.trades.iter().map(|t| t.ts);
+        let orders = self.orders.iter().map(|o| o.ts);
+        let book = self.book.iter().map(|l| l.ts);
+        let news = self.news.iter().map(|n| n.ts);
+        trades.chain(orders).chain(book).chain(news).max()
+    }
+
+    /// The watermark to push the batch with under `strategy`: its lead
+    /// ahead of the newest event.
+    pub fn watermark(&self, strategy: &WatermarkStrategy) -> Option<i64> {
+        self.max_event_ts().map(|ts| strategy.watermark_for(ts))
+    }
+
+    /// Move out every record older than `watermark`. Windows that far back
+    /// have closed, so the pipeline would drop them; a feed handler can
+    /// count or log them instead.
+    pub fn take_late(&mut self, watermark: i64) -> Batch {
+        fn split<T>(records: &mut Vec<T>, late: impl Fn(&T) -> bool) -> Vec<T> {
+            let (old, keep) = std::mem::take(records).into_iter().partition(late);
+            *records = keep;
+            old
+        }
+        Batch {
+            trades: split(&mut self.trades, |t| t.ts < watermark),
+            orders: split(&mut self.orders, |o| o.ts < watermark),
+            book: split(&mut self.book, |l| l.ts < watermark),
+            news: split(&mut self.news, |n| n.ts < watermark),
+        }
+    }
+}
+
+/// A feed the engine can ingest from. `None` means the source is done, e.g.
+/// a file read to the end; a live feed waits for its next batch instead.
+pub trait EventSource {
+    fn next_batch(&mut self) -> impl Future<Output = Option<Batch>> + Send;
+}
+
+/// The mock market as a source: one generator cycle per batch, stamped with
+/// `clock`'s time. It never runs dry; pace it from outside.
+pub struct GeneratorSource {
+    pub gen: FraudGenerator,
+    clock: Arc<dyn Clock>,
+}
+
+impl GeneratorSource {
+    pub fn new(gen: FraudGenerator, clock: Arc<dyn Clock>) -> Self {
+        Self { gen, clock }
+    }
+}
+
+impl EventSource for GeneratorSource {
+    async fn next_batch(&mut self) -> Option<Batch> {
+        Some(self.gen.batch(self.clock.now_ms()))
+    }
+}
+
+/// Run a cycle of `engine` on every batch `source` yields until it runs
+/// dry, returning how many alerts they raised.
+pub async fn ingest<S: EventSource>(engine: &mut Engine, source: &mut S) -> u64 {
+    let mut alerts = 0;
+    while let Some(batch) = source.next_batch().await {
+        alerts += engine.cycle_with(batch).await.len() as u64;
+    }
+    alerts
+}
@@ -0,0 +1,110 @@
+//! Warm standby pipeline for changing stream SQL without a cold start.
+//! A second pipeline, built from new options, is fed a copy of every push
+//! while the live one keeps raising alerts. Once it has seen `warmup_ms` of
+//! event time its windows hold the same history as the live ones, and the
+//! engine switches evaluation to it between cycles. The rule engine is kept
+//! across the switch, so its baselines, cooldowns and counts carry on.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{JoinConfig, SessionConfig, StandbyConfig};
+use crate::detection::{DetectionPipeline, PipelineOptions};
+use crate::types::*;
+
+/// A pipeline warming up alongside the live one.
+pub struct Standby {
+    pub pipeline: DetectionPipeline,
+    /// Event time of mirrored data needed before the switch.
+    pub warmup_ms: i64,
+    /// Watermark of the first mirrored push; warm-up counts from here.
+    first_watermark: Option<i64>,
+    last_watermark: Option<i64>,
+    /// Rows the standby's streams emitted while warming, polled and dropped.
+    pub rows_discarded: u64,
+}
+
+impl Standby {
+    pub fn new(pipeline: DetectionPipeline, warmup: Duration) -> Self {
+        Self { pipeline, warmup_ms: warmup.as_millis() as i64, first_watermark: None, last_watermark: None, rows_discarded: 0 }
+    }
+
+    /// Copy one push into the standby's sources. Records a full buffer
+    /// refuses are dropped: the standby only needs to be close to warm.
+    pub fn mirror(&mut self, trades: &[Trade], orders: &[Order], book: &[BookLevel], news: &[NewsEvent]) {
+        self.pipeline.trade_source.push_batch(trades.iter().cloned());
+        self.pipeline.order_source.push_batch(orders.iter().cloned());
+        self.pipeline.book_source.push_batch(book.iter().cloned());
+        self.pipeline.news_source.push_batch(news.iter().cloned());
+    }
+
+    pub fn watermark(&mut self, watermark: i64) {
+        self.pipeline.trade_source.watermark(watermark);
+        self.pipeline.order_source.watermark(watermark);
+        self.pipeline.book_source.watermark(watermark);
+        self.pipeline.news_source.watermark(watermark);
+        self.first_watermark.get_or_insert(watermark);
+        self.last_watermark = Some(watermark);
+    }
+
+    /// Poll and drop whatever the standby's streams emitted, so their output
+    /// doesn't queue up before the switch.
+    pub fn discard_output(&mut self) {
+        self.rows_discarded += self.pipeline.discard_output();
+    }
+
+    /// Event time mirrored so far.
+    pub fn warmed_ms(&self) -> i64 {
+        match (self.first_watermark, self.last_watermark) {
+            (Some(first), Some(last)) => last - first,
+            _ => 0,
+        }
+    }
+
+    pub fn is_warm(&self) -> bool {
+        self.first_watermark.is_some() && self.warmed_ms() >= self.warmup_ms
+    }
+
+    pub fn status(&self) -> StandbyStatus {
+        StandbyStatus { warmed_ms: self.warmed_ms(), warmup_ms: self.warmup_ms, rows_discarded: self.rows_discarded }
+    }
+}
+
+/// Progress of a warming standby, for the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandbyStatus {
+    pub warmed_ms: i64,
+    pub warmup_ms: i64,
+    pub rows_discarded: u64,
+}
+
+/// A request for a standby pipeline: the stream options it is built from,
+/// and optionally its own warm-up.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StandbyRequest {
+    pub sessions: SessionConfig,
+    pub join: JoinConfig,
+    pub disabled_streams: Vec<String>,
+    /// Extra `(name, CREATE STREAM sql)` pairs, as in `PipelineOptions`.
+    pub extra_streams: Vec<(String, String)>,
+    /// Overrides `[standby] warmup_secs`.
+    pub warmup_secs: Option<u64>,
+}
+
+impl StandbyRequest {
+    pub fn options(&self) -> PipelineOptions {
+        PipelineOptions {
+            sessions: self.sessions.clone(),
+            join: self.join.clone(),
+            disabled_streams: self.disabled_streams.clone(),
+            extra_streams: self.extra_streams.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn warmup(&self, config: &StandbyConfig) -> Duration {
+        Duration::from_secs(self.warmup_secs.unwrap_or(config.warmup_secs))
+    }
+}
@@ -0,0 +1,100 @@
+//! When a `run` ends, shared by its frontends: after `--duration` seconds,
+//! once `[stop] max_alerts` or `max_trades` is reached, or never; and the
+//! summary checkpoints an open-ended run writes on the way.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{Config, StopConfig};
+use crate::engine::Engine;
+use crate::runs::RunRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Duration,
+    MaxAlerts,
+    MaxTrades,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StopReason::Duration => "duration elapsed",
+            StopReason::MaxAlerts => "max_alerts reached",
+            StopReason::MaxTrades => "max_trades reached",
+        })
+    }
+}
+
+/// Decides when a run is over. Trades are counted across stats resets,
+/// which zero the engine's own totals.
+pub struct StopConditions {
+    duration: Option<Duration>,
+    max_alerts: Option<u64>,
+    max_trades: Option<u64>,
+    trades: u64,
+    last_trades: u64,
+    reason: Option<StopReason>,
+    checkpoint_path: Option<PathBuf>,
+    checkpoint_every: Duration,
+    last_checkpoint: Duration,
+}
+
+impl StopConditions {
+    /// `duration_secs` of 0 runs until another condition, or the user, stops it.
+    pub fn new(duration_secs: u64, config: &StopConfig) -> Self {
+        Self {
+            duration: (duration_secs > 0).then(|| Duration::from_secs(duration_secs)),
+            max_alerts: config.max_alerts,
+            max_trades: config.max_trades,
+            trades: 0,
+            last_trades: 0,
+            reason: None,
+            checkpoint_path: config.checkpoint_path.clone().filter(|_| config.checkpoint_secs > 0),
+            checkpoint_every: Duration::from_secs(config.checkpoint_secs),
+            last_checkpoint: Duration::ZERO,
+        }
+    }
+
+    /// Whether `engine` has met a stop condition; check before each cycle.
+    pub fn reached(&mut self, engine: &Engine) -> bool {
+        let total = engine.total_trades;
+        self.trades += total.checked_sub(self.last_trades).unwrap_or(total);
+        self.last_trades = total;
+
+        if self.reason.is_none() {
+            self.reason = if self.duration.is_some_and(|d| engine.elapsed() >= d) {
+                Some(StopReason::Duration)
+            } else if self.max_alerts.is_some_and(|max| engine.alerts_delivered() >= max) {
+                Some(StopReason::MaxAlerts)
+            } else if self.max_trades.is_some_and(|max| self.trades >= max) {
+                Some(StopReason::MaxTrades)
+            } else {
+                None
+            };
+        }
+        self.reason.is_some()
+    }
+
+    /// The condition that ended the run; `None` if it was stopped by hand.
+    pub fn reason(&self) -> Option<StopReason> {
+        self.reason
+    }
+
+    /// Rewrite the run summary if a checkpoint is due. A failed write is
+    /// reported and the run carries on.
+    pub fn checkpoint(&mut self, engine: &mut Engine, ui: &str, fraud_rate: f64, config: &Config) {
+        let Some(path) = &self.checkpoint_path else {
+            return;
+        };
+        let now = engine.elapsed();
+        if now < self.last_checkpoint + self.checkpoint_every {
+            return;
+        }
+        self.last_checkpoint = now;
+        if let Err(e) = RunRecord::capture(engine, ui, fraud_rate, config).write(path) {
+            eprintln!("Run checkpoint write to {} failed: {e}", path.display());
+        }
+    }
+}
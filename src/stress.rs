@@ -1,17 +1,42 @@
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::alerts::AlertEngine;
-use crate::detection;
+use crate::clock::SystemClock;
+use crate::detection::{self, PipelineOptions};
+use crate::engine::{Engine, WatermarkStats, WatermarkStrategy, STREAM_NAMES};
 use crate::generator::FraudGenerator;
-use crate::latency::LatencyTracker;
+use crate::quality::{QualityReport, QualityTracker};
+use crate::config::SloConfig;
+use crate::slo::{SloStatus, SloTracker};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StressLevel {
+    pub trades_per_cycle: usize,
+    pub sleep_ms: u64,
+    pub target_tps: u64,
+}
 
-struct StressLevel {
-    trades_per_cycle: usize,
-    sleep_ms: u64,
-    target_tps: u64,
+/// `TRADES:SLEEP_MS`, e.g. `200:20` for 200 trades every 20ms; the target
+/// rate is what that pace would give with no push or poll time.
+impl FromStr for StressLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (trades, sleep) = s.split_once(':').ok_or_else(|| format!("stress level '{s}' must be TRADES:SLEEP_MS, e.g. 200:20"))?;
+        let trades_per_cycle: usize = trades.trim().parse().map_err(|_| format!("stress level '{s}': '{trades}' is not a trade count"))?;
+        let sleep_ms: u64 = sleep.trim().parse().map_err(|_| format!("stress level '{s}': '{sleep}' is not a number of milliseconds"))?;
+        if trades_per_cycle == 0 || sleep_ms == 0 {
+            return Err(format!("stress level '{s}' needs at least 1 trade and 1ms"));
+        }
+        Ok(Self { trades_per_cycle, sleep_ms, target_tps: trades_per_cycle as u64 * 1000 / sleep_ms })
+    }
 }
 
-const LEVELS: &[StressLevel] = &[
+/// The default ramp, from a quiet market to past saturation.
+pub const LEVELS: &[StressLevel] = &[
     StressLevel { trades_per_cycle: 10,   sleep_ms: 100, target_tps: 100 },
     StressLevel { trades_per_cycle: 25,   sleep_ms: 100, target_tps: 250 },
     StressLevel { trades_per_cycle: 50,   sleep_ms: 50,  target_tps: 1_000 },
@@ -34,115 +59,68 @@ struct LevelResult {
     proc_p50: u64,
     proc_p95: u64,
     proc_p99: u64,
-    stream_counts: [u64; 6],
+    stream_counts: [u64; STREAM_NAMES.len()],
+    /// Records a full source buffer refused even after retries.
+    rejected_records: u64,
+    /// Event time from a bar's close to the poll that saw it.
+    bar_lag_p99: u64,
+    watermarks: WatermarkStats,
     duration_secs: f64,
+    /// Detection quality over the level's injections, when fraud was injected.
+    detection: Option<QualityReport>,
+    /// Alert latency against each `[slo]` objective over the level.
+    slo: Vec<SloStatus>,
 }
 
-pub async fn run(level_duration: u64) -> Result<(), Box<dyn std::error::Error>> {
-    let total_time = LEVELS.len() as u64 * level_duration;
+/// Width in characters of the bars in the ASCII charts.
+const CHART_WIDTH: usize = 50;
+
+/// Run every load level in `levels`. With a `fraud_rate`, scenarios are
+/// injected on top of the load and each level also reports how many were
+/// detected. When `plot` is set, also write a gnuplot script there that
+/// renders throughput and p99 latency per level to a PNG alongside it.
+/// Alert latency is held to `slo`'s objectives.
+pub async fn run(levels: &[StressLevel], level_duration: u64, warmup: u64, fraud_rate: f64, plot: Option<&Path>, slo: &SloConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if levels.is_empty() {
+        return Err("stress needs at least one level".into());
+    }
+    if !(0.0..=1.0).contains(&fraud_rate) {
+        return Err(format!("fraud rate {fraud_rate} must be between 0.0 and 1.0").into());
+    }
+    let slo = SloTracker::from_config(slo)?;
+    let total_time = levels.len() as u64 * (warmup + level_duration);
     println!("=== STRESS TEST ===");
-    println!("Levels: {}, Duration per level: {}s, Total estimated: {}s",
-        LEVELS.len(), level_duration, total_time);
+    println!("Levels: {}, Duration per level: {}s (+{}s warmup), Total estimated: {}s",
+        levels.len(), level_duration, warmup, total_time);
+    if fraud_rate > 0.0 {
+        println!("Fraud injected at {:.0}% of cycles", fraud_rate * 100.0);
+    }
     println!();
 
     let pipeline = detection::setup().await?;
-    let mut gen = FraudGenerator::new(0.0); // no fraud — pure throughput
-    let mut alert_engine = AlertEngine::new();
-    let mut latency = LatencyTracker::new();
+    let gen = FraudGenerator::new(fraud_rate); // 0 = no fraud, pure throughput
+    let mut engine = Engine::new(pipeline, gen, AlertEngine::new(), Arc::new(SystemClock::new()));
+    engine.slo = slo;
     let mut results: Vec<LevelResult> = Vec::new();
 
     let level_dur = Duration::from_secs(level_duration);
+    let warmup_dur = Duration::from_secs(warmup);
 
-    for (idx, level) in LEVELS.iter().enumerate() {
+    for (idx, level) in levels.iter().enumerate() {
         let level_num = idx + 1;
         print!("Level {}/{}: target ~{} trades/sec, {} trades/cycle, {}ms sleep ... ",
-            level_num, LEVELS.len(), level.target_tps, level.trades_per_cycle, level.sleep_ms);
-
-        latency.reset();
-        let mut total_trades = 0u64;
-        let mut total_orders = 0u64;
-        let mut total_alerts = 0u64;
-        let mut stream_counts: [u64; 6] = [0; 6];
-
-        // Sequential event timestamps: each cycle starts where the previous ended.
-        // This prevents cross-cycle JOIN fan-out from overlapping time ranges.
-        let mut event_ts: i64 = FraudGenerator::now_ms();
-        let cycle_span = FraudGenerator::stress_cycle_span_ms(level.trades_per_cycle);
-
-        let level_start = Instant::now();
-
-        while level_start.elapsed() < level_dur {
-            let gen_instant = Instant::now();
-
-            let (trades, orders) = gen.generate_stress_cycle(event_ts, level.trades_per_cycle);
-            total_trades += trades.len() as u64;
-            total_orders += orders.len() as u64;
-
-            let push_start = latency.record_push_start();
-            pipeline.trade_source.push_batch(trades);
-            if !orders.is_empty() {
-                pipeline.order_source.push_batch(orders);
-            }
-            // Watermark ahead of the latest event in this cycle
-            pipeline.trade_source.watermark(event_ts + cycle_span + 10_000);
-            pipeline.order_source.watermark(event_ts + cycle_span + 10_000);
-            latency.record_push_end(push_start);
-
-            // Advance event_ts past this cycle so the next cycle doesn't overlap
-            event_ts += cycle_span;
-
-            // Poll all streams
-            macro_rules! poll_stream {
-                ($sub:expr, $idx:expr, $eval:ident) => {
-                    if let Some(ref sub) = $sub {
-                        while let Some(rows) = sub.poll() {
-                            latency.record_poll();
-                            for row in &rows {
-                                stream_counts[$idx] += 1;
-                                if let Some(_alert) = alert_engine.$eval(row, gen_instant) {
-                                    latency.record_alert(gen_instant);
-                                    total_alerts += 1;
-                                }
-                            }
-                        }
-                    }
-                };
-            }
-
-            poll_stream!(pipeline.vol_baseline_sub, 0, evaluate_volume);
-            poll_stream!(pipeline.ohlc_vol_sub, 1, evaluate_ohlc);
-            poll_stream!(pipeline.rapid_fire_sub, 2, evaluate_rapid_fire);
-            poll_stream!(pipeline.wash_score_sub, 3, evaluate_wash);
-            poll_stream!(pipeline.suspicious_match_sub, 4, evaluate_match);
-            poll_stream!(pipeline.asof_match_sub, 5, evaluate_asof);
-
-            tokio::time::sleep(Duration::from_millis(level.sleep_ms)).await;
-        }
-
-        let elapsed = level_start.elapsed().as_secs_f64();
-        let actual_tps = (total_trades as f64 / elapsed) as u64;
+            level_num, levels.len(), level.target_tps, level.trades_per_cycle, level.sleep_ms);
 
-        let push = latency.push_stats();
-        let proc = latency.processing_stats();
-
-        println!("{} trades/sec (push p99={}us)", actual_tps, push.p99_us);
-
-        results.push(LevelResult {
-            level: level_num,
-            target_tps: level.target_tps,
-            actual_tps,
-            total_trades,
-            total_orders,
-            total_alerts,
-            push_p50: push.p50_us,
-            push_p95: push.p95_us,
-            push_p99: push.p99_us,
-            proc_p50: proc.p50_us,
-            proc_p95: proc.p95_us,
-            proc_p99: proc.p99_us,
-            stream_counts,
-            duration_secs: elapsed,
-        });
+        let mut result = run_level(&mut engine, level_num, level, warmup_dur, level_dur).await;
+        if fraud_rate > 0.0 {
+            result.detection = Some(engine.quality.report());
+        }
+        print!("{} trades/sec (push p99={}us)", result.actual_tps, result.push_p99);
+        if result.rejected_records > 0 {
+            print!(", {} records rejected at a full buffer", result.rejected_records);
+        }
+        println!();
+        results.push(result);
     }
 
     // Print summary table
@@ -155,20 +133,434 @@ pub async fn run(level_duration: u64) -> Result<(), Box<dyn std::error::Error>>
     // Detailed latency breakdown
     println!();
     print_latency_detail(&results);
+    println!();
+    print_slo(&results);
+
+    if fraud_rate > 0.0 {
+        println!();
+        print_detection(&results);
+    }
+
+    // Charts
+    println!();
+    print_ascii_charts(&results);
+    if let Some(path) = plot {
+        write_gnuplot(&results, path)?;
+        println!();
+        println!("Gnuplot script written to {} (render with: gnuplot {})", path.display(), path.display());
+    }
 
     // Stream breakdown
     println!();
     println!("Stream output totals:");
-    let names = ["vol_baseline", "ohlc_vol", "rapid_fire", "wash_score", "suspicious_match", "asof_match"];
-    for (i, name) in names.iter().enumerate() {
+    for (i, name) in STREAM_NAMES.iter().enumerate() {
         let total: u64 = results.iter().map(|r| r.stream_counts[i]).sum();
         println!("  {:<20} {}", name, total);
     }
 
-    let _ = pipeline.db.shutdown().await;
+    engine.shutdown().await;
     Ok(())
 }
 
+/// Drive `engine` at one load level for `duration`, starting from fresh counters.
+/// The first `warmup` of cycles runs before the counters are reset, so window
+/// warm-up and allocator effects don't skew the measured percentiles.
+async fn run_level(engine: &mut Engine, level_num: usize, level: &StressLevel, warmup: Duration, duration: Duration) -> LevelResult {
+    // Sequential event timestamps: each cycle starts where the previous ended.
+    // This prevents cross-cycle JOIN fan-out from overlapping time ranges.
+    let mut event_ts: i64 = engine.clock.now_ms();
+
+    drive(engine, level, &mut event_ts, warmup).await;
+
+    // Detection quality is scored per level here only, so it resets too
+    engine.reset_stats();
+    engine.quality = QualityTracker::new();
+
+    let level_start = Instant::now();
+    let total_alerts = drive(engine, level, &mut event_ts, duration).await;
+
+    let elapsed = level_start.elapsed().as_secs_f64();
+    let actual_tps = (engine.total_trades as f64 / elapsed) as u64;
+
+    let push = engine.latency.push_stats();
+    let proc = engine.latency.processing_stats();
+
+    LevelResult {
+        level: level_num,
+        target_tps: level.target_tps,
+        actual_tps,
+        total_trades: engine.total_trades,
+        total_orders: engine.total_orders,
+        total_alerts,
+        push_p50: push.p50_us,
+        push_p95: push.p95_us,
+        push_p99: push.p99_us,
+        proc_p50: proc.p50_us,
+        proc_p95: proc.p95_us,
+        proc_p99: proc.p99_us,
+        stream_counts: engine.stream_counts,
+        rejected_records: engine.push_stats.rejected_records,
+        bar_lag_p99: engine.latency.bar_lag_stats().p99_us,
+        watermarks: engine.watermark_stats.clone(),
+        duration_secs: elapsed,
+        detection: None,
+        slo: engine.slo.status(chrono::Utc::now().timestamp_millis()),
+    }
+}
+
+/// Push and poll cycles at `level`'s pace for `duration`, returning the
+/// number of alerts raised.
+async fn drive(engine: &mut Engine, level: &StressLevel, event_ts: &mut i64, duration: Duration) -> u64 {
+    let cycle_span = FraudGenerator::stress_cycle_span_ms(level.trades_per_cycle);
+    let mut total_alerts = 0u64;
+    let start = Instant::now();
+
+    while start.elapsed() < duration {
+        let gen_instant = Instant::now();
+
+        let (trades, orders) = engine.gen.generate_stress_cycle(*event_ts, level.trades_per_cycle);
+        // Watermark ahead of the latest event in this cycle
+        let watermark = engine.watermark.watermark_for(*event_ts + cycle_span);
+        engine.push(trades, orders, watermark).await;
+
+        let cycle_ts = *event_ts;
+        // Advance event_ts past this cycle so the next cycle doesn't overlap
+        *event_ts += cycle_span;
+        let labels = engine.gen.drain_labels();
+        engine.quality.start_cycle(cycle_ts, labels);
+
+        total_alerts += engine.poll(cycle_ts, gen_instant).len() as u64;
+
+        engine.sleep(Duration::from_millis(level.sleep_ms)).await;
+    }
+    total_alerts
+}
+
+/// Pause between autotune cycles; short, so the batch size drives the rate.
+const AUTOTUNE_SLEEP_MS: u64 = 10;
+
+/// Length of each autotune window: one push p99 sample, one adjustment.
+const AUTOTUNE_WINDOW: Duration = Duration::from_secs(1);
+
+const AUTOTUNE_MIN_BATCH: usize = 1;
+const AUTOTUNE_MAX_BATCH: usize = 10_000;
+
+/// Sizes the stress batch (trades per cycle) to keep push p99 under a
+/// budget: cut by 30% for a window over it, grow by 25% for one under 80%
+/// of it, and hold in between.
+struct BatchTuner {
+    budget_us: u64,
+    batch: usize,
+}
+
+impl BatchTuner {
+    fn observe(&mut self, push_p99: u64) {
+        if push_p99 > self.budget_us {
+            self.batch = (self.batch * 7 / 10).max(AUTOTUNE_MIN_BATCH);
+        } else if push_p99 < self.budget_us * 8 / 10 {
+            self.batch = (self.batch * 5 / 4).max(self.batch + 1).min(AUTOTUNE_MAX_BATCH);
+        }
+    }
+}
+
+/// The autotune answer: of the `(batch, trades/sec, push p99)` windows,
+/// the one with the largest batch that stayed within `budget_us`.
+fn largest_within_budget(history: &[(usize, u64, u64)], budget_us: u64) -> Option<&(usize, u64, u64)> {
+    history.iter().filter(|(_, _, p99)| *p99 <= budget_us).max_by_key(|(batch, ..)| *batch)
+}
+
+/// Tune the batch size for `duration` seconds to keep push p99 under
+/// `budget_us`, printing each window's batch, throughput and p99, then the
+/// batch size it settled on.
+pub async fn run_autotune(duration: u64, warmup: u64, budget_us: u64) -> Result<(), Box<dyn std::error::Error>> {
+    println!("=== BATCH AUTOTUNE ===");
+    println!("Push p99 budget: {}, {}s (+{}s warmup), {}ms between cycles", format_latency(budget_us), duration, warmup, AUTOTUNE_SLEEP_MS);
+    println!();
+
+    let pipeline = detection::setup().await?;
+    let mut engine = Engine::new(pipeline, FraudGenerator::new(0.0), AlertEngine::new(), Arc::new(SystemClock::new()));
+    let mut tuner = BatchTuner { budget_us, batch: LEVELS[0].trades_per_cycle };
+    let mut event_ts = engine.clock.now_ms();
+    let level = |batch| StressLevel { trades_per_cycle: batch, sleep_ms: AUTOTUNE_SLEEP_MS, target_tps: 0 };
+    drive(&mut engine, &level(tuner.batch), &mut event_ts, Duration::from_secs(warmup)).await;
+
+    println!(" {:>6} {:>8} {:>10} {:>10}  batch", "Time", "Batch", "Actual/s", "Push p99");
+    println!("{}", "-".repeat(40 + CHART_WIDTH));
+    let mut history = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(duration) {
+        engine.latency.reset();
+        engine.total_trades = 0;
+        let window_start = Instant::now();
+        drive(&mut engine, &level(tuner.batch), &mut event_ts, AUTOTUNE_WINDOW).await;
+        let tps = (engine.total_trades as f64 / window_start.elapsed().as_secs_f64()) as u64;
+        let p99 = engine.latency.push_stats().p99_us;
+        history.push((tuner.batch, tps, p99));
+
+        let len = (tuner.batch as f64 / AUTOTUNE_MAX_BATCH as f64).sqrt() * CHART_WIDTH as f64;
+        let over = if p99 > budget_us { " over" } else { "" };
+        println!(
+            " {:>5}s {:>8} {:>10} {:>10}  |{}|{over}",
+            start.elapsed().as_secs(),
+            tuner.batch,
+            tps,
+            format_latency(p99),
+            bar(len.round() as usize)
+        );
+        tuner.observe(p99);
+    }
+    println!("{}", "-".repeat(40 + CHART_WIDTH));
+
+    match largest_within_budget(&history, budget_us) {
+        Some((batch, tps, p99)) => println!(
+            "Largest batch within budget: {batch} trades/cycle, ~{tps} trades/sec at push p99 {}",
+            format_latency(*p99)
+        ),
+        None => println!("No batch size kept push p99 under {}; even {AUTOTUNE_MIN_BATCH} trade/cycle was over", format_latency(budget_us)),
+    }
+
+    engine.shutdown().await;
+    Ok(())
+}
+
+/// Run the top load level once per tokio worker count and source buffer size,
+/// each on its own runtime, and print sustained TPS and push/proc p99 as a
+/// matrix.
+pub async fn run_matrix(level_duration: u64, warmup: u64, threads: &[usize], buffer_sizes: &[usize]) -> Result<(), Box<dyn std::error::Error>> {
+    if threads.contains(&0) {
+        return Err("--threads counts must be at least 1".into());
+    }
+    let level = &LEVELS[LEVELS.len() - 1];
+    println!("=== STRESS MATRIX ===");
+    println!("Load: {} trades/cycle, {}ms sleep, {}s per run (+{}s warmup), {} runs",
+        level.trades_per_cycle, level.sleep_ms, level_duration, warmup, threads.len() * buffer_sizes.len());
+    println!();
+
+    let mut cells = Vec::new();
+    for &workers in threads {
+        for &buffer_size in buffer_sizes {
+            print!("{workers} workers, buffer {buffer_size} ... ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            // A runtime can't be built inside this one, so each run gets a thread
+            let run = std::thread::spawn(move || -> Result<LevelResult, String> {
+                let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(workers).enable_all().build().map_err(|e| e.to_string())?;
+                rt.block_on(async {
+                    let options = PipelineOptions { buffer_size: Some(buffer_size), ..Default::default() };
+                    let pipeline = detection::setup_with(&options).await.map_err(|e| e.to_string())?;
+                    let mut engine = Engine::new(pipeline, FraudGenerator::new(0.0), AlertEngine::new(), Arc::new(SystemClock::new()));
+                    let level = &LEVELS[LEVELS.len() - 1];
+                    let result = run_level(&mut engine, 1, level, Duration::from_secs(warmup), Duration::from_secs(level_duration)).await;
+                    engine.shutdown().await;
+                    Ok(result)
+                })
+            });
+            let result = run.join().map_err(|_| "stress matrix run panicked")??;
+            println!("{} trades/sec (push p99={}us)", result.actual_tps, result.push_p99);
+            cells.push((workers, buffer_size, result));
+        }
+    }
+
+    println!();
+    print_matrix(&cells, threads, buffer_sizes);
+    Ok(())
+}
+
+/// One `--matrix` run: tokio workers, source buffer size and its result.
+type MatrixCell = (usize, usize, LevelResult);
+
+/// Workers down, buffer sizes across; each cell is `TPS push/proc p99`.
+fn print_matrix(cells: &[MatrixCell], threads: &[usize], buffer_sizes: &[usize]) {
+    let width = 10 + 26 * buffer_sizes.len();
+    println!("{}", "=".repeat(width));
+    println!("{:^width$}", "SUSTAINED TPS, PUSH/PROC p99");
+    println!("{}", "=".repeat(width));
+    print!(" {:<8}", "Workers");
+    for size in buffer_sizes {
+        print!(" {:>25}", format!("buffer {size}"));
+    }
+    println!();
+    println!("{}", "-".repeat(width));
+    for &workers in threads {
+        print!(" {:<8}", workers);
+        for &size in buffer_sizes {
+            let cell = cells.iter().find(|(w, b, _)| *w == workers && *b == size).map(|(.., r)| r);
+            let text = cell.map_or(String::new(), |r| {
+                format!("{} {}/{}", r.actual_tps, format_latency(r.push_p99), format_latency(r.proc_p99))
+            });
+            print!(" {:>25}", text);
+        }
+        println!();
+    }
+    println!("{}", "=".repeat(width));
+
+    if let Some(((workers, size, r), (min_workers, min_size, min_r))) = matrix_picks(cells) {
+        println!("Highest throughput: {workers} workers, buffer {size} (~{} trades/sec, push p99 {})", r.actual_tps, format_latency(r.push_p99));
+        println!("Smallest within 5% of it: {min_workers} workers, buffer {min_size} (~{} trades/sec)", min_r.actual_tps);
+    }
+}
+
+/// The highest-throughput cell, and the one with the fewest workers (then
+/// the smallest buffer) within 5% of it: adding cores past that buys little.
+fn matrix_picks(cells: &[MatrixCell]) -> Option<(&MatrixCell, &MatrixCell)> {
+    let peak = cells.iter().max_by_key(|(.., r)| r.actual_tps)?;
+    let smallest = cells.iter().filter(|(.., r)| r.actual_tps * 100 >= peak.2.actual_tps * 95).min_by_key(|(w, b, _)| (*w, *b))?;
+    Some((peak, smallest))
+}
+
+/// Run the top load level once with every stream, then once per stream with
+/// that stream disabled, and print the marginal cost of each.
+pub async fn run_stream_cost(level_duration: u64, warmup: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let level = &LEVELS[LEVELS.len() - 1];
+    let level_dur = Duration::from_secs(level_duration);
+    let warmup_dur = Duration::from_secs(warmup);
+    println!("=== STREAM COST ===");
+    println!("Load: {} trades/cycle, {}ms sleep, {}s per run (+{}s warmup), {} runs",
+        level.trades_per_cycle, level.sleep_ms, level_duration, warmup, STREAM_NAMES.len() + 1);
+    println!();
+
+    let mut runs: Vec<(String, LevelResult)> = Vec::new();
+    for (label, options) in stream_cost_configs() {
+        let pipeline = detection::setup_with(&options).await?;
+        let mut engine = Engine::new(pipeline, FraudGenerator::new(0.0), AlertEngine::new(), Arc::new(SystemClock::new()));
+
+        print!("{:<20} ... ", label);
+        let result = run_level(&mut engine, 1, level, warmup_dur, level_dur).await;
+        println!("{} trades/sec (proc p99={}us)", result.actual_tps, result.proc_p99);
+        runs.push((label, result));
+
+        engine.shutdown().await;
+    }
+
+    println!();
+    print_stream_cost_table(&runs);
+    Ok(())
+}
+
+/// The stream-cost runs, each with the label its row is printed under:
+/// every stream first as the baseline, then each stream disabled in turn.
+fn stream_cost_configs() -> Vec<(String, PipelineOptions)> {
+    let configs = std::iter::once(None).chain(STREAM_NAMES.iter().map(|n| Some(*n)));
+    configs
+        .map(|disabled| {
+            let label = match disabled {
+                Some(name) => format!("-{}", name),
+                None => "all streams".to_string(),
+            };
+            let options = PipelineOptions {
+                disabled_streams: disabled.into_iter().map(String::from).collect(),
+                ..Default::default()
+            };
+            (label, options)
+        })
+        .collect()
+}
+
+/// The stream whose removal freed the most throughput over the baseline
+/// (the first run), and how many trades/sec it freed.
+fn most_expensive_stream(runs: &[(String, LevelResult)]) -> Option<(&str, i64)> {
+    let (_, baseline) = runs.first()?;
+    let (label, r) = runs.iter().skip(1).max_by_key(|(_, r)| r.actual_tps)?;
+    Some((label.trim_start_matches('-'), r.actual_tps as i64 - baseline.actual_tps as i64))
+}
+
+fn print_stream_cost_table(runs: &[(String, LevelResult)]) {
+    let Some((_, baseline)) = runs.first() else { return };
+
+    println!("{}", "=".repeat(80));
+    println!("{:^80}", "MARGINAL STREAM COST");
+    println!("{}", "=".repeat(80));
+    println!(
+        " {:<20} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Config", "Actual/s", "TPS gain", "Push p99", "Proc p99", "p99 saved"
+    );
+    println!("{}", "-".repeat(80));
+
+    for (label, r) in runs {
+        let tps_gain = r.actual_tps as i64 - baseline.actual_tps as i64;
+        let p99_saved = baseline.proc_p99 as i64 - r.proc_p99 as i64;
+        println!(
+            " {:<20} {:>10} {:>+10} {:>10} {:>10} {:>10}",
+            label,
+            r.actual_tps,
+            tps_gain,
+            format_latency(r.push_p99),
+            format_latency(r.proc_p99),
+            if p99_saved < 0 { format!("-{}", format_latency(p99_saved.unsigned_abs())) } else { format_latency(p99_saved as u64) },
+        );
+    }
+    println!("{}", "=".repeat(80));
+
+    // The stream whose removal frees the most throughput is the bottleneck
+    if let Some((name, gain)) = most_expensive_stream(runs) {
+        println!("Most expensive stream: {} (+{} trades/sec when disabled)", name, gain);
+    }
+}
+
+/// Watermark strategies `run_watermarks` compares: label, `every_pushes`,
+/// `min_advance_ms`.
+const WATERMARK_STRATEGIES: &[(&str, u32, i64)] = &[
+    ("every push", 1, 0),
+    ("every 4th push", 4, 0),
+    ("every 16th push", 16, 0),
+    ("15s event time", 0, 15_000),
+    ("60s event time", 0, 60_000),
+];
+
+/// Run one mid load level (5s of event time per cycle) once per watermark
+/// strategy and print what batching watermarks does to latency and to how
+/// coarsely the streams' output arrives.
+pub async fn run_watermarks(level_duration: u64, warmup: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let level = &LEVELS[3];
+    println!("=== WATERMARK STRATEGIES ===");
+    println!("Load: {} trades/cycle, {}ms sleep, {}s per run (+{}s warmup), {} runs",
+        level.trades_per_cycle, level.sleep_ms, level_duration, warmup, WATERMARK_STRATEGIES.len());
+    println!();
+
+    let mut runs: Vec<(&str, LevelResult)> = Vec::new();
+    for &(label, every_pushes, min_advance_ms) in WATERMARK_STRATEGIES {
+        let pipeline = detection::setup().await?;
+        let mut engine = Engine::new(pipeline, FraudGenerator::new(0.0), AlertEngine::new(), Arc::new(SystemClock::new()));
+        engine.watermark = WatermarkStrategy { every_pushes, min_advance_ms, ..WatermarkStrategy::default() };
+
+        print!("{:<20} ... ", label);
+        let result = run_level(&mut engine, 1, level, Duration::from_secs(warmup), Duration::from_secs(level_duration)).await;
+        println!("{} watermarks over {} pushes (proc p99={}us)", result.watermarks.emitted, result.watermarks.pushes, result.proc_p99);
+        runs.push((label, result));
+
+        engine.shutdown().await;
+    }
+
+    println!();
+    print_watermark_table(&runs);
+    Ok(())
+}
+
+fn print_watermark_table(runs: &[(&str, LevelResult)]) {
+    println!("{}", "=".repeat(90));
+    println!("{:^90}", "WATERMARK STRATEGIES");
+    println!("{}", "=".repeat(90));
+    println!(
+        " {:<18} {:>10} {:>10} {:>10} {:>10} {:>10} {:>8} {:>8}",
+        "Strategy", "Actual/s", "Push p99", "Proc p99", "Bar lag", "Emitted", "Rows/bat", "Alerts"
+    );
+    println!("{}", "-".repeat(90));
+    for (label, r) in runs {
+        println!(
+            " {:<18} {:>10} {:>10} {:>10} {:>10} {:>10} {:>8.1} {:>8}",
+            label,
+            r.actual_tps,
+            format_latency(r.push_p99),
+            format_latency(r.proc_p99),
+            format_latency(r.bar_lag_p99),
+            r.watermarks.emitted,
+            r.watermarks.rows_per_batch(),
+            r.total_alerts,
+        );
+    }
+    println!("{}", "=".repeat(90));
+    println!("Bar lag is event time from an ohlc_vol bar's close to the poll that saw it (p99); Rows/bat is rows per non-empty poll.");
+}
+
 fn format_latency(us: u64) -> String {
     if us >= 1_000_000 {
         format!("{:.1}s", us as f64 / 1_000_000.0)
@@ -237,6 +629,103 @@ fn print_latency_detail(results: &[LevelResult]) {
     }
 }
 
+/// Injections caught per level, all scenarios together. A level that
+/// detects less than the one before it has started losing fraud to load.
+fn print_detection(results: &[LevelResult]) {
+    println!("Detection under load:");
+    println!(" {:<5} {:>10} {:>6} {:>6} {:>8} {:>8} {:>10}", "Level", "Injected", "TP", "FN", "Pending", "Recall", "Delay p50");
+    println!("{}", "-".repeat(60));
+    for r in results {
+        let Some(q) = &r.detection else { continue };
+        let tp: u64 = q.scenarios.iter().map(|s| s.true_positives).sum();
+        let fn_: u64 = q.scenarios.iter().map(|s| s.false_negatives).sum();
+        let pending: u64 = q.scenarios.iter().map(|s| s.pending).sum();
+        let recall = if tp + fn_ > 0 { format!("{:.0}%", tp as f64 / (tp + fn_) as f64 * 100.0) } else { "-".to_string() };
+        let delay = q.median_detection_delay_ms.map_or("-".to_string(), |ms| format!("{ms}ms"));
+        println!(" {:<5} {:>10} {:>6} {:>6} {:>8} {:>8} {:>10}", r.level, q.injections, tp, fn_, pending, recall, delay);
+    }
+}
+
+/// Alert latency SLO attainment per level, against the `[slo]` objectives
+/// of `stress --config`. A level under target is where load starts to delay alerts
+/// past what the objective allows.
+fn print_slo(results: &[LevelResult]) {
+    println!("Alert latency SLOs:");
+    println!(" {:<5} {:<18} {:>20} {:>8} {:>11} {:>8}", "Level", "Objective", "Target", "Alerts", "Attainment", "Budget");
+    println!("{}", "-".repeat(76));
+    for r in results {
+        for s in &r.slo {
+            let target = format!("{}% <= {}ms", s.percent, s.within_ms);
+            let (attainment, budget) = match s.attainment() {
+                Some(a) => (format!("{a:.2}%"), s.budget_left().to_string()),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            println!(" {:<5} {:<18} {:>20} {:>8} {:>11} {:>8}", r.level, s.name, target, s.alerts, attainment, budget);
+        }
+    }
+}
+
+fn bar(len: usize) -> String {
+    format!("{:<width$}", "█".repeat(len.min(CHART_WIDTH)), width = CHART_WIDTH)
+}
+
+/// Bar length for a latency on a log10 scale, so microsecond and second
+/// latencies fit on the same chart.
+fn log_bar_len(us: u64, max_us: u64) -> usize {
+    if us == 0 || max_us <= 1 {
+        return 0;
+    }
+    let scaled = (us as f64).log10().max(0.0) / (max_us as f64).log10();
+    (scaled * CHART_WIDTH as f64).round() as usize
+}
+
+fn print_ascii_charts(results: &[LevelResult]) {
+    let max_tps = results.iter().map(|r| r.actual_tps.max(r.target_tps)).max().unwrap_or(0).max(1);
+    println!("Actual throughput (trades/sec, target in brackets):");
+    for r in results {
+        let len = (r.actual_tps as f64 / max_tps as f64 * CHART_WIDTH as f64).round() as usize;
+        println!(" L{:<3} |{}| {} [{}]", r.level, bar(len), r.actual_tps, r.target_tps);
+    }
+
+    let max_us = results.iter().map(|r| r.push_p99.max(r.proc_p99)).max().unwrap_or(0);
+    println!();
+    println!("p99 latency (log scale):");
+    for r in results {
+        println!(" L{:<3} push |{}| {}", r.level, bar(log_bar_len(r.push_p99, max_us)), format_latency(r.push_p99));
+        println!("      proc |{}| {}", bar(log_bar_len(r.proc_p99, max_us)), format_latency(r.proc_p99));
+    }
+}
+
+/// Write a self-contained gnuplot script (data inline) that plots actual TPS
+/// and push/proc p99 per level to `<path>.png`.
+fn write_gnuplot(results: &[LevelResult], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let png = path.with_extension("png");
+    let mut script = String::new();
+    script.push_str("$data << EOD\n");
+    script.push_str("# level target_tps actual_tps push_p99_us proc_p99_us\n");
+    for r in results {
+        script.push_str(&format!("{} {} {} {} {}\n", r.level, r.target_tps, r.actual_tps, r.push_p99, r.proc_p99));
+    }
+    script.push_str("EOD\n\n");
+    script.push_str("set terminal pngcairo size 1000,600\n");
+    script.push_str(&format!("set output '{}'\n", png.display()));
+    script.push_str("set title 'Stress test: throughput vs p99 latency'\n");
+    script.push_str("set xlabel 'Level'\n");
+    script.push_str("set ylabel 'Trades/sec'\n");
+    script.push_str("set y2label 'p99 latency (us)'\n");
+    script.push_str("set logscale y2\n");
+    script.push_str("set ytics nomirror\n");
+    script.push_str("set y2tics\n");
+    script.push_str("set key top left\n");
+    script.push_str("set grid\n");
+    script.push_str("plot $data using 1:2 with lines dashtype 2 title 'Target TPS', \\\n");
+    script.push_str("     $data using 1:3 with linespoints title 'Actual TPS', \\\n");
+    script.push_str("     $data using 1:4 axes x1y2 with linespoints title 'Push p99', \\\n");
+    script.push_str("     $data using 1:5 axes x1y2 with linespoints title 'Proc p99'\n");
+    std::fs::write(path, script)?;
+    Ok(())
+}
+
 fn print_saturation_analysis(results: &[LevelResult]) {
     println!();
 
@@ -269,3 +758,152 @@ fn print_saturation_analysis(results: &[LevelResult]) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(actual_tps: u64, proc_p99: u64) -> LevelResult {
+        LevelResult {
+            level: 1,
+            target_tps: 200_000,
+            actual_tps,
+            total_trades: 0,
+            total_orders: 0,
+            total_alerts: 0,
+            push_p50: 0,
+            push_p95: 0,
+            push_p99: 0,
+            proc_p50: 0,
+            proc_p95: 0,
+            proc_p99,
+            stream_counts: [0; STREAM_NAMES.len()],
+            rejected_records: 0,
+            bar_lag_p99: 0,
+            watermarks: WatermarkStats::default(),
+            duration_secs: 1.0,
+            detection: None,
+            slo: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stream_cost_disables_each_stream_once_after_the_baseline() {
+        let configs = stream_cost_configs();
+        assert_eq!(configs.len(), STREAM_NAMES.len() + 1);
+        assert_eq!(configs[0].0, "all streams");
+        assert!(configs[0].1.disabled_streams.is_empty());
+        for ((label, options), name) in configs[1..].iter().zip(STREAM_NAMES) {
+            assert_eq!(label, &format!("-{name}"));
+            assert_eq!(options.disabled_streams, vec![name.to_string()]);
+        }
+    }
+
+    #[test]
+    fn most_expensive_stream_is_the_biggest_throughput_gain() {
+        let runs = vec![
+            ("all streams".to_string(), result(10_000, 900)),
+            ("-vol_baseline".to_string(), result(10_500, 800)),
+            ("-asof_match".to_string(), result(14_000, 400)),
+            ("-wash_score".to_string(), result(9_800, 950)),
+        ];
+        assert_eq!(most_expensive_stream(&runs), Some(("asof_match", 4_000)));
+    }
+
+    #[test]
+    fn most_expensive_stream_needs_a_disabled_run() {
+        assert_eq!(most_expensive_stream(&[]), None);
+        assert_eq!(most_expensive_stream(&[("all streams".to_string(), result(10_000, 900))]), None);
+    }
+
+    #[test]
+    fn latency_bars_are_log_scaled_to_the_slowest() {
+        assert_eq!(log_bar_len(1_000_000, 1_000_000), CHART_WIDTH);
+        assert_eq!(log_bar_len(1_000, 1_000_000), CHART_WIDTH / 2);
+        assert_eq!(log_bar_len(1, 1_000_000), 0);
+        assert_eq!(log_bar_len(0, 1_000_000), 0);
+        assert_eq!(log_bar_len(5, 1), 0);
+    }
+
+    #[test]
+    fn bars_are_padded_and_capped_at_the_chart_width() {
+        assert_eq!(bar(0).chars().count(), CHART_WIDTH);
+        assert_eq!(bar(CHART_WIDTH + 10).chars().filter(|c| *c == '█').count(), CHART_WIDTH);
+    }
+
+    #[test]
+    fn gnuplot_script_carries_the_data_and_png_path() {
+        let dir = std::env::temp_dir().join(format!("stress-plot-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stress.gp");
+        let mut level = result(9_500, 2_500);
+        level.push_p99 = 120;
+        write_gnuplot(&[level], &path).unwrap();
+
+        let script = std::fs::read_to_string(&path).unwrap();
+        assert!(script.contains("\n1 200000 9500 120 2500\n"));
+        assert!(script.contains(&format!("set output '{}'", dir.join("stress.png").display())));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batch_tuner_cuts_over_budget_and_grows_well_under_it() {
+        let mut tuner = BatchTuner { budget_us: 1_000, batch: 100 };
+        tuner.observe(1_001);
+        assert_eq!(tuner.batch, 70);
+        // Between 80% and 100% of the budget, both ends included, it holds
+        tuner.observe(1_000);
+        tuner.observe(800);
+        assert_eq!(tuner.batch, 70);
+        tuner.observe(799);
+        assert_eq!(tuner.batch, 87);
+    }
+
+    #[test]
+    fn batch_tuner_stays_within_its_bounds() {
+        let mut tuner = BatchTuner { budget_us: 1_000, batch: 1 };
+        tuner.observe(5_000);
+        assert_eq!(tuner.batch, AUTOTUNE_MIN_BATCH);
+        // A 25% step rounds to nothing on a tiny batch, so it grows by one
+        tuner.observe(0);
+        assert_eq!(tuner.batch, 2);
+
+        let mut tuner = BatchTuner { budget_us: 1_000, batch: 9_000 };
+        tuner.observe(0);
+        assert_eq!(tuner.batch, AUTOTUNE_MAX_BATCH);
+        tuner.observe(0);
+        assert_eq!(tuner.batch, AUTOTUNE_MAX_BATCH);
+    }
+
+    #[test]
+    fn autotune_settles_on_the_largest_batch_within_budget() {
+        let history = [(100, 10_000, 400), (125, 12_000, 900), (156, 14_000, 1_200), (109, 11_000, 1_000)];
+        assert_eq!(largest_within_budget(&history, 1_000), Some(&(125, 12_000, 900)));
+        assert_eq!(largest_within_budget(&history, 300), None);
+        assert_eq!(largest_within_budget(&[], 1_000), None);
+    }
+
+    #[test]
+    fn matrix_picks_the_peak_and_the_fewest_workers_near_it() {
+        let cells = vec![
+            (1, 4096, result(60_000, 0)),
+            (2, 4096, result(95_000, 0)),
+            (2, 65536, result(96_000, 0)),
+            (4, 4096, result(100_000, 0)),
+            (8, 4096, result(99_000, 0)),
+        ];
+        let (peak, smallest) = matrix_picks(&cells).unwrap();
+        assert_eq!((peak.0, peak.1), (4, 4096));
+        // 95_000 is exactly 95% of the peak, and the smaller buffer wins the tie on workers
+        assert_eq!((smallest.0, smallest.1), (2, 4096));
+
+        assert!(matrix_picks(&cells[..1]).is_some_and(|(p, s)| p.0 == 1 && s.0 == 1));
+        assert!(matrix_picks(&[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn matrix_rejects_zero_workers_before_running() {
+        let err = run_matrix(1, 0, &[2, 0], &[4096]).await.unwrap_err().to_string();
+        assert!(err.contains("at least 1"), "{err}");
+    }
+}
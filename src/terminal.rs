@@ -0,0 +1,76 @@
+//! What the terminal running the TUI can do. Consoles without the alternate
+//! screen, Unicode box drawing or 256 colors get a plainer TUI rather than a
+//! garbled one, and one without raw mode gets no TUI at all.
+
+use std::io::{self, IsTerminal};
+
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+
+use crate::config::CompatMode;
+
+/// Colors the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// The 16 basic ANSI colors only.
+    Ansi16,
+    /// The xterm 256-color palette or more.
+    Indexed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermCaps {
+    /// Draw in a separate screen that is restored on exit; without it the
+    /// TUI clears and draws over the scrollback.
+    pub alternate_screen: bool,
+    pub mouse: bool,
+    /// Box-drawing borders and arrow glyphs; ASCII otherwise.
+    pub unicode: bool,
+    pub colors: ColorDepth,
+}
+
+impl TermCaps {
+    pub const FULL: Self = Self { alternate_screen: true, mouse: true, unicode: true, colors: ColorDepth::Indexed };
+    pub const BASIC: Self = Self { alternate_screen: false, mouse: false, unicode: false, colors: ColorDepth::Ansi16 };
+
+    /// The capabilities `[tui] compat` asks for, detecting them from the
+    /// environment under `auto`.
+    pub fn for_mode(mode: CompatMode) -> Self {
+        match mode {
+            CompatMode::Auto => Self::detect(|name| std::env::var(name).ok(), cfg!(windows)),
+            CompatMode::Full => Self::FULL,
+            CompatMode::Basic => Self::BASIC,
+        }
+    }
+
+    /// Guess from `TERM`, `COLORTERM` and the locale, or on Windows from
+    /// whether this is Windows Terminal or the legacy console host.
+    pub fn detect(env: impl Fn(&str) -> Option<String>, windows: bool) -> Self {
+        let set = |name: &str| env(name).is_some_and(|v| !v.is_empty());
+        if windows {
+            // Windows Terminal and VS Code handle VT sequences; conhost may not
+            return if set("WT_SESSION") || set("TERM_PROGRAM") { Self::FULL } else { Self::BASIC };
+        }
+
+        let term = env("TERM").unwrap_or_default();
+        if term.is_empty() || term == "dumb" {
+            return Self::BASIC;
+        }
+        // DEC terminals and their emulations have no alternate screen buffer
+        let vt = term.starts_with("vt");
+        let colorterm = env("COLORTERM").unwrap_or_default();
+        let colors = if term.contains("256color") || colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::Indexed
+        } else {
+            ColorDepth::Ansi16
+        };
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().find_map(|name| env(name).filter(|v| !v.is_empty()));
+        let unicode = !vt && locale.is_some_and(|l| l.to_ascii_lowercase().replace('-', "").contains("utf8"));
+        Self { alternate_screen: !vt, mouse: !vt, unicode, colors }
+    }
+}
+
+/// Whether stdin and stdout are a terminal that can be put in raw mode,
+/// which the TUI needs to read keys. Leaves the terminal as it found it.
+pub fn raw_mode_available() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal() && enable_raw_mode().and_then(|_| disable_raw_mode()).is_ok()
+}
@@ -0,0 +1,230 @@
+//! Helpers for driving the detection pipeline with known data in tests.
+//!
+//! ```ignore
+//! let tp = TestPipeline::new().await?;
+//! tp.push_and_seal(trades, vec![], 120_000);
+//! let rows = tp.expect_rows(tp.ohlc_vol(), |r| r.symbol == "TSLA", Duration::from_secs(5)).await;
+//! ```
+
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+use laminar_db::{FromBatch, TypedSubscription};
+
+use crate::alerts::Alert;
+use crate::detection::{self, DetectionPipeline, PipelineOptions};
+use crate::engine::Engine;
+use crate::types::*;
+
+/// How long a subscription must stay empty after producing rows before its
+/// output is treated as settled.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(300);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+pub struct TestPipeline {
+    pub pipeline: DetectionPipeline,
+}
+
+impl Deref for TestPipeline {
+    type Target = DetectionPipeline;
+
+    fn deref(&self) -> &DetectionPipeline {
+        &self.pipeline
+    }
+}
+
+impl TestPipeline {
+    pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_options(&PipelineOptions::default()).await
+    }
+
+    /// Use `options.extra_streams` to add custom streams, then `subscribe` to them.
+    pub async fn with_options(options: &PipelineOptions) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self { pipeline: detection::setup_with(options).await? })
+    }
+
+    /// Subscribe to a stream by name, e.g. one added through `extra_streams`.
+    pub fn subscribe<T: Clone + FromBatch>(&self, name: &str) -> Result<TypedSubscription<T>, Box<dyn std::error::Error>> {
+        Ok(self.pipeline.db.subscribe::<T>(name)?)
+    }
+
+    /// Push trades and orders, then advance every source's watermark so each
+    /// window ending before `watermark` closes.
+    pub fn push_and_seal(&self, trades: Vec<Trade>, orders: Vec<Order>, watermark: i64) {
+        if !trades.is_empty() {
+            self.pipeline.trade_source.push_batch(trades);
+        }
+        if !orders.is_empty() {
+            self.pipeline.order_source.push_batch(orders);
+        }
+        self.pipeline.trade_source.watermark(watermark);
+        self.pipeline.order_source.watermark(watermark);
+        self.pipeline.book_source.watermark(watermark);
+        self.pipeline.news_source.watermark(watermark);
+    }
+
+    /// Push order-book levels, then advance every watermark to `watermark`.
+    pub fn push_book_and_seal(&self, levels: Vec<BookLevel>, watermark: i64) {
+        if !levels.is_empty() {
+            self.pipeline.book_source.push_batch(levels);
+        }
+        self.push_and_seal(Vec::new(), Vec::new(), watermark);
+    }
+
+    /// Push news events, then advance every watermark to `watermark`.
+    pub fn push_news_and_seal(&self, news: Vec<NewsEvent>, watermark: i64) {
+        if !news.is_empty() {
+            self.pipeline.news_source.push_batch(news);
+        }
+        self.push_and_seal(Vec::new(), Vec::new(), watermark);
+    }
+
+    /// Poll until the stream's output settles, returning every row seen.
+    /// `timeout` is only reached when the stream produces nothing at all.
+    pub async fn collect<T: Clone + FromBatch>(&self, stream: &TypedSubscription<T>, timeout: Duration) -> Vec<T> {
+        collect(stream, timeout).await
+    }
+
+    /// Poll until at least one row matches `predicate` and the output settles,
+    /// returning the matching rows. Panics with every row seen if none match
+    /// before `timeout`.
+    pub async fn expect_rows<T, F>(&self, stream: &TypedSubscription<T>, predicate: F, timeout: Duration) -> Vec<T>
+    where
+        T: Clone + Debug + FromBatch,
+        F: Fn(&T) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut seen = Vec::new();
+        let mut last_match = None;
+        while Instant::now() < deadline {
+            while let Some(rows) = stream.poll() {
+                if rows.iter().any(&predicate) {
+                    last_match = Some(Instant::now());
+                }
+                seen.extend(rows);
+            }
+            if last_match.is_some_and(|t| t.elapsed() >= QUIET_PERIOD) {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        while let Some(rows) = stream.poll() {
+            seen.extend(rows);
+        }
+
+        let matching: Vec<T> = seen.iter().filter(|r| predicate(r)).cloned().collect();
+        assert!(!matching.is_empty(), "no matching rows within {:?}; saw {} rows: {:?}", timeout, seen.len(), seen);
+        matching
+    }
+
+    /// Wait the full `timeout` and panic if any row matches `predicate`.
+    pub async fn expect_no_rows<T, F>(&self, stream: &TypedSubscription<T>, predicate: F, timeout: Duration)
+    where
+        T: Clone + Debug + FromBatch,
+        F: Fn(&T) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        let mut matching = Vec::new();
+        while Instant::now() < deadline {
+            while let Some(rows) = stream.poll() {
+                matching.extend(rows.into_iter().filter(|r| predicate(r)));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        assert!(matching.is_empty(), "expected no matching rows, got {}: {:?}", matching.len(), matching);
+    }
+
+    pub fn vol_baseline(&self) -> &TypedSubscription<VolumeBaseline> {
+        self.pipeline.vol_baseline_sub.as_ref().expect("vol_baseline stream should exist")
+    }
+
+    pub fn ohlc_vol(&self) -> &TypedSubscription<OhlcVolatility> {
+        self.pipeline.ohlc_vol_sub.as_ref().expect("ohlc_vol stream should exist")
+    }
+
+    pub fn rapid_fire(&self) -> &TypedSubscription<RapidFireBurst> {
+        self.pipeline.rapid_fire_sub.as_ref().expect("rapid_fire stream should exist")
+    }
+
+    pub fn wash_score(&self) -> &TypedSubscription<WashScore> {
+        self.pipeline.wash_score_sub.as_ref().expect("wash_score stream should exist")
+    }
+
+    pub fn suspicious_match(&self) -> &TypedSubscription<SuspiciousMatch> {
+        self.pipeline.suspicious_match_sub.as_ref().expect("suspicious_match stream should exist")
+    }
+
+    pub fn account_activity(&self) -> &TypedSubscription<AccountActivity> {
+        self.pipeline.account_activity_sub.as_ref().expect("account_activity stream should exist")
+    }
+
+    pub fn book_imbalance(&self) -> &TypedSubscription<BookImbalance> {
+        self.pipeline.book_imbalance_sub.as_ref().expect("book_imbalance stream should exist")
+    }
+
+    pub fn momentum_burst(&self) -> &TypedSubscription<MomentumBurst> {
+        self.pipeline.momentum_burst_sub.as_ref().expect("momentum_burst stream should exist")
+    }
+
+    pub fn pre_news(&self) -> &TypedSubscription<PreNewsTrade> {
+        self.pipeline.pre_news_sub.as_ref().expect("pre_news stream should exist")
+    }
+
+    pub fn price_improvement(&self) -> &TypedSubscription<PriceImprovement> {
+        self.pipeline.price_improvement_sub.as_ref().expect("price_improvement stream should exist")
+    }
+
+    pub fn counterparty_volume(&self) -> &TypedSubscription<CounterpartyVolume> {
+        self.pipeline.counterparty_volume_sub.as_ref().expect("counterparty_volume stream should exist")
+    }
+
+    pub async fn shutdown(self) {
+        let _ = self.pipeline.db.shutdown().await;
+    }
+}
+
+/// Free-standing `TestPipeline::collect` for subscriptions owned elsewhere,
+/// e.g. by an `Engine`.
+pub async fn collect<T: Clone + FromBatch>(stream: &TypedSubscription<T>, timeout: Duration) -> Vec<T> {
+    let deadline = Instant::now() + timeout;
+    let mut results = Vec::new();
+    let mut last_row = None;
+    while Instant::now() < deadline {
+        while let Some(rows) = stream.poll() {
+            results.extend(rows);
+            last_row = Some(Instant::now());
+        }
+        if last_row.is_some_and(|t| t.elapsed() >= QUIET_PERIOD) {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    // Final drain
+    while let Some(rows) = stream.poll() {
+        results.extend(rows);
+    }
+    results
+}
+
+/// Poll `engine` until no stream has produced a row for `QUIET_PERIOD`,
+/// returning every alert raised. Waiting out each push this way keeps
+/// micro-batch boundaries, and therefore alert order, reproducible.
+pub async fn settle(engine: &mut Engine, cycle_ts: i64, timeout: Duration) -> Vec<Alert> {
+    let deadline = Instant::now() + timeout;
+    let gen_instant = Instant::now();
+    let mut alerts = Vec::new();
+    let mut last_count: u64 = engine.stream_counts.iter().sum();
+    let mut last_change = Instant::now();
+    while Instant::now() < deadline && last_change.elapsed() < QUIET_PERIOD {
+        alerts.extend(engine.poll(cycle_ts, gen_instant));
+        let count: u64 = engine.stream_counts.iter().sum();
+        if count != last_count {
+            last_count = count;
+            last_change = Instant::now();
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    alerts
+}
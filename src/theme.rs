@@ -0,0 +1,304 @@
+//! TUI color themes, selected with `[tui] theme` in the config.
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::border;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders};
+
+use crate::config::ThemeName;
+use crate::terminal::{ColorDepth, TermCaps};
+
+/// Panel borders for terminals without box-drawing characters.
+pub const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// The 16 ANSI colors as the VGA text palette draws them, which is what
+/// legacy consoles show; its brown yellow keeps dark oranges out of red.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (170, 0, 0)),
+    (Color::Green, (0, 170, 0)),
+    (Color::Yellow, (170, 85, 0)),
+    (Color::Blue, (0, 0, 170)),
+    (Color::Magenta, (170, 0, 170)),
+    (Color::Cyan, (0, 170, 170)),
+    (Color::Gray, (170, 170, 170)),
+    (Color::DarkGray, (85, 85, 85)),
+    (Color::LightRed, (255, 85, 85)),
+    (Color::LightGreen, (85, 255, 85)),
+    (Color::LightYellow, (255, 255, 85)),
+    (Color::LightBlue, (85, 85, 255)),
+    (Color::LightMagenta, (255, 85, 255)),
+    (Color::LightCyan, (85, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Styles by role. Draw code picks a role and adds its own modifiers, so a
+/// theme only decides what each role looks like.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// App name and the keys in the help overlay.
+    pub title: Style,
+    /// Secondary figures such as processing latency and medium severity.
+    pub accent: Style,
+    /// Table headers.
+    pub text: Style,
+    /// Hints, labels and idle counters.
+    pub muted: Style,
+    /// A heatmap symbol whose price hasn't moved.
+    pub neutral: Style,
+    /// Trades, active streams, rising prices, healthy gauges.
+    pub good: Style,
+    /// Alert counters, high severity, gauges filling up.
+    pub warn: Style,
+    /// Critical severity, stalled streams, falling prices, full gauges.
+    pub bad: Style,
+    /// Orders.
+    pub info: Style,
+    /// Shadow-mode counts.
+    pub shadow: Style,
+    /// `JOIN FAN-OUT`.
+    pub warn_banner: Style,
+    /// `DEGRADED`.
+    pub alarm_banner: Style,
+    /// Heatmap cells with 1-2, 3-5 and 6+ alerts in the last minute.
+    pub heat: [Style; 3],
+    /// Panel borders.
+    pub borders: border::Set,
+    /// Heatmap price direction: up, down, unchanged.
+    pub arrows: [char; 3],
+}
+
+impl Theme {
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self::dark(),
+            ThemeName::Light => Self::light(),
+            ThemeName::HighContrast => Self::high_contrast(),
+            ThemeName::Monochrome => Self::monochrome(),
+        }
+    }
+
+    /// A bordered panel titled `title`.
+    pub fn panel<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        Block::default().borders(Borders::ALL).border_set(self.borders).title(title)
+    }
+
+    /// This theme as `caps` can draw it: ASCII borders and arrows without
+    /// Unicode, and each color moved to the nearest ANSI one on 16 colors.
+    pub fn for_terminal(mut self, caps: &TermCaps) -> Self {
+        if !caps.unicode {
+            self.borders = ASCII_BORDER;
+            self.arrows = ['^', 'v', '.'];
+        }
+        if caps.colors == ColorDepth::Ansi16 {
+            for style in [
+                &mut self.title,
+                &mut self.accent,
+                &mut self.text,
+                &mut self.muted,
+                &mut self.neutral,
+                &mut self.good,
+                &mut self.warn,
+                &mut self.bad,
+                &mut self.info,
+                &mut self.shadow,
+                &mut self.warn_banner,
+                &mut self.alarm_banner,
+            ]
+            .into_iter()
+            .chain(self.heat.iter_mut())
+            {
+                style.fg = style.fg.map(ansi16);
+                style.bg = style.bg.map(ansi16);
+            }
+        }
+        self
+    }
+
+    fn dark() -> Self {
+        let fg = |c| Style::default().fg(c);
+        Self {
+            title: fg(Color::Cyan),
+            accent: fg(Color::Cyan),
+            text: fg(Color::White),
+            muted: fg(Color::DarkGray),
+            neutral: fg(Color::Gray),
+            good: fg(Color::Green),
+            warn: fg(Color::Yellow),
+            bad: fg(Color::Red),
+            info: fg(Color::Blue),
+            shadow: fg(Color::Magenta),
+            warn_banner: Style::default().fg(Color::Black).bg(Color::Yellow),
+            alarm_banner: Style::default().fg(Color::White).bg(Color::Red),
+            heat: [
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+                Style::default().fg(Color::Black).bg(Color::LightRed),
+                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            ],
+            borders: border::PLAIN,
+            arrows: ['▲', '▼', '·'],
+        }
+    }
+
+    /// For light terminal backgrounds, where yellow and white text wash out.
+    fn light() -> Self {
+        let fg = |c| Style::default().fg(c);
+        // xterm 256-color dark orange; readable on white, unlike yellow
+        let amber = Color::Indexed(130);
+        Self {
+            title: fg(Color::Blue),
+            accent: fg(Color::Blue),
+            text: fg(Color::Black),
+            muted: fg(Color::DarkGray),
+            neutral: fg(Color::DarkGray),
+            good: fg(Color::Green),
+            warn: fg(amber),
+            bad: fg(Color::Red),
+            info: fg(Color::Blue),
+            shadow: fg(Color::Magenta),
+            warn_banner: Style::default().fg(Color::White).bg(amber),
+            alarm_banner: Style::default().fg(Color::White).bg(Color::Red),
+            heat: [
+                Style::default().fg(Color::Black).bg(Color::LightYellow),
+                Style::default().fg(Color::Black).bg(Color::LightRed),
+                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            ],
+            borders: border::PLAIN,
+            arrows: ['▲', '▼', '·'],
+        }
+    }
+
+    /// Bright colors only, with hints in gray rather than dark gray.
+    fn high_contrast() -> Self {
+        let fg = |c| Style::default().fg(c);
+        Self {
+            title: fg(Color::LightCyan),
+            accent: fg(Color::LightCyan),
+            text: fg(Color::White),
+            muted: fg(Color::Gray),
+            neutral: fg(Color::White),
+            good: fg(Color::LightGreen),
+            warn: fg(Color::LightYellow),
+            bad: fg(Color::LightRed),
+            info: fg(Color::LightBlue),
+            shadow: fg(Color::LightMagenta),
+            warn_banner: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            alarm_banner: Style::default().fg(Color::White).bg(Color::Red),
+            heat: [
+                Style::default().fg(Color::Black).bg(Color::LightYellow),
+                Style::default().fg(Color::Black).bg(Color::LightRed),
+                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            ],
+            borders: border::PLAIN,
+            arrows: ['▲', '▼', '·'],
+        }
+    }
+
+    /// No color at all, for casting and recording: roles are told apart by
+    /// weight, dimming and reverse video.
+    fn monochrome() -> Self {
+        let m = |m| Style::default().add_modifier(m);
+        Self {
+            title: Style::default(),
+            accent: Style::default(),
+            text: Style::default(),
+            muted: m(Modifier::DIM),
+            neutral: m(Modifier::DIM),
+            good: Style::default(),
+            warn: m(Modifier::BOLD),
+            bad: m(Modifier::BOLD | Modifier::UNDERLINED),
+            info: Style::default(),
+            shadow: m(Modifier::ITALIC),
+            warn_banner: m(Modifier::REVERSED),
+            alarm_banner: m(Modifier::REVERSED),
+            heat: [m(Modifier::UNDERLINED), m(Modifier::REVERSED), m(Modifier::REVERSED | Modifier::BOLD)],
+            borders: border::PLAIN,
+            arrows: ['▲', '▼', '·'],
+        }
+    }
+}
+
+/// The nearest of the 16 ANSI colors to `color`; named colors and `Reset`
+/// pass through.
+pub fn ansi16(color: Color) -> Color {
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i @ 0..=15) => return ANSI16[i as usize].0,
+        Color::Indexed(i @ 16..=231) => {
+            // xterm 6x6x6 cube
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = i - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        Color::Indexed(i) => {
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+        other => return other,
+    };
+    let distance = |(r, g, b): (u8, u8, u8)| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, rgb.0) + d(g, rgb.1) + d(b, rgb.2)
+    };
+    ANSI16.iter().min_by_key(|(_, c)| distance(*c)).map(|(c, _)| *c).expect("palette is not empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn themes_are_selected_by_config_name() {
+        let config = Config::parse("[tui]\ntheme = \"high-contrast\"\n").unwrap();
+        assert_eq!(config.tui.theme, ThemeName::HighContrast);
+        assert_eq!(Theme::named(config.tui.theme).bad, Style::default().fg(Color::LightRed));
+        assert_eq!(Theme::named(ThemeName::default()).bad, Style::default().fg(Color::Red));
+        assert!(Config::parse("[tui]\ntheme = \"solarized\"\n").is_err());
+    }
+
+    #[test]
+    fn monochrome_uses_no_color() {
+        let theme = Theme::named(ThemeName::Monochrome);
+        let styles = [theme.title, theme.muted, theme.good, theme.warn, theme.bad, theme.warn_banner, theme.alarm_banner].into_iter().chain(theme.heat);
+        for style in styles {
+            assert_eq!((style.fg, style.bg), (None, None));
+        }
+        assert_ne!(theme.warn, theme.bad, "roles stay apart by modifier");
+    }
+
+    #[test]
+    fn ansi16_picks_the_nearest_basic_color() {
+        assert_eq!(ansi16(Color::Indexed(3)), Color::Yellow);
+        // The light theme's amber (175, 95, 0) lands on the VGA brown yellow, not red
+        assert_eq!(ansi16(Color::Indexed(130)), Color::Yellow);
+        assert_eq!(ansi16(Color::Indexed(232)), Color::Black);
+        assert_eq!(ansi16(Color::Indexed(255)), Color::White);
+        assert_eq!(ansi16(Color::Rgb(0, 160, 180)), Color::Cyan);
+        assert_eq!(ansi16(Color::LightRed), Color::LightRed);
+        assert_eq!(ansi16(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn basic_terminals_get_ascii_borders_and_ansi_colors() {
+        let light = Theme::named(ThemeName::Light);
+        let full = light.for_terminal(&TermCaps::FULL);
+        assert_eq!((full.warn, full.arrows), (light.warn, light.arrows));
+
+        let basic = light.for_terminal(&TermCaps::BASIC);
+        assert_eq!(basic.borders.top_left, "+");
+        assert_eq!(basic.arrows, ['^', 'v', '.']);
+        assert_eq!(basic.warn.fg, Some(Color::Yellow));
+        assert_eq!(basic.warn_banner.bg, Some(Color::Yellow));
+        assert_eq!(basic.bad, light.bad, "named colors pass through");
+    }
+}
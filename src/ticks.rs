@@ -0,0 +1,71 @@
+//! Tick-level rate-of-change detection. Runs in the engine on every pushed
+//! trade, outside LaminarDB, so a sharp move is seen the cycle it happens
+//! rather than when its 5s `ohlc_vol` bar closes.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::config::TickConfig;
+use crate::types::Trade;
+
+/// Longest `[ticks] window_ms`; longer horizons are what the SQL bars are for.
+/// Also the evidence window of a PriceVelocity alert.
+pub const TICK_MAX_WINDOW_MS: i64 = 10_000;
+
+/// A symbol's price moving `pct` (signed, as a fraction) from `from_price`
+/// at `from_ts` to `to_price` at `ts`.
+#[derive(Debug, Clone)]
+pub struct TickMove {
+    pub symbol: String,
+    pub from_price: f64,
+    pub to_price: f64,
+    pub from_ts: i64,
+    pub ts: i64,
+    pub pct: f64,
+}
+
+/// Keeps each symbol's trade prices over the last `window_ms` and reports a
+/// move when the newest price is `move_pct` or more away from any of them.
+/// A symbol's history is cleared when it moves, so one move fires once.
+pub struct TickDetector {
+    move_pct: f64,
+    window_ms: i64,
+    ticks: HashMap<String, VecDeque<(i64, f64)>>,
+}
+
+impl TickDetector {
+    /// The detector `[ticks]` describes, or `None` while `move_pct` is 0.
+    pub fn from_config(config: &TickConfig) -> Result<Option<Self>, String> {
+        if config.move_pct == 0.0 {
+            return Ok(None);
+        }
+        if !(config.move_pct > 0.0 && config.move_pct.is_finite()) {
+            return Err(format!("[ticks] move_pct must be positive, got {}", config.move_pct));
+        }
+        if config.window_ms == 0 || config.window_ms as i64 > TICK_MAX_WINDOW_MS {
+            return Err(format!("[ticks] window_ms must be 1..={TICK_MAX_WINDOW_MS}, got {}", config.window_ms));
+        }
+        Ok(Some(Self { move_pct: config.move_pct, window_ms: config.window_ms as i64, ticks: HashMap::new() }))
+    }
+
+    /// Feed one batch of trades, in event-time order per symbol, and return
+    /// the moves they complete.
+    pub fn observe(&mut self, trades: &[Trade]) -> Vec<TickMove> {
+        let mut moves = Vec::new();
+        for t in trades.iter().filter(|t| t.price > 0.0) {
+            let history = self.ticks.entry(t.symbol.clone()).or_default();
+            while history.front().is_some_and(|&(ts, _)| ts < t.ts - self.window_ms) {
+                history.pop_front();
+            }
+            let farthest = history
+                .iter()
+                .map(|&(ts, price)| (ts, price, (t.price - price) / price))
+                .max_by(|a, b| a.2.abs().total_cmp(&b.2.abs()));
+            if let Some((from_ts, from_price, pct)) = farthest.filter(|m| m.2.abs() >= self.move_pct) {
+                moves.push(TickMove { symbol: t.symbol.clone(), from_price, to_price: t.price, from_ts, ts: t.ts, pct });
+                history.clear();
+            }
+            history.push_back((t.ts, t.price));
+        }
+        moves
+    }
+}
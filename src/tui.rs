@@ -1,261 +1,1144 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
 use crossterm::execute;
 use crossterm::terminal::{
-    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    self as term, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
+use futures::StreamExt;
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
+use ratatui::widgets::{Clear, Paragraph, Row, Table};
 use ratatui::Terminal;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio_tungstenite::tungstenite::Message;
 
-use crate::alerts::{Alert, AlertEngine, AlertSeverity};
-use crate::detection;
-use crate::generator::FraudGenerator;
-use crate::latency::LatencyTracker;
+use crate::alerts::{Alert, AlertEngine, AlertRetention, AlertSeverity, Cooldown};
+use crate::archive::AlertArchive;
+use crate::audit::AuditLog;
+use crate::blotter::{Blotter, Evidence};
+use crate::breakpoints::{BreakReason, Breakpoints};
+use crate::clock::SystemClock;
+use crate::config::{Config, KeysConfig};
+use crate::detection::{self, PipelineOptions};
+use crate::engine::{Engine, WatermarkStrategy, STREAM_NAMES};
+use crate::generator::{self, FraudGenerator};
+use crate::ids::{IdLedger, UidGenerator};
+use crate::latency::LatencyStats;
+use crate::limits::LoadGovernor;
+use crate::locale;
+use crate::notify::Notifier;
+use crate::pacing::Pacer;
+use crate::recording::{Pace, Player, RecordedCycle, Recorder};
+use crate::reviews::{AlertReviews, Disposition, ReviewUpdate};
+use crate::runs::RunRecord;
+use crate::search::AlertIndex;
+use crate::slo::{SloStatus, SloTracker};
+use crate::stop::StopConditions;
+use crate::terminal::{self, TermCaps};
+use crate::theme::Theme;
+use crate::ticks::TickDetector;
+use crate::web::{DashboardUpdate, WebPublisher};
 
 struct App {
+    /// Latest totals; its `alerts` are moved into the feed as they arrive.
+    view: DashboardUpdate,
     alerts: VecDeque<Alert>,
-    latency: LatencyTracker,
-    alert_engine: AlertEngine,
-    stream_counts: [u64; 6],
-    total_trades: u64,
-    total_orders: u64,
-    total_alerts: u64,
-    uptime: Instant,
+    /// `[retention]`: how many alerts, and how old, the feed keeps.
+    retention: AlertRetention,
     should_quit: bool,
     scroll_offset: usize,
-    prices: std::collections::HashMap<String, f64>,
+    title: String,
+    /// Tape for evidence replay; only a local engine has one.
+    blotter: Option<Blotter>,
+    /// Evidence of the alert at the top of the feed, shown in place of it.
+    evidence: Option<Evidence>,
+    /// Notes and dispositions; only a local engine has them.
+    reviews: Option<AlertReviews>,
+    /// Note being typed; while set, keys go into it.
+    note: Option<NoteInput>,
+    /// Why the last review could not be saved.
+    review_error: Option<String>,
+    heat: SymbolHeat,
+    keys: Keymap,
+    show_help: bool,
+    /// Local runs only.
+    notifier: Option<Notifier>,
+    refresh: Refresh,
+    theme: Theme,
+    /// Feed filter; while `editing`, keys go into the query.
+    search: Option<Search>,
+    /// Entity the feed follows, on top of any search.
+    pin: Option<Pin>,
+    /// Stream tab selected, showing only that detection stream's alerts;
+    /// none for the `all` tab.
+    tab: Option<&'static str>,
+    /// Set by the reset key until the engine loop takes it.
+    reset_stats: bool,
+    /// The breakpoint the engine is stopped at; no events are generated until resumed.
+    paused: Option<BreakReason>,
+    /// Set by the resume key while paused, until the engine loop takes it.
+    resume: bool,
+    /// Something on screen changed since the last frame.
+    dirty: bool,
+    last_draw: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct Search {
+    query: String,
+    editing: bool,
+    /// Once the query is entered, the blotter's ranked hits for it, which
+    /// reach past the feed's `[retention]`. Without a blotter (`attach`) the
+    /// feed is filtered instead.
+    ranked: Option<Vec<Alert>>,
+}
+
+/// Most ranked search hits the feed shows.
+const SEARCH_RESULTS: usize = 500;
+
+impl Search {
+    /// Case-insensitive substring match on account, symbol or description.
+    fn matches(&self, alert: &Alert) -> bool {
+        let query = self.query.to_lowercase();
+        let description = alert.description().to_string();
+        let found = [alert.account_id.as_deref(), alert.symbol.as_deref(), Some(description.as_str())]
+            .into_iter()
+            .flatten()
+            .any(|field| field.to_lowercase().contains(&query));
+        found
+    }
+}
+
+/// A note being typed for `alert`.
+#[derive(Debug)]
+struct NoteInput {
+    alert: Alert,
+    text: String,
+}
+
+/// An account or symbol pinned from the feed, for following one suspect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pin {
+    Account(String),
+    Symbol(String),
+}
+
+impl Pin {
+    fn matches(&self, alert: &Alert) -> bool {
+        match self {
+            Pin::Account(id) => alert.account_id.as_deref() == Some(id),
+            Pin::Symbol(sym) => alert.symbol.as_deref() == Some(sym),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Pin::Account(id) => format!(" PINNED account {id} "),
+            Pin::Symbol(sym) => format!(" PINNED symbol {sym} "),
+        }
+    }
+}
+
+/// How often the TUI redraws. The engine cycle and input polling keep their
+/// own cadence, so a slower refresh doesn't change the load.
+#[derive(Debug, Clone, Copy)]
+pub struct Refresh {
+    pub interval: Duration,
+    /// Skip frames where nothing changed: no input, no new alerts, no flag
+    /// flips, and the same uptime second.
+    pub low_cpu: bool,
+}
+
+/// Longest each loop waits for input; a local engine's cycle can cut it short.
+const INPUT_POLL: Duration = Duration::from_millis(150);
+
+/// Something a key can be bound to in `[keys]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    Evidence,
+    ScrollUp,
+    ScrollDown,
+    Search,
+    NextMatch,
+    PrevMatch,
+    PinAccount,
+    PinSymbol,
+    ResetStats,
+    Resume,
+    TruePositive,
+    FalsePositive,
+    Benign,
+    Note,
+    NextTab,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Quit,
+        Action::Help,
+        Action::Evidence,
+        Action::ScrollUp,
+        Action::ScrollDown,
+        Action::Search,
+        Action::NextMatch,
+        Action::PrevMatch,
+        Action::PinAccount,
+        Action::PinSymbol,
+        Action::ResetStats,
+        Action::Resume,
+        Action::TruePositive,
+        Action::FalsePositive,
+        Action::Benign,
+        Action::Note,
+        Action::NextTab,
+    ];
+
+    /// The action's key in `[keys]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Evidence => "evidence",
+            Action::ScrollUp => "scroll_up",
+            Action::ScrollDown => "scroll_down",
+            Action::Search => "search",
+            Action::NextMatch => "next_match",
+            Action::PrevMatch => "prev_match",
+            Action::PinAccount => "pin_account",
+            Action::PinSymbol => "pin_symbol",
+            Action::ResetStats => "reset_stats",
+            Action::Resume => "resume",
+            Action::TruePositive => "true_positive",
+            Action::FalsePositive => "false_positive",
+            Action::Benign => "benign",
+            Action::Note => "note",
+            Action::NextTab => "next_tab",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show or close this help",
+            Action::Evidence => "Show or close the evidence of the top alert",
+            Action::ScrollUp => "Scroll the alert feed up",
+            Action::ScrollDown => "Scroll the alert feed down",
+            Action::Search => "Filter the feed by account, symbol or description",
+            Action::NextMatch => "Next older search match",
+            Action::PrevMatch => "Next newer search match",
+            Action::PinAccount => "Follow the top alert's account, or unpin",
+            Action::PinSymbol => "Follow the top alert's symbol, or unpin",
+            Action::ResetStats => "Zero latency and throughput stats (local engine only)",
+            Action::Resume => "Carry on from a breakpoint (local engine or replay)",
+            Action::TruePositive => "Mark the top alert a true positive (local engine only)",
+            Action::FalsePositive => "Mark the top alert a false positive (local engine only)",
+            Action::Benign => "Mark the top alert benign (local engine only)",
+            Action::Note => "Add a note to the top alert (local engine only)",
+            Action::NextTab => "Show the next stream tab's alerts",
+        }
+    }
+
+    fn configured(self, keys: &KeysConfig) -> &[String] {
+        match self {
+            Action::Quit => &keys.quit,
+            Action::Help => &keys.help,
+            Action::Evidence => &keys.evidence,
+            Action::ScrollUp => &keys.scroll_up,
+            Action::ScrollDown => &keys.scroll_down,
+            Action::Search => &keys.search,
+            Action::NextMatch => &keys.next_match,
+            Action::PrevMatch => &keys.prev_match,
+            Action::PinAccount => &keys.pin_account,
+            Action::PinSymbol => &keys.pin_symbol,
+            Action::ResetStats => &keys.reset_stats,
+            Action::Resume => &keys.resume,
+            Action::TruePositive => &keys.true_positive,
+            Action::FalsePositive => &keys.false_positive,
+            Action::Benign => &keys.benign,
+            Action::Note => &keys.note,
+            Action::NextTab => &keys.next_tab,
+        }
+    }
+}
+
+/// `[keys]` resolved to key codes. A key bound to two actions is an error.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyCode, Action)>,
+}
+
+impl Keymap {
+    pub fn from_config(keys: &KeysConfig) -> Result<Self, String> {
+        let mut bindings: Vec<(KeyCode, Action)> = Vec::new();
+        for action in Action::ALL {
+            for name in action.configured(keys) {
+                let code = parse_key(name).ok_or_else(|| format!("[keys] {}: unknown key '{name}'", action.name()))?;
+                match bindings.iter().find(|(c, _)| *c == code) {
+                    Some((_, other)) if *other != action => {
+                        return Err(format!("[keys] '{name}' is bound to both {} and {}", other.name(), action.name()));
+                    }
+                    Some(_) => {}
+                    None => bindings.push((code, action)),
+                }
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        self.bindings.iter().find(|(c, _)| *c == code).map(|&(_, action)| action)
+    }
+
+    /// The keys bound to `action`, as `q/Esc`.
+    pub fn keys_for(&self, action: Action) -> String {
+        let names: Vec<String> = self.bindings.iter().filter(|(_, a)| *a == action).map(|(c, _)| key_name(*c)).collect();
+        if names.is_empty() {
+            "(unbound)".to_string()
+        } else {
+            names.join("/")
+        }
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 12] = [
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Enter", KeyCode::Enter),
+    ("Tab", KeyCode::Tab),
+    ("Esc", KeyCode::Esc),
+    ("Backspace", KeyCode::Backspace),
+];
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(KeyCode::Char(c)),
+        _ => NAMED_KEYS.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|&(_, code)| code),
+    }
+}
+
+fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(c) => c.to_string(),
+        _ => NAMED_KEYS.iter().find(|(_, c)| *c == code).map_or_else(|| format!("{code:?}"), |(n, _)| n.to_string()),
+    }
+}
+
+/// How far back the heatmap looks for price change and alert intensity.
+const HEAT_WINDOW: Duration = Duration::from_secs(60);
+/// Width of one heatmap cell, including the gap to its neighbour.
+const HEAT_CELL_WIDTH: u16 = 24;
+
+/// The last minute of prices and alerts per symbol, for the heatmap.
+#[derive(Default)]
+struct SymbolHeat {
+    prices: HashMap<String, VecDeque<(Instant, f64)>>,
+    alerts: HashMap<String, VecDeque<Instant>>,
+}
+
+impl SymbolHeat {
+    fn record(&mut self, now: Instant, update: &DashboardUpdate) {
+        for alert in &update.alerts {
+            if let Some(symbol) = &alert.symbol {
+                self.alerts.entry(symbol.clone()).or_default().push_back(now);
+            }
+        }
+        for (symbol, &price) in &update.prices {
+            self.prices.entry(symbol.clone()).or_default().push_back((now, price));
+        }
+        let cutoff = now.checked_sub(HEAT_WINDOW).unwrap_or(now);
+        for samples in self.prices.values_mut() {
+            // Keep one sample at or before the cutoff as the baseline
+            while samples.len() > 1 && samples[1].0 <= cutoff {
+                samples.pop_front();
+            }
+        }
+        for times in self.alerts.values_mut() {
+            while times.front().is_some_and(|&t| t < cutoff) {
+                times.pop_front();
+            }
+        }
+    }
+
+    /// Price change over the window, as a fraction of the oldest sample.
+    fn change(&self, symbol: &str) -> f64 {
+        match self.prices.get(symbol).map(|s| (s.front(), s.back())) {
+            Some((Some(&(_, first)), Some(&(_, last)))) if first > 0.0 => (last - first) / first,
+            _ => 0.0,
+        }
+    }
+
+    fn alert_count(&self, symbol: &str) -> usize {
+        self.alerts.get(symbol).map_or(0, VecDeque::len)
+    }
 }
 
 impl App {
-    fn new() -> Self {
+    fn new(title: String, keys: Keymap, refresh: Refresh, theme: Theme, retention: AlertRetention) -> Self {
         Self {
-            alerts: VecDeque::with_capacity(200),
-            latency: LatencyTracker::new(),
-            alert_engine: AlertEngine::new(),
-            stream_counts: [0; 6],
-            total_trades: 0,
-            total_orders: 0,
-            total_alerts: 0,
-            uptime: Instant::now(),
+            view: DashboardUpdate::default(),
+            alerts: VecDeque::new(),
+            retention,
             should_quit: false,
             scroll_offset: 0,
-            prices: std::collections::HashMap::new(),
+            title,
+            blotter: None,
+            evidence: None,
+            reviews: None,
+            note: None,
+            review_error: None,
+            heat: SymbolHeat::default(),
+            keys,
+            show_help: false,
+            notifier: None,
+            refresh,
+            theme,
+            search: None,
+            pin: None,
+            tab: None,
+            reset_stats: false,
+            paused: None,
+            resume: false,
+            dirty: true,
+            last_draw: None,
         }
     }
 
-    fn add_alert(&mut self, alert: Alert) {
-        self.total_alerts += 1;
-        if self.alerts.len() >= 200 {
-            self.alerts.pop_front();
+    fn apply(&mut self, mut update: DashboardUpdate) {
+        self.heat.record(Instant::now(), &update);
+        self.dirty |= !update.alerts.is_empty()
+            || update.uptime_secs != self.view.uptime_secs
+            || update.degraded != self.view.degraded
+            || update.join_overloaded != self.view.join_overloaded
+            || update.slo != self.view.slo;
+        for alert in std::mem::take(&mut update.alerts) {
+            self.add_alert(alert);
         }
-        self.alerts.push_back(alert);
+        // Aged-out alerts leave the feed even when nothing new arrives
+        self.retention.trim(&mut self.alerts, chrono::Utc::now().timestamp_millis());
+        self.view = update;
+        self.rank_search();
     }
-}
 
-pub async fn run(fraud_rate: f64, duration: u64) -> Result<(), Box<dyn std::error::Error>> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    /// Refresh the ranked hits of an entered search, if there's a blotter.
+    fn rank_search(&mut self) {
+        if let (Some(blotter), Some(search)) = (&self.blotter, self.search.as_mut().filter(|s| !s.editing)) {
+            search.ranked = Some(blotter.search(&search.query, SEARCH_RESULTS));
+        }
+    }
 
-    let result = run_app(&mut terminal, fraud_rate, duration).await;
+    /// The feed as shown: alerts from the selected stream tab for the
+    /// pinned entity that match the search, best match first once it's
+    /// ranked, else newest first; every alert when none is set.
+    fn visible(&self) -> Box<dyn Iterator<Item = &Alert> + '_> {
+        let pinned = |a: &&Alert| self.pin.as_ref().is_none_or(|p| p.matches(a)) && self.tab.is_none_or(|t| a.alert_type.stream() == Some(t));
+        match self.search.as_ref().and_then(|s| s.ranked.as_ref()) {
+            Some(ranked) => Box::new(ranked.iter().filter(pinned)),
+            None => Box::new(self.alerts.iter().rev().filter(pinned).filter(|a| self.search.as_ref().is_none_or(|s| s.matches(a)))),
+        }
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+    /// The `n`th alert of the feed as shown.
+    fn nth_visible(&self, n: usize) -> Option<&Alert> {
+        self.visible().nth(n)
+    }
 
-    result
-}
+    /// Pin the account or symbol of the alert at the top of the feed, or
+    /// unpin when something is already pinned.
+    fn toggle_pin(&mut self, pin: fn(&Alert) -> Option<Pin>) {
+        if self.pin.is_none() {
+            self.pin = self.nth_visible(self.scroll_offset).and_then(pin);
+        } else {
+            self.pin = None;
+        }
+        self.scroll_offset = 0;
+    }
 
-async fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    fraud_rate: f64,
-    duration: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pipeline = detection::setup().await?;
-    let mut gen = FraudGenerator::new(fraud_rate);
-    let mut app = App::new();
+    /// The feed's tabs: `all`, then each stream with an alert in the feed,
+    /// in `STREAM_NAMES` order. The selected tab stays even once its alerts
+    /// have aged out.
+    fn tabs(&self) -> Vec<Option<&'static str>> {
+        let streams = STREAM_NAMES.iter().filter(|&&s| self.tab == Some(s) || self.alerts.iter().any(|a| a.alert_type.stream() == Some(s)));
+        std::iter::once(None).chain(streams.map(|&s| Some(s))).collect()
+    }
 
-    let run_duration = if duration == 0 {
-        Duration::from_secs(3600)
-    } else {
-        Duration::from_secs(duration)
-    };
+    fn select_tab(&mut self, tab: Option<&'static str>) {
+        self.tab = tab;
+        self.evidence = None;
+        self.scroll_offset = 0;
+    }
 
-    while !app.should_quit && app.uptime.elapsed() < run_duration {
-        terminal.draw(|f| draw(f, &app))?;
-
-        // Handle input
-        if event::poll(Duration::from_millis(150))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
-                        KeyCode::Up => {
-                            if app.scroll_offset > 0 {
-                                app.scroll_offset -= 1;
-                            }
-                        }
-                        KeyCode::Down => {
-                            app.scroll_offset = app.scroll_offset.saturating_add(1);
-                        }
-                        _ => {}
-                    }
+    /// Select the tab after the current one, wrapping back to `all`.
+    fn next_tab(&mut self) {
+        let tabs = self.tabs();
+        let current = tabs.iter().position(|&t| t == self.tab).unwrap_or(0);
+        self.select_tab(tabs[(current + 1) % tabs.len()]);
+    }
+
+    /// The tab drawn at `column` of the tab row starting at `x`.
+    fn tab_at(&self, x: u16, column: u16) -> Option<Option<&'static str>> {
+        let mut start = x;
+        for tab in self.tabs() {
+            let end = start + tab_label(tab).len() as u16;
+            if (start..end).contains(&column) {
+                return Some(tab);
+            }
+            // One column for the divider
+            start = end + 1;
+        }
+        None
+    }
+
+    /// Stop at a breakpoint, with the feed scrolled back to the newest alert.
+    fn pause(&mut self, reason: BreakReason) {
+        self.paused = Some(reason);
+        self.scroll_offset = 0;
+        self.dirty = true;
+    }
+
+    fn add_alert(&mut self, alert: Alert) {
+        self.retention.push(&mut self.alerts, alert);
+    }
+
+    /// Draw a frame if the refresh interval has passed and, in low-CPU mode,
+    /// something changed.
+    fn draw_if_due(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
+        if self.frame_due(Instant::now()) {
+            terminal.draw(|f| draw(f, self))?;
+            self.last_draw = Some(Instant::now());
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Whether a frame is due at `now`: the refresh interval has passed
+    /// since the last one and, in low-CPU mode, something changed.
+    fn frame_due(&self, now: Instant) -> bool {
+        let due = self.last_draw.is_none_or(|t| now.duration_since(t) >= self.refresh.interval);
+        due && (self.dirty || !self.refresh.low_cpu)
+    }
+
+    /// Wait up to `wait` for a key press or mouse event and apply it.
+    fn handle_input(&mut self, wait: Duration) -> io::Result<()> {
+        if event::poll(wait)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key(key.code),
+                Event::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+                    let (width, height) = term::size()?;
+                    self.handle_mouse(mouse, layout(Rect::new(0, 0, width, height))[1]);
                 }
+                Event::Resize(..) => {}
+                _ => return Ok(()),
             }
+            // Answer input on the next pass, whatever the refresh interval
+            self.dirty = true;
+            self.last_draw = None;
         }
+        Ok(())
+    }
 
-        // Generate + push
-        let ts = FraudGenerator::now_ms();
-        let gen_instant = Instant::now();
-        let (trades, orders) = gen.generate_cycle(ts);
-        app.total_trades += trades.len() as u64;
-        app.total_orders += orders.len() as u64;
-
-        // Update prices from generator
-        for (sym, price) in gen.current_prices() {
-            app.prices.insert(sym.clone(), *price);
-        }
-
-        let push_start = app.latency.record_push_start();
-        pipeline.trade_source.push_batch(trades);
-        if !orders.is_empty() {
-            pipeline.order_source.push_batch(orders);
-        }
-        pipeline.trade_source.watermark(ts + 10_000);
-        pipeline.order_source.watermark(ts + 10_000);
-        app.latency.record_push_end(push_start);
-
-        // Poll all streams
-        if let Some(ref sub) = pipeline.vol_baseline_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[0] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_volume(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
-                    }
+    /// The wheel scrolls the feed; a click on a stream tab selects it, a
+    /// click on an alert opens its evidence, and a click on the evidence
+    /// view or the help closes it. `area` is the feed's, tab row included.
+    fn handle_mouse(&mut self, mouse: MouseEvent, area: Rect) {
+        let [tabs, feed] = feed_layout(area);
+        let at = (mouse.column, mouse.row).into();
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            MouseEventKind::ScrollDown => self.scroll_offset = self.scroll_offset.saturating_add(1),
+            MouseEventKind::Down(MouseButton::Left) if self.show_help => self.show_help = false,
+            MouseEventKind::Down(MouseButton::Left) if self.evidence.is_some() && area.contains(at) => self.evidence = None,
+            MouseEventKind::Down(MouseButton::Left) if tabs.contains(at) => {
+                if let Some(tab) = self.tab_at(tabs.x, mouse.column) {
+                    self.select_tab(tab);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) if feed.contains(at) => {
+                // Below the border and the header row
+                if let Some(row) = mouse.row.checked_sub(feed.y + 2) {
+                    self.show_evidence(self.scroll_offset + row as usize);
                 }
             }
+            _ => {}
         }
-        if let Some(ref sub) = pipeline.ohlc_vol_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[1] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_ohlc(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
-                    }
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        if let Some(note) = self.note.as_mut() {
+            match code {
+                KeyCode::Char(c) => note.text.push(c),
+                KeyCode::Backspace => {
+                    note.text.pop();
                 }
+                KeyCode::Enter => {
+                    let note = self.note.take().expect("editing a note");
+                    self.review(&note.alert, ReviewUpdate { disposition: None, note: Some(note.text) });
+                }
+                KeyCode::Esc => self.note = None,
+                _ => {}
             }
+            return;
         }
-        if let Some(ref sub) = pipeline.rapid_fire_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[2] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_rapid_fire(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
-                    }
+        if let Some(search) = self.search.as_mut().filter(|s| s.editing) {
+            match code {
+                KeyCode::Char(c) => search.query.push(c),
+                KeyCode::Backspace => {
+                    search.query.pop();
                 }
+                KeyCode::Enter if search.query.is_empty() => self.search = None,
+                KeyCode::Enter => search.editing = false,
+                KeyCode::Esc => self.search = None,
+                _ => return,
             }
+            self.scroll_offset = 0;
+            self.rank_search();
+            return;
         }
-        if let Some(ref sub) = pipeline.wash_score_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[3] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_wash(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
-                    }
+        // Esc closes an overlay, then clears the search, whatever the bindings say
+        if code == KeyCode::Esc && self.show_help {
+            self.show_help = false;
+            return;
+        }
+        if code == KeyCode::Esc && self.evidence.is_some() {
+            self.evidence = None;
+            return;
+        }
+        if code == KeyCode::Esc && self.search.is_some() {
+            self.search = None;
+            self.scroll_offset = 0;
+            return;
+        }
+        if code == KeyCode::Esc && self.pin.is_some() {
+            self.pin = None;
+            self.scroll_offset = 0;
+            return;
+        }
+        match self.keys.action(code) {
+            Some(Action::Quit) => self.should_quit = true,
+            Some(Action::Help) => self.show_help = !self.show_help,
+            Some(Action::Evidence) => self.toggle_evidence(),
+            Some(Action::ScrollUp) => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            Some(Action::ScrollDown) => self.scroll_offset = self.scroll_offset.saturating_add(1),
+            Some(Action::Search) => {
+                self.evidence = None;
+                self.search = Some(Search { editing: true, ..Default::default() });
+                self.scroll_offset = 0;
+            }
+            // Step the top of the filtered feed to the next older or newer match
+            Some(Action::NextMatch) if self.search.is_some() => {
+                let matches = self.visible().count();
+                self.scroll_offset = (self.scroll_offset + 1).min(matches.saturating_sub(1));
+            }
+            Some(Action::PrevMatch) if self.search.is_some() => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            Some(Action::PinAccount) => self.toggle_pin(|a| a.account_id.clone().map(Pin::Account)),
+            Some(Action::PinSymbol) => self.toggle_pin(|a| a.symbol.clone().map(Pin::Symbol)),
+            Some(Action::ResetStats) => self.reset_stats = true,
+            Some(Action::Resume) => self.resume = self.paused.is_some(),
+            Some(Action::TruePositive) => self.dispose(Disposition::TruePositive),
+            Some(Action::FalsePositive) => self.dispose(Disposition::FalsePositive),
+            Some(Action::Benign) => self.dispose(Disposition::Benign),
+            Some(Action::Note) if self.reviews.is_some() => {
+                if let Some(alert) = self.nth_visible(self.scroll_offset).cloned() {
+                    self.note = Some(NoteInput { alert, text: String::new() });
                 }
             }
+            Some(Action::Note) => {}
+            Some(Action::NextTab) => self.next_tab(),
+            Some(Action::NextMatch | Action::PrevMatch) | None => {}
         }
-        if let Some(ref sub) = pipeline.suspicious_match_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[4] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_match(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
+    }
+
+    /// Show or close the evidence of the alert at the top of the feed.
+    fn toggle_evidence(&mut self) {
+        if self.evidence.take().is_some() {
+            return;
+        }
+        self.show_evidence(self.scroll_offset);
+    }
+
+    /// Record `disposition` for the alert at the top of the feed.
+    fn dispose(&mut self, disposition: Disposition) {
+        if let Some(alert) = self.nth_visible(self.scroll_offset).cloned() {
+            self.review(&alert, ReviewUpdate { disposition: Some(disposition), note: None });
+        }
+    }
+
+    fn review(&mut self, alert: &Alert, update: ReviewUpdate) {
+        let Some(reviews) = &self.reviews else { return };
+        if update.is_empty() {
+            return;
+        }
+        self.review_error = reviews.review(alert, update).err().map(|e| e.to_string());
+    }
+
+    /// Show the evidence of the `nth` alert in the feed, when there's a tape to replay.
+    fn show_evidence(&mut self, nth: usize) {
+        if let (Some(blotter), Some(alert)) = (&self.blotter, self.nth_visible(nth)) {
+            self.evidence = Some(blotter.evidence_for(alert));
+        }
+    }
+}
+
+/// Drive the engine from the terminal UI. When `web` is set, the same engine
+/// also feeds that web dashboard; `[notify]` in `config` enables desktop
+/// notifications.
+pub async fn run(
+    fraud_rate: f64,
+    duration: u64,
+    config: &Config,
+    web: Option<WebPublisher>,
+    refresh: Refresh,
+) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    // Load the scoring model before taking over the terminal so errors are readable
+    let alert_engine = AlertEngine::from_config(config)?;
+    let caps = TermCaps::for_mode(config.tui.compat);
+    let theme = Theme::named(config.tui.theme).for_terminal(&caps);
+    let retention = alert_engine.retention();
+    let mut app = App::new(" Sentinel ".to_string(), Keymap::from_config(&config.keys)?, refresh, theme, retention);
+    app.notifier = Notifier::from_config(&config.notify)?;
+
+    let mut terminal = enter_terminal(&caps)?;
+    let result = run_app(&mut terminal, fraud_rate, duration, config, alert_engine, web, app).await;
+    leave_terminal(&mut terminal, &caps)?;
+    result
+}
+
+/// Render a remote engine's dashboard feed (`ws://host:port/ws`) instead of
+/// running a pipeline locally. Runs until quit; a dropped connection is shown
+/// in the header and the last state stays on screen.
+pub async fn attach(url: &str, config: &Config, refresh: Refresh) -> Result<(), Box<dyn std::error::Error>> {
+    if !terminal::raw_mode_available() {
+        return Err("attach needs an interactive terminal that supports raw mode".into());
+    }
+    // Connect before taking over the terminal so errors are readable
+    let caps = TermCaps::for_mode(config.tui.compat);
+    let theme = Theme::named(config.tui.theme).for_terminal(&caps);
+    let retention = AlertRetention::from_config(&config.retention)?;
+    let app = App::new(format!(" Sentinel @ {url} "), Keymap::from_config(&config.keys)?, refresh, theme, retention);
+    let (mut ws, _) = tokio_tungstenite::connect_async(url).await?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(Ok(msg)) = ws.next().await {
+            match msg {
+                // Frames from a different build may not parse; skip rather than drop the feed
+                Message::Text(json) => {
+                    let Ok(update) = serde_json::from_str::<DashboardUpdate>(&json) else { continue };
+                    if tx.send(update).is_err() {
+                        break;
                     }
                 }
+                Message::Close(_) => break,
+                _ => {}
             }
         }
-        if let Some(ref sub) = pipeline.asof_match_sub {
-            while let Some(rows) = sub.poll() {
-                app.latency.record_poll();
-                for row in &rows {
-                    app.stream_counts[5] += 1;
-                    if let Some(alert) = app.alert_engine.evaluate_asof(row, gen_instant) {
-                        app.latency.record_alert(gen_instant);
-                        app.add_alert(alert);
-                    }
+    });
+
+    let mut terminal = enter_terminal(&caps)?;
+    let result = attach_app(&mut terminal, app, rx, format!(" Sentinel @ {url} (disconnected) "));
+    leave_terminal(&mut terminal, &caps)?;
+    result?;
+    Ok(())
+}
+
+/// Render the TUI from a row recording's `cycles`, played through `player`
+/// `speed` times as fast as they were recorded and stopping at
+/// `[breakpoints]` as a run does. `name` titles the window.
+pub async fn replay(name: &str, cycles: Vec<RecordedCycle>, player: Player, speed: f64, config: &Config, refresh: Refresh) -> Result<(), Box<dyn std::error::Error>> {
+    if !terminal::raw_mode_available() {
+        return Err("replay --ui tui needs an interactive terminal that supports raw mode".into());
+    }
+    let caps = TermCaps::for_mode(config.tui.compat);
+    let theme = Theme::named(config.tui.theme).for_terminal(&caps);
+    let retention = AlertRetention::from_config(&config.retention)?;
+    let app = App::new(format!(" Sentinel (replay of {name}) "), Keymap::from_config(&config.keys)?, refresh, theme, retention);
+    let playback = Playback::new(cycles, player, speed, Breakpoints::from_config(&config.breakpoints)?);
+
+    let mut terminal = enter_terminal(&caps)?;
+    let result = replay_app(&mut terminal, app, playback, format!(" Sentinel (replay of {name}) (finished) "));
+    leave_terminal(&mut terminal, &caps)?;
+    result?;
+    Ok(())
+}
+
+/// A recording being played into the TUI at its recorded pace.
+struct Playback {
+    cycles: std::iter::Peekable<std::vec::IntoIter<RecordedCycle>>,
+    player: Player,
+    pace: Pace,
+    breakpoints: Breakpoints,
+    /// When the current pause began, to hold the pace back by its length.
+    paused_at: Option<Instant>,
+}
+
+impl Playback {
+    fn new(cycles: Vec<RecordedCycle>, player: Player, speed: f64, breakpoints: Breakpoints) -> Self {
+        Self { cycles: cycles.into_iter().peekable(), player, pace: Pace::new(speed), breakpoints, paused_at: None }
+    }
+
+    /// How long until the next cycle is due; none once every cycle has played.
+    fn until_next(&mut self) -> Option<Duration> {
+        self.cycles.peek().map(|c| self.pace.until(c.at_ms))
+    }
+
+    /// Play the next cycle into `app` if it's due, pausing `app` before a
+    /// cycle at or past `[breakpoints] at` or after one that raises an alert
+    /// at `on_severity`. While paused, only the resume key moves it on.
+    fn step(&mut self, app: &mut App) {
+        if app.paused.is_some() {
+            if std::mem::take(&mut app.resume) {
+                app.paused = None;
+                app.dirty = true;
+                if let Some(paused_at) = self.paused_at.take() {
+                    self.pace.hold(paused_at.elapsed());
                 }
             }
+            return;
+        }
+        if !self.until_next().is_some_and(|wait| wait.is_zero()) {
+            return;
         }
+        let cycle_ts = self.cycles.peek().expect("a cycle is due").cycle_ts;
+        if let Some(reason) = self.breakpoints.before_cycle(cycle_ts) {
+            self.pause(app, reason);
+            return;
+        }
+        let update = self.player.play(self.cycles.next().expect("a cycle is due"));
+        let hit = self.breakpoints.after_cycle(&update.alerts);
+        app.apply(update);
+        if let Some(reason) = hit {
+            self.pause(app, reason);
+        }
+    }
+
+    fn pause(&mut self, app: &mut App, reason: BreakReason) {
+        app.pause(reason);
+        self.paused_at = Some(Instant::now());
     }
+}
 
-    let _ = pipeline.db.shutdown().await;
+/// Play `playback` into the TUI until quit; once every cycle has played,
+/// the title becomes `ended_title`.
+fn replay_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, mut app: App, mut playback: Playback, ended_title: String) -> io::Result<()> {
+    while !app.should_quit {
+        app.draw_if_due(terminal)?;
+        let wait = match playback.until_next() {
+            Some(wait) if app.paused.is_none() => wait.min(INPUT_POLL),
+            Some(_) => INPUT_POLL,
+            None => {
+                if app.title != ended_title {
+                    app.title = ended_title.clone();
+                    app.dirty = true;
+                }
+                INPUT_POLL
+            }
+        };
+        app.handle_input(wait)?;
+        playback.step(&mut app);
+    }
     Ok(())
 }
 
-fn draw(f: &mut ratatui::Frame, app: &App) {
-    let size = f.area();
+/// Render updates from `rx` until quit; once the sender is gone, the title
+/// becomes `ended_title`.
+fn attach_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    mut app: App,
+    mut rx: mpsc::UnboundedReceiver<DashboardUpdate>,
+    ended_title: String,
+) -> io::Result<()> {
+    while !app.should_quit {
+        app.draw_if_due(terminal)?;
+        app.handle_input(INPUT_POLL)?;
 
-    // Top bar
-    let chunks = Layout::default()
+        loop {
+            match rx.try_recv() {
+                Ok(update) => app.apply(update),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if app.title != ended_title {
+                        app.title = ended_title.clone();
+                        app.dirty = true;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn enter_terminal(caps: &TermCaps) -> io::Result<Terminal<CrosstermBackend<io::Stdout>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    if caps.alternate_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    if caps.mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    // Without an alternate screen, draw over the scrollback from a blank page
+    if !caps.alternate_screen {
+        terminal.clear()?;
+    }
+    Ok(terminal)
+}
+
+fn leave_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, caps: &TermCaps) -> io::Result<()> {
+    disable_raw_mode()?;
+    if caps.mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    if caps.alternate_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    } else {
+        terminal.clear()?;
+    }
+    terminal.show_cursor()
+}
+
+async fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    fraud_rate: f64,
+    duration: u64,
+    config: &Config,
+    alert_engine: AlertEngine,
+    mut web: Option<WebPublisher>,
+    mut app: App,
+) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    let mut pacer = Pacer::from_config(config)?;
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(pacer.interval());
+    let join = detection::guard_join_band(&config.join, trades_per_sec, orders_per_sec);
+    let pipeline = detection::setup_with(&PipelineOptions { sessions: config.sessions.clone(), join: join.clone(), ..Default::default() }).await?;
+    let gen = FraudGenerator::new(fraud_rate).with_benign_rate(config.generator.benign_rate).with_shard(config.generator.shard);
+    let mut engine = Engine::new(pipeline, gen, alert_engine, Arc::new(SystemClock::new()));
+    engine.governor = Some(LoadGovernor::new(config.limits.clone()).with_join(join));
+    engine.cycle_budget = config.limits.cycle_budget();
+    engine.poll_budget = config.limits.poll_budget();
+    engine.eval_worker = config.limits.eval_worker;
+    engine.watermark = WatermarkStrategy::from_config(&config.watermark)?;
+    engine.archive = AlertArchive::open(&config.archive)?;
+    engine.ids = IdLedger::open(&config.ids)?;
+    engine.uids = UidGenerator::from_config(&config.ids);
+    engine.ticks = TickDetector::from_config(&config.ticks)?;
+    engine.audit = AuditLog::open(config)?;
+    engine.recorder = Recorder::open(&config.recording)?;
+    engine.slo = SloTracker::from_config(&config.slo)?;
+    if let Some(web) = &web {
+        web.set_audit(engine.audit.clone());
+    }
+    let blotter = web.as_ref().map_or_else(
+        || {
+            let blotter = Blotter::new();
+            blotter.set_index(AlertIndex::from_config(&config.search));
+            blotter
+        },
+        WebPublisher::blotter,
+    );
+    blotter.set_archive(engine.archive.clone());
+    engine.blotter = Some(blotter.clone());
+    app.blotter = Some(blotter);
+    let reviews = match &web {
+        Some(web) => web.reviews(),
+        None => AlertReviews::open(&config.reviews)?,
+    };
+    engine.reviews = Some(reviews.clone());
+    app.reviews = Some(reviews);
+
+    let mut stop = StopConditions::new(duration, &config.stop);
+    let mut breakpoints = Breakpoints::from_config(&config.breakpoints)?;
+    // While paused the stop conditions wait too, so a breakpoint can be inspected at leisure
+    while !app.should_quit && (app.paused.is_some() || !stop.reached(&engine)) {
+        app.draw_if_due(terminal)?;
+        if app.paused.is_some() {
+            app.handle_input(INPUT_POLL)?;
+            if std::mem::take(&mut app.resume) {
+                app.paused = None;
+                app.dirty = true;
+                engine.resume();
+            }
+            continue;
+        }
+        app.handle_input(pacer.until_next(engine.elapsed()).min(INPUT_POLL))?;
+        if !pacer.due(engine.elapsed()) {
+            continue;
+        }
+        if let Some(reason) = breakpoints.before_cycle(engine.clock.now_ms()) {
+            app.pause(reason);
+            continue;
+        }
+
+        let web_reset = web.as_ref().is_some_and(WebPublisher::take_stats_reset);
+        if std::mem::take(&mut app.reset_stats) || web_reset {
+            engine.reset_stats();
+        }
+        if let Some(web) = &web {
+            web.apply_silences(&mut engine);
+        }
+        let start = engine.elapsed();
+        let alerts = engine.cycle().await;
+        pacer.record(start, &engine);
+        if let Some(notifier) = &app.notifier {
+            notifier.notify(&alerts);
+        }
+        let hit = breakpoints.after_cycle(&alerts);
+        let update = DashboardUpdate::from_engine(&engine, alerts);
+        if let Some(web) = &mut web {
+            web.publish(&update);
+        }
+        app.apply(update);
+        if let Some(reason) = hit {
+            app.pause(reason);
+        }
+        stop.checkpoint(&mut engine, "tui", fraud_rate, config);
+    }
+    if let Some(timeout) = config.drain.timeout() {
+        let drained = engine.drain(timeout).await;
+        if let Some(web) = &mut web {
+            web.publish(&DashboardUpdate::from_engine(&engine, drained));
+        }
+    }
+    if let Some(web) = &mut web {
+        web.flush();
+    }
+
+    let record = RunRecord::capture(&mut engine, "tui", fraud_rate, config);
+    engine.shutdown().await;
+    Ok(record)
+}
+
+/// Header, alert feed, latency + SLOs and streams, counts + heatmap, top to
+/// bottom.
+fn layout(size: Rect) -> std::rc::Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),  // header
             Constraint::Min(10),   // alert feed
-            Constraint::Length(9), // latency + streams
-            Constraint::Length(9), // counts + prices
+            Constraint::Length(14), // latency + SLOs, streams
+            Constraint::Length(15), // counts + prices
         ])
-        .split(size);
+        .split(size)
+}
+
+/// The feed's area split into its stream tab row and the feed itself.
+fn feed_layout(area: Rect) -> [Rect; 2] {
+    Layout::default().direction(Direction::Vertical).constraints([Constraint::Length(1), Constraint::Min(0)]).areas(area)
+}
+
+fn draw(f: &mut ratatui::Frame, app: &App) {
+    let size = f.area();
+    let chunks = layout(size);
 
     draw_header(f, app, chunks[0]);
-    draw_alert_feed(f, app, chunks[1]);
+    match &app.evidence {
+        Some(evidence) => draw_evidence(f, &app.theme, evidence, &app.view.cooldowns, chunks[1]),
+        None => {
+            let [tabs, feed] = feed_layout(chunks[1]);
+            draw_tabs(f, app, tabs);
+            draw_alert_feed(f, app, feed);
+        }
+    }
     draw_latency_and_streams(f, app, chunks[2]);
-    draw_counts_and_prices(f, app, chunks[3]);
+    draw_counts_and_heatmap(f, app, chunks[3]);
+    if app.show_help {
+        draw_help(f, app, size);
+    }
+}
+
+/// Every binding in a box over the middle of the screen.
+fn draw_help(f: &mut ratatui::Frame, app: &App, size: Rect) {
+    let theme = &app.theme;
+    let mut lines: Vec<Line> = Action::ALL
+        .iter()
+        .map(|&action| {
+            Line::from(vec![
+                Span::styled(format!(" {:<14}", app.keys.keys_for(action)), theme.title.add_modifier(Modifier::BOLD)),
+                Span::raw(action.describe()),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(vec![
+        Span::styled(format!(" {:<14}", "Mouse"), theme.title.add_modifier(Modifier::BOLD)),
+        Span::raw("Wheel scrolls the feed; click a stream tab to filter it, an alert for its evidence"),
+    ]));
+    lines.push(Line::default());
+    lines.push(Line::from(Span::styled(" Esc closes this or the evidence view, then clears the search and pin; remap under [keys]", theme.muted)));
+
+    let width = 80.min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(size.x + (size.width - width) / 2, size.y + (size.height - height) / 2, width, height);
+    f.render_widget(Clear, area);
+    let p = Paragraph::new(lines).block(theme.panel(" Keys "));
+    f.render_widget(p, area);
 }
 
 fn draw_header(f: &mut ratatui::Frame, app: &App, area: Rect) {
-    let elapsed = app.uptime.elapsed().as_secs();
-    let header = vec![
-        Span::styled(" laminardb-fraud-detect ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    let theme = &app.theme;
+    let mut header = vec![
+        Span::styled(" laminardb-fraud-detect ", theme.title.add_modifier(Modifier::BOLD)),
         Span::raw(" | "),
-        Span::styled(format!("Alerts: {}", app.total_alerts), Style::default().fg(Color::Yellow)),
+        Span::styled(
+            format!("Alerts: {} delivered / {} generated", locale::count(app.view.alerts_delivered), locale::count(app.view.alerts_generated)),
+            theme.warn,
+        ),
+        Span::styled(
+            format!(
+                " ({} below min score, {} held back)",
+                locale::count(app.view.model_suppressed),
+                locale::count(app.view.cooldown_suppressed)
+            ),
+            theme.muted,
+        ),
         Span::raw(" | "),
-        Span::styled(format!("Trades: {}", app.total_trades), Style::default().fg(Color::Green)),
+        Span::styled(format!("Trades: {}", locale::count(app.view.total_trades)), theme.good),
         Span::raw(" | "),
-        Span::styled(format!("Orders: {}", app.total_orders), Style::default().fg(Color::Blue)),
+        Span::styled(format!("Orders: {}", locale::count(app.view.total_orders)), theme.info),
         Span::raw(" | "),
-        Span::raw(format!("Uptime: {}s", elapsed)),
+        Span::raw(format!("Uptime: {}s", app.view.uptime_secs)),
+        Span::styled(format!(" (cycle #{})", app.view.heartbeat.seq), theme.muted),
         Span::raw(" | "),
-        Span::styled("q=quit  Up/Down=scroll", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            format!("{}=help  {}=quit", app.keys.keys_for(Action::Help), app.keys.keys_for(Action::Quit)),
+            theme.muted,
+        ),
     ];
+    if let Some(pin) = &app.pin {
+        header.insert(1, Span::styled(pin.label(), theme.warn_banner.add_modifier(Modifier::BOLD)));
+    }
+    if app.view.join_overloaded {
+        header.insert(1, Span::styled(" JOIN FAN-OUT ", theme.warn_banner.add_modifier(Modifier::BOLD)));
+    }
+    if app.view.degraded {
+        header.insert(1, Span::styled(" DEGRADED ", theme.alarm_banner.add_modifier(Modifier::BOLD)));
+    }
+    if let Some(reason) = &app.paused {
+        let banner = format!(" PAUSED at {reason} ({}=resume) ", app.keys.keys_for(Action::Resume));
+        header.insert(1, Span::styled(banner, theme.alarm_banner.add_modifier(Modifier::BOLD)));
+    }
     let p = Paragraph::new(Line::from(header))
-        .block(Block::default().borders(Borders::ALL).title(" Sentinel "));
+        .block(theme.panel(app.title.as_str()));
     f.render_widget(p, area);
 }
 
 fn draw_alert_feed(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let max_visible = (area.height as usize).saturating_sub(2);
     let total = app.alerts.len();
     let _start = if total > max_visible {
@@ -266,22 +1149,20 @@ fn draw_alert_feed(f: &mut ratatui::Frame, app: &App, area: Rect) {
     };
 
     let rows: Vec<Row> = app
-        .alerts
-        .iter()
-        .rev()
+        .visible()
         .skip(app.scroll_offset)
         .take(max_visible)
         .map(|alert| {
-            let (sev_str, sev_color) = match alert.severity {
-                AlertSeverity::Critical => ("CRIT", Color::Red),
-                AlertSeverity::High => ("HIGH", Color::Yellow),
-                AlertSeverity::Medium => (" MED", Color::Cyan),
+            let (sev_str, sev_style) = match alert.severity {
+                AlertSeverity::Critical => ("CRIT", theme.bad),
+                AlertSeverity::High => ("HIGH", theme.warn),
+                AlertSeverity::Medium => (" MED", theme.accent),
             };
             Row::new(vec![
-                ratatui::widgets::Cell::from(Span::styled(sev_str, Style::default().fg(sev_color).add_modifier(Modifier::BOLD))),
+                ratatui::widgets::Cell::from(Span::styled(sev_str, sev_style.add_modifier(Modifier::BOLD))),
                 ratatui::widgets::Cell::from(format!("{:<17}", alert.alert_type.label())),
-                ratatui::widgets::Cell::from(alert.description.clone()),
-                ratatui::widgets::Cell::from(format!("{}us", alert.latency_us)),
+                ratatui::widgets::Cell::from(review_tag(app, alert) + &alert.description().to_string()),
+                ratatui::widgets::Cell::from(locale::micros(alert.latency_us)),
             ])
         })
         .collect();
@@ -297,64 +1178,185 @@ fn draw_alert_feed(f: &mut ratatui::Frame, app: &App, area: Rect) {
     )
     .header(
         Row::new(vec!["SEV", "TYPE", "DESCRIPTION", "LATENCY"])
-            .style(Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+            .style(theme.text.add_modifier(Modifier::BOLD)),
     )
-    .block(Block::default().borders(Borders::ALL).title(format!(" Alert Feed ({}) ", total)));
+    .block(theme.panel(feed_title(app, total)));
 
     f.render_widget(table, area);
 }
 
+/// ` all ` or ` <stream> `, as drawn in the tab row.
+fn tab_label(tab: Option<&str>) -> String {
+    format!(" {} ", tab.unwrap_or("all"))
+}
+
+/// One tab per stream with alerts in the feed, the selected one highlighted.
+fn draw_tabs(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut spans = Vec::new();
+    for (i, tab) in app.tabs().into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled("|", theme.muted));
+        }
+        let style = if tab == app.tab { theme.title.add_modifier(Modifier::BOLD | Modifier::REVERSED) } else { theme.text };
+        spans.push(Span::styled(tab_label(tab), style));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// `[FP] ` for a reviewed alert, `[note] ` for one with only notes.
+fn review_tag(app: &App, alert: &Alert) -> String {
+    match app.reviews.as_ref().and_then(|r| r.get(alert.id)) {
+        Some(review) => match review.disposition {
+            Some(d) => format!("[{}] ", d.tag()),
+            None => "[note] ".to_string(),
+        },
+        None => String::new(),
+    }
+}
+
+fn feed_title(app: &App, total: usize) -> String {
+    if let Some(note) = &app.note {
+        return format!(" Note on #{} (Enter saves, Esc cancels): {}_ ", note.alert.id, note.text);
+    }
+    if let Some(e) = &app.review_error {
+        return format!(" Alert Feed ({total}) - review not saved: {e} ");
+    }
+    match &app.search {
+        None if app.pin.is_some() => format!(" Alert Feed ({} of {total} pinned) ", app.visible().count()),
+        None => match app.tab {
+            Some(tab) => format!(" Alert Feed ({} of {total} from {tab}) ", app.visible().count()),
+            None => format!(" Alert Feed ({total}) "),
+        },
+        Some(search) if search.ranked.is_some() => format!(" Alert Feed ({} ranked for /{}) ", app.visible().count(), search.query),
+        Some(search) => {
+            let cursor = if search.editing { "_" } else { "" };
+            format!(" Alert Feed ({} of {total} match /{}{cursor}) ", app.visible().count(), search.query)
+        }
+    }
+}
+
+/// The tape behind one alert: its trades, then its orders, oldest first,
+/// after any cooldown holding back repeats of it.
+fn draw_evidence(f: &mut ratatui::Frame, theme: &Theme, evidence: &Evidence, cooldowns: &[Cooldown], area: Rect) {
+    let alert = &evidence.alert;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(format!("  #{} {} ", alert.id, alert.alert_type.label()), theme.warn.add_modifier(Modifier::BOLD)),
+            Span::raw(alert.description().to_string()),
+        ]),
+        Line::from(Span::styled(
+            format!(
+                "  window {}..{}  trades={} orders={}{}",
+                locale::time(evidence.start_ms),
+                locale::time(evidence.end_ms),
+                evidence.trades.len(),
+                evidence.orders.len(),
+                alert.gap_ms.map(|g| format!("  order->trade gap={g}ms")).unwrap_or_default()
+            ),
+            theme.muted,
+        )),
+    ];
+    let subject = alert.account_id.as_ref().or(alert.symbol.as_ref());
+    // As of the last update: cooldowns run on the engine's event time
+    let cooldown = cooldowns.iter().find(|c| c.alert_type == alert.alert_type.label() && Some(&c.subject) == subject);
+    if let Some(c) = cooldown {
+        lines.push(Line::from(Span::styled(format!("  {} ({} held back)", c.describe(), c.suppressed), theme.info)));
+    }
+    for t in &evidence.trades {
+        lines.push(Line::from(vec![
+            Span::styled("  TRADE ", theme.good),
+            Span::raw(format!("{} {:<9} {:<5} {:<4} {:>6} @ {:.2}", locale::time(t.ts), t.account_id, t.symbol, t.side, t.volume, t.price)),
+        ]));
+    }
+    for o in &evidence.orders {
+        lines.push(Line::from(vec![
+            Span::styled("  ORDER ", theme.info),
+            Span::raw(format!("{} {:<9} {:<5} {:<4} {:>6} @ {:.2}  {}", locale::time(o.ts), o.account_id, o.symbol, o.side, o.quantity, o.price, o.order_id)),
+        ]));
+    }
+    let p = Paragraph::new(lines).block(theme.panel(" Evidence (e/Esc to close) "));
+    f.render_widget(p, area);
+}
+
 fn draw_latency_and_streams(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
 
     // Latency panel
-    let push = app.latency.push_stats();
-    let proc = app.latency.processing_stats();
-    let alert_lat = app.latency.alert_stats();
+    let push = &app.view.latency.push;
+    let proc = &app.view.latency.processing;
+    let alert_lat = &app.view.latency.alert;
+    let (tick_lag, bar_lag) = (&app.view.latency.tick_lag, &app.view.latency.bar_lag);
+    let pushes = &app.view.push_stats;
+    let pressure_style = match pushes.buffer_pressure {
+        p if p >= 0.8 => theme.bad,
+        p if p >= 0.5 => theme.warn,
+        _ => theme.good,
+    };
 
     let latency_text = vec![
         Line::from(vec![
-            Span::styled("  Push:  ", Style::default().fg(Color::Green)),
-            Span::raw(format!("p50={:<6} p95={:<6} p99={:<6}", push.p50_us, push.p95_us, push.p99_us)),
+            Span::styled("  Push:  ", theme.good),
+            Span::raw(percentiles(push)),
         ]),
         Line::from(vec![
-            Span::styled("  Proc:  ", Style::default().fg(Color::Cyan)),
-            Span::raw(format!("p50={:<6} p95={:<6} p99={:<6}", proc.p50_us, proc.p95_us, proc.p99_us)),
+            Span::styled("  Proc:  ", theme.accent),
+            Span::raw(percentiles(proc)),
         ]),
         Line::from(vec![
-            Span::styled("  Alert: ", Style::default().fg(Color::Yellow)),
-            Span::raw(format!("p50={:<6} p95={:<6} p99={:<6}", alert_lat.p50_us, alert_lat.p95_us, alert_lat.p99_us)),
+            Span::styled("  Alert: ", theme.warn),
+            Span::raw(percentiles(alert_lat)),
         ]),
-        Line::from(""),
         Line::from(vec![
-            Span::styled("  Min: ", Style::default().fg(Color::DarkGray)),
-            Span::raw(format!("{}us", push.min_us)),
+            Span::styled("  Move-to-alert p50: ", theme.muted),
+            Span::raw(format!("tick {}ms  bar {}ms", tick_lag.p50_us / 1000, bar_lag.p50_us / 1000)),
+        ]),
+        Line::from(vec![
+            Span::styled("  Min: ", theme.muted),
+            Span::raw(locale::micros(push.min_us)),
             Span::raw("  "),
-            Span::styled("Max: ", Style::default().fg(Color::DarkGray)),
-            Span::raw(format!("{}us", alert_lat.max_us)),
+            Span::styled("Max: ", theme.muted),
+            Span::raw(locale::micros(alert_lat.max_us)),
+        ]),
+        Line::from(vec![
+            Span::styled("  Buffer: ", theme.muted),
+            Span::styled(format!("{:>3.0}%", pushes.buffer_pressure * 100.0), pressure_style),
+            Span::raw(format!("  deferred={} rejected={}", locale::count(pushes.deferred_batches), locale::count(pushes.rejected_batches))),
         ]),
     ];
     let latency_widget = Paragraph::new(latency_text)
-        .block(Block::default().borders(Borders::ALL).title(" Latency (us) "));
-    f.render_widget(latency_widget, chunks[0]);
+        .block(theme.panel(" Latency (us) "));
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(8), Constraint::Min(3)])
+        .split(chunks[0]);
+    f.render_widget(latency_widget, left[0]);
+
+    // Alert latency against each [slo] objective
+    let slo_text: Vec<Line> = match app.view.slo.as_slice() {
+        [] => vec![Line::from(Span::styled("  No [slo] objectives", theme.muted))],
+        objectives => objectives.iter().map(|s| slo_line(theme, s)).collect(),
+    };
+    f.render_widget(Paragraph::new(slo_text).block(theme.panel(" Latency SLOs ")), left[1]);
 
     // Stream counters panel
-    let names = ["vol_baseline", "ohlc_vol", "rapid_fire", "wash_score", "suspicious_match", "asof_match"];
-    let stream_rows: Vec<Row> = names
+    let stream_rows: Vec<Row> = app
+        .view
+        .streams
         .iter()
-        .enumerate()
-        .map(|(i, name)| {
-            let color = if app.stream_counts[i] > 0 { Color::Green } else { Color::Red };
+        .map(|stream| {
+            let style = if stream.active { theme.good } else { theme.bad };
             Row::new(vec![
                 ratatui::widgets::Cell::from(Span::styled(
-                    if app.stream_counts[i] > 0 { " OK " } else { "WAIT" },
-                    Style::default().fg(color),
+                    if stream.active { " OK " } else { "WAIT" },
+                    style,
                 )),
-                ratatui::widgets::Cell::from(format!("{:<20}", name)),
-                ratatui::widgets::Cell::from(format!("{}", app.stream_counts[i])),
+                ratatui::widgets::Cell::from(format!("{:<20}", stream.name)),
+                ratatui::widgets::Cell::from(locale::count(stream.count)),
             ])
         })
         .collect();
@@ -363,55 +1365,459 @@ fn draw_latency_and_streams(f: &mut ratatui::Frame, app: &App, area: Rect) {
         stream_rows,
         [Constraint::Length(5), Constraint::Length(21), Constraint::Min(8)],
     )
-    .block(Block::default().borders(Borders::ALL).title(" Detection Streams "));
+    .block(theme.panel(" Detection Streams "));
     f.render_widget(stream_table, chunks[1]);
 }
 
-fn draw_counts_and_prices(f: &mut ratatui::Frame, app: &App, area: Rect) {
+/// An objective's attainment over the window, red while under target, then
+/// since start and the error budget left.
+fn slo_line<'a>(theme: &Theme, s: &SloStatus) -> Line<'a> {
+    let pct = |a: Option<f64>| a.map_or("-".to_string(), |a| format!("{a:.2}%"));
+    let style = if s.breached() {
+        theme.bad
+    } else if s.budget_left() < 0 {
+        theme.warn
+    } else {
+        theme.good
+    };
+    Line::from(vec![
+        Span::styled(format!("  {:<14}", s.name), theme.muted),
+        Span::raw(format!("{}% <= {}ms  ", s.percent, s.within_ms)),
+        Span::styled(format!("now {}", pct(s.window_attainment())), style),
+        Span::raw(format!("  all {}  budget {}", pct(s.attainment()), s.budget_left())),
+    ])
+}
+
+fn percentiles(stats: &LatencyStats) -> String {
+    let us = locale::count;
+    format!("p50={:<7} p95={:<7} p99={:<7}", us(stats.p50_us), us(stats.p95_us), us(stats.p99_us))
+}
+
+fn draw_counts_and_heatmap(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
 
-    // Alert counts by type
-    let counts = app.alert_engine.alert_counts();
-    let type_names = ["VolumeAnomaly", "PriceSpike", "RapidFire", "WashTrading", "SuspiciousMatch", "FrontRunning"];
+    // Alert counts by type, with would-have-fired counts from shadow-mode rules
+    let counts = &app.view.alert_counts;
+    let shadow = &app.view.shadow_counts;
+    let type_names = ["VolumeAnomaly", "PriceSpike", "PriceVelocity", "RapidFire", "WashTrading", "SuspiciousMatch", "FrontRunning", "MlAnomaly", "Diversification", "BookImbalance", "MomentumIgnition", "InsiderTrading", "PriceImprovement", "CounterpartyConcentration", "EngineStall", "FeedIntegrity"];
     let count_rows: Vec<Row> = type_names
         .iter()
         .map(|name| {
             let c = counts.get(*name).copied().unwrap_or(0);
-            let color = if c > 0 { Color::Yellow } else { Color::DarkGray };
+            let style = if c > 0 { theme.warn } else { theme.muted };
+            let shadowed = shadow.get(*name).map_or(String::new(), |s| format!("shadow {}", locale::count(*s)));
             Row::new(vec![
-                ratatui::widgets::Cell::from(Span::styled(format!("{:<18}", name), Style::default().fg(color))),
-                ratatui::widgets::Cell::from(Span::styled(format!("{}", c), Style::default().fg(color))),
+                ratatui::widgets::Cell::from(Span::styled(format!("{:<18}", name), style)),
+                ratatui::widgets::Cell::from(Span::styled(locale::count(c), style)),
+                ratatui::widgets::Cell::from(Span::styled(shadowed, theme.shadow)),
             ])
         })
         .collect();
 
     let count_table = Table::new(
         count_rows,
-        [Constraint::Length(19), Constraint::Min(6)],
+        [Constraint::Length(19), Constraint::Length(8), Constraint::Min(12)],
     )
-    .block(Block::default().borders(Borders::ALL).title(" Alert Counts "));
+    .block(theme.panel(" Alert Counts "));
     f.render_widget(count_table, chunks[0]);
 
-    // Symbol prices
-    let mut symbols: Vec<_> = app.prices.iter().collect();
-    symbols.sort_by_key(|(s, _)| (*s).clone());
-    let price_rows: Vec<Row> = symbols
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(4)])
+        .split(chunks[1]);
+    draw_heatmap(f, app, right[0]);
+    draw_symbol_alerts(f, app, right[1]);
+}
+
+/// Delivered alerts per symbol over the run, with the type behind most of them.
+fn draw_symbol_alerts(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let rows: Vec<Row> = app
+        .view
+        .symbol_alerts
         .iter()
-        .map(|(sym, price)| {
+        .map(|s| {
+            let top = s.top_type().map_or(String::new(), |(t, n)| format!("{t} {}", locale::count(n)));
+            let mut style = theme.warn;
+            if app.pin.as_ref().is_some_and(|p| *p == Pin::Symbol(s.symbol.clone())) {
+                style = style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD);
+            }
             Row::new(vec![
-                ratatui::widgets::Cell::from(Span::styled(format!("{:<6}", sym), Style::default().fg(Color::White).add_modifier(Modifier::BOLD))),
-                ratatui::widgets::Cell::from(format!("{:.2}", price)),
+                ratatui::widgets::Cell::from(Span::styled(s.symbol.clone(), style)),
+                ratatui::widgets::Cell::from(Span::styled(locale::count(s.total), style)),
+                ratatui::widgets::Cell::from(Span::styled(top, theme.muted)),
             ])
         })
         .collect();
 
-    let price_table = Table::new(
-        price_rows,
-        [Constraint::Length(7), Constraint::Min(12)],
-    )
-    .block(Block::default().borders(Borders::ALL).title(" Symbol Prices "));
-    f.render_widget(price_table, chunks[1]);
+    let table = Table::new(rows, [Constraint::Length(7), Constraint::Length(8), Constraint::Min(12)])
+        .header(Row::new(vec!["SYMBOL", "ALERTS", "TOP TYPE"]).style(theme.text.add_modifier(Modifier::BOLD)))
+        .block(theme.panel(" Alerts by Symbol "));
+    f.render_widget(table, area);
+}
+
+/// One cell per symbol, wrapped to the panel width: background by alerts in
+/// the last minute, arrow and percentage by price change over it.
+fn draw_heatmap(f: &mut ratatui::Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let mut symbols: Vec<_> = app.view.prices.iter().collect();
+    symbols.sort_by_key(|(s, _)| (*s).clone());
+    let per_row = (area.width.saturating_sub(2) / HEAT_CELL_WIDTH).max(1) as usize;
+
+    let mut lines: Vec<Line> = symbols
+        .chunks(per_row)
+        .map(|row| {
+            let cells = row.iter().flat_map(|(sym, price)| {
+                let change = app.heat.change(sym);
+                let alerts = app.heat.alert_count(sym);
+                let [up, down, flat] = theme.arrows;
+                let arrow = if change > 0.0 { up } else if change < 0.0 { down } else { flat };
+                let text = format!(" {:<5}{:>8.2} {arrow}{:>+5.1}% ", sym, price, change * 100.0);
+                let mut style = heat_style(theme, alerts, change);
+                if app.pin.as_ref().is_some_and(|p| *p == Pin::Symbol((*sym).clone())) {
+                    style = style.add_modifier(Modifier::UNDERLINED | Modifier::BOLD);
+                }
+                [Span::styled(text, style), Span::raw(" ")]
+            });
+            Line::from(cells.collect::<Vec<_>>())
+        })
+        .collect();
+    lines.push(Line::default());
+    lines.push(Line::from(vec![
+        Span::styled("alerts/min ", theme.muted),
+        Span::styled(" 0 ", heat_style(theme, 0, 0.0)),
+        Span::styled(" 1-2 ", heat_style(theme, 1, 0.0)),
+        Span::styled(" 3-5 ", heat_style(theme, 3, 0.0)),
+        Span::styled(" 6+ ", heat_style(theme, 6, 0.0)),
+    ]));
+
+    let p = Paragraph::new(lines).block(theme.panel(" Symbol Heatmap (1m) "));
+    f.render_widget(p, area);
+}
+
+fn heat_style(theme: &Theme, alerts: usize, change: f64) -> Style {
+    match alerts {
+        0 if change > 0.0 => theme.good,
+        0 if change < 0.0 => theme.bad,
+        0 => theme.neutral,
+        1..=2 => theme.heat[0],
+        3..=5 => theme.heat[1],
+        _ => theme.heat[2],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertSeverity, AlertType};
+
+    /// An app as `attach` builds it: no blotter, reviews or notifier.
+    fn app() -> App {
+        let config = Config::default();
+        let refresh = Refresh { interval: Duration::from_millis(150), low_cpu: false };
+        App::new(" Sentinel @ ws://test ".into(), Keymap::from_config(&config.keys).unwrap(), refresh, Theme::named(config.tui.theme), AlertRetention::default())
+    }
+
+    fn alert(id: u64, account: &str, symbol: &str) -> Alert {
+        Alert {
+            id,
+            alert_type: AlertType::WashTrading,
+            severity: AlertSeverity::High,
+            symbol: Some(symbol.into()),
+            account_id: Some(account.into()),
+            detail: AlertDetail::Text(String::new()),
+            latency_us: 0,
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    fn visible_ids(app: &App) -> Vec<u64> {
+        app.visible().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn feed_frames_add_alerts_and_replace_the_totals() {
+        let mut app = app();
+        app.apply(DashboardUpdate { alerts: vec![alert(1, "ACC-1", "AAPL")], total_trades: 100, uptime_secs: 1, ..Default::default() });
+        app.apply(DashboardUpdate { alerts: vec![alert(2, "ACC-2", "MSFT")], total_trades: 250, uptime_secs: 2, ..Default::default() });
+        assert_eq!(visible_ids(&app), vec![2, 1]);
+        assert_eq!(app.view.total_trades, 250);
+        assert!(app.view.alerts.is_empty(), "alerts move into the feed");
+
+        // A frame with no alerts only updates the totals
+        app.dirty = false;
+        app.apply(DashboardUpdate { total_trades: 300, uptime_secs: 2, ..Default::default() });
+        assert_eq!(visible_ids(&app), vec![2, 1]);
+        assert_eq!(app.view.total_trades, 300);
+        assert!(!app.dirty);
+    }
+
+    #[test]
+    fn search_filters_the_feed_without_a_blotter() {
+        let mut app = app();
+        app.apply(DashboardUpdate {
+            alerts: vec![alert(1, "ACC-1", "AAPL"), alert(2, "ACC-2", "MSFT"), alert(3, "ACC-1", "MSFT")],
+            ..Default::default()
+        });
+        app.search = Some(Search { query: "acc-1".into(), editing: false, ranked: None });
+        app.rank_search();
+        assert!(app.search.as_ref().unwrap().ranked.is_none());
+        assert_eq!(visible_ids(&app), vec![3, 1]);
+
+        app.search = None;
+        app.pin = Some(Pin::Symbol("MSFT".into()));
+        assert_eq!(visible_ids(&app), vec![3, 2]);
+    }
+
+    #[test]
+    fn heatmap_keeps_a_minute_of_prices_and_alerts() {
+        let mut heat = SymbolHeat::default();
+        let start = Instant::now();
+        let frame = |price: f64, alerts: Vec<Alert>| DashboardUpdate { prices: HashMap::from([("AAPL".to_string(), price)]), alerts, ..Default::default() };
+
+        heat.record(start, &frame(100.0, vec![alert(1, "ACC-1", "AAPL")]));
+        heat.record(start + Duration::from_secs(30), &frame(102.0, vec![alert(2, "ACC-1", "AAPL"), alert(3, "ACC-2", "MSFT")]));
+        assert!((heat.change("AAPL") - 0.02).abs() < 1e-9);
+        assert_eq!((heat.alert_count("AAPL"), heat.alert_count("MSFT")), (2, 1));
+
+        // 70s in, the 0s sample is still the only one at or before the cutoff
+        heat.record(start + Duration::from_secs(70), &frame(99.96, Vec::new()));
+        assert!((heat.change("AAPL") + 0.0004).abs() < 1e-9);
+        assert_eq!((heat.alert_count("AAPL"), heat.alert_count("MSFT")), (1, 1));
+
+        // 91s in, the 30s sample takes over as the baseline and its alerts age out
+        heat.record(start + Duration::from_secs(91), &frame(99.96, Vec::new()));
+        assert!((heat.change("AAPL") + 0.02).abs() < 1e-9);
+        assert_eq!((heat.alert_count("AAPL"), heat.alert_count("MSFT")), (0, 0));
+        assert_eq!((heat.change("GOOGL"), heat.alert_count("GOOGL")), (0.0, 0));
+    }
+
+    #[test]
+    fn heat_style_colours_price_moves_until_alerts_take_over() {
+        let theme = Theme::named(Config::default().tui.theme);
+        assert_eq!(heat_style(&theme, 0, 0.01), theme.good);
+        assert_eq!(heat_style(&theme, 0, -0.01), theme.bad);
+        assert_eq!(heat_style(&theme, 0, 0.0), theme.neutral);
+        assert_eq!(heat_style(&theme, 2, -0.01), theme.heat[0]);
+        assert_eq!(heat_style(&theme, 3, 0.0), theme.heat[1]);
+        assert_eq!(heat_style(&theme, 5, 0.0), theme.heat[1]);
+        assert_eq!(heat_style(&theme, 6, 0.0), theme.heat[2]);
+    }
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent { kind: MouseEventKind::Down(MouseButton::Left), column, row, modifiers: crossterm::event::KeyModifiers::NONE }
+    }
+
+    /// A feed with two WashTrading alerts (`wash_score`) and a RapidFire one (`rapid_fire`).
+    fn tabbed_app() -> App {
+        let mut app = app();
+        let burst = Alert { alert_type: AlertType::RapidFire, ..alert(2, "ACC-2", "MSFT") };
+        app.apply(DashboardUpdate { alerts: vec![alert(1, "ACC-1", "AAPL"), burst, alert(3, "ACC-1", "MSFT")], ..Default::default() });
+        app
+    }
+
+    #[test]
+    fn clicking_a_stream_tab_filters_the_feed() {
+        let mut app = tabbed_app();
+        assert_eq!(app.tabs(), vec![None, Some("rapid_fire"), Some("wash_score")]);
+        // Tab row at y 3: " all " in 0..5, " rapid_fire " in 6..18, " wash_score " in 19..31
+        let area = Rect::new(0, 3, 80, 20);
+        app.scroll_offset = 1;
+        app.handle_mouse(click(20, 3), area);
+        assert_eq!(app.tab, Some("wash_score"));
+        assert_eq!((visible_ids(&app), app.scroll_offset), (vec![3, 1], 0));
+
+        // The divider and the row's empty tail select nothing
+        app.handle_mouse(click(18, 3), area);
+        app.handle_mouse(click(60, 3), area);
+        assert_eq!(app.tab, Some("wash_score"));
+
+        app.handle_mouse(click(6, 3), area);
+        assert_eq!((app.tab, visible_ids(&app)), (Some("rapid_fire"), vec![2]));
+        app.handle_mouse(click(4, 3), area);
+        assert_eq!((app.tab, visible_ids(&app)), (None, vec![3, 2, 1]));
+    }
+
+    #[test]
+    fn clicks_close_the_help_before_reaching_the_tabs() {
+        let mut app = tabbed_app();
+        app.show_help = true;
+        app.handle_mouse(click(20, 3), Rect::new(0, 3, 80, 20));
+        assert!(!app.show_help);
+        assert_eq!(app.tab, None);
+
+        app.handle_mouse(MouseEvent { kind: MouseEventKind::ScrollDown, ..click(0, 0) }, Rect::new(0, 3, 80, 20));
+        assert_eq!(app.scroll_offset, 1);
+        app.handle_mouse(MouseEvent { kind: MouseEventKind::ScrollUp, ..click(0, 0) }, Rect::new(0, 3, 80, 20));
+        app.handle_mouse(MouseEvent { kind: MouseEventKind::ScrollUp, ..click(0, 0) }, Rect::new(0, 3, 80, 20));
+        assert_eq!(app.scroll_offset, 0);
+    }
+
+    #[test]
+    fn next_tab_wraps_and_keeps_an_empty_selected_tab() {
+        let mut app = tabbed_app();
+        for expected in [Some("rapid_fire"), Some("wash_score"), None] {
+            app.handle_key(KeyCode::Tab);
+            assert_eq!(app.tab, expected);
+        }
+
+        // A selected stream whose alerts have left the feed keeps its tab
+        app.tab = Some("book_imbalance");
+        assert_eq!(app.tabs(), vec![None, Some("rapid_fire"), Some("wash_score"), Some("book_imbalance")]);
+        assert!(visible_ids(&app).is_empty());
+        app.handle_key(KeyCode::Tab);
+        assert_eq!(app.tab, None);
+    }
+
+    #[test]
+    fn frames_wait_for_the_refresh_interval() {
+        let mut app = app();
+        let start = Instant::now();
+        assert!(app.frame_due(start), "the first frame is drawn at once");
+        app.last_draw = Some(start);
+        app.dirty = false;
+        assert!(!app.frame_due(start + Duration::from_millis(149)));
+        assert!(app.frame_due(start + Duration::from_millis(150)), "redrawn every interval even when unchanged");
+    }
+
+    #[test]
+    fn low_cpu_frames_wait_for_a_change() {
+        let mut app = app();
+        app.refresh.low_cpu = true;
+        let start = Instant::now();
+        assert!(app.frame_due(start));
+        app.last_draw = Some(start);
+        app.dirty = false;
+        assert!(!app.frame_due(start + Duration::from_secs(5)));
+
+        // The same uptime second with no alerts changes nothing; a flag flip does
+        app.apply(DashboardUpdate::default());
+        assert!(!app.frame_due(start + Duration::from_secs(5)));
+        app.apply(DashboardUpdate { degraded: true, ..Default::default() });
+        assert!(!app.frame_due(start + Duration::from_millis(100)), "still held to the refresh interval");
+        assert!(app.frame_due(start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn pin_keys_follow_the_top_alert_until_unpinned() {
+        let mut app = app();
+        app.apply(DashboardUpdate {
+            alerts: vec![alert(1, "ACC-1", "AAPL"), alert(2, "ACC-2", "MSFT"), alert(3, "ACC-1", "MSFT"), alert(4, "ACC-3", "GOOGL")],
+            ..Default::default()
+        });
+        app.scroll_offset = 1;
+        app.handle_key(KeyCode::Char('p'));
+        assert_eq!(app.pin, Some(Pin::Account("ACC-1".into())));
+        assert_eq!((visible_ids(&app), app.scroll_offset), (vec![3, 1], 0));
+
+        // New alerts for the pinned account join the feed; others stay hidden
+        app.apply(DashboardUpdate { alerts: vec![alert(5, "ACC-2", "AAPL"), alert(6, "ACC-1", "GOOGL")], ..Default::default() });
+        assert_eq!(visible_ids(&app), vec![6, 3, 1]);
+        assert_eq!(feed_title(&app, app.alerts.len()), " Alert Feed (3 of 6 pinned) ");
+
+        // Pressing either pin key again unpins
+        app.handle_key(KeyCode::Char('P'));
+        assert_eq!(app.pin, None);
+        app.handle_key(KeyCode::Char('P'));
+        assert_eq!(app.pin, Some(Pin::Symbol("GOOGL".into())));
+        assert_eq!(visible_ids(&app), vec![6, 4]);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.pin, None);
+    }
+
+    #[test]
+    fn pinning_an_empty_feed_or_an_alert_without_the_field_does_nothing() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('p'));
+        assert_eq!(app.pin, None);
+
+        let ml = Alert { alert_type: AlertType::MlAnomaly, symbol: None, ..alert(1, "ACC-1", "AAPL") };
+        app.apply(DashboardUpdate { alerts: vec![ml], ..Default::default() });
+        app.handle_key(KeyCode::Char('P'));
+        assert_eq!(app.pin, None);
+        assert_eq!(Pin::Symbol("AAPL".into()).label(), " PINNED symbol AAPL ");
+    }
+
+    /// A cycle due at once, at event time `cycle_ts`, with one rapid-fire
+    /// burst of `burst_trades` when set.
+    fn cycle(cycle_ts: i64, burst_trades: Option<i64>) -> RecordedCycle {
+        let bursts = burst_trades.map(|trades| {
+            let burst = crate::types::RapidFireBurst { account_id: format!("ACC-{cycle_ts}"), burst_trades: trades, burst_volume: 1_000, low: 100.0, high: 100.0, burst_notional: 1e9 };
+            crate::evaluation::RowBatch::RapidFire(vec![burst])
+        });
+        RecordedCycle { at_ms: 0, cycle_ts, total_trades: 0, total_orders: 0, prices: HashMap::new(), batches: bursts.into_iter().collect() }
+    }
+
+    fn playback(cycles: Vec<RecordedCycle>, breakpoints: crate::config::BreakpointConfig) -> Playback {
+        let player = Player::from_config(&Config::default()).unwrap();
+        Playback::new(cycles, player, 1.0, Breakpoints::from_config(&breakpoints).unwrap())
+    }
+
+    #[test]
+    fn replay_stops_before_the_breakpoint_cycle_until_resumed() {
+        let mut app = app();
+        let at = crate::config::BreakpointConfig { at: Some("2000".into()), ..Default::default() };
+        let mut playback = playback(vec![cycle(1_000, None), cycle(2_000, Some(60)), cycle(3_000, None)], at);
+
+        playback.step(&mut app);
+        assert_eq!(app.view.heartbeat.seq, 1);
+        playback.step(&mut app);
+        assert_eq!(app.paused, Some(BreakReason::EventTime { at_ms: 2_000 }));
+        assert!(visible_ids(&app).is_empty(), "the breakpoint cycle hasn't played");
+        playback.step(&mut app);
+        assert_eq!(app.view.heartbeat.seq, 1, "nothing plays while paused");
+
+        app.handle_key(KeyCode::Char('c'));
+        playback.step(&mut app);
+        assert!(app.paused.is_none());
+        playback.step(&mut app);
+        playback.step(&mut app);
+        assert_eq!((app.view.heartbeat.seq, visible_ids(&app)), (3, vec![1]));
+        assert_eq!(playback.until_next(), None);
+        // Each breakpoint fires once
+        assert!(app.paused.is_none());
+    }
+
+    #[test]
+    fn replay_stops_after_a_cycle_raising_a_severe_alert() {
+        let mut app = app();
+        let on_high = crate::config::BreakpointConfig { on_severity: Some(crate::config::SeverityLevel::High), ..Default::default() };
+        // 10 trades is a Medium burst, 30 a High one
+        let mut playback = playback(vec![cycle(1_000, Some(10)), cycle(2_000, Some(30)), cycle(3_000, Some(60))], on_high);
+
+        playback.step(&mut app);
+        assert!(app.paused.is_none());
+        playback.step(&mut app);
+        assert!(matches!(app.paused, Some(BreakReason::Alert { id: 2, severity: AlertSeverity::High, .. })));
+        assert_eq!(visible_ids(&app), vec![2, 1], "the alert that hit is in the feed");
+
+        app.handle_key(KeyCode::Char('c'));
+        playback.step(&mut app);
+        playback.step(&mut app);
+        assert!(app.paused.is_none(), "a later Critical doesn't stop it again");
+        assert_eq!(visible_ids(&app), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn replay_pace_waits_for_the_recorded_time() {
+        let mut app = app();
+        let mut later = cycle(1_000, None);
+        later.at_ms = 60_000;
+        let mut playback = playback(vec![cycle(0, None), later], Default::default());
+        playback.step(&mut app);
+        assert!(playback.until_next().is_some_and(|wait| wait > Duration::from_secs(50)));
+        playback.step(&mut app);
+        assert_eq!(app.view.heartbeat.seq, 1, "the second cycle isn't due yet");
+
+        playback.pace.hold(Duration::from_secs(5));
+        assert!(playback.until_next().is_some_and(|wait| wait > Duration::from_secs(60)), "a pause pushes it back");
+    }
 }
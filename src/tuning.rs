@@ -0,0 +1,205 @@
+//! Threshold suggestions from operator dispositions. For each rule with a
+//! single threshold, the reviewed alerts' measurements show how far the
+//! threshold could move before it loses a confirmed true positive, and how
+//! many confirmed false positives that move would remove.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::{Config, VolumeMode};
+use crate::reviews::{AlertReview, Disposition};
+
+/// Which way a rule's measurement must cross its threshold to fire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fires {
+    /// At or above the threshold; tightening raises it.
+    Above,
+    /// Below the threshold; tightening lowers it.
+    Below,
+}
+
+/// The threshold behind one alert type, as a `[section] key` of the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threshold {
+    pub section: &'static str,
+    pub key: &'static str,
+    pub fires: Fires,
+    /// Whole-number threshold, e.g. a trade count.
+    pub integer: bool,
+}
+
+impl Threshold {
+    /// The threshold `alert_type` fires on under `config`, if it has exactly one.
+    pub fn for_alert(alert_type: &str, config: &Config) -> Option<Self> {
+        let (section, key, fires, integer) = match alert_type {
+            "VolumeAnomaly" if config.volume.mode == VolumeMode::Adv => ("volume", "adv_pct_threshold", Fires::Above, false),
+            "VolumeAnomaly" => ("thresholds", "volume_ratio", Fires::Above, false),
+            "PriceSpike" => ("thresholds", "price_range_pct", Fires::Above, false),
+            "RapidFire" => ("thresholds", "rapid_fire_trades", Fires::Above, true),
+            "WashTrading" => ("thresholds", "wash_imbalance", Fires::Below, false),
+            "SuspiciousMatch" => ("thresholds", "match_price_diff", Fires::Below, false),
+            "FrontRunning" => ("thresholds", "front_run_spread", Fires::Below, false),
+            "Diversification" => ("thresholds", "diversification_ratio", Fires::Above, false),
+            "MomentumIgnition" => ("thresholds", "momentum_displacement_pct", Fires::Above, false),
+            "CounterpartyConcentration" => ("thresholds", "concentration_share", Fires::Above, false),
+            "MlAnomaly" => ("thresholds", "ml_score", Fires::Above, false),
+            _ => return None,
+        };
+        Some(Self { section, key, fires, integer })
+    }
+
+    pub fn get(&self, config: &Config) -> f64 {
+        let t = &config.thresholds;
+        match self.key {
+            "adv_pct_threshold" => config.volume.adv_pct_threshold,
+            "volume_ratio" => t.volume_ratio,
+            "price_range_pct" => t.price_range_pct,
+            "rapid_fire_trades" => t.rapid_fire_trades as f64,
+            "wash_imbalance" => t.wash_imbalance,
+            "match_price_diff" => t.match_price_diff,
+            "front_run_spread" => t.front_run_spread,
+            "diversification_ratio" => t.diversification_ratio,
+            "momentum_displacement_pct" => t.momentum_displacement_pct,
+            "concentration_share" => t.concentration_share,
+            "ml_score" => t.ml_score,
+            key => unreachable!("no threshold {key}"),
+        }
+    }
+
+    pub fn set(&self, config: &mut Config, value: f64) {
+        let t = &mut config.thresholds;
+        match self.key {
+            "adv_pct_threshold" => config.volume.adv_pct_threshold = value,
+            "volume_ratio" => t.volume_ratio = value,
+            "price_range_pct" => t.price_range_pct = value,
+            "rapid_fire_trades" => t.rapid_fire_trades = value as i64,
+            "wash_imbalance" => t.wash_imbalance = value,
+            "match_price_diff" => t.match_price_diff = value,
+            "front_run_spread" => t.front_run_spread = value,
+            "diversification_ratio" => t.diversification_ratio = value,
+            "momentum_displacement_pct" => t.momentum_displacement_pct = value,
+            "concentration_share" => t.concentration_share = value,
+            "ml_score" => t.ml_score = value,
+            key => unreachable!("no threshold {key}"),
+        }
+    }
+}
+
+/// What the reviews say about one rule's threshold.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub alert_type: String,
+    /// `section.key` in the config file.
+    pub threshold: String,
+    pub fires: Fires,
+    pub current: f64,
+    /// `None` when no move removes a false positive without losing a true one.
+    pub suggested: Option<f64>,
+    /// Reviewed alerts with a measurement.
+    pub true_positives: u64,
+    pub false_positives: u64,
+    /// False positives the suggested threshold would no longer fire on.
+    pub removed: u64,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(suggested) = self.suggested else {
+            return write!(
+                f,
+                "{}: keep {} at {} (no move removes any of {} false positive(s) without losing one of {} true positive(s))",
+                self.alert_type, self.threshold, self.current, self.false_positives, self.true_positives
+            );
+        };
+        let verb = if self.fires == Fires::Above { "raising" } else { "lowering" };
+        write!(
+            f,
+            "{}: {verb} {} from {} to {} would remove {} of {} false positive(s) ({:.0}%) while keeping all {} true positive(s)",
+            self.alert_type,
+            self.threshold,
+            self.current,
+            suggested,
+            self.removed,
+            self.false_positives,
+            self.removed as f64 / self.false_positives as f64 * 100.0,
+            self.true_positives
+        )
+    }
+}
+
+/// A suggestion for every rule with at least one reviewed false positive
+/// and one reviewed true positive to bound the move, by alert type. Reviews
+/// without a measurement, benign ones included, are ignored.
+pub fn suggest(reviews: &[AlertReview], config: &Config) -> Vec<Suggestion> {
+    let mut by_type: BTreeMap<&str, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for r in reviews {
+        let (Some(metric), Some(disposition)) = (r.metric, r.disposition) else { continue };
+        let (tps, fps) = by_type.entry(&r.alert_type).or_default();
+        match disposition {
+            Disposition::TruePositive => tps.push(metric),
+            Disposition::FalsePositive => fps.push(metric),
+            Disposition::Benign => {}
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    for (alert_type, (tps, fps)) in by_type {
+        let Some(threshold) = Threshold::for_alert(alert_type, config) else { continue };
+        if tps.is_empty() || fps.is_empty() {
+            continue;
+        }
+        let current = threshold.get(config);
+        // Orient every measurement so "further past the threshold" is larger
+        let sign = if threshold.fires == Fires::Above { 1.0 } else { -1.0 };
+        let weakest_tp = tps.iter().map(|m| m * sign).fold(f64::INFINITY, f64::min);
+        let removable: Vec<f64> = fps.iter().map(|m| m * sign).filter(|&m| m < weakest_tp).collect();
+        let suggested = removable.iter().copied().reduce(f64::max).map(|strongest_fp| {
+            let value = if threshold.integer {
+                // Fires at or above, so one past the strongest false positive
+                strongest_fp.floor() + 1.0
+            } else {
+                between(strongest_fp, weakest_tp)
+            };
+            value * sign
+        });
+        suggestions.push(Suggestion {
+            alert_type: alert_type.to_string(),
+            threshold: format!("{}.{}", threshold.section, threshold.key),
+            fires: threshold.fires,
+            current,
+            suggested,
+            true_positives: tps.len() as u64,
+            false_positives: fps.len() as u64,
+            removed: removable.len() as u64,
+        });
+    }
+    suggestions
+}
+
+/// `config` with every suggested threshold applied.
+pub fn apply(suggestions: &[Suggestion], config: &Config) -> Config {
+    let mut tuned = config.clone();
+    for s in suggestions {
+        if let (Some(value), Some(threshold)) = (s.suggested, Threshold::for_alert(&s.alert_type, config)) {
+            threshold.set(&mut tuned, value);
+        }
+    }
+    tuned
+}
+
+/// The midpoint of `lo..hi`, rounded to as few significant digits as keep
+/// it strictly inside.
+fn between(lo: f64, hi: f64) -> f64 {
+    let mid = (lo + hi) / 2.0;
+    for digits in 1..=6 {
+        let scale = 10f64.powi(digits - 1 - mid.abs().log10().floor() as i32);
+        let rounded = (mid * scale).round() / scale;
+        if lo < rounded && rounded < hi {
+            return rounded;
+        }
+    }
+    mid
+}
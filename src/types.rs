@@ -1,20 +1,25 @@
 use laminar_derive::{FromRow, Record};
+use serde::{Deserialize, Serialize};
 
 // ── Input Types (pushed into sources) ──
 
-#[derive(Debug, Clone, Record)]
+#[derive(Debug, Clone, Record, Serialize)]
 pub struct Trade {
     pub account_id: String,
+    /// Account on the other side of the fill; empty when not known.
+    pub counterparty: String,
     pub symbol: String,
     pub side: String,
     pub price: f64,
     pub volume: i64,
     pub order_ref: String,
+    /// Position in its feed, gap-checked on push; 0 for an unsequenced record.
+    pub seq: i64,
     #[event_time]
     pub ts: i64,
 }
 
-#[derive(Debug, Clone, Record)]
+#[derive(Debug, Clone, Record, Serialize)]
 pub struct Order {
     pub order_id: String,
     pub account_id: String,
@@ -22,21 +27,52 @@ pub struct Order {
     pub side: String,
     pub quantity: i64,
     pub price: f64,
+    /// Position in its feed, gap-checked on push; 0 for an unsequenced record.
+    pub seq: i64,
+    #[event_time]
+    pub ts: i64,
+}
+
+/// One level of a symbol's simulated limit order book. A snapshot is every
+/// level of one symbol at one `ts`.
+#[derive(Debug, Clone, Record, Serialize)]
+pub struct BookLevel {
+    pub symbol: String,
+    /// `bid` or `ask`.
+    pub side: String,
+    /// 1 is the best price on its side.
+    pub level: i64,
+    pub price: f64,
+    pub size: i64,
+    #[event_time]
+    pub ts: i64,
+}
+
+/// A news headline about one symbol.
+#[derive(Debug, Clone, Record, Serialize)]
+pub struct NewsEvent {
+    pub symbol: String,
+    pub headline: String,
+    /// Expected price effect, -1.0 to 1.0: the sign is the direction, the
+    /// magnitude how market-moving the news is.
+    pub impact: f64,
     #[event_time]
     pub ts: i64,
 }
 
 // ── Output Types (polled from subscriptions) ──
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct VolumeBaseline {
     pub symbol: String,
+    pub window_start: i64,
     pub total_volume: i64,
     pub trade_count: i64,
     pub avg_price: f64,
+    pub total_notional: f64,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct OhlcVolatility {
     pub symbol: String,
     pub bar_start: i64,
@@ -46,28 +82,33 @@ pub struct OhlcVolatility {
     pub close: f64,
     pub volume: i64,
     pub price_range: f64,
+    pub notional: f64,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct RapidFireBurst {
     pub account_id: String,
     pub burst_trades: i64,
     pub burst_volume: i64,
     pub low: f64,
     pub high: f64,
+    pub burst_notional: f64,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct WashScore {
     pub account_id: String,
     pub symbol: String,
+    pub window_start: i64,
     pub buy_volume: i64,
     pub sell_volume: i64,
     pub buy_count: i64,
     pub sell_count: i64,
+    pub buy_notional: f64,
+    pub sell_notional: f64,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct SuspiciousMatch {
     pub symbol: String,
     pub trade_price: f64,
@@ -77,9 +118,67 @@ pub struct SuspiciousMatch {
     pub side: String,
     pub order_price: f64,
     pub price_diff: f64,
+    pub notional: f64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AccountWindow {
+    pub account_id: String,
+    pub window_start: i64,
+    pub trade_count: i64,
+    pub total_volume: i64,
+    pub buy_volume: i64,
+    pub sell_volume: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AccountActivity {
+    pub account_id: String,
+    pub window_start: i64,
+    pub distinct_symbols: i64,
+    pub trade_count: i64,
+    pub total_notional: f64,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct BookImbalance {
+    pub symbol: String,
+    pub window_start: i64,
+    pub bid_depth: i64,
+    pub ask_depth: i64,
+    pub mid_price: f64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct MomentumBurst {
+    pub account_id: String,
+    pub symbol: String,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub buy_count: i64,
+    pub sell_count: i64,
+    pub volume: i64,
+    pub vwap: f64,
+    pub low: f64,
+    pub high: f64,
+    pub notional: f64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PreNewsTrade {
+    pub account_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub volume: i64,
+    pub trade_price: f64,
+    pub trade_ts: i64,
+    pub news_ts: i64,
+    pub headline: String,
+    pub impact: f64,
+    pub notional: f64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct AsofMatch {
     pub symbol: String,
     pub trade_price: f64,
@@ -88,5 +187,40 @@ pub struct AsofMatch {
     pub order_id: String,
     pub order_account: String,
     pub order_price: f64,
+    pub order_quantity: i64,
+    pub trade_ts: i64,
+    pub order_ts: i64,
     pub price_spread: f64,
+    pub notional: f64,
+}
+
+/// A trade with a known counterparty, against the best quote on the side it
+/// traded into (the ask for a buy, the bid for a sell) at the time.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PriceImprovement {
+    pub account_id: String,
+    pub counterparty: String,
+    pub symbol: String,
+    pub side: String,
+    pub order_ref: String,
+    pub trade_price: f64,
+    pub quote_price: f64,
+    /// How far the fill is through the quote in the account's favour, as a
+    /// fraction of the quote; negative when it paid up.
+    pub improvement: f64,
+    pub volume: i64,
+    pub trade_ts: i64,
+    pub notional: f64,
+}
+
+/// One account's trading against one counterparty in a `counterparty_volume`
+/// window. Windows re-emit while open, each row the window's total so far.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct CounterpartyVolume {
+    pub account_id: String,
+    pub counterparty: String,
+    pub window_start: i64,
+    pub trade_count: i64,
+    pub volume: i64,
+    pub notional: f64,
 }
@@ -1,246 +1,1469 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
-use axum::response::IntoResponse;
-use axum::routing::get;
-use axum::Router;
-use serde::Serialize;
+use axum::extract::{ConnectInfo, Path, Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::services::ServeDir;
 
-use crate::alerts::{Alert, AlertEngine};
-use crate::detection;
-use crate::generator::FraudGenerator;
-use crate::latency::{LatencyStats, LatencyTracker};
+use crate::alerts::{Alert, AlertEngine, AlertRetention, AlertSeverity, Cooldown, SymbolAlerts, SILENCE_TYPE};
+use crate::archive::AlertArchive;
+use crate::audit::AuditLog;
+use crate::auth::{self, Authorizer};
+use crate::blotter::Blotter;
+use crate::clock::SystemClock;
+use crate::config::{Config, Scope, SeverityLevel, SlackConfig, WebConfig};
+use crate::detection::{self, PipelineOptions};
+use crate::engine::{Engine, Heartbeat, PushStats, WatermarkStrategy, STREAM_NAMES};
+use crate::evaluation::EvalStats;
+use crate::generator::{self, FraudGenerator};
+use crate::ids::{IdLedger, UidGenerator};
+use crate::latency::LatencyStats;
+use crate::limits::LoadGovernor;
+use crate::pacing::Pacer;
+use crate::recording::Recorder;
+use crate::reviews::{AlertReview, AlertReviews, ReviewUpdate};
+use crate::risk::AccountRisk;
+use crate::runs::{self, RunComparison, RunRecord, RunSummary};
+use crate::search::AlertIndex;
+use crate::sequence::FeedStats;
+use crate::slack::{self, SlackCommand, SlackReply, SlashCommand};
+use crate::slo::{SloStatus, SloTracker};
+use crate::standby::{Standby, StandbyRequest, StandbyStatus};
+use crate::stop::StopConditions;
+use crate::ticks::TickDetector;
 
-#[derive(Clone, Serialize)]
-struct DashboardUpdate {
-    alerts: Vec<Alert>,
-    latency: LatencyUpdate,
-    streams: Vec<StreamStatus>,
-    alert_counts: HashMap<String, u64>,
-    total_trades: u64,
-    total_orders: u64,
-    total_alerts: u64,
-    uptime_secs: u64,
-    prices: HashMap<String, f64>,
+/// One WebSocket frame: this cycle's alerts plus the engine's running totals.
+/// The web page and `attach` mode both render from it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DashboardUpdate {
+    pub alerts: Vec<Alert>,
+    pub latency: LatencyUpdate,
+    pub streams: Vec<StreamStatus>,
+    pub alert_counts: HashMap<String, u64>,
+    pub total_trades: u64,
+    pub total_orders: u64,
+    pub total_alerts: u64,
+    pub uptime_secs: u64,
+    pub prices: HashMap<String, f64>,
+    /// Overload state from the engine's governor; absent from older engines.
+    #[serde(default)]
+    pub degraded: bool,
+    #[serde(default)]
+    pub shed_cycles: u64,
+    #[serde(default)]
+    pub dropped_alerts: u64,
+    /// Alerts the rules raised vs. alerts that reached the feed; the gap is
+    /// what model suppression and the alert cap absorbed.
+    #[serde(default)]
+    pub alerts_generated: u64,
+    #[serde(default)]
+    pub alerts_delivered: u64,
+    #[serde(default)]
+    pub suppressed_alerts: u64,
+    /// `suppressed_alerts` split by cause: below `model_min_score`, or held
+    /// back by a cooldown or silence.
+    #[serde(default)]
+    pub model_suppressed: u64,
+    #[serde(default)]
+    pub cooldown_suppressed: u64,
+    #[serde(default)]
+    pub push_stats: PushStats,
+    /// Estimated `suspicious_match` rows/s at the measured rate, and whether
+    /// that is over the `[join]` guardrail.
+    #[serde(default)]
+    pub join_fanout_per_sec: f64,
+    #[serde(default)]
+    pub join_overloaded: bool,
+    /// Would-have-fired alerts from shadow-mode rules this cycle, and their totals.
+    #[serde(default)]
+    pub shadow_alerts: Vec<Alert>,
+    #[serde(default)]
+    pub shadow_counts: HashMap<String, u64>,
+    #[serde(default)]
+    pub heartbeat: Heartbeat,
+    /// `[cooldown]` suppressions still running, soonest to expire first.
+    #[serde(default)]
+    pub cooldowns: Vec<Cooldown>,
+    /// Delivered alerts per symbol and type, most-alerted symbol first.
+    #[serde(default)]
+    pub symbol_alerts: Vec<SymbolAlerts>,
+    /// Sequence gaps and duplicates per input feed since start.
+    #[serde(default)]
+    pub feed_integrity: BTreeMap<String, FeedStats>,
+    /// `[retention]`, for the web page to bound its alert table by.
+    #[serde(default)]
+    pub retention: AlertRetention,
+    /// Queue depth and wait time of the evaluation worker; zero without one.
+    #[serde(default)]
+    pub eval_stats: EvalStats,
+    /// Warm-up of a standby pipeline, while one is warming.
+    #[serde(default)]
+    pub standby: Option<StandbyStatus>,
+    #[serde(default)]
+    pub pipeline_switches: u64,
+    /// Per-account bursts, wash volume, score and alert count: on an
+    /// instance, the accounts its last cycle touched; from `coordinate`,
+    /// every account within `[coordinator] account_window_secs`, added up.
+    #[serde(default)]
+    pub accounts: Vec<AccountRisk>,
+    /// Alert latency against each `[slo]` objective.
+    #[serde(default)]
+    pub slo: Vec<SloStatus>,
 }
 
-#[derive(Clone, Serialize)]
-struct LatencyUpdate {
-    push: LatencyStats,
-    processing: LatencyStats,
-    alert: LatencyStats,
+/// Filters of `/api/alerts` and `/api/alerts.csv`; an alert must match every
+/// one given.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AlertQuery {
+    /// Words to search for; results come ranked instead of by time.
+    pub q: Option<String>,
+    #[serde(rename = "type")]
+    pub alert_type: Option<String>,
+    /// This severity or higher.
+    pub severity: Option<SeverityLevel>,
+    pub symbol: Option<String>,
+    pub account: Option<String>,
+    /// Event time at or after this, in epoch milliseconds.
+    pub since_ms: Option<i64>,
+    /// Keep only the newest this many.
+    pub limit: Option<usize>,
 }
 
-#[derive(Clone, Serialize)]
-struct StreamStatus {
-    name: String,
-    count: u64,
-    active: bool,
+impl AlertQuery {
+    pub fn matches(&self, alert: &Alert) -> bool {
+        self.alert_type.as_ref().is_none_or(|t| t.eq_ignore_ascii_case(alert.alert_type.label()))
+            && self.severity.is_none_or(|s| alert.severity >= AlertSeverity::from(s))
+            && self.symbol.as_ref().is_none_or(|s| alert.symbol.as_ref() == Some(s))
+            && self.account.as_ref().is_none_or(|a| alert.account_id.as_ref() == Some(a))
+            && self.since_ms.is_none_or(|since| alert.timestamp_ms >= since)
+    }
+
+    /// The matching alerts, oldest first.
+    pub fn apply(&self, alerts: Vec<Alert>) -> Vec<Alert> {
+        let mut kept: Vec<Alert> = alerts.into_iter().filter(|a| self.matches(a)).collect();
+        if let Some(limit) = self.limit {
+            kept.drain(..kept.len().saturating_sub(limit));
+        }
+        kept
+    }
+
+    /// With `q`, the blotter's search hits that match the other filters,
+    /// best first and `limit` the top ones; without, [`AlertQuery::apply`]
+    /// over its recent alerts.
+    pub fn run(&self, blotter: &Blotter) -> Vec<Alert> {
+        match self.q.as_deref().filter(|q| !q.trim().is_empty()) {
+            Some(q) => {
+                let hits = blotter.search(q, usize::MAX).into_iter().filter(|a| self.matches(a));
+                hits.take(self.limit.unwrap_or(usize::MAX)).collect()
+            }
+            None => self.apply(blotter.recent_alerts()),
+        }
+    }
+}
+
+/// One figure behind `/api/stats` and `/api/stats.csv`, long-format so a
+/// spreadsheet can pivot it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatRow {
+    /// `totals`, `latency`, `streams`, `alerts` or `symbols`.
+    pub group: String,
+    pub name: String,
+    pub value: f64,
+}
+
+/// Filters of `/api/stats` and `/api/stats.csv`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StatsQuery {
+    pub group: Option<String>,
+    pub name: Option<String>,
+}
+
+impl StatsQuery {
+    pub fn apply(&self, rows: &[StatRow]) -> Vec<StatRow> {
+        rows.iter()
+            .filter(|r| self.group.as_ref().is_none_or(|g| g == &r.group))
+            .filter(|r| self.name.as_ref().is_none_or(|n| n == &r.name))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The running totals of one update as [`StatRow`]s: counters, latency
+/// percentiles in microseconds, rows per stream, and alerts per type and
+/// per symbol.
+pub fn stat_rows(update: &DashboardUpdate) -> Vec<StatRow> {
+    let row = |group: &str, name: &str, value: f64| StatRow { group: group.to_string(), name: name.to_string(), value };
+    let mut rows = vec![
+        row("totals", "trades", update.total_trades as f64),
+        row("totals", "orders", update.total_orders as f64),
+        row("totals", "alerts", update.total_alerts as f64),
+        row("totals", "alerts_generated", update.alerts_generated as f64),
+        row("totals", "suppressed_alerts", update.suppressed_alerts as f64),
+        row("totals", "model_suppressed", update.model_suppressed as f64),
+        row("totals", "cooldown_suppressed", update.cooldown_suppressed as f64),
+        row("totals", "dropped_alerts", update.dropped_alerts as f64),
+        row("totals", "shed_cycles", update.shed_cycles as f64),
+        row("totals", "uptime_secs", update.uptime_secs as f64),
+    ];
+    let l = &update.latency;
+    for (name, stats) in [("push", &l.push), ("processing", &l.processing), ("alert", &l.alert), ("tick_lag", &l.tick_lag), ("bar_lag", &l.bar_lag)] {
+        for (p, us) in [("p50_us", stats.p50_us), ("p95_us", stats.p95_us), ("p99_us", stats.p99_us), ("max_us", stats.max_us)] {
+            rows.push(row("latency", &format!("{name}_{p}"), us as f64));
+        }
+    }
+    rows.extend(update.streams.iter().map(|s| row("streams", &s.name, s.count as f64)));
+    let by_type: BTreeMap<_, _> = update.alert_counts.iter().collect();
+    rows.extend(by_type.into_iter().map(|(t, &n)| row("alerts", t, n as f64)));
+    rows.extend(update.symbol_alerts.iter().map(|s| row("symbols", &s.symbol, s.total as f64)));
+    for s in &update.slo {
+        rows.push(row("slo", &format!("{}_alerts", s.name), s.alerts as f64));
+        rows.push(row("slo", &format!("{}_budget_left", s.name), s.budget_left() as f64));
+        for (name, attainment) in [("attainment", s.attainment()), ("window_attainment", s.window_attainment())] {
+            if let Some(a) = attainment {
+                rows.push(row("slo", &format!("{}_{name}", s.name), a));
+            }
+        }
+    }
+    rows
+}
+
+/// `alerts` as CSV, one row per alert with its description last.
+pub fn alerts_csv(alerts: &[Alert]) -> String {
+    let mut out = String::from("id,timestamp_ms,alert_type,severity,symbol,account_id,score,metric,gap_ms,latency_us,uid,description\n");
+    let opt = |v: Option<String>| v.unwrap_or_default();
+    for a in alerts {
+        out += &format!(
+            "{},{},{},{:?},{},{},{},{},{},{},{},{}\n",
+            a.id,
+            a.timestamp_ms,
+            a.alert_type.label(),
+            a.severity,
+            csv_field(a.symbol.as_deref().unwrap_or("")),
+            csv_field(a.account_id.as_deref().unwrap_or("")),
+            opt(a.score.map(|s| s.to_string())),
+            opt(a.metric.map(|m| m.to_string())),
+            opt(a.gap_ms.map(|g| g.to_string())),
+            a.latency_us,
+            a.uid.as_deref().unwrap_or(""),
+            csv_field(&a.description().to_string()),
+        );
+    }
+    out
+}
+
+/// `rows` as CSV with a `group,name,value` header.
+pub fn stats_csv(rows: &[StatRow]) -> String {
+    let mut out = String::from("group,name,value\n");
+    for r in rows {
+        out += &format!("{},{},{}\n", csv_field(&r.group), csv_field(&r.name), r.value);
+    }
+    out
+}
+
+/// Quote a field when it holds a comma, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// `GET /api/alerts/summary`: delivered alert totals by type and by symbol.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertSummary {
+    pub total_alerts: u64,
+    pub by_type: HashMap<String, u64>,
+    pub by_symbol: Vec<SymbolAlerts>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LatencyUpdate {
+    pub push: LatencyStats,
+    pub processing: LatencyStats,
+    pub alert: LatencyStats,
+    /// Price move to detection, tick path vs. `ohlc_vol` bar path.
+    #[serde(default)]
+    pub tick_lag: LatencyStats,
+    #[serde(default)]
+    pub bar_lag: LatencyStats,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StreamStatus {
+    pub name: String,
+    pub count: u64,
+    pub active: bool,
+    /// Cycles that ended with rows left over after `[limits] max_rows_per_stream`.
+    #[serde(default)]
+    pub budget_exhausted: u64,
+    /// Rows carried over into the next cycle.
+    #[serde(default)]
+    pub carried_rows: u64,
+}
+
+impl DashboardUpdate {
+    /// Snapshot `engine` after a cycle that raised `alerts`.
+    pub fn from_engine(engine: &Engine, alerts: Vec<Alert>) -> Self {
+        let streams = STREAM_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| StreamStatus {
+                name: name.to_string(),
+                count: engine.stream_counts[i],
+                active: engine.stream_counts[i] > 0,
+                budget_exhausted: engine.budget_exhausted[i],
+                carried_rows: engine.carried_rows[i],
+            })
+            .collect();
+
+        Self {
+            alerts,
+            latency: LatencyUpdate {
+                push: engine.latency.push_stats(),
+                processing: engine.latency.processing_stats(),
+                alert: engine.latency.alert_stats(),
+                tick_lag: engine.latency.tick_lag_stats(),
+                bar_lag: engine.latency.bar_lag_stats(),
+            },
+            streams,
+            alert_counts: engine.alert_engine.alert_counts().clone(),
+            total_trades: engine.total_trades,
+            total_orders: engine.total_orders,
+            total_alerts: engine.alert_engine.total_alerts(),
+            uptime_secs: engine.elapsed().as_secs(),
+            prices: engine.gen.current_prices().clone(),
+            degraded: engine.degraded(),
+            shed_cycles: engine.governor.as_ref().map_or(0, |g| g.shed_cycles),
+            dropped_alerts: engine.governor.as_ref().map_or(0, |g| g.dropped_alerts),
+            alerts_generated: engine.alerts_generated(),
+            alerts_delivered: engine.alerts_delivered(),
+            suppressed_alerts: engine.alert_engine.suppressed_alerts(),
+            model_suppressed: engine.alert_engine.model_suppressed(),
+            cooldown_suppressed: engine.alert_engine.cooldown_suppressed(),
+            push_stats: engine.push_stats.clone(),
+            join_fanout_per_sec: engine.governor.as_ref().map_or(0.0, |g| g.join_fanout_per_sec()),
+            join_overloaded: engine.join_overloaded(),
+            shadow_alerts: engine.shadow_alerts.clone(),
+            shadow_counts: engine.alert_engine.shadow_alert_counts().clone(),
+            heartbeat: engine.heartbeat.clone(),
+            cooldowns: engine.alert_engine.active_cooldowns(engine.clock.now_ms()),
+            symbol_alerts: engine.alert_engine.symbol_breakdown(),
+            feed_integrity: engine.sequences.stats().clone(),
+            retention: engine.alert_engine.retention(),
+            eval_stats: engine.eval_stats.clone(),
+            standby: engine.standby.as_ref().map(Standby::status),
+            pipeline_switches: engine.pipeline_switches,
+            accounts: engine.alert_engine.account_risk().to_vec(),
+            slo: engine.slo.status(chrono::Utc::now().timestamp_millis()),
+        }
+    }
 }
 
 struct AppState {
     tx: broadcast::Sender<String>,
+    blotter: Blotter,
+    stats_reset: Arc<AtomicBool>,
+    connections: Connections,
+    keepalive: WebConfig,
+    /// Cooldowns as of the last published update.
+    cooldowns: Arc<Mutex<Vec<Cooldown>>>,
+    /// Alert totals as of the last published update.
+    alert_summary: Arc<Mutex<AlertSummary>>,
+    /// Behind `/api/stats`, as of the last published update.
+    stats: Arc<Mutex<Vec<StatRow>>>,
+    /// Behind `/api/accounts/risk`, as of the last published update.
+    accounts: Arc<Mutex<Vec<AccountRisk>>>,
+    /// Recorded runs behind `/api/runs`.
+    runs_dir: PathBuf,
+    reviews: AlertReviews,
+    standby_request: Arc<Mutex<Option<StandbyRequest>>>,
+    slack: SlackConfig,
+    silences: Arc<Mutex<Vec<(String, Duration)>>>,
+    auth: Authorizer,
+    audit: Arc<Mutex<Option<AuditLog>>>,
+}
+
+impl AppState {
+    fn audit_call(&self, token: Option<&str>, scope: Scope, method: &str, path: &str, status: u16) {
+        if let Some(audit) = &*self.audit.lock().unwrap() {
+            if let Err(e) = audit.record_api_call(token, scope, method, path, status) {
+                eprintln!("Audit log error: {e}");
+            }
+        }
+    }
 }
 
-pub async fn run(port: u16, fraud_rate: f64, duration: u64) -> Result<(), Box<dyn std::error::Error>> {
+/// One dashboard WebSocket client, as reported by `/api/connections`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionStats {
+    pub id: u64,
+    pub addr: String,
+    pub connected_secs: u64,
+    pub messages_sent: u64,
+    /// Updates the client fell too far behind to receive.
+    pub skipped: u64,
+    /// Updates queued for it after the last send.
+    pub lag: usize,
+    /// Round trip of the last answered ping.
+    pub rtt_ms: Option<u64>,
+    /// Since the client last sent anything, pongs included.
+    pub idle_ms: u64,
+}
+
+/// Body of `/api/connections`: open clients, plus totals that include
+/// clients since disconnected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConnectionsReport {
+    pub clients: usize,
+    pub total_connections: u64,
+    pub messages_sent: u64,
+    /// Clients closed for not answering pings within `idle_timeout_secs`.
+    pub timed_out: u64,
+    pub connections: Vec<ConnectionStats>,
+}
+
+struct Connection {
+    stats: ConnectionStats,
+    connected: Instant,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct ConnectionsInner {
+    open: HashMap<u64, Connection>,
+    total_connections: u64,
+    messages_sent: u64,
+    timed_out: u64,
+}
+
+/// Live WebSocket clients, shared by their socket tasks and `/api/connections`.
+#[derive(Clone, Default)]
+struct Connections {
+    inner: Arc<Mutex<ConnectionsInner>>,
+}
+
+impl Connections {
+    fn open(&self, addr: String) -> u64 {
+        let mut inner = self.inner.lock().unwrap();
+        inner.total_connections += 1;
+        let id = inner.total_connections;
+        let now = Instant::now();
+        let stats = ConnectionStats { id, addr, ..Default::default() };
+        inner.open.insert(id, Connection { stats, connected: now, last_seen: now });
+        id
+    }
+
+    fn sent(&self, id: u64, lag: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.messages_sent += 1;
+        if let Some(c) = inner.open.get_mut(&id) {
+            c.stats.messages_sent += 1;
+            c.stats.lag = lag;
+        }
+    }
+
+    fn skipped(&self, id: u64, n: u64) {
+        if let Some(c) = self.inner.lock().unwrap().open.get_mut(&id) {
+            c.stats.skipped += n;
+        }
+    }
+
+    fn seen(&self, id: u64, rtt: Option<Duration>) {
+        if let Some(c) = self.inner.lock().unwrap().open.get_mut(&id) {
+            c.last_seen = Instant::now();
+            if let Some(rtt) = rtt {
+                c.stats.rtt_ms = Some(rtt.as_millis() as u64);
+            }
+        }
+    }
+
+    fn idle(&self, id: u64) -> Duration {
+        self.inner.lock().unwrap().open.get(&id).map_or(Duration::ZERO, |c| c.last_seen.elapsed())
+    }
+
+    fn close(&self, id: u64, timed_out: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.open.remove(&id);
+        if timed_out {
+            inner.timed_out += 1;
+        }
+    }
+
+    fn report(&self) -> ConnectionsReport {
+        let inner = self.inner.lock().unwrap();
+        let mut connections: Vec<ConnectionStats> = inner
+            .open
+            .values()
+            .map(|c| ConnectionStats {
+                connected_secs: c.connected.elapsed().as_secs(),
+                idle_ms: c.last_seen.elapsed().as_millis() as u64,
+                ..c.stats.clone()
+            })
+            .collect();
+        connections.sort_by_key(|c| c.id);
+        ConnectionsReport {
+            clients: connections.len(),
+            total_connections: inner.total_connections,
+            messages_sent: inner.messages_sent,
+            timed_out: inner.timed_out,
+            connections,
+        }
+    }
+}
+
+/// Broadcasts engine state to the WebSocket clients of a dashboard started
+/// with [`serve`]. Any frontend driving an `Engine` can publish through it.
+pub struct WebPublisher {
+    tx: broadcast::Sender<String>,
+    blotter: Blotter,
+    reviews: AlertReviews,
+    /// Set by `POST /api/stats/reset` until the engine loop takes it.
+    stats_reset: Arc<AtomicBool>,
+    /// Set by `POST /api/pipeline/standby` until the engine loop takes it.
+    standby_request: Arc<Mutex<Option<StandbyRequest>>>,
+    /// Accounts silenced by `/fraud silence` until the engine loop takes them.
+    silences: Arc<Mutex<Vec<(String, Duration)>>>,
+    /// Where privileged API calls are recorded; set from the engine's.
+    audit: Arc<Mutex<Option<AuditLog>>>,
+    /// Behind `/api/cooldowns`; refreshed on every publish, held or sent.
+    cooldowns: Arc<Mutex<Vec<Cooldown>>>,
+    /// Behind `/api/alerts/summary`; refreshed like `cooldowns`.
+    alert_summary: Arc<Mutex<AlertSummary>>,
+    /// Behind `/api/stats`; refreshed like `cooldowns`.
+    stats: Arc<Mutex<Vec<StatRow>>>,
+    /// Behind `/api/accounts/risk`; refreshed like `cooldowns`.
+    accounts: Arc<Mutex<Vec<AccountRisk>>>,
+    /// Minimum gap between sends; `None` sends every update.
+    min_interval: Option<Duration>,
+    last_sent: Option<Instant>,
+    /// Latest update held back by the interval, carrying the alerts of every
+    /// update held since the last send.
+    held: Option<DashboardUpdate>,
+}
+
+impl WebPublisher {
+    /// The blotter behind `/api/accounts/{id}/activity` and
+    /// `/api/alerts/{id}/evidence`; set it on the engine this publisher
+    /// reports so the endpoints have data.
+    pub fn blotter(&self) -> Blotter {
+        self.blotter.clone()
+    }
+
+    /// The reviews behind `/api/alerts/{id}/review`; set them on the engine
+    /// this publisher reports so dispositions reach its quality report.
+    pub fn reviews(&self) -> AlertReviews {
+        self.reviews.clone()
+    }
+
+    /// Record privileged API calls in `audit`, normally the engine's.
+    pub fn set_audit(&self, audit: Option<AuditLog>) {
+        *self.audit.lock().unwrap() = audit;
+    }
+
+    /// Whether `POST /api/stats/reset` was called since the last check; the
+    /// loop driving the engine should then call `Engine::reset_stats`.
+    pub fn take_stats_reset(&self) -> bool {
+        self.stats_reset.swap(false, Ordering::Relaxed)
+    }
+
+    /// The standby requested by `POST /api/pipeline/standby` since the last
+    /// check, if any; the loop driving the engine should build it and pass
+    /// it to `Engine::start_standby`.
+    pub fn take_standby_request(&self) -> Option<StandbyRequest> {
+        self.standby_request.lock().unwrap().take()
+    }
+
+    /// Apply the silences Slack's `/fraud silence` asked for since the last
+    /// call to `engine`'s rules.
+    pub fn apply_silences(&self, engine: &mut Engine) {
+        let now_ms = engine.clock.now_ms();
+        for (account, duration) in std::mem::take(&mut *self.silences.lock().unwrap()) {
+            engine.alert_engine.silence(&account, duration, now_ms);
+        }
+    }
+
+    /// Send one update to every connected client, or hold it if that would
+    /// exceed the broadcast interval (doubled while degraded). A held update
+    /// is replaced by the next one, which inherits its alerts, so a send
+    /// carries the latest totals plus every alert since the last send.
+    pub fn publish(&mut self, update: &DashboardUpdate) {
+        *self.cooldowns.lock().unwrap() = update.cooldowns.clone();
+        *self.alert_summary.lock().unwrap() = AlertSummary {
+            total_alerts: update.total_alerts,
+            by_type: update.alert_counts.clone(),
+            by_symbol: update.symbol_alerts.clone(),
+        };
+        *self.stats.lock().unwrap() = stat_rows(update);
+        *self.accounts.lock().unwrap() = update.accounts.clone();
+        let mut next = update.clone();
+        if let Some(held) = self.held.take() {
+            next.alerts.splice(0..0, held.alerts);
+            next.shadow_alerts.splice(0..0, held.shadow_alerts);
+        }
+        if let (Some(interval), Some(last)) = (self.min_interval, self.last_sent) {
+            let interval = if update.degraded { interval * 2 } else { interval };
+            if last.elapsed() < interval {
+                self.held = Some(next);
+                return;
+            }
+        }
+        self.send(&next);
+    }
+
+    /// Send any held update now, e.g. when the run ends.
+    pub fn flush(&mut self) {
+        if let Some(held) = self.held.take() {
+            self.send(&held);
+        }
+    }
+
+    fn send(&mut self, update: &DashboardUpdate) {
+        self.last_sent = Some(Instant::now());
+        if let Ok(json) = serde_json::to_string(update) {
+            let _ = self.tx.send(json);
+        }
+    }
+}
+
+/// Bind the dashboard on `port` and serve it in the background, sending one
+/// update per `[web] broadcast_interval_ms`, or else at most `[limits]
+/// max_ws_updates_per_sec` (0 = every cycle), and keeping clients alive per
+/// `[web]`.
+pub async fn serve(port: u16, config: &Config) -> Result<WebPublisher, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+    serve_on(listener, config).await
+}
+
+/// [`serve`] on an already-bound listener, e.g. an ephemeral port in tests.
+pub async fn serve_on(listener: TcpListener, config: &Config) -> Result<WebPublisher, Box<dyn std::error::Error>> {
+    // Load the certificate before binding anything to it so a bad one fails the run
+    let tls = load_tls(&config.web).await?;
     let (tx, _) = broadcast::channel::<String>(256);
-    let state = Arc::new(AppState { tx: tx.clone() });
+    let blotter = Blotter::new();
+    blotter.set_index(AlertIndex::from_config(&config.search));
+    let reviews = AlertReviews::open(&config.reviews)?;
+    let stats_reset = Arc::new(AtomicBool::new(false));
+    let standby_request = Arc::new(Mutex::new(None));
+    let silences = Arc::new(Mutex::new(Vec::new()));
+    let audit = Arc::new(Mutex::new(None));
+    let cooldowns = Arc::new(Mutex::new(Vec::new()));
+    let alert_summary = Arc::new(Mutex::new(AlertSummary::default()));
+    let stats = Arc::new(Mutex::new(Vec::new()));
+    let accounts = Arc::new(Mutex::new(Vec::new()));
+    let state = Arc::new(AppState {
+        tx: tx.clone(),
+        blotter: blotter.clone(),
+        stats_reset: stats_reset.clone(),
+        connections: Connections::default(),
+        keepalive: config.web.clone(),
+        cooldowns: cooldowns.clone(),
+        alert_summary: alert_summary.clone(),
+        stats: stats.clone(),
+        accounts: accounts.clone(),
+        runs_dir: config.web.runs_dir.clone().unwrap_or_else(|| PathBuf::from("runs")),
+        reviews: reviews.clone(),
+        standby_request: standby_request.clone(),
+        slack: config.slack.clone(),
+        silences: silences.clone(),
+        auth: Authorizer::from_config(&config.auth)?,
+        audit: audit.clone(),
+    });
 
-    let app = Router::new()
-        .route("/ws", get(ws_handler))
+    // REST and static responses are gzipped when the client accepts it. The
+    // WebSocket route stays outside the layer: the 101 upgrade has no body to
+    // compress, and permessage-deflate isn't available in axum's tungstenite.
+    let http = Router::new()
+        .route("/api/accounts/risk", get(account_risk_handler))
+        .route("/api/accounts/:id/activity", get(activity_handler))
+        .route("/api/alerts/:id/evidence", get(evidence_handler))
+        .route("/api/alerts/:id/review", get(review_handler).post(update_review_handler))
+        .route("/api/reviews", get(reviews_handler))
+        .route("/api/stats/reset", post(stats_reset_handler))
+        .route("/api/pipeline/standby", post(standby_handler))
+        .route("/api/slack/command", post(slack_command_handler))
+        .route("/api/connections", get(connections_handler))
+        .route("/api/cooldowns", get(cooldowns_handler))
+        .route("/api/alerts", get(alerts_handler))
+        .route("/api/alerts.csv", get(alerts_csv_handler))
+        .route("/api/alerts/summary", get(alert_summary_handler))
+        .route("/api/stats", get(stats_handler))
+        .route("/api/stats.csv", get(stats_csv_handler))
+        .route("/api/runs", get(runs_handler))
+        .route("/api/runs/compare", get(compare_runs_handler))
         .fallback_service(ServeDir::new("static"))
-        .with_state(state);
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .layer(CompressionLayer::new())
+        .layer(cors_layer(&config.web.cors_origins)?);
+    let ws = Router::new().route("/ws", get(ws_handler)).layer(middleware::from_fn_with_state(state.clone(), authorize));
+    let app = ws.merge(http);
+    // Behind a proxy that forwards a path prefix unchanged, mount everything under it
+    let base = base_path(&config.web.base_path)?;
+    let app = if base.is_empty() { app } else { Router::new().nest(&base, app) };
+    let app = app.with_state(state);
 
-    // Spawn the detection engine
-    let engine_tx = tx.clone();
-    tokio::spawn(async move {
-        if let Err(e) = run_engine(engine_tx, fraud_rate, duration).await {
-            eprintln!("Engine error: {e}");
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    println!("Dashboard at {scheme}://localhost:{}{base}/", listener.local_addr()?.port());
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        #[cfg(feature = "tls")]
+        Some(tls) => {
+            let listener = listener.into_std()?;
+            tokio::spawn(async move {
+                if let Err(e) = axum_server::from_tcp_rustls(listener, tls).serve(service).await {
+                    eprintln!("Web server error: {e}");
+                }
+            });
         }
-    });
+        _ => {
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(listener, service).await {
+                    eprintln!("Web server error: {e}");
+                }
+            });
+        }
+    }
+
+    let max_updates_per_sec = config.limits.max_ws_updates_per_sec;
+    let min_interval = match config.web.broadcast_interval_ms {
+        0 => (max_updates_per_sec > 0).then(|| Duration::from_secs(1) / max_updates_per_sec),
+        ms => Some(Duration::from_millis(ms)),
+    };
+    Ok(WebPublisher { tx, blotter, reviews, stats_reset, standby_request, silences, audit, cooldowns, alert_summary, stats, accounts, min_interval, last_sent: None, held: None })
+}
+
+/// `[web] base_path` as `/prefix`, or empty to serve at the root.
+fn base_path(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let trimmed = path.trim_end_matches('/');
+    if !trimmed.is_empty() && !trimmed.starts_with('/') {
+        return Err(format!("base path '{path}' must start with '/'").into());
+    }
+    Ok(trimmed.to_string())
+}
+
+/// CORS for the REST API: none by default, `*` for any origin, or an
+/// explicit list of portal origins.
+fn cors_layer(origins: &[String]) -> Result<CorsLayer, Box<dyn std::error::Error>> {
+    let allow = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let list = origins
+            .iter()
+            .map(|o| HeaderValue::from_str(o).map_err(|_| format!("invalid CORS origin '{o}'")))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(list)
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(allow)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]))
+}
+
+#[cfg(feature = "tls")]
+type TlsConfig = axum_server::tls_rustls::RustlsConfig;
+#[cfg(not(feature = "tls"))]
+type TlsConfig = std::convert::Infallible;
+
+/// The `[web] tls_cert`/`tls_key` pair (or `--tls-cert`/`--tls-key`), if set.
+async fn load_tls(web: &WebConfig) -> Result<Option<TlsConfig>, Box<dyn std::error::Error>> {
+    let (cert, key) = match (&web.tls_cert, &web.tls_key) {
+        (None, None) => return Ok(None),
+        (Some(cert), Some(key)) => (cert, key),
+        _ => return Err("TLS needs both a certificate and a key (--tls-cert and --tls-key)".into()),
+    };
+    #[cfg(not(feature = "tls"))]
+    {
+        let _ = (cert, key);
+        Err("serving over TLS requires building with --features tls".into())
+    }
+    #[cfg(feature = "tls")]
+    TlsConfig::from_pem_file(cert, key)
+        .await
+        .map(Some)
+        .map_err(|e| format!("failed to load TLS certificate {} / key {}: {e}", cert.display(), key.display()).into())
+}
+
+/// Serve the dashboard and drive the engine until `duration` elapses.
+pub async fn run(
+    port: u16,
+    fraud_rate: f64,
+    duration: u64,
+    config: Config,
+) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+    let gen = FraudGenerator::new(fraud_rate).with_benign_rate(config.generator.benign_rate).with_shard(config.generator.shard);
+    run_on(listener, gen, duration, config).await
+}
+
+/// [`run`] on an already-bound listener with a given generator, so tests can
+/// use an ephemeral port and a seeded generator.
+pub async fn run_on(
+    listener: TcpListener,
+    gen: FraudGenerator,
+    duration: u64,
+    config: Config,
+) -> Result<RunRecord, Box<dyn std::error::Error>> {
+    let alert_engine = AlertEngine::from_config(&config)?;
+    let mut pacer = Pacer::from_config(&config)?;
+    let mut web = serve_on(listener, &config).await?;
+
+    let (trades_per_sec, orders_per_sec) = generator::normal_rates(pacer.interval());
+    let join = detection::guard_join_band(&config.join, trades_per_sec, orders_per_sec);
+    let pipeline = detection::setup_with(&PipelineOptions { sessions: config.sessions.clone(), join: join.clone(), ..Default::default() }).await?;
+    let fraud_rate = gen.fraud_rate;
+    let mut engine = Engine::new(pipeline, gen, alert_engine, Arc::new(SystemClock::new()));
+    engine.governor = Some(LoadGovernor::new(config.limits.clone()).with_join(join));
+    engine.cycle_budget = config.limits.cycle_budget();
+    engine.poll_budget = config.limits.poll_budget();
+    engine.eval_worker = config.limits.eval_worker;
+    engine.watermark = WatermarkStrategy::from_config(&config.watermark)?;
+    engine.archive = AlertArchive::open(&config.archive)?;
+    engine.ids = IdLedger::open(&config.ids)?;
+    engine.uids = UidGenerator::from_config(&config.ids);
+    engine.ticks = TickDetector::from_config(&config.ticks)?;
+    engine.audit = AuditLog::open(&config)?;
+    engine.recorder = Recorder::open(&config.recording)?;
+    engine.slo = SloTracker::from_config(&config.slo)?;
+    web.set_audit(engine.audit.clone());
+    let blotter = web.blotter();
+    blotter.set_archive(engine.archive.clone());
+    engine.blotter = Some(blotter);
+    engine.reviews = Some(web.reviews());
+
+    let mut stop = StopConditions::new(duration, &config.stop);
+    while !stop.reached(&engine) {
+        if web.take_stats_reset() {
+            engine.reset_stats();
+        }
+        if let Some(request) = web.take_standby_request() {
+            start_standby(&mut engine, &request, &config).await;
+        }
+        web.apply_silences(&mut engine);
+        let start = engine.elapsed();
+        let recent_alerts = engine.cycle().await;
+        pacer.record(start, &engine);
+        web.publish(&DashboardUpdate::from_engine(&engine, recent_alerts));
+        stop.checkpoint(&mut engine, "web", fraud_rate, &config);
+        pacer.wait(&engine).await;
+    }
+    if let Some(timeout) = config.drain.timeout() {
+        let drained = engine.drain(timeout).await;
+        web.publish(&DashboardUpdate::from_engine(&engine, drained));
+    }
+    web.flush();
+
+    let record = RunRecord::capture(&mut engine, "web", fraud_rate, &config);
+    engine.shutdown().await;
+    Ok(record)
+}
 
-    let addr = format!("0.0.0.0:{port}");
-    println!("Dashboard at http://localhost:{port}");
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
-    Ok(())
+/// Build the pipeline a standby request describes and start warming it
+/// alongside the engine's live one. A pipeline that fails to build is
+/// reported and the live one carries on alone.
+pub async fn start_standby(engine: &mut Engine, request: &StandbyRequest, config: &Config) {
+    match detection::setup_with(&request.options()).await {
+        Ok(pipeline) => {
+            let warmup = request.warmup(&config.standby);
+            eprintln!("Standby pipeline started; switching over after {}s of data", warmup.as_secs());
+            engine.start_standby(pipeline, warmup);
+        }
+        Err(e) => eprintln!("Standby pipeline failed to build: {e}"),
+    }
 }
 
 async fn ws_handler(
     ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
     let rx = state.tx.subscribe();
-    ws.on_upgrade(move |socket| handle_socket(socket, rx))
+    let client = client_addr(&headers, addr, &state.keepalive.trusted_proxies);
+    ws.on_upgrade(move |socket| handle_socket(socket, rx, state, client))
 }
 
-async fn handle_socket(mut socket: WebSocket, mut rx: broadcast::Receiver<String>) {
-    while let Ok(msg) = rx.recv().await {
-        if socket.send(Message::Text(msg.into())).await.is_err() {
-            break;
-        }
+/// The client's address: when the peer is a `[web] trusted_proxies` entry,
+/// the last `X-Forwarded-For` hop not itself a trusted proxy; else the peer
+/// address, since anyone can send the header.
+fn client_addr(headers: &HeaderMap, peer: SocketAddr, trusted: &[IpAddr]) -> String {
+    if !trusted.contains(&peer.ip()) {
+        return peer.to_string();
     }
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').map(str::trim).find(|hop| hop.parse().map_or(true, |ip: IpAddr| !trusted.contains(&ip))))
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| peer.to_string(), str::to_string)
 }
 
-async fn run_engine(
-    tx: broadcast::Sender<String>,
-    fraud_rate: f64,
-    duration: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let pipeline = detection::setup().await?;
-    let mut gen = FraudGenerator::new(fraud_rate);
-    let mut alert_engine = AlertEngine::new();
-    let mut latency = LatencyTracker::new();
-    let mut total_trades = 0u64;
-    let mut total_orders = 0u64;
-    let mut stream_counts: [u64; 6] = [0; 6];
-    let mut prices: HashMap<String, f64> = HashMap::new();
-    let mut recent_alerts: Vec<Alert> = Vec::new();
-
-    let run_duration = if duration == 0 {
-        Duration::from_secs(3600)
-    } else {
-        Duration::from_secs(duration)
-    };
-    let start = Instant::now();
+/// Open dashboard clients with their send counts, lag and ping round trip.
+async fn connections_handler(State(state): State<Arc<AppState>>) -> Json<ConnectionsReport> {
+    Json(state.connections.report())
+}
 
-    while start.elapsed() < run_duration {
-        let ts = FraudGenerator::now_ms();
-        let gen_instant = Instant::now();
+/// Per-account risk as of the last published update; see
+/// [`DashboardUpdate::accounts`].
+async fn account_risk_handler(State(state): State<Arc<AppState>>) -> Json<Vec<AccountRisk>> {
+    Json(state.accounts.lock().unwrap().clone())
+}
 
-        let (trades, orders) = gen.generate_cycle(ts);
-        total_trades += trades.len() as u64;
-        total_orders += orders.len() as u64;
+/// Active `[cooldown]` suppressions, with time left as of the engine's last
+/// cycle. Cooldowns run on event time, which only the engine knows.
+async fn cooldowns_handler(State(state): State<Arc<AppState>>) -> Json<Vec<Cooldown>> {
+    Json(state.cooldowns.lock().unwrap().clone())
+}
 
-        for (sym, price) in gen.current_prices() {
-            prices.insert(sym.clone(), *price);
-        }
+/// Delivered alert totals by type and by symbol, as of the last update.
+async fn alert_summary_handler(State(state): State<Arc<AppState>>) -> Json<AlertSummary> {
+    Json(state.alert_summary.lock().unwrap().clone())
+}
 
-        let push_start = latency.record_push_start();
-        pipeline.trade_source.push_batch(trades);
-        if !orders.is_empty() {
-            pipeline.order_source.push_batch(orders);
-        }
-        pipeline.trade_source.watermark(ts + 10_000);
-        pipeline.order_source.watermark(ts + 10_000);
-        latency.record_push_end(push_start);
+/// Recent alerts matching the query, oldest first, from the blotter's last
+/// 1,000; with `q`, ranked search hits over the `[search]` index instead.
+async fn alerts_handler(Query(q): Query<AlertQuery>, State(state): State<Arc<AppState>>) -> Json<Vec<Alert>> {
+    Json(q.run(&state.blotter))
+}
 
-        recent_alerts.clear();
+/// `/api/alerts` as a CSV download.
+async fn alerts_csv_handler(Query(q): Query<AlertQuery>, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    csv_response("alerts.csv", alerts_csv(&q.run(&state.blotter)))
+}
 
-        // Poll all streams
-        if let Some(ref sub) = pipeline.vol_baseline_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[0] += 1;
-                    if let Some(alert) = alert_engine.evaluate_volume(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
-                    }
-                }
-            }
+/// Running totals, latency, stream and alert counts as of the last update.
+async fn stats_handler(Query(q): Query<StatsQuery>, State(state): State<Arc<AppState>>) -> Json<Vec<StatRow>> {
+    Json(q.apply(&state.stats.lock().unwrap()))
+}
+
+/// `/api/stats` as a CSV download.
+async fn stats_csv_handler(Query(q): Query<StatsQuery>, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    csv_response("stats.csv", stats_csv(&q.apply(&state.stats.lock().unwrap())))
+}
+
+fn csv_response(filename: &str, body: String) -> impl IntoResponse {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+}
+
+/// Every recorded run, oldest first.
+async fn runs_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let dir = state.runs_dir.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        runs::list(&dir).map(|runs| runs.iter().map(RunSummary::from).collect::<Vec<_>>()).map_err(|e| e.to_string())
+    })
+    .await;
+    match result {
+        Ok(Ok(runs)) => Json(runs).into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// `?a=` and `?b=` of `/api/runs/compare`: run id prefixes, as `runs diff` takes.
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+    a: String,
+    b: String,
+}
+
+/// Two recorded runs side by side, or 404 if either prefix matches no run
+/// or several.
+async fn compare_runs_handler(Query(q): Query<CompareQuery>, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let dir = state.runs_dir.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<RunComparison, String> {
+        let a = runs::find(&dir, &q.a).map_err(|e| e.to_string())?;
+        let b = runs::find(&dir, &q.b).map_err(|e| e.to_string())?;
+        Ok(runs::compare(&a, &b))
+    })
+    .await;
+    match result {
+        Ok(Ok(comparison)) => Json(comparison).into_response(),
+        Ok(Err(e)) => (StatusCode::NOT_FOUND, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// An account's recent trades, orders, alerts, and risk scores, or 404 if
+/// the engine has not seen it.
+async fn activity_handler(
+    Path(account_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let blotter = state.blotter.clone();
+    let result = tokio::task::spawn_blocking(move || blotter.activity(&account_id).map_err(|e| e.to_string())).await;
+    match result {
+        Ok(Ok(Some(activity))) => Json(activity).into_response(),
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// The raw trades and orders in an alert's window, or 404 if the alert is
+/// neither recent nor archived.
+async fn evidence_handler(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let blotter = state.blotter.clone();
+    let result = tokio::task::spawn_blocking(move || blotter.evidence(id).map_err(|e| e.to_string())).await;
+    match result {
+        Ok(Ok(Some(evidence))) => Json(evidence).into_response(),
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// An alert's notes and disposition, or 404 if it has none.
+async fn review_handler(Path(id): Path<u64>, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match state.reviews.get(id) {
+        Some(review) => Json(review).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Every reviewed alert, by ID.
+async fn reviews_handler(State(state): State<Arc<AppState>>) -> Json<Vec<AlertReview>> {
+    Json(state.reviews.all())
+}
+
+/// Set an alert's disposition and/or add a note: 400 for an empty update,
+/// 404 if the alert is neither recent nor archived.
+async fn update_review_handler(
+    Path(id): Path<u64>,
+    State(state): State<Arc<AppState>>,
+    Json(update): Json<ReviewUpdate>,
+) -> impl IntoResponse {
+    if update.is_empty() {
+        return (StatusCode::BAD_REQUEST, "a review needs a disposition or a note".to_string()).into_response();
+    }
+    let (blotter, reviews) = (state.blotter.clone(), state.reviews.clone());
+    let result = tokio::task::spawn_blocking(move || -> Result<Option<AlertReview>, String> {
+        let Some(alert) = blotter.alert(id).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        reviews.review(&alert, update).map(Some).map_err(|e| e.to_string())
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(review))) => Json(review).into_response(),
+        Ok(Ok(None)) => StatusCode::NOT_FOUND.into_response(),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Ask the engine to zero its latency and throughput stats; it does so
+/// before its next cycle.
+async fn stats_reset_handler(State(state): State<Arc<AppState>>) -> StatusCode {
+    state.stats_reset.store(true, Ordering::Relaxed);
+    StatusCode::ACCEPTED
+}
+
+/// Queue a standby pipeline for the engine loop to build. A request made
+/// before the loop took the last one replaces it.
+async fn standby_handler(State(state): State<Arc<AppState>>, Json(request): Json<StandbyRequest>) -> StatusCode {
+    *state.standby_request.lock().unwrap() = Some(request);
+    StatusCode::ACCEPTED
+}
+
+/// Check the bearer token against the scope the route needs (see
+/// [`auth::required_scope`]) and audit every call above `read`. The token
+/// comes from `Authorization: Bearer`, or `?token=` on `/ws`, since browsers
+/// can't set headers on a WebSocket.
+async fn authorize(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let (method, path) = (request.method().to_string(), request.uri().path().to_string());
+    let Some(scope) = auth::required_scope(&method, &path) else {
+        return next.run(request).await;
+    };
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| {
+            let query = request.uri().query().filter(|_| path == "/ws")?;
+            serde_urlencoded::from_str::<HashMap<String, String>>(query).ok()?.remove("token")
+        });
+    let token = presented.as_deref().and_then(|t| state.auth.authenticate(t));
+    let response = if !state.auth.is_enabled() {
+        next.run(request).await
+    } else {
+        match token {
+            None => (StatusCode::UNAUTHORIZED, "a valid bearer token is required").into_response(),
+            Some(t) if t.scope < scope => (StatusCode::FORBIDDEN, format!("token '{}' has {} scope; this call needs {}", t.name, t.scope.label(), scope.label())).into_response(),
+            Some(_) => next.run(request).await,
         }
-        if let Some(ref sub) = pipeline.ohlc_vol_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[1] += 1;
-                    if let Some(alert) = alert_engine.evaluate_ohlc(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
-                    }
-                }
-            }
+    };
+    if scope > Scope::Read {
+        state.audit_call(token.map(|t| t.name.as_str()), scope, &method, &path, response.status().as_u16());
+    }
+    response
+}
+
+/// A Slack slash command, signed with `[slack] signing_secret`. Status reads
+/// the last update's figures, `ack` notes the alert's review as
+/// `update_review_handler` would, and `silence` is queued for the engine
+/// loop like a stats reset.
+async fn slack_command_handler(State(state): State<Arc<AppState>>, headers: HeaderMap, body: String) -> impl IntoResponse {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+    let now_secs = chrono::Utc::now().timestamp();
+    if let Err(e) = slack::verify(&state.slack, &header("x-slack-request-timestamp"), &body, &header("x-slack-signature"), now_secs) {
+        return (StatusCode::UNAUTHORIZED, e).into_response();
+    }
+    let Ok(form) = serde_urlencoded::from_str::<SlashCommand>(&body) else {
+        return (StatusCode::BAD_REQUEST, "invalid slash command form").into_response();
+    };
+    let reply = match SlackCommand::parse(&form.text) {
+        Err(e) => SlackReply::ephemeral(format!("{e}. {}", slack::HELP)),
+        Ok(SlackCommand::Help) => SlackReply::ephemeral(slack::HELP),
+        Ok(SlackCommand::Status) => SlackReply::ephemeral(slack_status(&state)),
+        Ok(SlackCommand::Ack { id }) => {
+            state.audit_call(Some(&format!("slack:@{}", form.user_name)), Scope::Operate, "SLACK", &format!("{} {}", form.command, form.text), 200);
+            slack_ack(&state, id, &form.user_name).await
         }
-        if let Some(ref sub) = pipeline.rapid_fire_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[2] += 1;
-                    if let Some(alert) = alert_engine.evaluate_rapid_fire(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
-                    }
-                }
-            }
+        Ok(SlackCommand::Silence { account, duration }) => {
+            state.audit_call(Some(&format!("slack:@{}", form.user_name)), Scope::Operate, "SLACK", &format!("{} {}", form.command, form.text), 200);
+            state.silences.lock().unwrap().push((account.clone(), duration));
+            SlackReply::in_channel(format!("Alerts for {account} silenced for {}s by @{}", duration.as_secs(), form.user_name))
         }
-        if let Some(ref sub) = pipeline.wash_score_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[3] += 1;
-                    if let Some(alert) = alert_engine.evaluate_wash(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
+    };
+    Json(reply).into_response()
+}
+
+/// One line of running totals plus the busiest alert types, as of the last
+/// published update.
+fn slack_status(state: &AppState) -> String {
+    let stats = state.stats.lock().unwrap();
+    let stat = |group: &str, name: &str| stats.iter().find(|r| r.group == group && r.name == name).map_or(0, |r| r.value as u64);
+    let summary = state.alert_summary.lock().unwrap();
+    let mut top: Vec<(&String, &u64)> = summary.by_type.iter().collect();
+    top.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let top: Vec<String> = top.iter().take(3).map(|(t, n)| format!("{t} {n}")).collect();
+    let silenced = state.cooldowns.lock().unwrap().iter().filter(|c| c.alert_type == SILENCE_TYPE).count();
+    format!(
+        "Up {}s: {} trades, {} alerts ({} suppressed), alert p99 {}us, {silenced} silenced. Top: {}",
+        stat("totals", "uptime_secs"),
+        stat("totals", "trades"),
+        stat("totals", "alerts"),
+        stat("totals", "suppressed_alerts"),
+        stat("latency", "alert_p99_us"),
+        if top.is_empty() { "none".to_string() } else { top.join(", ") },
+    )
+}
+
+/// Note on alert `id`'s review that `user` acknowledged it from Slack.
+async fn slack_ack(state: &AppState, id: u64, user: &str) -> SlackReply {
+    let (blotter, reviews) = (state.blotter.clone(), state.reviews.clone());
+    let update = ReviewUpdate { disposition: None, note: Some(format!("Acknowledged by @{user} via Slack")) };
+    let result = tokio::task::spawn_blocking(move || -> Result<Option<Alert>, String> {
+        let Some(alert) = blotter.alert(id).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        reviews.review(&alert, update).map_err(|e| e.to_string())?;
+        Ok(Some(alert))
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    match result {
+        Ok(Some(alert)) => SlackReply::in_channel(format!("Alert #{id} ({}) acknowledged by @{user}", alert.description())),
+        Ok(None) => SlackReply::ephemeral(format!("No alert #{id}")),
+        Err(e) => SlackReply::ephemeral(format!("Alert #{id} not acknowledged: {e}")),
+    }
+}
+
+/// Forward updates to one client, pinging it every `ping_interval_secs` and
+/// closing it once it has been silent for `idle_timeout_secs`, so clients
+/// that stop reading don't hold a connection forever.
+async fn handle_socket(mut socket: WebSocket, mut rx: broadcast::Receiver<String>, state: Arc<AppState>, addr: String) {
+    let connections = &state.connections;
+    let id = connections.open(addr);
+    let idle_timeout = Duration::from_secs(state.keepalive.idle_timeout_secs);
+    let mut ping = tokio::time::interval(Duration::from_secs(state.keepalive.ping_interval_secs.max(1)));
+    ping.tick().await;
+    let mut ping_sent: Option<Instant> = None;
+    let mut timed_out = false;
+
+    loop {
+        tokio::select! {
+            update = rx.recv() => match update {
+                Ok(msg) => {
+                    if socket.send(Message::Text(msg)).await.is_err() {
+                        break;
                     }
+                    connections.sent(id, rx.len());
                 }
-            }
-        }
-        if let Some(ref sub) = pipeline.suspicious_match_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[4] += 1;
-                    if let Some(alert) = alert_engine.evaluate_match(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
-                    }
+                // A slow client misses the oldest updates but stays connected
+                Err(RecvError::Lagged(n)) => connections.skipped(id, n),
+                Err(RecvError::Closed) => break,
+            },
+            _ = ping.tick() => {
+                if !idle_timeout.is_zero() && connections.idle(id) > idle_timeout {
+                    timed_out = true;
+                    break;
                 }
-            }
-        }
-        if let Some(ref sub) = pipeline.asof_match_sub {
-            while let Some(rows) = sub.poll() {
-                latency.record_poll();
-                for row in &rows {
-                    stream_counts[5] += 1;
-                    if let Some(alert) = alert_engine.evaluate_asof(row, gen_instant) {
-                        latency.record_alert(gen_instant);
-                        recent_alerts.push(alert);
-                    }
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
                 }
+                ping_sent = Some(Instant::now());
             }
+            inbound = socket.recv() => match inbound {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(Message::Pong(_))) => connections.seen(id, ping_sent.take().map(|t| t.elapsed())),
+                Some(Ok(_)) => connections.seen(id, None),
+            },
         }
+    }
+    connections.close(id, timed_out);
+}
 
-        // Broadcast update to WebSocket clients
-        let names = ["vol_baseline", "ohlc_vol", "rapid_fire", "wash_score", "suspicious_match", "asof_match"];
-        let streams: Vec<StreamStatus> = names
-            .iter()
-            .enumerate()
-            .map(|(i, name)| StreamStatus {
-                name: name.to_string(),
-                count: stream_counts[i],
-                active: stream_counts[i] > 0,
-            })
-            .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::{AlertDetail, AlertType};
 
+    fn alert(id: u64) -> Alert {
+        Alert {
+            id,
+            alert_type: AlertType::WashTrading,
+            severity: AlertSeverity::High,
+            symbol: Some("AAPL".into()),
+            account_id: Some("ACC-1".into()),
+            detail: AlertDetail::Text(String::new()),
+            latency_us: 0,
+            timestamp_ms: 0,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }
+    }
+
+    /// A publisher on an ephemeral port that sends every update it is given.
+    async fn publisher(config: &mut Config) -> WebPublisher {
+        config.web.broadcast_interval_ms = 0;
+        config.limits.max_ws_updates_per_sec = 0;
+        serve_on(TcpListener::bind("127.0.0.1:0").await.unwrap(), config).await.unwrap()
+    }
+
+    fn received(rx: &mut broadcast::Receiver<String>) -> DashboardUpdate {
+        serde_json::from_str(&rx.try_recv().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn every_client_gets_each_update() {
+        let mut web = publisher(&mut Config::default()).await;
+        // The page and an `attach` TUI are both just subscribers of the one engine's feed
+        let mut page = web.tx.subscribe();
+        let mut attached = web.tx.subscribe();
+
+        web.publish(&DashboardUpdate { alerts: vec![alert(1), alert(2)], total_alerts: 2, ..Default::default() });
+        for rx in [&mut page, &mut attached] {
+            let update = received(rx);
+            assert_eq!(update.alerts.iter().map(|a| a.id).collect::<Vec<_>>(), vec![1, 2]);
+            assert_eq!(update.total_alerts, 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn publishing_without_clients_still_refreshes_the_rest_snapshots() {
+        let mut web = publisher(&mut Config::default()).await;
         let update = DashboardUpdate {
-            alerts: recent_alerts.clone(),
-            latency: LatencyUpdate {
-                push: latency.push_stats(),
-                processing: latency.processing_stats(),
-                alert: latency.alert_stats(),
-            },
-            streams,
-            alert_counts: alert_engine.alert_counts().clone(),
-            total_trades,
-            total_orders,
-            total_alerts: alert_engine.total_alerts(),
-            uptime_secs: start.elapsed().as_secs(),
-            prices: prices.clone(),
+            alerts: vec![alert(1)],
+            total_alerts: 1,
+            alert_counts: HashMap::from([("WashTrading".to_string(), 1)]),
+            ..Default::default()
         };
+        web.publish(&update);
 
-        if let Ok(json) = serde_json::to_string(&update) {
-            let _ = tx.send(json);
-        }
+        let summary = web.alert_summary.lock().unwrap().clone();
+        assert_eq!(summary.total_alerts, 1);
+        assert_eq!(summary.by_type.get("WashTrading"), Some(&1));
+    }
+
+    fn ids(alerts: &[Alert]) -> Vec<u64> {
+        alerts.iter().map(|a| a.id).collect()
+    }
+
+    #[tokio::test]
+    async fn held_updates_are_coalesced_into_the_next_send() {
+        let mut config = Config::default();
+        let mut web = publisher(&mut config).await;
+        web.min_interval = Some(Duration::from_secs(60));
+        let mut rx = web.tx.subscribe();
+
+        web.publish(&DashboardUpdate { alerts: vec![alert(1)], total_alerts: 1, ..Default::default() });
+        assert_eq!(ids(&received(&mut rx).alerts), vec![1], "the first update goes out at once");
+        web.publish(&DashboardUpdate { alerts: vec![alert(2)], shadow_alerts: vec![alert(3)], total_alerts: 2, ..Default::default() });
+        web.publish(&DashboardUpdate { alerts: vec![alert(4)], total_alerts: 3, ..Default::default() });
+        assert!(rx.try_recv().is_err(), "held inside the interval");
+
+        web.flush();
+        let update = received(&mut rx);
+        assert_eq!((ids(&update.alerts), ids(&update.shadow_alerts)), (vec![2, 4], vec![3]));
+        assert_eq!(update.total_alerts, 3, "the latest totals win");
+        web.flush();
+        assert!(rx.try_recv().is_err(), "nothing left to flush");
+    }
+
+    #[tokio::test]
+    async fn degraded_updates_wait_twice_the_interval() {
+        let mut config = Config::default();
+        let mut web = publisher(&mut config).await;
+        web.min_interval = Some(Duration::from_secs(10));
+        let mut rx = web.tx.subscribe();
+
+        web.last_sent = Some(Instant::now() - Duration::from_secs(15));
+        web.publish(&DashboardUpdate { alerts: vec![alert(1)], degraded: true, ..Default::default() });
+        assert!(rx.try_recv().is_err());
+        web.publish(&DashboardUpdate { alerts: vec![alert(2)], ..Default::default() });
+        assert_eq!(ids(&received(&mut rx).alerts), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn broadcast_interval_overrides_the_update_rate_limit() {
+        let listener = || async { TcpListener::bind("127.0.0.1:0").await.unwrap() };
+        let mut config = Config::default();
+        config.web.broadcast_interval_ms = 0;
+        config.limits.max_ws_updates_per_sec = 4;
+        assert_eq!(serve_on(listener().await, &config).await.unwrap().min_interval, Some(Duration::from_millis(250)));
+        config.web.broadcast_interval_ms = 500;
+        assert_eq!(serve_on(listener().await, &config).await.unwrap().min_interval, Some(Duration::from_millis(500)));
+        config.web.broadcast_interval_ms = 0;
+        config.limits.max_ws_updates_per_sec = 0;
+        assert_eq!(serve_on(listener().await, &config).await.unwrap().min_interval, None);
+    }
+
+    #[tokio::test]
+    async fn tls_needs_both_halves_of_the_pair() {
+        let mut web = WebConfig::default();
+        assert!(load_tls(&web).await.unwrap().is_none(), "plain HTTP by default");
 
-        tokio::time::sleep(Duration::from_millis(200)).await;
+        web.tls_cert = Some("cert.pem".into());
+        let err = load_tls(&web).await.err().unwrap().to_string();
+        assert!(err.contains("both a certificate and a key"), "{err}");
+        web.tls_cert = None;
+        web.tls_key = Some("key.pem".into());
+        assert!(load_tls(&web).await.is_err());
     }
 
-    let _ = pipeline.db.shutdown().await;
-    Ok(())
+    #[tokio::test]
+    async fn an_unloadable_certificate_fails_serving() {
+        let mut config = Config::default();
+        let missing = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        config.web.tls_cert = Some(missing.join("cert.pem"));
+        config.web.tls_key = Some(missing.join("key.pem"));
+        let err = serve_on(TcpListener::bind("127.0.0.1:0").await.unwrap(), &config).await.err().unwrap().to_string();
+        #[cfg(feature = "tls")]
+        assert!(err.contains("failed to load TLS certificate"), "{err}");
+        #[cfg(not(feature = "tls"))]
+        assert!(err.contains("--features tls"), "{err}");
+    }
+
+    /// A raw HTTP/1.1 GET against `addr`, returning the status line and headers.
+    async fn get_head(addr: SocketAddr, path: &str, origin: &str) -> String {
+        raw_head(addr, &format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nOrigin: {origin}\r\nConnection: close\r\n\r\n")).await
+    }
+
+    /// Send `request` to `addr` as is, returning the status line and headers.
+    async fn raw_head(addr: SocketAddr, request: &str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.split("\r\n\r\n").next().unwrap().to_lowercase()
+    }
+
+    #[tokio::test]
+    async fn the_api_is_served_under_the_base_path_with_cors() {
+        let mut config = Config::default();
+        config.web.base_path = "/fraud/".into();
+        config.web.cors_origins = vec!["https://portal.example".into()];
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _web = serve_on(listener, &config).await.unwrap();
+
+        let head = get_head(addr, "/fraud/api/cooldowns", "https://portal.example").await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(head.contains("access-control-allow-origin: https://portal.example"), "{head}");
+
+        let head = get_head(addr, "/fraud/api/cooldowns", "https://elsewhere.example").await;
+        assert!(!head.contains("access-control-allow-origin"), "{head}");
+        assert!(get_head(addr, "/api/cooldowns", "https://portal.example").await.starts_with("http/1.1 404"));
+    }
+
+    #[tokio::test]
+    async fn cors_preflights_allow_the_authorization_header() {
+        let mut config = Config::default();
+        config.web.cors_origins = vec!["https://portal.example".into()];
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _web = serve_on(listener, &config).await.unwrap();
+
+        let head = raw_head(
+            addr,
+            "OPTIONS /api/alerts/42/review HTTP/1.1\r\nHost: localhost\r\nOrigin: https://portal.example\r\n\
+             Access-Control-Request-Method: POST\r\nAccess-Control-Request-Headers: authorization\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(head.contains("access-control-allow-origin: https://portal.example"), "{head}");
+        let allowed = head.lines().find_map(|l| l.strip_prefix("access-control-allow-headers: ")).unwrap_or_default();
+        assert!(allowed.contains("authorization") && allowed.contains("content-type"), "{head}");
+    }
+
+    #[test]
+    fn base_paths_are_normalised() {
+        assert_eq!(base_path("").unwrap(), "");
+        assert_eq!(base_path("/").unwrap(), "");
+        assert_eq!(base_path("/fraud/").unwrap(), "/fraud");
+        assert_eq!(base_path("/ops/fraud").unwrap(), "/ops/fraud");
+        assert!(base_path("fraud").is_err());
+    }
+
+    #[test]
+    fn cors_origins_must_be_header_values() {
+        assert!(cors_layer(&[]).is_ok());
+        assert!(cors_layer(&["*".to_string()]).is_ok());
+        let err = cors_layer(&["https://ok.example".to_string(), "bad\norigin".to_string()]).err().unwrap().to_string();
+        assert!(err.contains("invalid CORS origin"), "{err}");
+    }
+
+    #[test]
+    fn client_address_is_forwarded_only_by_trusted_proxies() {
+        let peer: SocketAddr = "10.0.0.5:4000".parse().unwrap();
+        let trusted: Vec<IpAddr> = vec!["10.0.0.5".parse().unwrap(), "10.0.0.1".parse().unwrap()];
+        let forwarded = |value: &str| HeaderMap::from_iter([(header::HeaderName::from_static("x-forwarded-for"), HeaderValue::from_str(value).unwrap())]);
+        assert_eq!(client_addr(&HeaderMap::new(), peer, &trusted), "10.0.0.5:4000");
+        assert_eq!(client_addr(&forwarded("203.0.113.7, 10.0.0.1"), peer, &trusted), "203.0.113.7");
+        assert_eq!(client_addr(&forwarded("198.51.100.9, 203.0.113.7, 10.0.0.1"), peer, &trusted), "203.0.113.7", "hops before the first untrusted one are the client's to forge");
+        assert_eq!(client_addr(&forwarded(" "), peer, &trusted), "10.0.0.5:4000");
+        assert_eq!(client_addr(&forwarded("203.0.113.7"), peer, &[]), "10.0.0.5:4000", "untrusted peer");
+    }
 }
@@ -0,0 +1,162 @@
+//! Golden-file regression test for the alert sequence of a fixed scenario.
+//!
+//! A seeded generator on a virtual clock replays the same trades every run,
+//! and each push is settled before the next, so the emitted alerts are
+//! reproducible. Compares alert type, severity, symbol, and account per cycle
+//! against `tests/golden/alerts.json`.
+//!
+//! After an intended rule change, bless the new sequence with:
+//!
+//!     UPDATE_GOLDEN=1 cargo test --test golden
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use laminardb_fraud_detect::alerts::{Alert, AlertEngine};
+use laminardb_fraud_detect::clock::{Clock, VirtualClock};
+use laminardb_fraud_detect::detection;
+use laminardb_fraud_detect::engine::Engine;
+use laminardb_fraud_detect::generator::{FraudGenerator, FraudScenario};
+use laminardb_fraud_detect::testkit;
+
+const SEED: u64 = 42;
+const BASE_TS: i64 = 1_000_000;
+const CYCLE: Duration = Duration::from_millis(200);
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/alerts.json");
+
+enum Step {
+    Normal(usize),
+    Inject(FraudScenario),
+}
+
+/// Warm up the volume baseline, then inject each scenario with quiet cycles between.
+const SCRIPT: &[Step] = &[
+    Step::Normal(10),
+    Step::Inject(FraudScenario::VolumeSpike),
+    Step::Normal(3),
+    Step::Inject(FraudScenario::RapidFire),
+    Step::Normal(3),
+    Step::Inject(FraudScenario::WashTrading),
+    Step::Normal(3),
+    Step::Inject(FraudScenario::PriceManipulation),
+    Step::Normal(5),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct GoldenAlert {
+    cycle: usize,
+    alert_type: String,
+    severity: String,
+    symbol: Option<String>,
+    account_id: Option<String>,
+}
+
+impl GoldenAlert {
+    fn new(cycle: usize, alert: &Alert) -> Self {
+        Self {
+            cycle,
+            alert_type: alert.alert_type.label().to_string(),
+            severity: format!("{:?}", alert.severity),
+            symbol: alert.symbol.clone(),
+            account_id: alert.account_id.clone(),
+        }
+    }
+}
+
+/// Replay `SCRIPT`, with the rules on the evaluation worker if `eval_worker`.
+async fn run_script(eval_worker: bool) -> Vec<GoldenAlert> {
+    let pipeline = detection::setup().await.unwrap();
+    let clock = Arc::new(VirtualClock::new(BASE_TS));
+    let gen = FraudGenerator::with_seed(0.0, SEED);
+    let mut engine = Engine::new(pipeline, gen, AlertEngine::new(), clock.clone());
+    engine.eval_worker = eval_worker;
+
+    let mut sequence = Vec::new();
+    let mut cycle = 0;
+    for step in SCRIPT {
+        let (count, scenario) = match step {
+            Step::Normal(n) => (*n, None),
+            Step::Inject(s) => (1, Some(*s)),
+        };
+        for _ in 0..count {
+            let ts = clock.now_ms();
+            let (trades, orders) = match scenario {
+                Some(s) => engine.gen.generate_scenario_cycle(ts, s),
+                None => engine.gen.generate_cycle(ts),
+            };
+            let watermark = engine.watermark.watermark_for(ts);
+            engine.push(trades, orders, watermark).await;
+
+            // Alerts within one cycle are compared as a set
+            let mut alerts: Vec<GoldenAlert> = testkit::settle(&mut engine, ts, Duration::from_secs(5))
+                .await
+                .iter()
+                .map(|a| GoldenAlert::new(cycle, a))
+                .collect();
+            alerts.sort();
+            sequence.extend(alerts);
+
+            engine.sleep(CYCLE).await;
+            cycle += 1;
+        }
+    }
+
+    assert_eq!(engine.eval_stats.batches > 0, eval_worker, "rows reach the worker only when it is on");
+    engine.shutdown().await;
+    sequence
+}
+
+fn blessing() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some()
+}
+
+/// The checked-in sequence. A missing file fails rather than passing, so a
+/// checkout without it can't go green without checking anything.
+fn golden() -> Vec<GoldenAlert> {
+    let path = Path::new(GOLDEN_PATH);
+    assert!(
+        path.exists(),
+        "{GOLDEN_PATH} has not been blessed yet. Generate it with\n\n    \
+         UPDATE_GOLDEN=1 cargo test --test golden\n\n\
+         check the alerts it lists are the ones the scenario script should raise, and commit it."
+    );
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("can't read {GOLDEN_PATH} ({e}); run with UPDATE_GOLDEN=1 to bless the current sequence"));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("invalid {GOLDEN_PATH} ({e}); run with UPDATE_GOLDEN=1 to re-bless it"))
+}
+
+#[tokio::test]
+async fn test_golden_alert_sequence() {
+    let actual = run_script(false).await;
+    let path = Path::new(GOLDEN_PATH);
+
+    if blessing() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        eprintln!("Wrote {} alerts to {}", actual.len(), path.display());
+        return;
+    }
+
+    let expected = golden();
+    let diverged = (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i));
+    if let Some(i) = diverged {
+        panic!(
+            "Alert sequence diverges from {} at index {i}:\n  expected: {:?}\n  actual:   {:?}\n\
+             ({} expected, {} actual). Re-run with UPDATE_GOLDEN=1 if the change is intended.",
+            path.display(), expected.get(i), actual.get(i), expected.len(), actual.len()
+        );
+    }
+}
+
+// Rows reach the rules in polled order on the worker too, so it must
+// reproduce the golden sequence exactly. While blessing, the file is being
+// rewritten by the test above, so the inline run is the reference instead.
+#[tokio::test]
+async fn test_golden_alert_sequence_on_eval_worker() {
+    let expected = if blessing() { run_script(false).await } else { golden() };
+    let actual = run_script(true).await;
+    assert_eq!(actual, expected, "the evaluation worker changed the alert sequence");
+}
@@ -0,0 +1,485 @@
+//! Deterministic simulation of the full web stack.
+//!
+//! Boots `web::run_on` on an ephemeral port with a seeded generator, reads
+//! the dashboard feed over a real WebSocket client, and checks the
+//! `DashboardUpdate` contract the web page and `attach` mode rely on: every
+//! field present, counters non-decreasing, the streams array stable, and
+//! alerts well-formed. Midway it also checks `/api/connections` counts the
+//! client. A second test serves two recorded runs and compares them over
+//! `/api/runs/compare`, a third filters alerts and stats as JSON and CSV, and a
+//! fourth checks `[auth]` token scopes and the audit entries they leave. The
+//! last feeds two shard dashboards through `coordinate` and reads the totals
+//! and an account's bursts added up across them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::Message;
+
+use laminardb_fraud_detect::alerts::{Alert, AlertDetail, AlertSeverity, AlertType};
+use laminardb_fraud_detect::audit::{self, AuditEntry, AuditLog};
+use laminardb_fraud_detect::config::{ApiToken, Config, Scope};
+use laminardb_fraud_detect::coordinator;
+use laminardb_fraud_detect::engine::STREAM_NAMES;
+use laminardb_fraud_detect::generator::FraudGenerator;
+use laminardb_fraud_detect::latency::LatencyStats;
+use laminardb_fraud_detect::risk::AccountRisk;
+use laminardb_fraud_detect::runs::{RunComparison, RunRecord, RunSummary};
+use laminardb_fraud_detect::web::{self, ConnectionsReport, DashboardUpdate, StatRow};
+
+const SEED: u64 = 42;
+const FRAUD_RATE: f64 = 0.3;
+const RUN_SECS: u64 = 5;
+
+/// Top-level keys every frame carries, including those newer clients default.
+const FIELDS: [&str; 25] = [
+    "alerts",
+    "latency",
+    "streams",
+    "alert_counts",
+    "total_trades",
+    "total_orders",
+    "total_alerts",
+    "uptime_secs",
+    "prices",
+    "degraded",
+    "shed_cycles",
+    "dropped_alerts",
+    "alerts_generated",
+    "alerts_delivered",
+    "suppressed_alerts",
+    "push_stats",
+    "join_fanout_per_sec",
+    "join_overloaded",
+    "shadow_alerts",
+    "shadow_counts",
+    "heartbeat",
+    "cooldowns",
+    "symbol_alerts",
+    "feed_integrity",
+    "retention",
+];
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_web_stack_dashboard_contract() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut config = Config::default();
+    // One frame per cycle, so no alerts are coalesced across frames
+    config.limits.max_ws_updates_per_sec = 0;
+
+    let done = Arc::new(Notify::new());
+    let server = {
+        let done = done.clone();
+        async move {
+            let record = web::run_on(listener, FraudGenerator::with_seed(FRAUD_RATE, SEED), RUN_SECS, config).await;
+            done.notify_one();
+            record.map_err(|e| e.to_string())
+        }
+    };
+    let client = async {
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/ws")).await.expect("connect");
+        let mut frames = Vec::new();
+        let mut report = None;
+        loop {
+            tokio::select! {
+                msg = ws.next() => match msg {
+                    Some(Ok(Message::Text(json))) => {
+                        frames.push(json);
+                        if frames.len() == 3 {
+                            report = Some(http_get(addr, "/api/connections").await);
+                        }
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => panic!("WebSocket error: {e}"),
+                    None => break,
+                },
+                _ = done.notified() => break,
+            }
+        }
+        (frames, report)
+    };
+    let (record, (frames, report)) = tokio::join!(server, client);
+    let record = record.expect("web run");
+
+    let report: ConnectionsReport = serde_json::from_str(&report.expect("queried /api/connections")).expect("connections JSON");
+    assert_eq!(report.clients, 1, "the test client should be the one open connection");
+    assert!(report.connections[0].messages_sent >= 2, "frames before the last one read must be counted");
+
+    assert!(frames.len() >= 5, "expected a frame per cycle, got {}", frames.len());
+    let mut prev: Option<DashboardUpdate> = None;
+    let mut last_alert_id = 0;
+    for json in &frames {
+        let value: serde_json::Value = serde_json::from_str(json).expect("frame is JSON");
+        for field in FIELDS {
+            assert!(value.get(field).is_some(), "frame missing '{field}': {json}");
+        }
+        let update: DashboardUpdate = serde_json::from_value(value).expect("frame parses as DashboardUpdate");
+
+        let names: Vec<&str> = update.streams.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, STREAM_NAMES, "streams array changed shape");
+        assert_eq!(update.total_alerts, update.alert_counts.values().sum::<u64>(), "total_alerts is the sum of alert_counts");
+        assert_eq!(
+            update.alerts_generated,
+            update.alerts_delivered + update.suppressed_alerts + update.dropped_alerts,
+            "generated alerts are delivered, suppressed or dropped"
+        );
+
+        let by_symbol: u64 = update.symbol_alerts.iter().map(|s| s.total).sum();
+        assert!(by_symbol <= update.total_alerts, "symbol breakdown counts more alerts than were delivered");
+        for s in &update.symbol_alerts {
+            assert_eq!(s.total, s.by_type.values().sum::<u64>(), "{} total is the sum of its types", s.symbol);
+        }
+        for (feed, stats) in &update.feed_integrity {
+            assert_eq!((stats.gaps, stats.duplicates), (0, 0), "generated {feed} feed is numbered without gaps or repeats");
+        }
+
+        for alert in &update.alerts {
+            assert!(alert.id > last_alert_id, "alert ids must increase across frames: {} after {last_alert_id}", alert.id);
+            last_alert_id = alert.id;
+            assert!(!alert.description().to_string().is_empty(), "alert {} has no description", alert.id);
+            if !matches!(alert.alert_type, AlertType::EngineStall | AlertType::FeedIntegrity) {
+                assert!(alert.symbol.is_some() || alert.account_id.is_some(), "alert {} names no symbol or account", alert.id);
+            }
+            assert!(alert.timestamp_ms > 0, "alert {} has no timestamp", alert.id);
+            assert!(
+                update.alert_counts.get(alert.alert_type.label()).is_some_and(|&n| n > 0),
+                "alert {} not counted under '{}'",
+                alert.id,
+                alert.alert_type.label()
+            );
+        }
+
+        if let Some(prev) = &prev {
+            assert!(update.total_trades >= prev.total_trades, "total_trades decreased");
+            assert!(update.total_orders >= prev.total_orders, "total_orders decreased");
+            assert!(update.total_alerts >= prev.total_alerts, "total_alerts decreased");
+            assert!(update.alerts_generated >= prev.alerts_generated, "alerts_generated decreased");
+            assert!(update.alerts_delivered >= prev.alerts_delivered, "alerts_delivered decreased");
+            assert!(update.uptime_secs >= prev.uptime_secs, "uptime_secs decreased");
+            assert!(update.heartbeat.seq > prev.heartbeat.seq, "heartbeat seq did not advance");
+            for (now, before) in update.streams.iter().zip(&prev.streams) {
+                assert!(now.count >= before.count, "stream '{}' count decreased", now.name);
+            }
+            assert_counts_grow(&prev.alert_counts, &update.alert_counts);
+            assert_counts_grow(&prev.shadow_counts, &update.shadow_counts);
+        }
+        prev = Some(update);
+    }
+
+    let last = prev.unwrap();
+    assert!(last.total_trades > 0, "no trades reached the dashboard");
+    assert!(last.total_trades <= record.total_trades, "dashboard ahead of the engine");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_run_comparison_endpoint() {
+    let dir = std::env::temp_dir().join(format!("runs-compare-{}", uuid::Uuid::new_v4()));
+    let a = recorded_run("aaaa0001", 1_000, 10.0, 400);
+    let b = RunRecord {
+        id: "bbbb0002".into(),
+        started_at_ms: 2_000,
+        total_trades: 2_000,
+        alert: LatencyStats { p50_us: 100, p99_us: 300, ..LatencyStats::default() },
+        ..a.clone()
+    };
+    // Saved newest first; the listing still comes back oldest first
+    b.save(&dir).unwrap();
+    a.save(&dir).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut config = Config::default();
+    config.web.runs_dir = Some(dir.clone());
+    let _web = web::serve_on(listener, &config).await.expect("serve");
+
+    let runs: Vec<RunSummary> = serde_json::from_str(&http_get(addr, "/api/runs").await).expect("runs JSON");
+    let ids: Vec<&str> = runs.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["aaaa0001", "bbbb0002"], "runs listed oldest first");
+    assert_eq!(runs[1].trades_per_sec, 200.0);
+
+    let comparison: RunComparison =
+        serde_json::from_str(&http_get(addr, "/api/runs/compare?a=aaaa&b=bbbb").await).expect("comparison JSON");
+    assert_eq!((comparison.a.id.as_str(), comparison.b.id.as_str()), ("aaaa0001", "bbbb0002"));
+    let sections: Vec<&str> = comparison.sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(sections, ["Throughput", "Latency", "Alert counts", "Detection quality"]);
+    let row = |section: &str, metric: &str| {
+        comparison.sections.iter().find(|s| s.name == section).and_then(|s| s.rows.iter().find(|r| r.metric == metric)).cloned().unwrap()
+    };
+    let throughput = row("Throughput", "Trades/sec");
+    assert_eq!((throughput.a, throughput.b, throughput.change), (Some(100.0), Some(200.0), Some(100.0)));
+    let p99 = row("Latency", "Alert p99");
+    assert_eq!((p99.unit.as_str(), p99.a, p99.b, p99.change), ("us", Some(400.0), Some(300.0), Some(-25.0)));
+    let recall = row("Detection quality", "Recall (all scenarios)");
+    assert!(recall.ratio && recall.change.is_none(), "runs without injected fraud have no recall to compare");
+
+    let (head, body) = http_request(addr, "/api/runs/compare?a=aaaa&b=cccc").await;
+    assert!(head.starts_with("HTTP/1.1 404"), "unknown run: {head}");
+    assert!(body.contains("no run matching 'cccc'"), "404 names the missing run: {body}");
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_csv_exports() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut web = web::serve_on(listener, &Config::default()).await.expect("serve");
+    let alert = |id: u64, alert_type: AlertType, severity: AlertSeverity, account: &str, description: &str| Alert {
+        id,
+        alert_type,
+        severity,
+        symbol: Some("AAPL".into()),
+        account_id: Some(account.into()),
+        detail: AlertDetail::Text(description.into()),
+        latency_us: 120,
+        timestamp_ms: 1_000 * id as i64,
+        score: None,
+        gap_ms: None,
+        metric: None,
+        uid: None,
+    };
+    web.blotter().record_alerts(&[
+        alert(1, AlertType::WashTrading, AlertSeverity::Medium, "W1", "wash, 3 round trips"),
+        alert(2, AlertType::WashTrading, AlertSeverity::Critical, "W2", "said \"wash\""),
+        alert(3, AlertType::RapidFire, AlertSeverity::High, "W1", "burst"),
+    ]);
+    let update = DashboardUpdate {
+        total_trades: 500,
+        alert_counts: HashMap::from([("WashTrading".to_string(), 2), ("RapidFire".to_string(), 1)]),
+        total_alerts: 3,
+        ..DashboardUpdate::default()
+    };
+    web.publish(&update);
+
+    let json: Vec<Alert> = serde_json::from_str(&http_get(addr, "/api/alerts?type=washtrading").await).expect("alerts JSON");
+    assert_eq!(json.iter().map(|a| a.id).collect::<Vec<_>>(), [1, 2]);
+    let hits: Vec<Alert> = serde_json::from_str(&http_get(addr, "/api/alerts?q=wash+round&account=W1").await).expect("search JSON");
+    assert_eq!(hits.iter().map(|a| a.id).collect::<Vec<_>>(), [1], "every word must match, as well as the filters");
+    let (head, csv) = http_request(addr, "/api/alerts.csv?type=WashTrading&severity=high").await;
+    assert!(head.starts_with("HTTP/1.1 200") && head.to_lowercase().contains("content-type: text/csv"), "{head}");
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].starts_with("id,timestamp_ms,alert_type,severity"));
+    assert_eq!(lines.len(), 2, "only the critical wash alert: {csv}");
+    assert!(lines[1].starts_with("2,2000,WashTrading,Critical,AAPL,W2,"), "{}", lines[1]);
+    assert!(lines[1].ends_with(",\"said \"\"wash\"\"\""), "quotes escaped: {}", lines[1]);
+    let csv = http_get(addr, "/api/alerts.csv?account=W1&limit=1").await;
+    assert_eq!(csv.lines().count(), 2);
+    assert!(csv.lines().nth(1).unwrap().starts_with("3,"), "the newest of W1's alerts");
+    assert!(http_get(addr, "/api/alerts.csv?account=W1").await.contains("\"wash, 3 round trips\""));
+
+    let stats: Vec<StatRow> = serde_json::from_str(&http_get(addr, "/api/stats?group=alerts").await).expect("stats JSON");
+    let names: Vec<(&str, f64)> = stats.iter().map(|r| (r.name.as_str(), r.value)).collect();
+    assert_eq!(names, [("RapidFire", 1.0), ("WashTrading", 2.0)]);
+    let csv = http_get(addr, "/api/stats.csv?group=totals&name=trades").await;
+    assert_eq!(csv, "group,name,value\ntotals,trades,500\n");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_scoped_api_tokens() {
+    let audit_path = std::env::temp_dir().join(format!("api-audit-{}.jsonl", uuid::Uuid::new_v4()));
+    let mut config = Config::default();
+    config.audit.path = Some(audit_path.clone());
+    config.auth.tokens = [("grafana", "read-token-0123456789", Scope::Read), ("oncall", "operate-token-0123456789", Scope::Operate), ("ops", "admin-token-0123456789", Scope::Admin)]
+        .map(|(name, token, scope)| ApiToken { name: name.into(), token: token.into(), scope })
+        .to_vec();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let web = web::serve_on(listener, &config).await.expect("serve");
+    web.set_audit(AuditLog::open(&config).unwrap());
+    web.blotter().record_alerts(&[Alert {
+        id: 7,
+        alert_type: AlertType::WashTrading,
+        severity: AlertSeverity::High,
+        symbol: Some("AAPL".into()),
+        account_id: Some("W1".into()),
+        detail: AlertDetail::Text("wash".into()),
+        latency_us: 120,
+        timestamp_ms: 7_000,
+        score: None,
+        gap_ms: None,
+        metric: None,
+        uid: None,
+    }]);
+
+    let status = |head: String| head.split_whitespace().nth(1).unwrap_or_default().to_string();
+    let call = |method: &'static str, path: &'static str, token: Option<&'static str>| async move {
+        status(http_call(addr, method, path, token, if method == "POST" { "{\"note\":\"seen\"}" } else { "" }).await.0)
+    };
+    assert_eq!(call("GET", "/api/stats", None).await, "401", "no token");
+    assert_eq!(call("GET", "/api/stats", Some("not-a-token-at-all")).await, "401");
+    assert_eq!(call("GET", "/api/stats", Some("read-token-0123456789")).await, "200");
+    assert_eq!(call("GET", "/api/stats", Some("admin-token-0123456789")).await, "200", "admin can read");
+    assert_eq!(call("POST", "/api/alerts/7/review", Some("read-token-0123456789")).await, "403");
+    assert_eq!(call("POST", "/api/alerts/7/review", Some("operate-token-0123456789")).await, "200");
+    assert_eq!(call("POST", "/api/stats/reset", Some("operate-token-0123456789")).await, "403");
+    assert_eq!(call("POST", "/api/stats/reset", Some("admin-token-0123456789")).await, "202");
+    assert!(web.take_stats_reset());
+
+    let entries = audit::read_entries(&audit_path).unwrap();
+    let AuditEntry::ConfigChange { config, .. } = &entries[0] else { panic!("startup config first") };
+    assert!(config.auth.tokens.iter().all(|t| t.token == "<redacted>"), "tokens never reach the log");
+    let calls: Vec<(Option<&str>, Scope, &str, u16)> = entries[1..]
+        .iter()
+        .map(|e| match e {
+            AuditEntry::ApiCall { token, scope, path, status, .. } => (token.as_deref(), *scope, path.as_str(), *status),
+            other => panic!("only privileged calls are audited: {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            (Some("grafana"), Scope::Operate, "/api/alerts/7/review", 403),
+            (Some("oncall"), Scope::Operate, "/api/alerts/7/review", 200),
+            (Some("oncall"), Scope::Admin, "/api/stats/reset", 403),
+            (Some("ops"), Scope::Admin, "/api/stats/reset", 202),
+        ]
+    );
+    std::fs::remove_file(&audit_path).ok();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_coordinator_fan_in() {
+    let mut shards = Vec::new();
+    let mut upstreams = Vec::new();
+    for _ in 0..2 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        upstreams.push(format!("ws://{}/ws", listener.local_addr().unwrap()));
+        shards.push(web::serve_on(listener, &Config::default()).await.expect("serve shard"));
+    }
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let update = |trades: u64, account: &str, symbol: &str| DashboardUpdate {
+        alerts: vec![Alert {
+            id: 1,
+            alert_type: AlertType::WashTrading,
+            severity: AlertSeverity::High,
+            symbol: Some(symbol.into()),
+            account_id: Some(account.into()),
+            detail: AlertDetail::Text("wash".into()),
+            latency_us: 120,
+            timestamp_ms: 1_000,
+            score: None,
+            gap_ms: None,
+            metric: None,
+            uid: None,
+        }],
+        total_trades: trades,
+        total_alerts: 1,
+        // Three trades on each shard: under the rapid-fire threshold of five, over it together
+        accounts: vec![AccountRisk { account_id: "X9".into(), burst_trades: 3, burst_volume: 300, burst_notional: 30_000.0, instances: 1, ..AccountRisk::default() }],
+        ..DashboardUpdate::default()
+    };
+    // The coordinator connects while the shards publish; keep publishing until both count
+    let check = async {
+        let mut totals = String::new();
+        for _ in 0..100 {
+            shards[0].publish(&update(500, "W1", "AAPL"));
+            shards[1].publish(&update(300, "W2", "GOOGL"));
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            totals = http_get(addr, "/api/stats.csv?group=totals&name=trades").await;
+            if totals.ends_with("totals,trades,800\n") {
+                break;
+            }
+        }
+        assert_eq!(totals, "group,name,value\ntotals,trades,800\n", "trades from both shards");
+        let alerts: Vec<Alert> = serde_json::from_str(&http_get(addr, "/api/alerts?type=WashTrading").await).expect("alerts JSON");
+        for account in ["W1", "W2"] {
+            assert!(alerts.iter().any(|a| a.account_id.as_deref() == Some(account)), "{account}'s alert reached the coordinator");
+        }
+        let alerts: Vec<Alert> = serde_json::from_str(&http_get(addr, "/api/alerts?type=RapidFire&account=X9").await).expect("alerts JSON");
+        assert!(!alerts.is_empty(), "X9's bursts fire once added up");
+        let accounts: Vec<AccountRisk> = serde_json::from_str(&http_get(addr, "/api/accounts/risk").await).expect("accounts JSON");
+        let x9 = accounts.iter().find(|a| a.account_id == "X9").expect("X9 in the merged accounts");
+        assert_eq!((x9.instances, x9.burst_trades), (2, 6));
+    };
+    tokio::select! {
+        result = coordinator::run_on(listener, upstreams, 30, Config::default()) => panic!("coordinator stopped early: {:?}", result.err().map(|e| e.to_string())),
+        () = check => {}
+    }
+}
+
+/// A clean run of `elapsed_secs` that pushed 1000 trades.
+fn recorded_run(id: &str, started_at_ms: i64, elapsed_secs: f64, alert_p99_us: u64) -> RunRecord {
+    RunRecord {
+        id: id.into(),
+        started_at_ms,
+        ui: "none".into(),
+        fraud_rate: 0.0,
+        config: Config::default(),
+        elapsed_secs,
+        uptime_secs: elapsed_secs,
+        total_trades: 1_000,
+        total_orders: 1_000,
+        total_alerts: 0,
+        suppressed_alerts: 0,
+        alert_counts: HashMap::new(),
+        severity_counts: HashMap::new(),
+        stream_counts: Default::default(),
+        shadow_counts: HashMap::new(),
+        push: LatencyStats::default(),
+        processing: LatencyStats::default(),
+        alert: LatencyStats { p99_us: alert_p99_us, ..LatencyStats::default() },
+        tick_lag: LatencyStats::default(),
+        bar_lag: LatencyStats::default(),
+        drain: None,
+        slo: Vec::new(),
+        quality: None,
+    }
+}
+
+/// Body of a plain HTTP/1.1 GET, without pulling in an HTTP client.
+async fn http_get(addr: std::net::SocketAddr, path: &str) -> String {
+    let (head, body) = http_request(addr, path).await;
+    assert!(head.starts_with("HTTP/1.1 200"), "GET {path}: {head}");
+    body
+}
+
+/// Status head and body of a GET, whatever the status.
+async fn http_request(addr: std::net::SocketAddr, path: &str) -> (String, String) {
+    http_call(addr, "GET", path, None, "").await
+}
+
+/// Status head and body of any call, with an optional bearer token and a
+/// JSON body.
+async fn http_call(addr: std::net::SocketAddr, method: &str, path: &str, token: Option<&str>, body: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let auth = token.map(|t| format!("Authorization: Bearer {t}\r\n")).unwrap_or_default();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n{auth}Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").expect("HTTP response");
+    // Compressed routes stream their body, so it comes back chunked even
+    // when this client asks for no encoding
+    let body = if head.to_ascii_lowercase().contains("transfer-encoding: chunked") { unchunk(body) } else { body.to_string() };
+    (head.to_string(), body)
+}
+
+/// Join the chunks of a chunked HTTP/1.1 body.
+fn unchunk(mut body: &str) -> String {
+    let mut joined = String::new();
+    while let Some((size, rest)) = body.split_once("\r\n") {
+        let size = usize::from_str_radix(size.trim(), 16).expect("chunk size");
+        if size == 0 {
+            break;
+        }
+        joined.push_str(&rest[..size]);
+        body = &rest[size + 2..];
+    }
+    joined
+}
+
+fn assert_counts_grow(before: &HashMap<String, u64>, now: &HashMap<String, u64>) {
+    for (label, &n) in before {
+        let m = now.get(label).copied().unwrap_or(0);
+        assert!(m >= n, "'{label}' count fell from {n} to {m}");
+    }
+}